@@ -1,5 +1,5 @@
 use rodio::Source; //TODO: replace rodio with something better
-use std::{fs, io};
+use std::{fs, io, time::Duration};
 
 type AudioBuffers = Vec<rodio::source::Buffered<rodio::Decoder<io::BufReader<fs::File>>>>;
 
@@ -49,6 +49,10 @@ impl SamplerPlayer {
   }
 
   pub fn play(&self, sample_index: usize) {
+    self.play_with_volume(sample_index, 1.0);
+  }
+
+  pub fn play_with_volume(&self, sample_index: usize, volume: f32) {
     if sample_index >= self.clip_buffers.len() {
       return;
     }
@@ -57,10 +61,31 @@ impl SamplerPlayer {
       Some(device) => {
         rodio::play_raw(
           device,
-          self.clip_buffers[sample_index].clone().convert_samples(),
+          self.clip_buffers[sample_index]
+            .clone()
+            .amplify(volume)
+            .convert_samples(),
         );
       }
       _ => {}
     }
   }
+
+  /// Play a sample, stopping it after a length of time.
+  pub fn play_with_length(&self, sample_index: usize, volume: f32, length: Duration) {
+    if sample_index >= self.clip_buffers.len() {
+      return;
+    }
+
+    if let Some(device) = &self.device {
+      rodio::play_raw(
+        device,
+        self.clip_buffers[sample_index]
+          .clone()
+          .amplify(volume)
+          .take_duration(length)
+          .convert_samples(),
+      );
+    }
+  }
 }
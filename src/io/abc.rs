@@ -0,0 +1,1047 @@
+use crate::{
+  chords, composition,
+  io::notation::{self, BarChord},
+  notes::{self, Key},
+  FailResult,
+};
+use music_timer::time_signature::TimeSignature;
+use std::collections::HashMap;
+
+/// The unit note length of exported tunes, an eighth note.
+const UNIT_NOTE_VALUE: u32 = 8;
+
+/// The ticks of a beat, 8 beat intervals.
+const BEAT_TICKS: u64 = composition::BEAT_INTERVAL_TICKS as u64 * 8;
+
+/// The octave chord symbols are voiced from, as in compositions loaded from
+/// YAML.
+const CHORD_OCTAVE: i8 = 3;
+
+/// The letters of the sharps of key signatures in order. The flats are in the
+/// reverse order.
+const SHARP_LETTERS: [char; 7] = ['F', 'C', 'G', 'D', 'A', 'E', 'B'];
+
+/// Write a composition as ABC notation, a tune for each pattern.
+///
+/// Each tune has the pattern's name, time signature, tempo and key. Chord
+/// symbols are written in quotes over the voiced chords, which last until the
+/// next event and are tied across bar lines. Rests after a chord are marked
+/// `"N.C."`.
+///
+/// # Arguments
+/// * `composition` - The composition to write.
+pub fn composition_to_abc(composition: &composition::Composition) -> String {
+  let mut abc = format!("%abc-2.1\n% {}\n", composition.get_name());
+  for (index, pattern) in composition.get_patterns().iter().enumerate() {
+    abc.push('\n');
+    abc.push_str(&pattern_to_abc(pattern, index + 1));
+  }
+  abc
+}
+
+/// Write a pattern as an ABC tune.
+///
+/// # Arguments
+/// * `pattern` - The pattern to write.
+/// * `reference` - The reference number of the tune, its `X:` field.
+pub fn pattern_to_abc(pattern: &composition::Pattern, reference: usize) -> String {
+  let key = notation::to_pattern_key(pattern);
+  let fifths = notation::key_to_fifths(key);
+  let (numerator, denominator) = pattern.get_time_signature().as_tuple();
+
+  let mut abc = format!("X:{}\n", reference);
+  abc.push_str(&format!("T:{}\n", pattern.get_name()));
+  abc.push_str(&format!("M:{}/{}\n", numerator, denominator));
+  abc.push_str(&format!("L:1/{}\n", UNIT_NOTE_VALUE));
+  abc.push_str(&format!("Q:1/{}={}\n", denominator, pattern.get_bpm()));
+  abc.push_str(&format!("K:{}\n", notation::to_note_name(key, fifths)));
+
+  let bars = notation::pattern_to_bars(pattern);
+  let mut lines: Vec<String> = Vec::new();
+  let mut line: Vec<String> = Vec::new();
+  let mut is_chord_before = false;
+  for (i, bar) in bars.iter().enumerate() {
+    let mut items: Vec<String> = Vec::new();
+    if i > 0 && bars[i - 1].get_time_signature() != bar.get_time_signature() {
+      let (numerator, denominator) = bar.get_time_signature().as_tuple();
+      items.push(format!("[M:{}/{}]", numerator, denominator));
+    }
+
+    // Accidentals last until the end of the bar
+    let mut accidentals: HashMap<(char, i8), i8> = HashMap::new();
+    for chord in bar.get_chords() {
+      let chord_end = chord.get_start() + chord.get_length();
+      for (j, (units, bpm)) in bar.get_tempos().iter().enumerate() {
+        // The pattern's tempo is written in the header
+        let is_header_tempo = i == 0 && j == 0;
+        if !is_header_tempo && *units >= chord.get_start() && *units < chord_end {
+          items.push(format!(
+            "[Q:1/{}={}]",
+            bar.get_time_signature().get_denominator(),
+            bpm
+          ));
+        }
+      }
+      items.push(chord_to_abc(
+        chord,
+        fifths,
+        is_chord_before,
+        &mut accidentals,
+      ));
+      is_chord_before = !chord.get_notes().is_empty();
+    }
+
+    line.push(items.join(" "));
+    if line.len() == notation::BARS_PER_LINE || i + 1 == bars.len() {
+      lines.push(line.join(" | "));
+      line.clear();
+    }
+  }
+
+  if !lines.is_empty() {
+    abc.push_str(&lines.join(" |\n"));
+    abc.push_str(" |]\n");
+  }
+  abc
+}
+
+/// The chord symbol and voiced notes of a chord, or a rest, as lengths tied
+/// together.
+fn chord_to_abc(
+  chord: &BarChord,
+  fifths: i8,
+  is_chord_before: bool,
+  accidentals: &mut HashMap<(char, i8), i8>,
+) -> String {
+  let symbol = match chord.get_label() {
+    Some(label) => format!("\"{}\"", label.to_symbol(fifths)),
+    None if chord.get_notes().is_empty() && is_chord_before => {
+      format!("\"{}\"", notation::NO_CHORD)
+    }
+    None => String::new(),
+  };
+
+  let mut midi_notes = chord.get_notes().clone();
+  midi_notes.sort_unstable();
+  let pitches: Vec<String> = midi_notes
+    .iter()
+    .map(|note| to_abc_pitch(*note, fifths, accidentals))
+    .collect();
+  let notes = match pitches.len() {
+    0 => "z".to_string(),
+    1 => pitches[0].clone(),
+    _ => format!("[{}]", pitches.concat()),
+  };
+
+  let note_values = notation::to_note_values(chord.get_length());
+  let lengths: Vec<String> = note_values
+    .iter()
+    .enumerate()
+    .map(|(i, (_, _, units))| {
+      let is_tied = !midi_notes.is_empty() && (i + 1 < note_values.len() || chord.is_tied());
+      let tie = if is_tied { "-" } else { "" };
+      format!("{}{}{}", notes, to_abc_length(*units), tie)
+    })
+    .collect();
+  format!("{}{}", symbol, lengths.join(" "))
+}
+
+/// Get the ABC pitch of a midi note, where `C` is middle C and `c` the octave
+/// above. An accidental is written when the note differs from the key
+/// signature or an accidental earlier in the bar.
+fn to_abc_pitch(midi_note: u8, fifths: i8, accidentals: &mut HashMap<(char, i8), i8>) -> String {
+  // The octave of `C`, middle C
+  const UPPERCASE_OCTAVE: i8 = 4;
+  let (letter, alter, octave) = notation::to_pitch(midi_note, fifths);
+
+  let current_alter = *accidentals
+    .get(&(letter, octave))
+    .unwrap_or(&to_key_signature_alter(letter, fifths));
+  let accidental = if alter == current_alter {
+    ""
+  } else {
+    accidentals.insert((letter, octave), alter);
+    match alter {
+      1 => "^",
+      -1 => "_",
+      _ => "=",
+    }
+  };
+
+  let name = if octave > UPPERCASE_OCTAVE {
+    let marks = "'".repeat((octave - UPPERCASE_OCTAVE - 1) as usize);
+    format!("{}{}", letter.to_ascii_lowercase(), marks)
+  } else {
+    let marks = ",".repeat((UPPERCASE_OCTAVE - octave) as usize);
+    format!("{}{}", letter, marks)
+  };
+  format!("{}{}", accidental, name)
+}
+
+/// Get the ABC length of notation units, in unit note lengths, e.g. `3`, `/`
+/// or `3/2`.
+fn to_abc_length(units: u32) -> String {
+  let unit = notation::WHOLE_NOTE_UNITS / UNIT_NOTE_VALUE;
+  let divisor = notation::to_greatest_common_divisor(units, unit);
+  match (units / divisor, unit / divisor) {
+    (1, 1) => String::new(),
+    (numerator, 1) => numerator.to_string(),
+    (1, 2) => "/".to_string(),
+    (1, denominator) => format!("/{}", denominator),
+    (numerator, denominator) => format!("{}/{}", numerator, denominator),
+  }
+}
+
+/// Get the alteration a key signature gives a letter.
+fn to_key_signature_alter(letter: char, fifths: i8) -> i8 {
+  let mut sharps = SHARP_LETTERS.iter().take(fifths.max(0) as usize);
+  let mut flats = SHARP_LETTERS.iter().rev().take((-fifths).max(0) as usize);
+  if sharps.any(|sharp| *sharp == letter) {
+    1
+  } else if flats.any(|flat| *flat == letter) {
+    -1
+  } else {
+    0
+  }
+}
+
+/// Read the tunes of ABC notation into patterns, one for each `X:` field, or a
+/// single pattern when there are none.
+///
+/// Each chord symbol becomes a chord event where it is written, voiced in the
+/// tune's key, and `"N.C."` becomes a rest. Notes and rests only place the
+/// chord symbols, through their lengths, broken rhythms, tuplets and
+/// multi-measure rests. The title, meter, tempo and key of a tune set the name,
+/// time signature, tempo and chord keys of its pattern, and meter and tempo
+/// fields in the tune body change them. Repeats aren't expanded and the bass
+/// note of a slash chord is left out.
+///
+/// # Arguments
+/// * `abc` - The ABC notation to read.
+pub fn abc_to_patterns(abc: &str) -> Result<Vec<composition::Pattern>, FailResult> {
+  let has_references = abc.lines().any(|line| line.trim().starts_with("X:"));
+  let mut tunes: Vec<Vec<&str>> = if has_references {
+    Vec::new()
+  } else {
+    vec![Vec::new()]
+  };
+
+  // Tunes start with a reference number and end with an empty line
+  let mut is_in_tune = !has_references;
+  for line in abc.lines().map(str::trim) {
+    if line.starts_with("X:") {
+      tunes.push(Vec::new());
+      is_in_tune = true;
+    } else if line.is_empty() {
+      is_in_tune = is_in_tune && !has_references;
+    } else if is_in_tune && !line.starts_with('%') {
+      if let Some(tune) = tunes.last_mut() {
+        tune.push(line);
+      }
+    }
+  }
+
+  tunes
+    .iter()
+    .filter(|tune| !tune.is_empty())
+    .enumerate()
+    .map(|(index, tune)| tune_to_pattern(tune, index))
+    .collect()
+}
+
+/// Reads the body of a tune into a pattern as it is played through.
+struct TuneReader {
+  pattern: composition::Pattern,
+  key: Key,
+  /// The unit note length as a fraction of a whole note.
+  unit: (u64, u64),
+  tick: u32,
+  chord_symbol: Option<String>,
+  /// The ticks of the last note, lengthened or shortened by a broken rhythm.
+  previous_ticks: u32,
+  /// The fraction of its length the next note has after a broken rhythm.
+  broken_rhythm: Option<(u64, u64)>,
+  /// The fraction of their length tuplet notes have, and the count of notes
+  /// left in the tuplet.
+  tuplet: Option<(u64, u64, u32)>,
+}
+
+fn tune_to_pattern(lines: &[&str], index: usize) -> Result<composition::Pattern, FailResult> {
+  const DEFAULT_BPM: f32 = 120.0;
+
+  let header_length = lines
+    .iter()
+    .position(|line| line.starts_with("K:"))
+    .map_or(0, |position| position + 1);
+  let (header, body) = lines.split_at(header_length);
+  let header_fields: Vec<(char, &str)> = header.iter().filter_map(|line| to_field(line)).collect();
+
+  let mut reader = TuneReader {
+    pattern: composition::Pattern::new(
+      &format!("tune_{}", index + 1),
+      DEFAULT_BPM,
+      TimeSignature::new(4, 4),
+    ),
+    key: Key::C,
+    unit: (1, 8),
+    tick: 0,
+    chord_symbol: None,
+    previous_ticks: 0,
+    broken_rhythm: None,
+    tuplet: None,
+  };
+
+  // The meter sets the default unit note length and the beat of the tempo
+  for (field, value) in &header_fields {
+    if *field == 'M' {
+      reader.read_field(*field, value)?;
+    }
+  }
+  let (numerator, denominator) = reader.pattern.get_time_signature().as_tuple();
+  if (numerator as f32 / denominator as f32) < 0.75 {
+    reader.unit = (1, 16);
+  }
+  for (field, value) in &header_fields {
+    if *field != 'M' {
+      reader.read_field(*field, value)?;
+    }
+  }
+  if let Some((_, title)) = header_fields.iter().find(|(field, _)| *field == 'T') {
+    reader.pattern.set_name(title);
+  }
+
+  for line in body {
+    match to_field(line) {
+      Some((field, value)) => reader.read_field(field, value)?,
+      None => reader.read_line(line)?,
+    }
+  }
+
+  reader.pattern.check()?;
+  Ok(reader.pattern)
+}
+
+/// Get the letter and value of a field line, e.g. `M:6/8`.
+fn to_field(line: &str) -> Option<(char, &str)> {
+  let mut chars = line.chars();
+  match (chars.next(), chars.next()) {
+    (Some(field), Some(':')) if field.is_ascii_alphabetic() => {
+      let value = &line[2..];
+      let value = value.split('%').next().unwrap_or(value);
+      Some((field, value.trim()))
+    }
+    _ => None,
+  }
+}
+
+impl TuneReader {
+  fn read_field(&mut self, field: char, value: &str) -> Result<(), FailResult> {
+    match field {
+      'M' => match parse_meter(value) {
+        Some(signature) => self.set_time_signature(signature)?,
+        None if value.contains('/') => return Err(FailResult::ImportABC),
+        None => (),
+      },
+      'L' => {
+        if let Some(unit) = parse_fraction(value) {
+          self.unit = unit;
+        }
+      }
+      'Q' => {
+        let denominator = self.get_time_signature().get_denominator();
+        if let Some(bpm) = parse_tempo(value, self.unit, denominator) {
+          self.set_bpm(bpm)?;
+        }
+      }
+      'K' => {
+        if let Some(key) = parse_key(value) {
+          self.key = key;
+        }
+      }
+      _ => (),
+    }
+    Ok(())
+  }
+
+  /// Meter changes part way through a bar start on the following bar.
+  fn set_time_signature(&mut self, signature: TimeSignature) -> Result<(), FailResult> {
+    if self.tick == 0 {
+      return self.pattern.set_time_signature(signature);
+    }
+
+    let (time, offset) = self.pattern.get_time_at_tick_index(self.tick);
+    let is_bar_start = time.get_beat() == 1 && time.get_beat_interval() == 1 && offset == 0;
+    let bar = if is_bar_start {
+      time.get_bar()
+    } else {
+      time.get_bar() + 1
+    };
+    if signature != self.pattern.get_time_signature_at(bar) {
+      self.pattern.push_time_signature_change(bar, signature);
+    }
+    Ok(())
+  }
+
+  fn set_bpm(&mut self, bpm: f32) -> Result<(), FailResult> {
+    if self.tick == 0 {
+      return self.pattern.set_bpm(bpm);
+    }
+
+    let (time, _) = self.pattern.get_time_at_tick_index(self.tick);
+    self
+      .pattern
+      .push_tempo_change(time, composition::TempoChange::Instant(bpm));
+    Ok(())
+  }
+
+  fn get_time_signature(&self) -> TimeSignature {
+    let (time, _) = self.pattern.get_time_at_tick_index(self.tick);
+    self.pattern.get_time_signature_at(time.get_bar())
+  }
+
+  /// Read a line of the tune body, its chord symbols, notes, rests and inline
+  /// fields.
+  fn read_line(&mut self, line: &str) -> Result<(), FailResult> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+      let c = chars[i];
+      i += 1;
+      match c {
+        '%' => break,
+        '"' => {
+          let text = read_until(&chars, &mut i, '"');
+          let is_annotation = text.starts_with(|c| "^_<>@".contains(c));
+          if !is_annotation {
+            self.chord_symbol = Some(text.trim().to_string());
+          }
+        }
+        '!' | '+' => {
+          read_until(&chars, &mut i, c);
+        }
+        '{' => {
+          read_until(&chars, &mut i, '}');
+        }
+        '[' if chars.get(i + 1) == Some(&':') => {
+          let field = read_until(&chars, &mut i, ']');
+          if let Some((field, value)) = to_field(&field) {
+            self.read_field(field, value)?;
+          }
+        }
+        '[' if matches!(chars.get(i), Some(c) if c.is_ascii_digit() || *c == '|') => (),
+        '[' => {
+          // A chord lasts as long as its first note
+          let mut length = None;
+          while i < chars.len() && chars[i] != ']' {
+            if is_note_letter(chars[i]) {
+              i += 1;
+              skip_octave_marks(&chars, &mut i);
+              let note_length = read_length(&chars, &mut i)?;
+              length = length.or(Some(note_length));
+            } else {
+              i += 1;
+            }
+          }
+          i += 1;
+          let (numerator, denominator) = length.unwrap_or((1, 1));
+          let (multiplier, divisor) = read_length(&chars, &mut i)?;
+          let length = numerator
+            .checked_mul(multiplier)
+            .zip(denominator.checked_mul(divisor))
+            .ok_or(FailResult::ImportABC)?;
+          self.place_note(length)?;
+        }
+        '(' if matches!(chars.get(i), Some(c) if c.is_ascii_digit()) => {
+          self.read_tuplet(&chars, &mut i)
+        }
+        '>' | '<' => {
+          let mut count = 1;
+          while chars.get(i) == Some(&c) {
+            count += 1;
+            i += 1;
+          }
+          self.read_broken_rhythm(c == '>', count)?;
+        }
+        _ if is_note_letter(c) => {
+          skip_octave_marks(&chars, &mut i);
+          let length = read_length(&chars, &mut i)?;
+          self.place_note(length)?;
+        }
+        'z' | 'x' => {
+          let length = read_length(&chars, &mut i)?;
+          self.place_note(length)?;
+        }
+        'Z' | 'X' => {
+          let bars = read_number(&chars, &mut i).unwrap_or(1);
+          let bar_ticks = self.get_time_signature().get_numerator() as u64 * BEAT_TICKS;
+          let ticks = bars.checked_mul(bar_ticks).ok_or(FailResult::ImportABC)?;
+          self.place(ticks)?;
+        }
+        _ => (),
+      }
+    }
+    Ok(())
+  }
+
+  /// Read a tuplet, `(p:q:r`, where `r` notes take the time of `q`.
+  fn read_tuplet(&mut self, chars: &[char], i: &mut usize) {
+    let notes = read_number(chars, i).unwrap_or(3);
+    let read_part = |i: &mut usize| {
+      if chars.get(*i) == Some(&':') {
+        *i += 1;
+        read_number(chars, i)
+      } else {
+        None
+      }
+    };
+    let time = read_part(i);
+    let count = read_part(i);
+
+    let (numerator, _) = self.get_time_signature().as_tuple();
+    let is_compound = numerator % 3 == 0 && numerator > 3;
+    let time = time.unwrap_or(match notes {
+      3 | 6 => 2,
+      2 | 4 | 8 => 3,
+      _ if is_compound => 3,
+      _ => 2,
+    });
+    if notes > 0 {
+      self.tuplet = Some((time, notes, count.unwrap_or(notes) as u32));
+    }
+  }
+
+  /// Lengthen the last note and shorten the next, or the other way around, by
+  /// a dot for each `>` or `<`.
+  fn read_broken_rhythm(&mut self, is_first_longer: bool, count: u32) -> Result<(), FailResult> {
+    let divisor = 1u64.checked_shl(count).ok_or(FailResult::ImportABC)?;
+    let change = (self.previous_ticks as u64)
+      .checked_mul(divisor - 1)
+      .ok_or(FailResult::ImportABC)?
+      / divisor;
+    if is_first_longer {
+      self.tick = to_tick(self.tick as u64 + change)?;
+      self.broken_rhythm = Some((1, divisor));
+    } else {
+      self.tick = self.tick.saturating_sub(change as u32);
+      let multiplier = divisor.checked_mul(2).ok_or(FailResult::ImportABC)? - 1;
+      self.broken_rhythm = Some((multiplier, divisor));
+    }
+    Ok(())
+  }
+
+  /// Place a note, rest or chord of a length in unit note lengths.
+  fn place_note(&mut self, length: (u64, u64)) -> Result<(), FailResult> {
+    let mut fractions = vec![self.unit, length];
+    if let Some(broken_rhythm) = self.broken_rhythm.take() {
+      fractions.push(broken_rhythm);
+    }
+    if let Some((multiplier, divisor, count)) = self.tuplet {
+      fractions.push((multiplier, divisor));
+      self.tuplet = match count {
+        0 | 1 => None,
+        _ => Some((multiplier, divisor, count - 1)),
+      };
+    }
+
+    let whole_note_ticks = BEAT_TICKS * self.get_time_signature().get_denominator() as u64;
+    let (numerator, denominator) = fractions
+      .iter()
+      .try_fold(
+        (whole_note_ticks, 1u64),
+        |(numerator, denominator), (multiplier, divisor)| {
+          Some((
+            numerator.checked_mul(*multiplier)?,
+            denominator.checked_mul(*divisor)?,
+          ))
+        },
+      )
+      .ok_or(FailResult::ImportABC)?;
+    self.place((numerator + denominator / 2) / denominator)
+  }
+
+  /// Place the chord symbol written before a note, then move past the note.
+  fn place(&mut self, ticks: u64) -> Result<(), FailResult> {
+    let tick = to_tick(self.tick as u64 + ticks)?;
+    if let Some(text) = self.chord_symbol.take() {
+      let event = self.to_chord_event(&text)?;
+      let index = self.pattern.len();
+      let is_repeated = index > 0 && {
+        let previous = self.pattern.get_event(index - 1);
+        self
+          .pattern
+          .get_tick_index(previous.get_time(), previous.get_offset())
+          == self.tick
+      };
+      if is_repeated {
+        self.pattern.replace_event(index - 1, event)?;
+      } else {
+        self.pattern.push_chord_event(event)?;
+      }
+    }
+
+    self.tick = tick;
+    self.previous_ticks = ticks as u32;
+    Ok(())
+  }
+
+  /// Build the chord event of a chord symbol at the current tick, voiced in
+  /// the tune's key.
+  fn to_chord_event(&self, text: &str) -> Result<composition::ChordEvent, FailResult> {
+    const NOTES_IN_OCTAVE_COUNT: i8 = 12;
+    const HALF_OCTAVE: i8 = 6;
+    let (time, offset) = self.pattern.get_time_at_tick_index(self.tick);
+
+    if text == notation::NO_CHORD || text == "NC" {
+      let symbol =
+        composition::ChordSymbol::new(chords::REST_NAME, self.key, 0, Vec::new(), CHORD_OCTAVE);
+      return Ok(composition::ChordEvent::new_with_symbol(
+        time,
+        offset,
+        Vec::new(),
+        symbol,
+      ));
+    }
+
+    let (root, name, intervals) =
+      parse_chord_symbol(text).ok_or_else(|| FailResult::UnknownChordSymbol(text.to_string()))?;
+    // Roots are voiced within a fifth below or a fourth above the key
+    let key_index = notes::key_to_index(self.key) as i8;
+    let transpose = (notes::key_to_index(root) as i8 - key_index + HALF_OCTAVE)
+      .rem_euclid(NOTES_IN_OCTAVE_COUNT)
+      - HALF_OCTAVE;
+
+    let midi_notes = chords::IntervalChord::new(intervals.clone(), 0)
+      .transpose(key_index)
+      .transpose(transpose)
+      .transpose_octave(CHORD_OCTAVE)
+      .to_midi();
+    let symbol = composition::ChordSymbol::new(&name, self.key, transpose, intervals, CHORD_OCTAVE);
+    Ok(composition::ChordEvent::new_with_symbol(
+      time, offset, midi_notes, symbol,
+    ))
+  }
+}
+
+/// Get a tick of the tune, or an error when it is past the last bar a pattern
+/// can reach.
+fn to_tick(tick: u64) -> Result<u32, FailResult> {
+  const MAX_TICK: u64 = (u16::MAX as u64 - 1) * BEAT_TICKS;
+  if tick < MAX_TICK {
+    Ok(tick as u32)
+  } else {
+    Err(FailResult::ImportABC)
+  }
+}
+
+/// Read up to a closing character, returning the text before it and moving
+/// past it.
+fn read_until(chars: &[char], i: &mut usize, end: char) -> String {
+  let text: String = chars[*i..].iter().take_while(|c| **c != end).collect();
+  *i = (*i + text.chars().count() + 1).min(chars.len());
+  text
+}
+
+fn read_number(chars: &[char], i: &mut usize) -> Option<u64> {
+  let digits: String = chars[*i..]
+    .iter()
+    .take_while(|c| c.is_ascii_digit())
+    .collect();
+  *i += digits.len();
+  digits.parse().ok()
+}
+
+/// Read the length after a note or rest, a fraction of the unit note length,
+/// e.g. `3`, `/`, `//` or `3/2`.
+fn read_length(chars: &[char], i: &mut usize) -> Result<(u64, u64), FailResult> {
+  let numerator = read_number(chars, i).unwrap_or(1);
+  let mut slashes = 0;
+  while chars.get(*i) == Some(&'/') {
+    slashes += 1;
+    *i += 1;
+  }
+  let denominator = match (slashes, read_number(chars, i)) {
+    (0, _) => 1,
+    (1, Some(denominator)) => denominator,
+    (_, _) => 1u64.checked_shl(slashes).ok_or(FailResult::ImportABC)?,
+  };
+  Ok((numerator, denominator.max(1)))
+}
+
+fn is_note_letter(c: char) -> bool {
+  matches!(c, 'A'..='G' | 'a'..='g')
+}
+
+fn skip_octave_marks(chars: &[char], i: &mut usize) {
+  while matches!(chars.get(*i), Some('\'') | Some(',')) {
+    *i += 1;
+  }
+}
+
+/// Parse a meter, e.g. `6/8`, `2+3/8`, `C` for common time or `C|` for cut
+/// time.
+fn parse_meter(text: &str) -> Option<TimeSignature> {
+  match text.trim() {
+    "C" => Some(TimeSignature::new(4, 4)),
+    "C|" => Some(TimeSignature::new(2, 2)),
+    text => {
+      let (numerator, denominator) = text.split_once('/')?;
+      let numerator = numerator.split('+').try_fold(0u8, |numerator, beats| {
+        numerator.checked_add(beats.trim().parse().ok()?)
+      })?;
+      Some(TimeSignature::new(
+        numerator,
+        denominator.trim().parse().ok()?,
+      ))
+    }
+  }
+}
+
+/// Parse a fraction of a whole note, e.g. `1/8`.
+fn parse_fraction(text: &str) -> Option<(u64, u64)> {
+  let (numerator, denominator) = text.trim().split_once('/')?;
+  let numerator: u64 = numerator.trim().parse().ok()?;
+  let denominator: u64 = denominator.trim().parse().ok()?;
+  if numerator == 0 || denominator == 0 {
+    None
+  } else {
+    Some((numerator, denominator))
+  }
+}
+
+/// Parse a tempo, e.g. `1/4=120` or `"Allegro" 3/8=60`, as beats per minute of
+/// the beat of a time signature. A tempo without a beat counts unit note
+/// lengths.
+fn parse_tempo(text: &str, unit: (u64, u64), denominator: u8) -> Option<f32> {
+  // Text in quotes names the tempo
+  let text: Vec<&str> = text.split('"').step_by(2).collect();
+  let text = text.join(" ");
+
+  let (beat, bpm) = match text.split_once('=') {
+    Some((beats, bpm)) => {
+      let beat = beats.split_whitespace().map(parse_fraction).try_fold(
+        (0u64, 1u64),
+        |(numerator, denominator), fraction| {
+          let (beat_numerator, beat_denominator) = fraction?;
+          Some((
+            numerator
+              .checked_mul(beat_denominator)?
+              .checked_add(beat_numerator.checked_mul(denominator)?)?,
+            denominator.checked_mul(beat_denominator)?,
+          ))
+        },
+      );
+      (beat?, bpm)
+    }
+    None => (unit, text.as_str()),
+  };
+  let bpm: f32 = bpm.trim().parse().ok()?;
+  Some(bpm * beat.0.checked_mul(denominator as u64)? as f32 / beat.1 as f32)
+}
+
+/// Parse a key, e.g. `D`, `Bb`, `F#m` or `A dorian`, as the major key with the
+/// same key signature.
+fn parse_key(text: &str) -> Option<Key> {
+  const NOTES_IN_OCTAVE_COUNT: i8 = 12;
+
+  let text = text.trim();
+  if text.starts_with("HP") || text.starts_with("Hp") {
+    return Some(Key::D);
+  }
+  let (tonic, rest) = parse_root(text)?;
+  let mode: String = rest
+    .trim_start()
+    .chars()
+    .take_while(|c| c.is_ascii_alphabetic())
+    .take(3)
+    .collect::<String>()
+    .to_lowercase();
+  let major_offset = match mode.as_str() {
+    "" | "maj" | "ion" => 0,
+    "m" | "min" | "aeo" => 3,
+    "mix" => 5,
+    "dor" => 10,
+    "phr" => 8,
+    "lyd" => 7,
+    "loc" => 1,
+    _ => return None,
+  };
+  let index = notes::key_to_index(tonic) as i8 + major_offset;
+  Some(notes::index_to_key(index.rem_euclid(NOTES_IN_OCTAVE_COUNT)))
+}
+
+/// Parse the root note of a chord symbol or key, returning it and the text
+/// after it.
+fn parse_root(text: &str) -> Option<(Key, &str)> {
+  const NOTES_IN_OCTAVE_COUNT: i8 = 12;
+
+  let letter = text.chars().next()?;
+  let index: i8 = match letter {
+    'C' => 0,
+    'D' => 2,
+    'E' => 4,
+    'F' => 5,
+    'G' => 7,
+    'A' => 9,
+    'B' => 11,
+    _ => return None,
+  };
+  let rest = &text[1..];
+  let (alter, rest) = match rest.chars().next() {
+    Some(accidental @ '#') | Some(accidental @ '♯') => (1, &rest[accidental.len_utf8()..]),
+    Some(accidental @ 'b') | Some(accidental @ '♭') => (-1, &rest[accidental.len_utf8()..]),
+    _ => (0, rest),
+  };
+  let key = notes::index_to_key((index + alter).rem_euclid(NOTES_IN_OCTAVE_COUNT));
+  Some((key, rest))
+}
+
+/// Parse a chord symbol, e.g. `Am7` or `G/B`, into its root, chord name and
+/// intervals. Chords written by their name in brackets, e.g. `C(DREAM)`, are
+/// internal chords. Chords without an internal chord are named by their
+/// suffix.
+fn parse_chord_symbol(text: &str) -> Option<(Key, String, Vec<i8>)> {
+  let (root, suffix) = parse_root(text)?;
+  let (name, intervals) = parse_suffix(suffix)?;
+  Some((root, name, intervals))
+}
+
+fn parse_suffix(suffix: &str) -> Option<(String, Vec<i8>)> {
+  let internal_name = suffix
+    .strip_prefix('(')
+    .and_then(|name| name.strip_suffix(')'));
+  if let Some(name) = internal_name {
+    let intervals = chords::string_to_chord(name);
+    if !intervals.is_empty() {
+      return Some((name.to_string(), intervals));
+    }
+  }
+
+  match chords::suffix_to_chord(suffix) {
+    Some((Some(name), intervals)) => Some((name.to_string(), intervals)),
+    Some((None, intervals)) => Some((suffix.to_string(), intervals)),
+    None => {
+      // Slash chords are read without their bass note
+      let (chord, bass) = suffix.rsplit_once('/')?;
+      match parse_root(bass) {
+        Some((_, "")) => parse_suffix(chord),
+        _ => None,
+      }
+    }
+  }
+}
+
+mod tests {
+
+  #[test]
+  fn test_abc_lengths() {
+    use crate::io::abc::*;
+
+    assert_eq!(to_abc_length(1920), "");
+    assert_eq!(to_abc_length(1920 * 6), "6");
+    assert_eq!(to_abc_length(960), "/");
+    assert_eq!(to_abc_length(480), "/4");
+    assert_eq!(to_abc_length(2880), "3/2");
+
+    let chars: Vec<char> = "3/2 // /4 2".chars().collect();
+    let mut i = 0;
+    let mut lengths = Vec::new();
+    while i < chars.len() {
+      lengths.push(read_length(&chars, &mut i).unwrap());
+      i += 1;
+    }
+    assert_eq!(lengths, vec![(3, 2), (1, 4), (1, 4), (2, 1)]);
+    let chars: Vec<char> = "/".repeat(64).chars().collect();
+    assert_eq!(read_length(&chars, &mut 0), Err(FailResult::ImportABC));
+
+    let mut accidentals = HashMap::new();
+    assert_eq!(to_abc_pitch(60, 0, &mut accidentals), "C");
+    assert_eq!(to_abc_pitch(72, 0, &mut accidentals), "c");
+    assert_eq!(to_abc_pitch(86, 0, &mut accidentals), "d'");
+    assert_eq!(to_abc_pitch(59, 0, &mut accidentals), "B,");
+    assert_eq!(to_abc_pitch(66, 0, &mut accidentals), "^F");
+    assert_eq!(to_abc_pitch(66, 0, &mut accidentals), "F");
+    assert_eq!(to_abc_pitch(65, 0, &mut accidentals), "=F");
+    assert_eq!(to_abc_pitch(70, -1, &mut HashMap::new()), "B");
+    assert_eq!(to_abc_pitch(71, -1, &mut HashMap::new()), "=B");
+  }
+
+  #[test]
+  fn test_abc_fields() {
+    use crate::io::abc::*;
+
+    assert_eq!(parse_meter("C|"), Some(TimeSignature::new(2, 2)));
+    assert_eq!(parse_meter("2+3/8"), Some(TimeSignature::new(5, 8)));
+    assert_eq!(parse_meter("none"), None);
+    assert_eq!(parse_meter("200+100/4"), None);
+    assert_eq!(parse_tempo("1/4=120", (1, 8), 4), Some(120.0));
+    assert_eq!(parse_tempo("\"Jig\" 3/8=60", (1, 8), 8), Some(180.0));
+    assert_eq!(parse_tempo("200", (1, 8), 4), Some(100.0));
+    assert_eq!(parse_key("Bb"), Some(Key::As));
+    assert_eq!(parse_key("Em"), Some(Key::G));
+    assert_eq!(parse_key("A dorian"), Some(Key::G));
+    assert_eq!(parse_key("F#mix"), Some(Key::B));
+    assert_eq!(
+      parse_chord_symbol("Bbm7"),
+      Some((
+        Key::As,
+        "MINOR_SEVENTH".to_string(),
+        chords::MINOR_SEVENTH.to_vec()
+      ))
+    );
+    assert_eq!(
+      parse_chord_symbol("G/B"),
+      Some((Key::G, "MAJOR".to_string(), chords::MAJOR.to_vec()))
+    );
+    assert_eq!(
+      parse_chord_symbol("C(DREAM)"),
+      Some((Key::C, "DREAM".to_string(), chords::DREAM.to_vec()))
+    );
+    assert_eq!(
+      parse_chord_symbol("Dsus2"),
+      Some((Key::D, "sus2".to_string(), vec![0, 2, 7]))
+    );
+    assert_eq!(parse_chord_symbol("H7"), None);
+  }
+
+  #[test]
+  fn test_abc_malformed_lengths() {
+    use crate::io::abc::*;
+
+    let tune = |header: &str, body: &str| format!("X:1\n{}K:C\n{}\n", header, body);
+    let malformed = [
+      tune("", &format!("\"C\"A{}", "/".repeat(64))),
+      tune("", "\"C\"Z99999999"),
+      tune("", "\"C\"X18446744073709551615"),
+      tune("M:200+100/4\n", "\"C\"A"),
+      tune("", &format!("\"C\"A{}B", ">".repeat(70))),
+      tune("", &format!("\"C\"A{}B", "<".repeat(70))),
+      tune("L:1/1\n", "\"C\"A99999999999999999"),
+      tune("", "\"C\"(2:9999999999999999 A"),
+      tune("", "\"C\"[C99999999999999999]99999999999999"),
+    ];
+    for abc in &malformed {
+      assert_eq!(
+        abc_to_patterns(abc).map(|patterns| patterns.len()),
+        Err(FailResult::ImportABC),
+        "{}",
+        abc
+      );
+    }
+  }
+
+  #[test]
+  fn test_abc_to_patterns() {
+    use crate::io::abc::*;
+    use music_timer::music_time::MusicTime;
+
+    let abc = "X:1\nT:The Kesh\nM:6/8\nL:1/8\nQ:3/8=100\nK:G\n|:\"G\"GAG GAB|\"D7\"ABA (3ABc d|\n\"Em\"e>d B \"C\"c2 !fermata!z|\"N.C.\"Z2|]\n\nX:2\nT:Reel\nK:Ador\n\"Am\"A8 [M:3/4]\"G\"[GBd]6|";
+    let patterns = abc_to_patterns(abc).unwrap();
+    assert_eq!(patterns.len(), 2);
+
+    let jig = &patterns[0];
+    assert_eq!(jig.get_name(), "The Kesh");
+    assert_eq!(jig.get_time_signature(), TimeSignature::new(6, 8));
+    assert_eq!(jig.get_bpm(), 300.0);
+
+    let times: Vec<(MusicTime, u8)> = jig
+      .get_chord_events()
+      .iter()
+      .map(|event| (*event.get_time(), event.get_offset()))
+      .collect();
+    assert_eq!(
+      times,
+      vec![
+        (MusicTime::new(1, 1, 1), 0),
+        (MusicTime::new(2, 1, 1), 0),
+        (MusicTime::new(3, 1, 1), 0),
+        (MusicTime::new(3, 4, 1), 0),
+        (MusicTime::new(4, 1, 1), 0),
+      ]
+    );
+
+    let symbol = jig.get_event(1).get_symbol().unwrap();
+    assert_eq!(symbol.get_name(), "DOMINANT_SEVENTH");
+    assert_eq!(symbol.get_key(), Key::G);
+    assert_eq!(symbol.get_transpose(), -5);
+    assert_eq!(jig.get_event(1).get_notes(), &vec![62, 66, 69, 72]);
+    assert_eq!(jig.get_event(2).get_notes(), &vec![64, 67, 71]);
+    assert_eq!(
+      jig.get_event(4).get_symbol().unwrap().get_name(),
+      chords::REST_NAME
+    );
+
+    let reel = &patterns[1];
+    assert_eq!(reel.get_name(), "Reel");
+    assert_eq!(reel.get_time_signature(), TimeSignature::new(4, 4));
+    assert_eq!(
+      reel.get_time_signature_changes(),
+      &vec![(2, TimeSignature::new(3, 4))]
+    );
+    assert_eq!(reel.get_event(0).get_symbol().unwrap().get_key(), Key::G);
+    assert_eq!(reel.get_event(1).get_time(), &MusicTime::new(2, 1, 1));
+
+    assert_eq!(
+      abc_to_patterns("K:C\n\"Cm7b9\"C8|").unwrap_err(),
+      FailResult::UnknownChordSymbol("Cm7b9".to_string())
+    );
+  }
+
+  #[test]
+  fn test_abc_round_trip() {
+    use crate::io::abc::*;
+    use crate::theory::composition::{ChordEvent, ChordSymbol, Composition, Pattern, TempoChange};
+    use music_timer::music_time::MusicTime;
+
+    let mut verse = Pattern::new("verse", 90.0, TimeSignature::new(3, 4));
+    let symbol = ChordSymbol::new(
+      "MINOR_SEVENTH",
+      Key::F,
+      2,
+      chords::MINOR_SEVENTH.to_vec(),
+      3,
+    );
+    verse
+      .push_chord_event(ChordEvent::new_with_symbol(
+        MusicTime::new(1, 2, 1),
+        0,
+        vec![67, 70, 74, 77],
+        symbol,
+      ))
+      .unwrap();
+    let rest = ChordSymbol::new(chords::REST_NAME, Key::F, 0, Vec::new(), 3);
+    verse
+      .push_chord_event(ChordEvent::new_with_symbol(
+        MusicTime::new(2, 3, 1),
+        0,
+        Vec::new(),
+        rest,
+      ))
+      .unwrap();
+    verse
+      .push_event(MusicTime::new(3, 1, 5), vec![60, 64, 67, 71])
+      .unwrap();
+    verse.push_tempo_change(MusicTime::new(3, 1, 1), TempoChange::Instant(100.0));
+    let composition = Composition::new_with_patterns("song", vec![verse]);
+
+    let abc = composition_to_abc(&composition);
+    assert!(abc.starts_with("%abc-2.1\n% song\n\nX:1\nT:verse\nM:3/4\nL:1/8\nQ:1/4=90\nK:F\n"));
+    assert!(abc.ends_with(
+      "z2 \"Gm7\"[GBdf]4- | [GBdf]4 \"N.C.\"z2 | [Q:1/4=100] z \"Cmaj7\"[CEG=B]4- [CEG=B] |]\n"
+    ));
+
+    let patterns = abc_to_patterns(&abc).unwrap();
+    let pattern = &patterns[0];
+    assert_eq!(pattern.get_name(), "verse");
+    assert_eq!(pattern.get_bpm(), 90.0);
+    assert_eq!(pattern.get_event(0).get_time(), &MusicTime::new(1, 2, 1));
+    assert_eq!(pattern.get_event(0).get_notes(), &vec![67, 70, 74, 77]);
+    let symbol = pattern.get_event(0).get_symbol().unwrap();
+    assert_eq!((symbol.get_key(), symbol.get_transpose()), (Key::F, 2));
+    assert_eq!(pattern.get_event(1).get_time(), &MusicTime::new(2, 3, 1));
+    assert!(pattern.get_event(1).get_notes().is_empty());
+    assert_eq!(
+      pattern.get_tempo_changes(),
+      &vec![(MusicTime::new(3, 1, 1), TempoChange::Instant(100.0))]
+    );
+  }
+}
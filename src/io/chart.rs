@@ -0,0 +1,224 @@
+use crate::{
+  composition,
+  io::notation::{self, Bar},
+};
+
+/// The cell of a beat without a new chord.
+const HELD_BEAT: &str = ".";
+
+/// Write a composition as a plain-text lead sheet chart.
+///
+/// Each pattern is a section headed by its name, key, time signature and
+/// tempo, followed by its bars. Bars have a cell for each beat of their time
+/// signature with the chord symbol starting on it, or `.` when the chord before
+/// is held. Chords starting within the same beat are joined by `~`, rests after
+/// a chord are `N.C.` and time signature changes are marked before their bar,
+/// e.g. `| Cmaj7 . . . | Am7 . D7 . | (3/4) G . . |`. Tempo changes are marked
+/// before the beat they reach their tempo on, e.g. `(Tempo: 100) G`.
+///
+/// # Arguments
+/// * `composition` - The composition to write.
+pub fn composition_to_chart(composition: &composition::Composition) -> String {
+  let mut chart = format!("{}\n", composition.get_name());
+
+  for pattern in composition.get_patterns() {
+    let key = notation::to_pattern_key(pattern);
+    let fifths = notation::key_to_fifths(key);
+    let (numerator, denominator) = pattern.get_time_signature().as_tuple();
+
+    chart.push_str(&format!("\n{}\n", pattern.get_name()));
+    chart.push_str(&format!(
+      "Key: {}  Time: {}/{}  Tempo: {}\n",
+      notation::to_note_name(key, fifths),
+      numerator,
+      denominator,
+      pattern.get_bpm()
+    ));
+
+    let bars = notation::pattern_to_bars(pattern);
+    let mut cells = bars_to_cells(&bars, fifths);
+    for (i, bar) in bars.iter().enumerate() {
+      for (j, (units, bpm)) in bar.get_tempos().iter().enumerate() {
+        // The pattern's tempo is written in the header
+        if i == 0 && j == 0 {
+          continue;
+        }
+        let beat = (*units / bar.get_beat_length()) as usize;
+        let beat = beat.min(cells[i].len() - 1);
+        cells[i][beat] = format!("(Tempo: {}) {}", bpm, cells[i][beat]);
+      }
+    }
+
+    let bars: Vec<String> = bars
+      .iter()
+      .zip(cells)
+      .enumerate()
+      .map(|(i, (bar, cells))| {
+        let is_new_signature =
+          i > 0 && bars[i - 1].get_time_signature() != bar.get_time_signature();
+        if is_new_signature {
+          let (numerator, denominator) = bar.get_time_signature().as_tuple();
+          format!("({}/{}) {}", numerator, denominator, cells.join(" "))
+        } else {
+          cells.join(" ")
+        }
+      })
+      .collect();
+    for line in bars.chunks(notation::BARS_PER_LINE) {
+      chart.push_str(&format!("| {} |\n", line.join(" | ")));
+    }
+  }
+  chart
+}
+
+/// Write a composition as a ChordPro song, each pattern a grid section of the
+/// same bars as the plain-text chart.
+///
+/// The song's title is the composition's name. Each grid is labelled with the
+/// pattern's name and preceded by its key, time signature and tempo, and time
+/// signature changes start a new grid.
+///
+/// # Arguments
+/// * `composition` - The composition to write.
+pub fn composition_to_chordpro(composition: &composition::Composition) -> String {
+  let mut chordpro = format!("{{title: {}}}\n", composition.get_name());
+
+  for pattern in composition.get_patterns() {
+    let key = notation::to_pattern_key(pattern);
+    let fifths = notation::key_to_fifths(key);
+    let bars = notation::pattern_to_bars(pattern);
+    let cells = bars_to_cells(&bars, fifths);
+
+    chordpro.push_str(&format!(
+      "\n{{key: {}}}\n{{tempo: {}}}\n",
+      notation::to_note_name(key, fifths),
+      pattern.get_bpm()
+    ));
+
+    // Bars are gridded in runs of the same time signature
+    let mut start = 0;
+    while start < bars.len() {
+      let signature = bars[start].get_time_signature();
+      let end = bars[start..]
+        .iter()
+        .position(|bar| bar.get_time_signature() != signature)
+        .map_or(bars.len(), |length| start + length);
+
+      let (numerator, denominator) = signature.as_tuple();
+      chordpro.push_str(&format!("{{time: {}/{}}}\n", numerator, denominator));
+      chordpro.push_str(&format!(
+        "{{start_of_grid: label=\"{}\"}}\n",
+        pattern.get_name().replace('"', "'")
+      ));
+      let run: Vec<String> = cells[start..end]
+        .iter()
+        .map(|cells| cells.join(" "))
+        .collect();
+      for line in run.chunks(notation::BARS_PER_LINE) {
+        chordpro.push_str(&format!("| {} |\n", line.join(" | ")));
+      }
+      chordpro.push_str("{end_of_grid}\n");
+      start = end;
+    }
+  }
+  chordpro
+}
+
+/// Get the cells of each bar, a chord symbol or `.` for each beat.
+fn bars_to_cells(bars: &[Bar], fifths: i8) -> Vec<Vec<String>> {
+  let mut is_chord_before = false;
+  bars
+    .iter()
+    .map(|bar| {
+      let beat_count = bar.get_time_signature().get_numerator() as usize;
+      let mut cells: Vec<Vec<String>> = vec![Vec::new(); beat_count];
+
+      for chord in bar.get_chords() {
+        let symbol = match chord.get_label() {
+          Some(label) => Some(label.to_symbol(fifths)),
+          None if chord.get_notes().is_empty() && is_chord_before => {
+            Some(notation::NO_CHORD.to_string())
+          }
+          None => None,
+        };
+        if let Some(symbol) = symbol {
+          let beat = (chord.get_start() / bar.get_beat_length()) as usize;
+          cells[beat.min(beat_count - 1)].push(symbol);
+        }
+        is_chord_before = !chord.get_notes().is_empty();
+      }
+
+      cells
+        .into_iter()
+        .map(|symbols| match symbols.len() {
+          0 => HELD_BEAT.to_string(),
+          _ => symbols.join("~"),
+        })
+        .collect()
+    })
+    .collect()
+}
+
+mod tests {
+
+  #[test]
+  fn test_composition_chart() {
+    use crate::io::chart::*;
+    use crate::theory::{
+      chords,
+      composition::{ChordEvent, ChordSymbol, Composition, Pattern, TempoChange},
+      notes::Key,
+    };
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut verse = Pattern::new("verse", 90.0, TimeSignature::new(4, 4));
+    let chord = |name: &str, transpose: i8, intervals: &[i8]| {
+      ChordSymbol::new(name, Key::F, transpose, intervals.to_vec(), 3)
+    };
+    verse
+      .push_chord_event(ChordEvent::new_with_symbol(
+        MusicTime::new(1, 1, 1),
+        0,
+        vec![52, 55, 59, 62],
+        chord("MAJOR_SEVENTH", -5, &chords::MAJOR_SEVENTH),
+      ))
+      .unwrap();
+    verse
+      .push_chord_event(ChordEvent::new_with_symbol(
+        MusicTime::new(2, 1, 1),
+        0,
+        vec![62, 65, 69, 72],
+        chord("MINOR_SEVENTH", -3, &chords::MINOR_SEVENTH),
+      ))
+      .unwrap();
+    verse
+      .push_event(MusicTime::new(2, 3, 1), vec![62, 66, 69, 72])
+      .unwrap();
+    verse
+      .push_event(MusicTime::new(2, 3, 5), vec![67, 71, 74])
+      .unwrap();
+    verse
+      .push_chord_event(ChordEvent::new_with_symbol(
+        MusicTime::new(3, 2, 1),
+        0,
+        Vec::new(),
+        chord(chords::REST_NAME, 0, &[]),
+      ))
+      .unwrap();
+    verse.push_time_signature_change(3, TimeSignature::new(3, 4));
+    verse.push_tempo_change(MusicTime::new(2, 3, 1), TempoChange::Instant(100.0));
+    let composition = Composition::new_with_patterns("song", vec![verse]);
+
+    let chart = composition_to_chart(&composition);
+    assert_eq!(
+      chart,
+      "song\n\nverse\nKey: F  Time: 4/4  Tempo: 90\n| Cmaj7 . . . | Dm7 . (Tempo: 100) D7~G . | (3/4) . N.C. . |\n"
+    );
+
+    let chordpro = composition_to_chordpro(&composition);
+    assert_eq!(
+      chordpro,
+      "{title: song}\n\n{key: F}\n{tempo: 90}\n{time: 4/4}\n{start_of_grid: label=\"verse\"}\n| Cmaj7 . . . | Dm7 . D7~G . |\n{end_of_grid}\n{time: 3/4}\n{start_of_grid: label=\"verse\"}\n| . N.C. . |\n{end_of_grid}\n"
+    );
+  }
+}
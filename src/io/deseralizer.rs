@@ -1,7 +1,79 @@
 #![allow(dead_code)]
 
 pub type CustomChords = Vec<(String, Vec<i8>)>;
-pub type PatternObject = (u16, u8, u8, String, i8);
+pub type PatternObject = (u16, u8, BeatIntervalObject, String, i8);
+pub type SignatureChangeObject = (u16, (u8, u8));
+/// A lane of a drum grid, `[drum name, grid]`.
+pub type DrumGridObject = (String, String);
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum TimeChangeObject {
+  Instant(u16, u8, u8, f32),
+  Ramp(u16, u8, u8, f32, (u16, u8, u8)),
+}
+
+/// A hit of a rhythm template, `[beat, beat interval]` sounding until the next
+/// hit or `[beat, beat interval, length in beat intervals]`.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum RhythmHitObject {
+  Hit(u8, u8),
+  HitWithLength(u8, u8, u8),
+}
+
+impl RhythmHitObject {
+  pub fn to_hit(&self) -> (u8, u8, Option<u8>) {
+    match self {
+      RhythmHitObject::Hit(beat, beat_interval) => (*beat, *beat_interval, None),
+      RhythmHitObject::HitWithLength(beat, beat_interval, length) => {
+        (*beat, *beat_interval, Some(*length))
+      }
+    }
+  }
+}
+
+/// The beat interval of a pattern event, either a beat interval on the grid
+/// or a tuplet `"k/n"`, the k-th of n equal divisions of the beat. Only
+/// tuplets whose n divides the ticks of a beat fall on the grid.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum BeatIntervalObject {
+  Interval(u8),
+  Tuplet(String),
+}
+
+impl BeatIntervalObject {
+  /// Get the beat interval and the offset in ticks after it. Returns `None` if
+  /// the tuplet is malformed or its divisions don't divide the ticks of a beat.
+  pub fn to_beat_interval(&self) -> Option<(u8, u8)> {
+    const BEAT_INTERVALS: u32 = 8;
+    let interval_ticks = crate::composition::BEAT_INTERVAL_TICKS as u32;
+    let beat_ticks = interval_ticks * BEAT_INTERVALS;
+
+    match self {
+      BeatIntervalObject::Interval(interval) => Some((*interval, 0)),
+      BeatIntervalObject::Tuplet(tuplet) => {
+        let mut parts = tuplet.split('/').map(|part| part.trim().parse::<u32>());
+        match (parts.next(), parts.next(), parts.next()) {
+          (Some(Ok(division)), Some(Ok(divisions)), None)
+            if divisions > 0
+              && beat_ticks % divisions == 0
+              && division > 0
+              && division <= divisions =>
+          {
+            let ticks = (division - 1) * (beat_ticks / divisions);
+            Some((
+              (ticks / interval_ticks + 1) as u8,
+              (ticks % interval_ticks) as u8,
+            ))
+          }
+          _ => None,
+        }
+      }
+    }
+  }
+}
 
 pub fn deserialize_file(file_name: &str) -> Result<CompositionParameters, crate::FailResult> {
   match std::fs::read_to_string(file_name) {
@@ -17,11 +89,28 @@ pub fn deserialize_string(string: &str) -> Result<CompositionParameters, crate::
   }
 }
 
+/// `None` for an empty list, so it is left out of the composition.
+fn to_option<T>(list: Vec<T>) -> Option<Vec<T>> {
+  if list.is_empty() {
+    None
+  } else {
+    Some(list)
+  }
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct CompositionParameters {
+  #[serde(skip_serializing_if = "Option::is_none")]
   name: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   master: Option<MasterParameters>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   chords: Option<CustomChords>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  grooves: Option<Vec<GrooveParameters>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  rhythms: Option<Vec<RhythmParameters>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   patterns: Option<Vec<PatternParameters>>,
 }
 
@@ -31,10 +120,31 @@ impl CompositionParameters {
       name: None,
       master: None,
       chords: None,
+      grooves: None,
+      rhythms: None,
       patterns: None,
     }
   }
 
+  /// Create composition parameters, leaving out empty sections.
+  pub fn new_with_patterns(
+    name: &str,
+    master: MasterParameters,
+    chords: CustomChords,
+    grooves: Vec<GrooveParameters>,
+    rhythms: Vec<RhythmParameters>,
+    patterns: Vec<PatternParameters>,
+  ) -> Self {
+    Self {
+      name: Some(name.to_string()),
+      master: Some(master),
+      chords: to_option(chords),
+      grooves: to_option(grooves),
+      rhythms: to_option(rhythms),
+      patterns: Some(patterns),
+    }
+  }
+
   pub fn get_name(&self) -> String {
     match &self.name {
       Some(name) => name.clone(),
@@ -50,25 +160,132 @@ impl CompositionParameters {
     &self.chords
   }
 
+  pub fn get_grooves(&self) -> &Option<Vec<GrooveParameters>> {
+    &self.grooves
+  }
+
+  pub fn get_rhythms(&self) -> &Option<Vec<RhythmParameters>> {
+    &self.rhythms
+  }
+
   pub fn get_patterns(&self) -> &Option<Vec<PatternParameters>> {
     &self.patterns
   }
 }
 
+/// A rhythm template shared by the patterns of a composition.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct RhythmParameters {
+  name: String,
+  beats: u8,
+  hits: Vec<RhythmHitObject>,
+}
+
+impl RhythmParameters {
+  pub fn new(name: &str, beats: u8, hits: Vec<RhythmHitObject>) -> Self {
+    Self {
+      name: name.to_string(),
+      beats,
+      hits,
+    }
+  }
+
+  pub fn get_name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn get_beats(&self) -> u8 {
+    self.beats
+  }
+
+  pub fn get_hits(&self) -> &Vec<RhythmHitObject> {
+    &self.hits
+  }
+}
+
+/// A groove template shared by the patterns of a composition.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct GrooveParameters {
+  name: String,
+  subdivision: u8,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  timing: Option<Vec<i16>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  velocity: Option<Vec<i8>>,
+}
+
+impl GrooveParameters {
+  pub fn new(name: &str, subdivision: u8, timing: Vec<i16>, velocity: Vec<i8>) -> Self {
+    Self {
+      name: name.to_string(),
+      subdivision,
+      timing: to_option(timing),
+      velocity: to_option(velocity),
+    }
+  }
+
+  pub fn get_name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn get_subdivision(&self) -> u8 {
+    self.subdivision
+  }
+
+  pub fn get_timing(&self) -> Vec<i16> {
+    self.timing.clone().unwrap_or_default()
+  }
+
+  pub fn get_velocity(&self) -> Vec<i8> {
+    self.velocity.clone().unwrap_or_default()
+  }
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub struct MasterParameters {
+  #[serde(skip_serializing_if = "Option::is_none")]
   key: Option<String>,
-  time: Option<u8>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  time: Option<f32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   signature: Option<(u8, u8)>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  swing: Option<(u8, f32)>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  groove: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  humanize: Option<HumanizeParameters>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  rhythm: Option<String>,
 }
 
 impl MasterParameters {
   const DEFAULT_KEY: &'static str = "C";
-  const DEFAULT_TIME: u8 = 120;
-  const DEFAULT_SIGNATURE: (u8, u8) = (4, 4);
+  pub(crate) const DEFAULT_TIME: f32 = 120.0;
+  pub(crate) const DEFAULT_SIGNATURE: (u8, u8) = (4, 4);
   const DEFAULT_METRONOME: bool = true;
   const DEFAULT_VERBOSE: u8 = 4;
 
+  pub fn new(
+    key: Option<String>,
+    time: Option<f32>,
+    signature: Option<(u8, u8)>,
+    swing: Option<(u8, f32)>,
+    groove: Option<String>,
+    humanize: Option<HumanizeParameters>,
+    rhythm: Option<String>,
+  ) -> Self {
+    Self {
+      key,
+      time,
+      signature,
+      swing,
+      groove,
+      humanize,
+      rhythm,
+    }
+  }
+
   pub fn from_overrides(defaults: &MasterParameters, overrides: &MasterParameters) -> Self {
     let key = Some(match &overrides.key {
       Some(key) => key.clone(),
@@ -85,10 +302,19 @@ impl MasterParameters {
       None => defaults.get_signature_or_default(),
     });
 
+    let swing = overrides.swing.or(defaults.swing);
+    let groove = overrides.groove.clone().or_else(|| defaults.groove.clone());
+    let humanize = overrides.humanize.or(defaults.humanize);
+    let rhythm = overrides.rhythm.clone().or_else(|| defaults.rhythm.clone());
+
     Self {
       key,
       time,
       signature,
+      swing,
+      groove,
+      humanize,
+      rhythm,
     }
   }
 
@@ -104,13 +330,13 @@ impl MasterParameters {
       None => MasterParameters::DEFAULT_KEY.to_string(),
     }
   }
-  pub fn get_time(&self) -> Option<u8> {
+  pub fn get_time(&self) -> Option<f32> {
     match self.time {
       Some(time) => Some(time),
       None => None,
     }
   }
-  pub fn get_time_or_default(&self) -> u8 {
+  pub fn get_time_or_default(&self) -> f32 {
     match self.time {
       Some(time) => time,
       None => MasterParameters::DEFAULT_TIME,
@@ -128,7 +354,23 @@ impl MasterParameters {
       None => MasterParameters::DEFAULT_SIGNATURE,
     }
   }
-  pub fn get_all_or_defaults(&self) -> (String, u8, (u8, u8)) {
+  /// Get the swung note value and the share of each pair of notes taken by the
+  /// first note. No swing plays straight.
+  pub fn get_swing(&self) -> Option<(u8, f32)> {
+    self.swing
+  }
+  /// Get the name of the groove template.
+  pub fn get_groove(&self) -> Option<String> {
+    self.groove.clone()
+  }
+  pub fn get_humanize(&self) -> Option<HumanizeParameters> {
+    self.humanize
+  }
+  /// Get the name of the rhythm template.
+  pub fn get_rhythm(&self) -> Option<String> {
+    self.rhythm.clone()
+  }
+  pub fn get_all_or_defaults(&self) -> (String, f32, (u8, u8)) {
     (
       self.get_key_or_default(),
       self.get_time_or_default(),
@@ -142,17 +384,106 @@ impl Default for MasterParameters {
       key: Some(MasterParameters::DEFAULT_KEY.to_string()),
       time: Some(MasterParameters::DEFAULT_TIME),
       signature: Some(MasterParameters::DEFAULT_SIGNATURE),
+      swing: None,
+      groove: None,
+      humanize: None,
+      rhythm: None,
     }
   }
 }
+
+/// Random offsets added to a pattern when it is exported or played. Without a
+/// seed a new seed is chosen each time the composition is loaded.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+pub struct HumanizeParameters {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  timing: Option<u8>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  spread: Option<u8>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  velocity: Option<u8>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  seed: Option<u64>,
+}
+
+impl HumanizeParameters {
+  pub fn new(timing: u8, spread: u8, velocity: u8, seed: Option<u64>) -> Self {
+    Self {
+      timing: Some(timing),
+      spread: Some(spread),
+      velocity: Some(velocity),
+      seed,
+    }
+  }
+
+  pub fn get_timing_or_default(&self) -> u8 {
+    self.timing.unwrap_or(0)
+  }
+  pub fn get_spread_or_default(&self) -> u8 {
+    self.spread.unwrap_or(0)
+  }
+  pub fn get_velocity_or_default(&self) -> u8 {
+    self.velocity.unwrap_or(0)
+  }
+  pub fn get_seed(&self) -> Option<u64> {
+    self.seed
+  }
+}
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct PatternParameters {
+  #[serde(skip_serializing_if = "Option::is_none")]
   name: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   master: Option<MasterParameters>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  time_changes: Option<Vec<TimeChangeObject>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  signature_changes: Option<Vec<SignatureChangeObject>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  bars: Option<u16>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  channel: Option<u8>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  arpeggio: Option<ArpeggioParameters>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  bass: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  melody: Option<MelodyParameters>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  drums: Option<DrumParameters>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   pattern: Option<Vec<PatternObject>>,
 }
 
 impl PatternParameters {
+  #[allow(clippy::too_many_arguments)]
+  pub fn new(
+    name: &str,
+    master: Option<MasterParameters>,
+    time_changes: Vec<TimeChangeObject>,
+    signature_changes: Vec<SignatureChangeObject>,
+    channel: Option<u8>,
+    arpeggio: Option<ArpeggioParameters>,
+    bass: Option<String>,
+    melody: Option<MelodyParameters>,
+    drums: Option<DrumParameters>,
+    pattern: Vec<PatternObject>,
+  ) -> Self {
+    Self {
+      name: Some(name.to_string()),
+      master,
+      time_changes: to_option(time_changes),
+      signature_changes: to_option(signature_changes),
+      bars: None,
+      channel,
+      arpeggio,
+      bass,
+      melody,
+      drums,
+      pattern: Some(pattern),
+    }
+  }
+
   pub fn get_name(&self) -> &Option<String> {
     &self.name
   }
@@ -161,9 +492,193 @@ impl PatternParameters {
     &self.pattern
   }
 
+  pub fn get_time_changes(&self) -> &Option<Vec<TimeChangeObject>> {
+    &self.time_changes
+  }
+
+  pub fn get_signature_changes(&self) -> &Option<Vec<SignatureChangeObject>> {
+    &self.signature_changes
+  }
+
   pub fn get_master(&self) -> &Option<MasterParameters> {
     &self.master
   }
+
+  pub fn get_bars(&self) -> Option<u16> {
+    self.bars
+  }
+
+  /// Get the midi channel of the pattern's chords, from 0 to 15.
+  pub fn get_channel(&self) -> Option<u8> {
+    self.channel
+  }
+
+  pub fn get_arpeggio(&self) -> &Option<ArpeggioParameters> {
+    &self.arpeggio
+  }
+
+  /// Get the style of the bass line generated from the pattern.
+  pub fn get_bass(&self) -> &Option<String> {
+    &self.bass
+  }
+
+  pub fn get_melody(&self) -> &Option<MelodyParameters> {
+    &self.melody
+  }
+
+  pub fn get_drums(&self) -> &Option<DrumParameters> {
+    &self.drums
+  }
+}
+
+/// The drums played under a pattern, a named style and or a grid of lanes.
+/// Grid lanes replace the lane of the same drum in the style.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct DrumParameters {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  style: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  subdivision: Option<u8>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  grid: Option<Vec<DrumGridObject>>,
+}
+
+impl DrumParameters {
+  const DEFAULT_SUBDIVISION: u8 = 16;
+
+  pub fn new(style: Option<String>, subdivision: u8, grid: Vec<DrumGridObject>) -> Self {
+    Self {
+      style,
+      subdivision: Some(subdivision),
+      grid: to_option(grid),
+    }
+  }
+
+  pub fn get_style(&self) -> &Option<String> {
+    &self.style
+  }
+  pub fn get_subdivision(&self) -> &Option<u8> {
+    &self.subdivision
+  }
+  pub fn get_subdivision_or_default(&self) -> u8 {
+    self
+      .subdivision
+      .unwrap_or(DrumParameters::DEFAULT_SUBDIVISION)
+  }
+  pub fn get_grid(&self) -> &Option<Vec<DrumGridObject>> {
+    &self.grid
+  }
+}
+
+/// The melody generated over a pattern, in the key of the pattern. Without a
+/// seed a new seed is chosen each time the composition is loaded.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct MelodyParameters {
+  scale: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  rate: Option<u8>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  density: Option<f32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  range: Option<(u8, u8)>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  contour: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  seed: Option<u64>,
+}
+
+impl MelodyParameters {
+  const DEFAULT_RATE: u8 = 8;
+  const DEFAULT_DENSITY: f32 = 0.5;
+  const DEFAULT_RANGE: (u8, u8) = (60, 79);
+  const DEFAULT_CONTOUR: &'static str = "arch";
+
+  pub fn new(
+    scale: &str,
+    rate: u8,
+    density: f32,
+    range: (u8, u8),
+    contour: &str,
+    seed: Option<u64>,
+  ) -> Self {
+    Self {
+      scale: scale.to_string(),
+      rate: Some(rate),
+      density: Some(density),
+      range: Some(range),
+      contour: Some(contour.to_string()),
+      seed,
+    }
+  }
+
+  pub fn get_scale(&self) -> &str {
+    &self.scale
+  }
+  pub fn get_rate_or_default(&self) -> u8 {
+    self.rate.unwrap_or(MelodyParameters::DEFAULT_RATE)
+  }
+  pub fn get_density_or_default(&self) -> f32 {
+    self.density.unwrap_or(MelodyParameters::DEFAULT_DENSITY)
+  }
+  pub fn get_range_or_default(&self) -> (u8, u8) {
+    self.range.unwrap_or(MelodyParameters::DEFAULT_RANGE)
+  }
+  pub fn get_contour_or_default(&self) -> &str {
+    self
+      .contour
+      .as_deref()
+      .unwrap_or(MelodyParameters::DEFAULT_CONTOUR)
+  }
+  pub fn get_seed(&self) -> Option<u64> {
+    self.seed
+  }
+}
+
+/// The arpeggiator of a pattern. Without a seed a new seed is chosen each time
+/// the composition is loaded.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct ArpeggioParameters {
+  order: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  rate: Option<u8>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  octaves: Option<u8>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  gate: Option<f32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  seed: Option<u64>,
+}
+
+impl ArpeggioParameters {
+  const DEFAULT_RATE: u8 = 16;
+  const DEFAULT_OCTAVES: u8 = 1;
+  const DEFAULT_GATE: f32 = 1.0;
+
+  pub fn new(order: &str, rate: u8, octaves: u8, gate: f32, seed: Option<u64>) -> Self {
+    Self {
+      order: order.to_string(),
+      rate: Some(rate),
+      octaves: Some(octaves),
+      gate: Some(gate),
+      seed,
+    }
+  }
+
+  pub fn get_order(&self) -> &str {
+    &self.order
+  }
+  pub fn get_rate_or_default(&self) -> u8 {
+    self.rate.unwrap_or(ArpeggioParameters::DEFAULT_RATE)
+  }
+  pub fn get_octaves_or_default(&self) -> u8 {
+    self.octaves.unwrap_or(ArpeggioParameters::DEFAULT_OCTAVES)
+  }
+  pub fn get_gate_or_default(&self) -> f32 {
+    self.gate.unwrap_or(ArpeggioParameters::DEFAULT_GATE)
+  }
+  pub fn get_seed(&self) -> Option<u64> {
+    self.seed
+  }
 }
 
 mod tests {
@@ -225,7 +740,7 @@ mod tests {
     match params.get_master() {
       Some(master) => {
         assert_eq!(master.get_key_or_default(), "D");
-        assert_eq!(master.get_time_or_default(), 128);
+        assert_eq!(master.get_time_or_default(), 128.0);
         assert_eq!(master.get_signature_or_default(), (3, 4));
       }
       None => assert!(false),
@@ -254,7 +769,7 @@ mod tests {
         match pattern.get_master() {
           Some(master) => {
             assert_eq!(master.get_key(), Some("C#".to_string()));
-            assert_eq!(master.get_time(), Some(69));
+            assert_eq!(master.get_time(), Some(69.0));
             assert_eq!(master.get_signature(), Some((4, 8)));
           }
           None => assert!(false),
@@ -269,6 +784,228 @@ mod tests {
     };
   }
 
+  #[test]
+  fn test_yaml_import_changes() {
+    use crate::io::deseralizer::*;
+
+    let params = deserialize_string(
+      r#"
+        patterns:
+            - name: part_a
+              time_changes:
+                  - [2, 1, 1, 100]
+                  - [3, 1, 1, 140, [5, 1, 1]]
+              signature_changes:
+                  - [4, [3, 4]]
+              pattern:
+                  - [1,1,1, MAJOR_SEVENTH, 0]
+
+            - name: part_b
+              pattern:
+                  - [1,1,1, MAJOR_SEVENTH, 0]
+            "#,
+    )
+    .unwrap();
+
+    match params.get_patterns() {
+      Some(patterns) => {
+        assert_eq!(
+          patterns[0].get_time_changes(),
+          &Some(vec![
+            TimeChangeObject::Instant(2, 1, 1, 100.0),
+            TimeChangeObject::Ramp(3, 1, 1, 140.0, (5, 1, 1)),
+          ])
+        );
+        assert_eq!(
+          patterns[0].get_signature_changes(),
+          &Some(vec![(4, (3, 4))])
+        );
+        assert_eq!(patterns[1].get_time_changes(), &None);
+        assert_eq!(patterns[1].get_signature_changes(), &None);
+      }
+      None => assert!(false),
+    };
+  }
+
+  #[test]
+  fn test_yaml_import_tuplets() {
+    use crate::io::deseralizer::*;
+
+    let params = deserialize_string(
+      r#"
+        patterns:
+            - name: part_a
+              pattern:
+                  - [1,1,1, MAJOR_SEVENTH, 0]
+                  - [1,1,"2/3", MAJOR_SEVENTH, 0]
+                  - [1,1,3/3, MAJOR_SEVENTH, 0]
+                  - [1,2,"5/16", MAJOR_SEVENTH, 0]
+            "#,
+    )
+    .unwrap();
+
+    match params.get_patterns() {
+      Some(patterns) => match patterns[0].get_pattern() {
+        Some(events) => {
+          let intervals: Vec<Option<(u8, u8)>> = events
+            .iter()
+            .map(|event| event.2.to_beat_interval())
+            .collect();
+          assert_eq!(
+            intervals,
+            vec![Some((1, 0)), Some((3, 40)), Some((6, 20)), Some((3, 0))]
+          );
+        }
+        None => assert!(false),
+      },
+      None => assert!(false),
+    };
+
+    assert_eq!(
+      BeatIntervalObject::Tuplet("3/5".to_string()).to_beat_interval(),
+      Some((4, 12))
+    );
+    for tuplet in &[
+      "0/3", "4/3", "1/0", "1/481", "2/7", "3/11", "a/3", "1/2/3", "3",
+    ] {
+      assert_eq!(
+        BeatIntervalObject::Tuplet(tuplet.to_string()).to_beat_interval(),
+        None
+      );
+    }
+  }
+
+  #[test]
+  fn test_yaml_import_grooves() {
+    use crate::io::deseralizer::*;
+
+    let params = deserialize_string(
+      r#"
+        master:
+            swing: [8, 0.66]
+        grooves:
+            - name: push
+              subdivision: 16
+              timing: [0, -10, 0, 5]
+              velocity: [10, -5]
+            - name: flat
+              subdivision: 8
+        rhythms:
+            - name: charleston
+              beats: 4
+              hits:
+                  - [1, 1, 3]
+                  - [2, 5]
+        patterns:
+            - name: part_a
+              channel: 4
+              arpeggio:
+                  order: up_down
+                  octaves: 2
+              bass: walking
+              melody:
+                  scale: dorian
+                  range: [62, 81]
+                  seed: 5
+              drums:
+                  style: rock
+                  grid:
+                      - [kick, "x...x...x...x..."]
+                      - [ride, "x.x.x.x.x.x.x.x."]
+              master:
+                  groove: push
+                  humanize:
+                      timing: 10
+                      velocity: 6
+                      seed: 42
+              pattern:
+                  - [1,1,1, MAJOR_SEVENTH, 0]
+            "#,
+    )
+    .unwrap();
+
+    match params.get_master() {
+      Some(master) => {
+        assert_eq!(master.get_swing(), Some((8, 0.66)));
+        assert_eq!(master.get_groove(), None);
+      }
+      None => assert!(false),
+    };
+
+    match params.get_rhythms() {
+      Some(rhythms) => {
+        assert_eq!(rhythms[0].get_name(), "charleston");
+        assert_eq!(rhythms[0].get_beats(), 4);
+        assert_eq!(
+          rhythms[0].get_hits(),
+          &vec![
+            RhythmHitObject::HitWithLength(1, 1, 3),
+            RhythmHitObject::Hit(2, 5)
+          ]
+        );
+        assert_eq!(rhythms[0].get_hits()[1].to_hit(), (2, 5, None));
+      }
+      None => assert!(false),
+    };
+
+    match params.get_grooves() {
+      Some(grooves) => {
+        assert_eq!(grooves[0].get_name(), "push");
+        assert_eq!(grooves[0].get_subdivision(), 16);
+        assert_eq!(grooves[0].get_timing(), vec![0, -10, 0, 5]);
+        assert_eq!(grooves[0].get_velocity(), vec![10, -5]);
+        assert_eq!(grooves[1].get_timing(), Vec::<i16>::new());
+        assert_eq!(grooves[1].get_velocity(), Vec::<i8>::new());
+      }
+      None => assert!(false),
+    };
+
+    match params.get_patterns() {
+      Some(patterns) => match patterns[0].get_arpeggio() {
+        Some(arpeggio) => {
+          assert_eq!(arpeggio.get_order(), "up_down");
+          assert_eq!(arpeggio.get_rate_or_default(), 16);
+          assert_eq!(arpeggio.get_octaves_or_default(), 2);
+          assert_eq!(arpeggio.get_gate_or_default(), 1.0);
+          assert_eq!(arpeggio.get_seed(), None);
+          assert_eq!(patterns[0].get_channel(), Some(4));
+          assert_eq!(patterns[0].get_bass(), &Some("walking".to_string()));
+          let melody = patterns[0].get_melody().as_ref().unwrap();
+          assert_eq!(melody.get_scale(), "dorian");
+          assert_eq!(melody.get_rate_or_default(), 8);
+          assert_eq!(melody.get_density_or_default(), 0.5);
+          assert_eq!(melody.get_range_or_default(), (62, 81));
+          assert_eq!(melody.get_contour_or_default(), "arch");
+          assert_eq!(melody.get_seed(), Some(5));
+          let drums = patterns[0].get_drums().as_ref().unwrap();
+          assert_eq!(drums.get_style(), &Some("rock".to_string()));
+          assert_eq!(drums.get_subdivision_or_default(), 16);
+          assert_eq!(
+            drums.get_grid().as_ref().unwrap()[1],
+            ("ride".to_string(), "x.x.x.x.x.x.x.x.".to_string())
+          );
+        }
+        None => assert!(false),
+      },
+      None => assert!(false),
+    };
+
+    match params.get_patterns() {
+      Some(patterns) => match patterns[0].get_master() {
+        Some(master) => {
+          assert_eq!(master.get_groove(), Some("push".to_string()));
+          let humanize = master.get_humanize().unwrap();
+          assert_eq!(humanize.get_timing_or_default(), 10);
+          assert_eq!(humanize.get_spread_or_default(), 0);
+          assert_eq!(humanize.get_velocity_or_default(), 6);
+          assert_eq!(humanize.get_seed(), Some(42));
+        }
+        None => assert!(false),
+      },
+      None => assert!(false),
+    };
+  }
+
   #[test]
   fn test_yaml_import_missing() {
     use crate::io::deseralizer::*;
@@ -309,7 +1046,7 @@ mod tests {
     let defaults = MasterParameters::default();
 
     const DEFAULT_KEY: &'static str = "C";
-    const DEFAULT_TIME: u8 = 120;
+    const DEFAULT_TIME: f32 = 120.0;
     const DEFAULT_SIGNATURE: (u8, u8) = (4, 4);
 
     assert_eq!(defaults.get_key_or_default(), DEFAULT_KEY);
@@ -328,18 +1065,26 @@ mod tests {
 
     let defaults = MasterParameters {
       key: None,
-      time: Some(130),
+      time: Some(130.0),
       signature: Some((4, 4)),
+      swing: Some((8, 0.6)),
+      groove: None,
+      humanize: None,
+      rhythm: Some("skank".to_string()),
     };
 
     assert_eq!(defaults.get_key_or_default(), "C");
-    assert_eq!(defaults.get_time_or_default(), 130);
+    assert_eq!(defaults.get_time_or_default(), 130.0);
     assert_eq!(defaults.get_signature_or_default(), (4, 4));
 
     let overrides = MasterParameters {
       key: Some("E".to_string()),
       time: None,
       signature: Some((3, 4)),
+      swing: None,
+      groove: Some("push".to_string()),
+      humanize: None,
+      rhythm: None,
     };
 
     assert_eq!(overrides.get_key(), Some("E".to_string()));
@@ -348,7 +1093,10 @@ mod tests {
 
     let master = MasterParameters::from_overrides(&defaults, &overrides);
     assert_eq!(master.get_key_or_default(), "E");
-    assert_eq!(master.get_time_or_default(), 130);
+    assert_eq!(master.get_time_or_default(), 130.0);
     assert_eq!(master.get_signature_or_default(), (3, 4));
+    assert_eq!(master.get_swing(), Some((8, 0.6)));
+    assert_eq!(master.get_groove(), Some("push".to_string()));
+    assert_eq!(master.get_rhythm(), Some("skank".to_string()));
   }
 }
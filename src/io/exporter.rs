@@ -1,84 +1,722 @@
-use crate::composition;
-use ghakuf::{
-  messages::{Message, MetaEvent, MidiEvent},
-  writer::Writer,
-};
-use music_timer::music_time;
-use std::path;
+use crate::{composition, theory::drums};
+use ghakuf::messages::{Message, MetaEvent, MidiEvent};
+use music_timer::{music_time, time_signature};
+use std::{io, path};
 
 pub fn export_composition(
   composition: &composition::Composition,
   parent_directory: &str,
+  options: &crate::MidiExportOptions,
 ) -> Result<crate::SuccessResult, crate::FailResult> {
+  options.check()?;
+
   let mut pattern_midi = Vec::with_capacity(composition.len());
   for pattern in composition.get_patterns() {
-    let pattern_messages = {
-      let pattern_name = pattern.get_name().to_string();
-      let mut messages = pattern_to_midi_meta(pattern);
-      messages.append(&mut pattern_to_midi_messages(pattern));
-      (pattern_name, messages)
-    };
-
-    pattern_midi.push(pattern_messages);
+    let pattern_name = pattern.get_name().to_string();
+    pattern_midi.push((pattern_name, pattern_to_file_messages(pattern, options)?));
   }
 
   let name = composition.get_name();
-  export_midi_patterns(name, &pattern_midi, parent_directory)
+  export_midi_patterns(name, &pattern_midi, parent_directory, options)
+}
+
+/// Write a pattern as a midi file to a writer, with the same tracks as the
+/// pattern's file in a composition export.
+pub fn write_pattern<W: io::Write>(
+  pattern: &composition::Pattern,
+  writer: &mut W,
+  options: &crate::MidiExportOptions,
+) -> Result<(), crate::FailResult> {
+  options.check()?;
+
+  let (messages, drum_track) = pattern_to_file_messages(pattern, options)?;
+  match write_midi_messages(writer, &messages, drum_track, options) {
+    Ok(()) => Ok(()),
+    Err(error) => Err(crate::FailResult::WriteMIDI(error.to_string())),
+  }
 }
 
+/// Write a composition as a single midi file to a writer, with the same tracks
+/// as a single file export.
+pub fn write_composition<W: io::Write>(
+  composition: &composition::Composition,
+  writer: &mut W,
+  options: &crate::MidiExportOptions,
+) -> Result<(), crate::FailResult> {
+  options.check()?;
+
+  let (messages, drum_track) = composition_to_midi_messages(composition, options.get_channel());
+  match write_midi_messages(writer, &messages, drum_track, options) {
+    Ok(()) => Ok(()),
+    Err(error) => Err(crate::FailResult::WriteMIDI(error.to_string())),
+  }
+}
+
+/// The messages of a midi file's tracks separated by track changes, with the
+/// index of the track of generated drums if there is one.
+type FileMessages = (Vec<Message>, Option<usize>);
+
+/// The messages of a pattern's midi file, its meta track and chords followed
+/// by any bass line, melody and drums on their own tracks.
+fn pattern_to_file_messages(
+  pattern: &composition::Pattern,
+  options: &crate::MidiExportOptions,
+) -> Result<FileMessages, crate::FailResult> {
+  let mut chord_pattern = pattern.clone();
+  if let Some(channel) = options.get_channel() {
+    chord_pattern.set_channel(channel)?;
+  }
+
+  let mut messages = pattern_to_midi_meta(pattern);
+  messages.append(&mut pattern_to_midi_messages(&chord_pattern));
+  if let Some(bass_line) = pattern.get_bass_line() {
+    messages.push(Message::TrackChange);
+    messages.append(&mut pattern_to_midi_messages(&bass_line));
+  }
+  if let Some(melody_line) = pattern.get_melody_line() {
+    messages.push(Message::TrackChange);
+    messages.append(&mut pattern_to_midi_messages(&melody_line));
+  }
+  let mut drum_track = None;
+  if pattern.get_drums().is_some() {
+    messages.push(Message::TrackChange);
+    drum_track = Some(count_track_changes(&messages));
+    messages.append(&mut drums_to_midi_messages(pattern));
+  }
+  Ok((messages, drum_track))
+}
+
+fn count_track_changes(messages: &[Message]) -> usize {
+  messages
+    .iter()
+    .filter(|message| **message == Message::TrackChange)
+    .count()
+}
+
+/// The file listing the files exports wrote to a directory. Only these files
+/// are replaced or removed by a later export.
+const EXPORT_MANIFEST_NAME: &str = ".chord_composer_export";
+
 pub fn export_midi_patterns(
   composition_name: &str,
-  patterns: &Vec<(String, Vec<Message>)>,
+  patterns: &[(String, FileMessages)],
   parent_directory: &str,
+  options: &crate::MidiExportOptions,
 ) -> Result<crate::SuccessResult, crate::FailResult> {
-  let target_dir = format!("{}/{}", parent_directory, composition_name);
-  
-  // Flush directory
-  let _ = std::fs::remove_dir_all(&target_dir).is_ok();
-  std::fs::create_dir(&target_dir).unwrap();
+  let directory_name = sanitise_file_name(composition_name);
+  let target_dir = match options.get_mode() {
+    crate::ExportMode::Versioned => {
+      to_free_path(path::Path::new(parent_directory), &directory_name, "")
+    }
+    _ => path::Path::new(parent_directory).join(&directory_name),
+  };
+
+  let file_names = to_unique_file_names(patterns.iter().map(|(name, _)| name.as_str()), "mid");
+  let manifest = read_export_manifest(&target_dir);
+
+  // Only replace the files of an earlier export. Without a manifest no
+  // existing file is replaced.
+  match options.get_mode() {
+    crate::ExportMode::Refuse if target_dir.exists() => {
+      return Err(crate::FailResult::ExportExists(to_path_string(&target_dir)));
+    }
+    crate::ExportMode::Overwrite => {
+      for file_name in &file_names {
+        let path = target_dir.join(file_name);
+        let replaceable = manifest
+          .as_ref()
+          .is_some_and(|previous_file_names| previous_file_names.contains(file_name));
+        if path.exists() && !replaceable {
+          return Err(crate::FailResult::ExportExists(to_path_string(&path)));
+        }
+      }
+    }
+    _ => (),
+  }
+  let previous_file_names = manifest.unwrap_or_default();
+
+  if std::fs::create_dir_all(&target_dir).is_err() {
+    return Err(crate::FailResult::CreateDirectory(to_path_string(
+      &target_dir,
+    )));
+  }
+  write_export_manifest(&target_dir, &file_names, &previous_file_names)?;
 
   // Export patterns
   let mut export_paths = Vec::with_capacity(patterns.len());
-  for (pattern_name, midi_messages) in patterns {
-    let path = format!("{}/{}.mid", target_dir, pattern_name);
-    export_midi_messages(&path, midi_messages)?;
+  for ((_, (midi_messages, drum_track)), file_name) in patterns.iter().zip(&file_names) {
+    let path = to_path_string(&target_dir.join(file_name));
+    export_midi_messages(&path, midi_messages, *drum_track, options)?;
     export_paths.push(path);
   }
 
+  // Remove the patterns of an earlier export that aren't in this one
+  for file_name in &previous_file_names {
+    let path = target_dir.join(file_name);
+    if !file_names.contains(file_name) && path.is_file() && std::fs::remove_file(&path).is_err() {
+      return Err(crate::FailResult::RemoveFile(to_path_string(&path)));
+    }
+  }
+  write_export_manifest(&target_dir, &file_names, &[])?;
+
   Ok(crate::SuccessResult::Export(export_paths))
 }
 
-fn export_midi_messages(path: &str, midi_messages: &Vec<Message>) -> Result<(), crate::FailResult> {
-  let path = path::Path::new(path);
-  let mut writer = Writer::new();
-  writer.running_status(false);
+/// Make a name safe to use as a file or directory name. Characters other than
+/// letters, digits, spaces, `-`, `_` and `.` become `_`, leading and trailing
+/// dots and spaces are removed, and names reserved by Windows are prefixed with
+/// `_`. An empty name becomes `untitled`.
+///
+/// # Arguments
+/// * `name` - The name to make safe.
+pub fn sanitise_file_name(name: &str) -> String {
+  const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+  ];
+
+  let name: String = name
+    .chars()
+    .map(|character| {
+      if character.is_alphanumeric() || " -_.".contains(character) {
+        character
+      } else {
+        '_'
+      }
+    })
+    .collect();
+  let name = name.trim_matches(|character| character == '.' || character == ' ');
+
+  let stem = name.split('.').next().unwrap_or("").to_uppercase();
+  if name.is_empty() {
+    "untitled".to_string()
+  } else if RESERVED_NAMES.contains(&stem.as_str()) {
+    format!("_{}", name)
+  } else {
+    name.to_string()
+  }
+}
+
+/// Get safe file names with an extension, adding `_2`, `_3` and so on to names
+/// already taken. Names are compared ignoring case, for case insensitive file
+/// systems.
+fn to_unique_file_names<'a>(names: impl Iterator<Item = &'a str>, extension: &str) -> Vec<String> {
+  let mut file_names: Vec<String> = Vec::new();
+  for name in names {
+    let name = sanitise_file_name(name);
+    let mut version = 1;
+    let file_name = loop {
+      let file_name = to_versioned_file_name(&name, version, extension);
+      let is_taken = file_names
+        .iter()
+        .any(|taken| taken.to_lowercase() == file_name.to_lowercase());
+      if !is_taken {
+        break file_name;
+      }
+      version += 1;
+    };
+    file_names.push(file_name);
+  }
+  file_names
+}
+
+/// Get the first path in a directory that doesn't exist, adding `_2`, `_3` and
+/// so on to the name.
+fn to_free_path(directory: &path::Path, name: &str, extension: &str) -> path::PathBuf {
+  let mut version = 1;
+  loop {
+    let path = directory.join(to_versioned_file_name(name, version, extension));
+    if !path.exists() {
+      return path;
+    }
+    version += 1;
+  }
+}
+
+fn to_versioned_file_name(name: &str, version: u32, extension: &str) -> String {
+  let name = match version {
+    1 => name.to_string(),
+    _ => format!("{}_{}", name, version),
+  };
+  match extension {
+    "" => name,
+    _ => format!("{}.{}", name, extension),
+  }
+}
+
+/// Read the file names earlier exports wrote to a directory, or `None` when the
+/// directory has no manifest. Names that aren't safe file names are ignored.
+fn read_export_manifest(directory: &path::Path) -> Option<Vec<String>> {
+  match std::fs::read_to_string(directory.join(EXPORT_MANIFEST_NAME)) {
+    Ok(manifest) => Some(
+      manifest
+        .lines()
+        .filter(|file_name| !file_name.is_empty() && sanitise_file_name(file_name) == *file_name)
+        .map(|file_name| file_name.to_string())
+        .collect(),
+    ),
+    _ => None,
+  }
+}
+
+/// Write the file names of an export, and any of an earlier export still in
+/// the directory.
+fn write_export_manifest(
+  directory: &path::Path,
+  file_names: &[String],
+  previous_file_names: &[String],
+) -> Result<(), crate::FailResult> {
+  let mut manifest: Vec<&str> = file_names.iter().map(|name| name.as_str()).collect();
+  for file_name in previous_file_names {
+    if !file_names.contains(file_name) {
+      manifest.push(file_name);
+    }
+  }
+
+  let path = directory.join(EXPORT_MANIFEST_NAME);
+  match std::fs::write(&path, manifest.join("\n") + "\n") {
+    Ok(()) => Ok(()),
+    _ => Err(crate::FailResult::ExportManifest(to_path_string(&path))),
+  }
+}
+
+/// Get the path to export a single file to, by whether a file is already at
+/// the path. Overwriting replaces the file but not a directory.
+pub(crate) fn to_export_file_path(
+  path: &str,
+  mode: crate::ExportMode,
+) -> Result<path::PathBuf, crate::FailResult> {
+  let export_path = path::Path::new(path);
+  match mode {
+    crate::ExportMode::Refuse if export_path.exists() => {
+      Err(crate::FailResult::ExportExists(path.to_string()))
+    }
+    crate::ExportMode::Overwrite if export_path.is_dir() => {
+      Err(crate::FailResult::ExportExists(path.to_string()))
+    }
+    crate::ExportMode::Versioned if export_path.exists() => {
+      let directory = export_path.parent().unwrap_or_else(|| path::Path::new(""));
+      let name = export_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+      let extension = export_path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_string())
+        .unwrap_or_default();
+      Ok(to_free_path(directory, &name, &extension))
+    }
+    _ => Ok(export_path.to_path_buf()),
+  }
+}
+
+fn to_path_string(path: &path::Path) -> String {
+  path.to_string_lossy().to_string()
+}
+
+/// Export a composition as a single midi file, its patterns laid end to end
+/// in order. The first track holds the tempo map and meter changes with a
+/// marker named after each pattern where it starts. The chords, bass lines,
+/// melodies and drums of every pattern follow on their own tracks.
+pub fn export_composition_single(
+  composition: &composition::Composition,
+  path: &str,
+  options: &crate::MidiExportOptions,
+) -> Result<crate::SuccessResult, crate::FailResult> {
+  options.check()?;
+
+  let export_path = to_export_file_path(path, options.get_mode())?;
+  let path = to_path_string(&export_path);
+  let (messages, drum_track) = composition_to_midi_messages(composition, options.get_channel());
+  export_midi_messages(&path, &messages, drum_track, options)?;
+
+  Ok(crate::SuccessResult::Export(vec![path]))
+}
+
+/// The meta track and note tracks of a composition, with the chords of every
+/// pattern on the channel given or on the pattern's own channel.
+fn composition_to_midi_messages(
+  composition: &composition::Composition,
+  chord_channel: Option<u8>,
+) -> FileMessages {
+  let mut timed_meta = Vec::new();
+  let mut chord_notes = Vec::new();
+  let mut bass_notes = Vec::new();
+  let mut melody_notes = Vec::new();
+  let mut drum_notes = Vec::new();
+
+  let performed_notes = |pattern: &composition::Pattern| -> Vec<composition::PerformedNote> {
+    pattern
+      .get_performed_events()
+      .into_iter()
+      .flat_map(|(_, notes)| notes)
+      .collect()
+  };
 
-  for message in midi_messages {
-    writer.push(message);
+  let mut start_tick_time = 0;
+  for pattern in composition.get_patterns() {
+    let marker = (MetaEvent::Marker, pattern.get_name().as_bytes().to_vec());
+    timed_meta.push((start_tick_time, marker));
+    timed_meta.extend(
+      pattern_to_timed_meta(pattern)
+        .into_iter()
+        .map(|(tick_time, meta)| (start_tick_time + tick_time, meta)),
+    );
+
+    let channel = chord_channel.unwrap_or_else(|| pattern.get_channel());
+    chord_notes.append(&mut to_track_notes(
+      &performed_notes(pattern),
+      channel,
+      start_tick_time,
+    ));
+    if let Some(bass_line) = pattern.get_bass_line() {
+      let channel = bass_line.get_channel();
+      bass_notes.append(&mut to_track_notes(
+        &performed_notes(&bass_line),
+        channel,
+        start_tick_time,
+      ));
+    }
+    if let Some(melody_line) = pattern.get_melody_line() {
+      let channel = melody_line.get_channel();
+      melody_notes.append(&mut to_track_notes(
+        &performed_notes(&melody_line),
+        channel,
+        start_tick_time,
+      ));
+    }
+    drum_notes.append(&mut to_track_notes(
+      &pattern.get_drum_notes(),
+      drums::DRUM_CHANNEL,
+      start_tick_time,
+    ));
+
+    start_tick_time += to_sample_tick_time(pattern.get_end_tick_index());
   }
 
-  match writer.write(&path) {
-    Err(_) => Err(crate::FailResult::ExportMIDI),
-    _ => Ok(()),
+  // A stable sort keeps the changes of each pattern after the pattern before
+  timed_meta.sort_by_key(|(tick_time, _)| *tick_time);
+  let mut messages = timed_meta_to_midi_messages(timed_meta);
+
+  const DRUM_TRACK_INDEX: usize = 3;
+  let name = composition.get_name();
+  let tracks = vec![
+    (name.to_string(), chord_notes),
+    (format!("{}_bass", name), bass_notes),
+    (format!("{}_melody", name), melody_notes),
+    (format!("{}_drums", name), drum_notes),
+  ];
+  let mut drum_track = None;
+  for (index, (track_name, notes)) in tracks.into_iter().enumerate() {
+    if index == 0 || !notes.is_empty() {
+      messages.push(Message::TrackChange);
+      if index == DRUM_TRACK_INDEX {
+        drum_track = Some(count_track_changes(&messages));
+      }
+      messages.append(&mut track_notes_to_midi_messages(&track_name, &notes));
+    }
+  }
+
+  (messages, drum_track)
+}
+
+fn export_midi_messages(
+  path: &str,
+  midi_messages: &[Message],
+  drum_track: Option<usize>,
+  options: &crate::MidiExportOptions,
+) -> Result<(), crate::FailResult> {
+  let mut writer = match std::fs::File::create(path) {
+    Ok(file) => io::BufWriter::new(file),
+    _ => return Err(crate::FailResult::ExportMIDI(path.to_string())),
+  };
+
+  let is_written = write_midi_messages(&mut writer, midi_messages, drum_track, options).is_ok();
+  match io::Write::flush(&mut writer) {
+    Ok(()) if is_written => Ok(()),
+    _ => Err(crate::FailResult::ExportMIDI(path.to_string())),
+  }
+}
+
+/// Write midi messages as a Standard MIDI File, with the resolution, format
+/// and settings of the options. Tracks are separated by track changes.
+fn write_midi_messages<W: io::Write>(
+  writer: &mut W,
+  midi_messages: &[Message],
+  drum_track: Option<usize>,
+  options: &crate::MidiExportOptions,
+) -> io::Result<()> {
+  const FORMAT_SINGLE_TRACK: u16 = 0;
+  const FORMAT_MULTI_TRACK: u16 = 1;
+  const HEADER_LENGTH: u32 = 6;
+
+  let format = match options.get_format() {
+    crate::MidiFileFormat::SingleTrack => FORMAT_SINGLE_TRACK,
+    crate::MidiFileFormat::MultiTrack => FORMAT_MULTI_TRACK,
+  };
+
+  let midi_messages = apply_export_options(midi_messages, drum_track, options);
+  let mut tracks: Vec<&[Message]> = midi_messages
+    .split(|message| *message == Message::TrackChange)
+    .collect();
+  // A leading track change starts the first track rather than ending one
+  if matches!(midi_messages.first(), None | Some(Message::TrackChange)) {
+    tracks.remove(0);
+  }
+
+  writer.write_all(b"MThd")?;
+  writer.write_all(&HEADER_LENGTH.to_be_bytes())?;
+  writer.write_all(&format.to_be_bytes())?;
+  writer.write_all(&(tracks.len() as u16).to_be_bytes())?;
+  writer.write_all(&options.get_ppq().to_be_bytes())?;
+
+  for track in tracks {
+    let track_binary: Vec<u8> = track.iter().flat_map(|message| message.binary()).collect();
+    writer.write_all(b"MTrk")?;
+    writer.write_all(&(track_binary.len() as u32).to_be_bytes())?;
+    writer.write_all(&track_binary)?;
+  }
+  Ok(())
+}
+
+/// A meta event at a tick time, with its data.
+type TimedMeta = (u32, (MetaEvent, Vec<u8>));
+
+/// A note of a midi track, the tick time it starts and ends at, its channel,
+/// midi note and velocity.
+type TrackNote = (u32, u32, u8, u8, u8);
+
+/// Apply the export options to the tracks of a midi file, the first track
+/// being the meta track. Tick times are scaled to the pulses per quarter note,
+/// velocities outside the generated drum track are moved from the default
+/// velocity, program changes start each pitched track and single track files
+/// merge every track in time order.
+fn apply_export_options(
+  midi_messages: &[Message],
+  drum_track: Option<usize>,
+  options: &crate::MidiExportOptions,
+) -> Vec<Message> {
+  const BEAT_SAMPLE: u64 = BEAT_INTERVAL_SAMPLE as u64 * 8;
+  const MAX_VELOCITY: i16 = 127;
+
+  let velocity_offset = options.get_velocity() as i16 - composition::DEFAULT_VELOCITY as i16;
+  let to_ppq_tick_time = |tick_time: u64| -> u64 {
+    (tick_time * options.get_ppq() as u64 + BEAT_SAMPLE / 2) / BEAT_SAMPLE
+  };
+
+  let mut tracks: Vec<Vec<(u64, Message)>> = midi_messages
+    .split(|message| *message == Message::TrackChange)
+    .enumerate()
+    .map(|(track_index, track)| {
+      let is_drum_track = drum_track == Some(track_index);
+      let mut tick_time = 0;
+      let mut timed_messages: Vec<(u64, Message)> = Vec::with_capacity(track.len() + 1);
+      for message in track {
+        tick_time += get_delta_time(message) as u64;
+        let message = match message {
+          Message::MetaEvent {
+            event: MetaEvent::EndOfTrack,
+            ..
+          } => continue,
+          Message::MidiEvent {
+            event: MidiEvent::NoteOn { ch, note, velocity },
+            ..
+          } if !is_drum_track && *velocity > 0 && velocity_offset != 0 => {
+            let velocity = (*velocity as i16 + velocity_offset).clamp(1, MAX_VELOCITY);
+            Message::MidiEvent {
+              delta_time: 0,
+              event: MidiEvent::NoteOn {
+                ch: *ch,
+                note: *note,
+                velocity: velocity as u8,
+              },
+            }
+          }
+          _ => message.clone(),
+        };
+        timed_messages.push((to_ppq_tick_time(tick_time), message));
+      }
+      timed_messages
+    })
+    .collect();
+
+  if !options.is_meta_track_included() && !tracks.is_empty() {
+    tracks.remove(0);
+  }
+
+  if let Some(program) = options.get_program() {
+    for track in tracks.iter_mut() {
+      let mut channels: Vec<u8> = Vec::new();
+      for (_, message) in track.iter() {
+        if let Message::MidiEvent {
+          event: MidiEvent::NoteOn { ch, .. },
+          ..
+        } = message
+        {
+          if *ch != drums::DRUM_CHANNEL && !channels.contains(ch) {
+            channels.push(*ch);
+          }
+        }
+      }
+
+      // Program changes follow the track name
+      let index = track
+        .iter()
+        .position(|(tick_time, message)| {
+          *tick_time > 0 || !matches!(message, Message::MetaEvent { .. })
+        })
+        .unwrap_or(track.len());
+      for (offset, channel) in channels.into_iter().enumerate() {
+        let program_change = Message::MidiEvent {
+          delta_time: 0,
+          event: MidiEvent::ProgramChange {
+            ch: channel,
+            program,
+          },
+        };
+        track.insert(index + offset, (0, program_change));
+      }
+    }
+  }
+
+  if options.get_format() == crate::MidiFileFormat::SingleTrack {
+    // A stable sort keeps the order of events at the same time
+    let mut track: Vec<(u64, Message)> = tracks.into_iter().flatten().collect();
+    track.sort_by_key(|(tick_time, _)| *tick_time);
+    tracks = vec![track];
+  }
+
+  let mut messages = Vec::with_capacity(midi_messages.len());
+  for (index, track) in tracks.into_iter().enumerate() {
+    if index > 0 {
+      messages.push(Message::TrackChange);
+    }
+
+    let mut total_time = 0;
+    for (tick_time, message) in track {
+      messages.push(with_delta_time(message, (tick_time - total_time) as u32));
+      total_time = tick_time;
+    }
+    messages.push(Message::MetaEvent {
+      delta_time: 0,
+      event: MetaEvent::EndOfTrack,
+      data: Vec::new(),
+    });
+  }
+  messages
+}
+
+fn get_delta_time(message: &Message) -> u32 {
+  match message {
+    Message::MetaEvent { delta_time, .. } => *delta_time,
+    Message::MidiEvent { delta_time, .. } => *delta_time,
+    Message::SysExEvent { delta_time, .. } => *delta_time,
+    Message::TrackChange => 0,
+  }
+}
+
+fn with_delta_time(message: Message, delta_time: u32) -> Message {
+  match message {
+    Message::MetaEvent { event, data, .. } => Message::MetaEvent {
+      delta_time,
+      event,
+      data,
+    },
+    Message::MidiEvent { event, .. } => Message::MidiEvent { delta_time, event },
+    Message::SysExEvent { event, data, .. } => Message::SysExEvent {
+      delta_time,
+      event,
+      data,
+    },
+    Message::TrackChange => Message::TrackChange,
   }
 }
 
 fn pattern_to_midi_meta(pattern: &composition::Pattern) -> Vec<Message> {
-  let mut messages = Vec::with_capacity(4);
-  let tempo = to_tempo_samples(pattern.get_bpm());
-  messages.push(Message::MetaEvent {
-    delta_time: 0,
-    event: MetaEvent::SetTempo,
-    data: vec![(tempo >> 16) as u8, (tempo >> 8) as u8, tempo as u8],
-  });
+  let mut messages = timed_meta_to_midi_messages(pattern_to_timed_meta(pattern));
+  messages.push(Message::TrackChange);
+  messages
+}
 
-  let time_signature = time_signature_to_data(pattern.get_time_signature().as_tuple());
+/// The tempo and time signature meta events of a pattern, from its start.
+fn pattern_to_timed_meta(pattern: &composition::Pattern) -> Vec<TimedMeta> {
+  let mut timed_messages = Vec::new();
 
-  messages.push(Message::MetaEvent {
-    delta_time: 0,
-    event: MetaEvent::TimeSignature,
-    data: time_signature,
-  });
+  let tempo_message = |bpm: f32| {
+    let tempo = to_tempo_samples(bpm);
+    (
+      MetaEvent::SetTempo,
+      vec![(tempo >> 16) as u8, (tempo >> 8) as u8, tempo as u8],
+    )
+  };
+
+  let time_signature_message = |time_signature: time_signature::TimeSignature| {
+    (
+      MetaEvent::TimeSignature,
+      time_signature_to_data(time_signature.as_tuple()),
+    )
+  };
+
+  timed_messages.push((0, tempo_message(pattern.get_bpm())));
+  timed_messages.push((0, time_signature_message(pattern.get_time_signature())));
+
+  let tempo_changes = pattern.get_tempo_changes();
+  for (i, (music_time, change)) in tempo_changes.iter().enumerate() {
+    let tick_time = to_pattern_tick_time(pattern, music_time);
+
+    match change {
+      composition::TempoChange::Instant(bpm) => {
+        timed_messages.push((tick_time, tempo_message(*bpm)));
+      }
+      composition::TempoChange::Ramp(_bpm, end_time) => {
+        // Step the tempo every beat interval until the ramp ends or
+        // the next tempo change begins
+        let start_index = pattern.get_beat_interval_index(music_time);
+        let end_index = {
+          let ramp_end_index = pattern.get_beat_interval_index(end_time);
+          match tempo_changes.get(i + 1) {
+            Some((next_time, _)) => ramp_end_index.min(pattern.get_beat_interval_index(next_time)),
+            None => ramp_end_index,
+          }
+        };
+
+        let mut step_time = *music_time;
+        for step_index in start_index..end_index {
+          let step_tick_time = tick_time + (step_index - start_index) * BEAT_INTERVAL_SAMPLE;
+          let bpm = pattern.get_bpm_at(&step_time);
+          timed_messages.push((step_tick_time, tempo_message(bpm)));
+          step_time.advance_beat_interval(&pattern.get_time_signature_at(step_time.get_bar()));
+        }
+
+        if end_index == pattern.get_beat_interval_index(end_time) {
+          let end_tick_time = tick_time + (end_index - start_index) * BEAT_INTERVAL_SAMPLE;
+          timed_messages.push((end_tick_time, tempo_message(pattern.get_bpm_at(end_time))));
+        }
+      }
+    }
+  }
+
+  for (bar, time_signature) in pattern.get_time_signature_changes() {
+    let tick_time = to_pattern_tick_time(pattern, &music_time::MusicTime::new(*bar, 1, 1));
+    timed_messages.push((tick_time, time_signature_message(*time_signature)));
+  }
+
+  timed_messages.sort_by_key(|(tick_time, _)| *tick_time);
+  timed_messages
+}
+
+/// A meta track of timed meta events, in time order.
+fn timed_meta_to_midi_messages(timed_messages: Vec<TimedMeta>) -> Vec<Message> {
+  let mut messages = Vec::with_capacity(timed_messages.len() + 2);
+  let mut total_time = 0;
+  for (tick_time, (event, data)) in timed_messages {
+    messages.push(Message::MetaEvent {
+      delta_time: tick_time - total_time,
+      event,
+      data,
+    });
+    total_time = tick_time;
+  }
 
   messages.push(Message::MetaEvent {
     delta_time: 0,
@@ -86,82 +724,87 @@ fn pattern_to_midi_meta(pattern: &composition::Pattern) -> Vec<Message> {
     data: Vec::new(),
   });
 
-  messages.push(Message::TrackChange);
-
   messages
 }
 
 fn pattern_to_midi_messages(pattern: &composition::Pattern) -> Vec<Message> {
+  // Notes are moved and accented by the pattern's swing, groove, humanize and
+  // arpeggiator
+  let notes: Vec<composition::PerformedNote> = pattern
+    .get_performed_events()
+    .into_iter()
+    .flat_map(|(_, notes)| notes)
+    .collect();
+  notes_to_midi_messages(pattern.get_name(), &notes, pattern.get_channel())
+}
+
+/// The drums of a pattern as a track on the General MIDI drum channel.
+fn drums_to_midi_messages(pattern: &composition::Pattern) -> Vec<Message> {
+  notes_to_midi_messages(
+    &format!("{}_drums", pattern.get_name()),
+    &pattern.get_drum_notes(),
+    drums::DRUM_CHANNEL,
+  )
+}
+
+fn notes_to_midi_messages(
+  track_name: &str,
+  notes: &[composition::PerformedNote],
+  channel: u8,
+) -> Vec<Message> {
+  track_notes_to_midi_messages(track_name, &to_track_notes(notes, channel, 0))
+}
+
+/// Convert performed notes to track notes on a channel, starting from a tick
+/// time.
+fn to_track_notes(
+  notes: &[composition::PerformedNote],
+  channel: u8,
+  start_tick_time: u32,
+) -> Vec<TrackNote> {
+  notes
+    .iter()
+    .map(|(tick_index, end_tick_index, note, velocity)| {
+      (
+        start_tick_time + to_sample_tick_time(*tick_index),
+        start_tick_time + to_sample_tick_time(*end_tick_index),
+        channel,
+        *note,
+        *velocity,
+      )
+    })
+    .collect()
+}
+
+fn track_notes_to_midi_messages(track_name: &str, notes: &[TrackNote]) -> Vec<Message> {
   let mut messages = Vec::new();
   let mut total_time = 0;
 
   messages.push(Message::MetaEvent {
     delta_time: 0,
     event: MetaEvent::SequenceOrTrackName,
-    data: pattern.get_name().as_bytes().to_vec(),
+    data: track_name.as_bytes().to_vec(),
   });
 
-  for i in 0..pattern.get_events().len() {
-    let (music_time, intervals) = pattern.get(i);
-
-    // Skip empty intervals
-    if intervals.is_empty() {
-      continue;
-    }
-
-    let mut push_event = |smf_note: u8, time: u32, velocity: u8| {
-      messages.push(Message::MidiEvent {
-        delta_time: time,
-        event: MidiEvent::NoteOn {
-          ch: 0,
-          note: smf_note,
-          velocity: velocity,
-        },
-      });
-    };
-
-    let mut push_events = |velocity: u8, bar: u16, beat: u8, beat_interval: u8| {
-      let delta_time = {
-        let numerator = pattern.get_time_signature().get_numerator();
-        let tick_time = to_tick_time(numerator, bar, beat, beat_interval);
-        let delta_time = tick_time - total_time;
-        total_time = to_tick_time(numerator, bar, beat, beat_interval);
-        delta_time
-      };
-      // Push notes into the chord
-      push_event(intervals[0] as u8, delta_time, velocity);
-      for i in 1..intervals.len() {
-        push_event(intervals[i], 0, velocity);
-      }
-    };
-
-    // Note on
-    const NOTE_ON: u8 = 64;
-    push_events(
-      NOTE_ON,
-      music_time.get_bar(),
-      music_time.get_beat(),
-      music_time.get_beat_interval(),
-    );
-
-    // Note off
-    let legato_end = {
-      let is_last_element = i == pattern.get_events().len() - 1;
-      if is_last_element {
-        music_time::MusicTime::new(music_time.get_bar() + 1, 1, 1)
-      } else {
-        let (next_music_time, _next_intervals) = pattern.get(i + 1);
-        next_music_time.clone()
-      }
-    };
+  const NOTE_OFF: u8 = 0;
+  let mut timed_notes = Vec::new();
+  for (tick_time, end_tick_time, channel, note, velocity) in notes {
+    timed_notes.push((*tick_time, *channel, *note, *velocity));
+    timed_notes.push((*end_tick_time, *channel, *note, NOTE_OFF));
+  }
 
-    const NOTE_OFF: u8 = 0;
-    push_events(
-      NOTE_OFF,
-      legato_end.get_bar(),
-      legato_end.get_beat(),
-      legato_end.get_beat_interval(),
-    );
+  // A stable sort keeps notes ending before the notes starting at the same time
+  timed_notes.sort_by_key(|(tick_time, _, _, _)| *tick_time);
+  for (tick_time, channel, note, velocity) in timed_notes {
+    messages.push(Message::MidiEvent {
+      delta_time: tick_time - total_time,
+      event: MidiEvent::NoteOn {
+        ch: channel,
+        note,
+        velocity,
+      },
+    });
+    total_time = tick_time;
   }
 
   // End track
@@ -188,8 +831,9 @@ fn time_signature_to_data(time_signature: (u8, u8)) -> Vec<u8> {
   vec![numerator, dd, 8, 24]
 }
 
+const BEAT_INTERVAL_SAMPLE: u32 = 60;
+
 fn to_tick_time(time_numerator: u8, bar: u16, beat: u8, beat_interval: u8) -> u32 {
-  const BEAT_INTERVAL_SAMPLE: u32 = 60;
   const BEAT_SAMPLE: u32 = BEAT_INTERVAL_SAMPLE * 8;
 
   let bar_corrected = bar as u32 - 1;
@@ -201,8 +845,30 @@ fn to_tick_time(time_numerator: u8, bar: u16, beat: u8, beat_interval: u8) -> u3
     + (beat_interval_corrected * BEAT_INTERVAL_SAMPLE)
 }
 
-fn to_tempo_samples(bpm: u8) -> u32 {
-  (60.0 * 1000000.0 / bpm as f64) as u32
+fn to_pattern_tick_time(pattern: &composition::Pattern, music_time: &music_time::MusicTime) -> u32 {
+  let bar_start = music_time::MusicTime::new(music_time.get_bar(), 1, 1);
+  let numerator = pattern
+    .get_time_signature_at(music_time.get_bar())
+    .get_numerator();
+
+  pattern.get_beat_interval_index(&bar_start) * BEAT_INTERVAL_SAMPLE
+    + to_tick_time(
+      numerator,
+      1,
+      music_time.get_beat(),
+      music_time.get_beat_interval(),
+    )
+}
+
+fn to_sample_tick_time(tick_index: u32) -> u32 {
+  tick_index * BEAT_INTERVAL_SAMPLE / composition::BEAT_INTERVAL_TICKS as u32
+}
+
+fn to_tempo_samples(bpm: f32) -> u32 {
+  const MAX_TEMPO_SAMPLES: f64 = 0xFFFFFF as f64;
+  (60.0 * 1000000.0 / bpm as f64)
+    .round()
+    .min(MAX_TEMPO_SAMPLES) as u32
 }
 
 mod test {
@@ -268,6 +934,388 @@ mod test {
     assert_eq!(to_tick_time(4, 1, 2, 8), BEAT_INTERVAL_SAMPLE * 15);
   }
 
+  #[test]
+  fn test_tempo_samples() {
+    use crate::io::exporter::*;
+    assert_eq!(to_tempo_samples(120.0), 500000);
+    assert_eq!(to_tempo_samples(69.0), 869565);
+    assert_eq!(to_tempo_samples(92.5), 648649);
+    assert_eq!(to_tempo_samples(300.0), 200000);
+    assert_eq!(to_tempo_samples(1.0), 0xFFFFFF);
+  }
+
+  #[test]
+  fn test_pattern_tick_time() {
+    use crate::io::exporter::*;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = composition::Pattern::new("changes", 120.0, TimeSignature::default());
+    pattern.push_time_signature_change(2, TimeSignature::new(3, 4));
+
+    assert_eq!(to_pattern_tick_time(&pattern, &MusicTime::new(1, 1, 1)), 0);
+    assert_eq!(
+      to_pattern_tick_time(&pattern, &MusicTime::new(2, 1, 1)),
+      1920
+    );
+    assert_eq!(
+      to_pattern_tick_time(&pattern, &MusicTime::new(3, 1, 1)),
+      3360
+    );
+    assert_eq!(
+      to_pattern_tick_time(&pattern, &MusicTime::new(3, 2, 2)),
+      3900
+    );
+  }
+
+  #[test]
+  fn test_pattern_tuplet_messages() {
+    use crate::io::exporter::*;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = composition::Pattern::new("tuplets", 120.0, TimeSignature::default());
+    pattern
+      .push_event(MusicTime::new(1, 1, 1), vec![60])
+      .unwrap();
+    pattern
+      .push_event_with_offset(MusicTime::new(1, 1, 3), 40, vec![62])
+      .unwrap();
+    pattern
+      .push_event_with_offset(MusicTime::new(1, 1, 6), 20, vec![64])
+      .unwrap();
+
+    let delta_times: Vec<(u32, u8)> = pattern_to_midi_messages(&pattern)
+      .iter()
+      .filter_map(|message| match message {
+        Message::MidiEvent {
+          delta_time,
+          event: MidiEvent::NoteOn { velocity, .. },
+        } => Some((*delta_time, *velocity)),
+        _ => None,
+      })
+      .collect();
+
+    assert_eq!(
+      delta_times,
+      vec![(0, 64), (160, 0), (0, 64), (160, 0), (0, 64), (1600, 0)]
+    );
+  }
+
+  #[test]
+  fn test_pattern_groove_messages() {
+    use crate::io::exporter::*;
+    use crate::theory::groove::{Groove, Swing};
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = composition::Pattern::new("swung", 120.0, TimeSignature::default());
+    pattern
+      .push_event(MusicTime::new(1, 1, 1), vec![60])
+      .unwrap();
+    pattern
+      .push_event(MusicTime::new(1, 1, 5), vec![62])
+      .unwrap();
+    pattern
+      .push_event(MusicTime::new(1, 2, 1), vec![64])
+      .unwrap();
+    pattern.set_swing(Some(Swing::new(8, 0.75))).unwrap();
+    pattern
+      .set_groove(Some(Groove::new("accent", 4, vec![0, 10], vec![20, -4])))
+      .unwrap();
+
+    let delta_times: Vec<(u32, u8)> = pattern_to_midi_messages(&pattern)
+      .iter()
+      .filter_map(|message| match message {
+        Message::MidiEvent {
+          delta_time,
+          event: MidiEvent::NoteOn { velocity, .. },
+        } => Some((*delta_time, *velocity)),
+        _ => None,
+      })
+      .collect();
+
+    assert_eq!(
+      delta_times,
+      vec![(0, 84), (360, 0), (0, 84), (130, 0), (0, 60), (1430, 0)]
+    );
+  }
+
+  #[test]
+  fn test_pattern_channel_messages() {
+    use crate::io::exporter::*;
+    use crate::theory::bass::BassStyle;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = composition::Pattern::new("bass", 120.0, TimeSignature::default());
+    pattern
+      .push_event(MusicTime::new(1, 1, 1), vec![60, 64, 67])
+      .unwrap();
+    let bass_line = pattern.bass_line(BassStyle::Root);
+
+    let notes: Vec<(u8, u8, u8)> = pattern_to_midi_messages(&bass_line)
+      .iter()
+      .filter_map(|message| match message {
+        Message::MidiEvent {
+          event: MidiEvent::NoteOn { ch, note, velocity },
+          ..
+        } => Some((*ch, *note, *velocity)),
+        _ => None,
+      })
+      .collect();
+
+    assert_eq!(notes, vec![(1, 36, 64), (1, 36, 0)]);
+  }
+
+  #[test]
+  fn test_drum_messages() {
+    use crate::io::exporter::*;
+    use crate::theory::drums::Drums;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = composition::Pattern::new("a", 120.0, TimeSignature::new(2, 4));
+    pattern
+      .push_event(MusicTime::new(1, 1, 1), vec![60])
+      .unwrap();
+    let drums = Drums::new("kicks", 4, vec![(36, vec![90])]);
+    assert!(pattern.set_drums(Some(drums)).is_ok());
+
+    let messages = drums_to_midi_messages(&pattern);
+    assert_eq!(
+      messages[0],
+      Message::MetaEvent {
+        delta_time: 0,
+        event: MetaEvent::SequenceOrTrackName,
+        data: b"a_drums".to_vec(),
+      }
+    );
+
+    let notes: Vec<(u32, u8, u8, u8)> = messages
+      .iter()
+      .filter_map(|message| match message {
+        Message::MidiEvent {
+          delta_time,
+          event: MidiEvent::NoteOn { ch, note, velocity },
+        } => Some((*delta_time, *ch, *note, *velocity)),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(
+      notes,
+      vec![
+        (0, 9, 36, 90),
+        (480, 9, 36, 0),
+        (0, 9, 36, 90),
+        (480, 9, 36, 0)
+      ]
+    );
+  }
+
+  #[test]
+  fn test_pattern_meta_changes() {
+    use crate::io::exporter::*;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = composition::Pattern::new("changes", 120.0, TimeSignature::default());
+    pattern.push_time_signature_change(2, TimeSignature::new(3, 4));
+    pattern.push_tempo_change(
+      MusicTime::new(2, 1, 1),
+      composition::TempoChange::Instant(60.0),
+    );
+    pattern.push_tempo_change(
+      MusicTime::new(3, 1, 1),
+      composition::TempoChange::Ramp(120.0, MusicTime::new(3, 1, 3)),
+    );
+
+    let messages = pattern_to_midi_meta(&pattern);
+    let meta: Vec<(u32, MetaEvent, Vec<u8>)> = messages
+      .iter()
+      .filter_map(|message| match message {
+        Message::MetaEvent {
+          delta_time,
+          event,
+          data,
+        } => Some((*delta_time, event.clone(), data.clone())),
+        _ => None,
+      })
+      .collect();
+
+    assert_eq!(
+      meta,
+      vec![
+        (0, MetaEvent::SetTempo, vec![0x07, 0xA1, 0x20]),
+        (0, MetaEvent::TimeSignature, vec![4, 2, 8, 24]),
+        (1920, MetaEvent::SetTempo, vec![0x0F, 0x42, 0x40]),
+        (0, MetaEvent::TimeSignature, vec![3, 2, 8, 24]),
+        (1440, MetaEvent::SetTempo, vec![0x0F, 0x42, 0x40]),
+        (60, MetaEvent::SetTempo, vec![0x0A, 0x2C, 0x2B]),
+        (60, MetaEvent::SetTempo, vec![0x07, 0xA1, 0x20]),
+        (0, MetaEvent::EndOfTrack, vec![]),
+      ]
+    );
+    assert_eq!(messages.last(), Some(&Message::TrackChange));
+  }
+
+  #[test]
+  fn test_composition_messages() {
+    use crate::io::exporter::*;
+    use crate::theory::drums::Drums;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut verse = composition::Pattern::new("verse", 120.0, TimeSignature::new(3, 4));
+    verse.push_event(MusicTime::new(1, 1, 1), vec![60]).unwrap();
+    verse.push_event(MusicTime::new(2, 1, 1), vec![62]).unwrap();
+    let mut chorus = composition::Pattern::new("chorus", 60.0, TimeSignature::default());
+    chorus
+      .push_event(MusicTime::new(1, 2, 1), vec![64])
+      .unwrap();
+    chorus.set_channel(3).unwrap();
+    let drums = Drums::new("kicks", 4, vec![(36, vec![90])]);
+    chorus.set_drums(Some(drums)).unwrap();
+    let composition = composition::Composition::new_with_patterns("song", vec![verse, chorus]);
+
+    let (messages, drum_track) = composition_to_midi_messages(&composition, None);
+    let tracks: Vec<&[Message]> = messages
+      .split(|message| *message == Message::TrackChange)
+      .collect();
+    assert_eq!(tracks.len(), 3);
+
+    let meta: Vec<(u32, MetaEvent, Vec<u8>)> = tracks[0]
+      .iter()
+      .filter_map(|message| match message {
+        Message::MetaEvent {
+          delta_time,
+          event,
+          data,
+        } => Some((*delta_time, event.clone(), data.clone())),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(
+      meta,
+      vec![
+        (0, MetaEvent::Marker, b"verse".to_vec()),
+        (0, MetaEvent::SetTempo, vec![0x07, 0xA1, 0x20]),
+        (0, MetaEvent::TimeSignature, vec![3, 2, 8, 24]),
+        (2880, MetaEvent::Marker, b"chorus".to_vec()),
+        (0, MetaEvent::SetTempo, vec![0x0F, 0x42, 0x40]),
+        (0, MetaEvent::TimeSignature, vec![4, 2, 8, 24]),
+        (0, MetaEvent::EndOfTrack, vec![]),
+      ]
+    );
+
+    let notes = |track: &[Message]| -> Vec<(u32, u8, u8, u8)> {
+      track
+        .iter()
+        .filter_map(|message| match message {
+          Message::MidiEvent {
+            delta_time,
+            event: MidiEvent::NoteOn { ch, note, velocity },
+          } => Some((*delta_time, *ch, *note, *velocity)),
+          _ => None,
+        })
+        .collect()
+    };
+    assert_eq!(
+      notes(tracks[1]),
+      vec![
+        (0, 0, 60, 64),
+        (1440, 0, 60, 0),
+        (0, 0, 62, 64),
+        (1440, 0, 62, 0),
+        (480, 3, 64, 64),
+        (1440, 3, 64, 0)
+      ]
+    );
+    assert_eq!(notes(tracks[2]).len(), 8);
+    assert_eq!(notes(tracks[2])[0], (2880, 9, 36, 90));
+    assert_eq!(
+      tracks[2][0],
+      Message::MetaEvent {
+        delta_time: 0,
+        event: MetaEvent::SequenceOrTrackName,
+        data: b"song_drums".to_vec(),
+      }
+    );
+    assert_eq!(drum_track, Some(2));
+
+    // Only the generated drums keep their velocities, chords on the drum
+    // channel are moved with the others
+    let options = crate::MidiExportOptions::default().with_velocity(100);
+    let (messages, drum_track) = composition_to_midi_messages(&composition, Some(9));
+    let messages = apply_export_options(&messages, drum_track, &options);
+    let tracks: Vec<&[Message]> = messages
+      .split(|message| *message == Message::TrackChange)
+      .collect();
+    assert_eq!(notes(tracks[1])[0], (0, 9, 60, 100));
+    assert_eq!(notes(tracks[2])[0], (2880, 9, 36, 90));
+  }
+
+  #[test]
+  fn test_export_options() {
+    use crate::io::exporter::*;
+    use crate::{MidiExportOptions, MidiFileFormat};
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = composition::Pattern::new("a", 120.0, TimeSignature::default());
+    pattern
+      .push_event(MusicTime::new(1, 1, 1), vec![60])
+      .unwrap();
+    pattern
+      .push_event(MusicTime::new(1, 2, 1), vec![62])
+      .unwrap();
+    let mut messages = pattern_to_midi_meta(&pattern);
+    messages.append(&mut pattern_to_midi_messages(&pattern));
+
+    let to_events = |messages: &[Message]| -> Vec<(u32, String)> {
+      messages
+        .iter()
+        .map(|message| match message {
+          Message::MetaEvent {
+            delta_time, event, ..
+          } => (*delta_time, format!("{:?}", event)),
+          Message::MidiEvent { delta_time, event } => (*delta_time, format!("{:?}", event)),
+          _ => (0, "TrackChange".to_string()),
+        })
+        .collect()
+    };
+
+    let default_messages = apply_export_options(&messages, None, &MidiExportOptions::default());
+    assert_eq!(default_messages, messages);
+
+    let options = MidiExportOptions::default()
+      .with_ppq(96)
+      .with_velocity(100)
+      .with_program(24)
+      .with_meta_track(false);
+    assert_eq!(
+      to_events(&apply_export_options(&messages, None, &options)),
+      vec![
+        (0, "SequenceOrTrackName".to_string()),
+        (0, "ProgramChange { ch: 0, program: 24 }".to_string()),
+        (0, "NoteOn { ch: 0, note: 60, velocity: 100 }".to_string()),
+        (96, "NoteOn { ch: 0, note: 60, velocity: 0 }".to_string()),
+        (0, "NoteOn { ch: 0, note: 62, velocity: 100 }".to_string()),
+        (288, "NoteOn { ch: 0, note: 62, velocity: 0 }".to_string()),
+        (0, "EndOfTrack".to_string()),
+      ]
+    );
+
+    let options = MidiExportOptions::default().with_format(MidiFileFormat::SingleTrack);
+    let single_track = apply_export_options(&messages, None, &options);
+    assert!(!single_track.contains(&Message::TrackChange));
+    assert_eq!(
+      to_events(&single_track),
+      vec![
+        (0, "SetTempo".to_string()),
+        (0, "TimeSignature".to_string()),
+        (0, "SequenceOrTrackName".to_string()),
+        (0, "NoteOn { ch: 0, note: 60, velocity: 64 }".to_string()),
+        (480, "NoteOn { ch: 0, note: 60, velocity: 0 }".to_string()),
+        (0, "NoteOn { ch: 0, note: 62, velocity: 64 }".to_string()),
+        (1440, "NoteOn { ch: 0, note: 62, velocity: 0 }".to_string()),
+        (0, "EndOfTrack".to_string()),
+      ]
+    );
+  }
+
   #[test]
   fn test_event_delta() {
     use crate::io::exporter::*;
@@ -288,4 +1336,26 @@ mod test {
     assert_eq!(to_delta_time(3, 1, 3), 60);
     assert_eq!(to_delta_time(3, 1, 6), 180);
   }
+
+  #[test]
+  fn test_sanitise_file_name() {
+    use crate::io::exporter::*;
+
+    assert_eq!(sanitise_file_name("part_a"), "part_a");
+    assert_eq!(sanitise_file_name("verse 2.1"), "verse 2.1");
+    assert_eq!(sanitise_file_name(".."), "untitled");
+    assert_eq!(sanitise_file_name(""), "untitled");
+    assert_eq!(sanitise_file_name("../x"), "_x");
+    assert_eq!(sanitise_file_name("/etc/passwd"), "_etc_passwd");
+    assert_eq!(sanitise_file_name("a\\b:c*?"), "a_b_c__");
+    assert_eq!(sanitise_file_name(" .hidden. "), "hidden");
+    assert_eq!(sanitise_file_name("con"), "_con");
+    assert_eq!(sanitise_file_name("Lpt1.mid"), "_Lpt1.mid");
+
+    let names = vec!["intro", "Intro", "../intro", "intro"];
+    assert_eq!(
+      to_unique_file_names(names.into_iter(), "mid"),
+      vec!["intro.mid", "Intro_2.mid", "_intro.mid", "intro_3.mid"]
+    );
+  }
 }
@@ -0,0 +1,366 @@
+use crate::{chords, composition, notes::Key, theory::drums::DRUM_CHANNEL};
+use ghakuf::{
+  messages::{MetaEvent, MidiEvent},
+  reader::{Handler, Reader},
+};
+use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+use std::path;
+
+/// A note read from a midi track, the tick it starts and ends at and the
+/// midi note.
+type TrackNote = (u32, u32, u8);
+
+/// The notes and name of a midi track.
+#[derive(Default)]
+struct Track {
+  name: Option<String>,
+  notes: Vec<TrackNote>,
+  held_notes: Vec<(u8, u32)>,
+  tick: u32,
+}
+
+/// Collects the tracks, tempos and time signatures of a midi file as it is
+/// read.
+#[derive(Default)]
+struct MidiHandler {
+  time_base: u16,
+  tracks: Vec<Track>,
+  tempos: Vec<(u32, f32)>,
+  signatures: Vec<(u32, (u8, u8))>,
+  is_tick_overflowed: bool,
+}
+
+impl MidiHandler {
+  fn track(&mut self) -> &mut Track {
+    if self.tracks.is_empty() {
+      self.tracks.push(Track::default());
+    }
+    self.tracks.last_mut().unwrap()
+  }
+
+  /// Move the current track on by a delta time and get its tick, or `None`
+  /// when the tick is past the largest midi tick.
+  fn advance_tick(&mut self, delta_time: u32) -> Option<u32> {
+    let track = self.track();
+    match track.tick.checked_add(delta_time) {
+      Some(tick) => {
+        track.tick = tick;
+        Some(tick)
+      }
+      None => {
+        self.is_tick_overflowed = true;
+        None
+      }
+    }
+  }
+}
+
+impl Handler for MidiHandler {
+  fn header(&mut self, _format: u16, _track: u16, time_base: u16) {
+    self.time_base = time_base;
+  }
+
+  fn meta_event(&mut self, delta_time: u32, event: &MetaEvent, data: &Vec<u8>) {
+    let tick = match self.advance_tick(delta_time) {
+      Some(tick) => tick,
+      None => return,
+    };
+    let track = self.track();
+
+    match event {
+      MetaEvent::SequenceOrTrackName => {
+        track.name = Some(String::from_utf8_lossy(data).trim().to_string());
+      }
+      MetaEvent::SetTempo if data.len() == 3 => {
+        let tempo = (data[0] as u32) << 16 | (data[1] as u32) << 8 | data[2] as u32;
+        if tempo > 0 {
+          self.tempos.push((tick, to_bpm(tempo)));
+        }
+      }
+      MetaEvent::TimeSignature if data.len() >= 2 => {
+        const MAX_DENOMINATOR_POWER: u8 = 6;
+        if data[0] > 0 && data[1] <= MAX_DENOMINATOR_POWER {
+          self.signatures.push((tick, (data[0], 1 << data[1])));
+        }
+      }
+      _ => (),
+    }
+  }
+
+  fn midi_event(&mut self, delta_time: u32, event: &MidiEvent) {
+    let tick = match self.advance_tick(delta_time) {
+      Some(tick) => tick,
+      None => return,
+    };
+    let track = self.track();
+
+    let (note, is_note_on) = match event {
+      MidiEvent::NoteOn { ch, .. } | MidiEvent::NoteOff { ch, .. } if *ch == DRUM_CHANNEL => return,
+      MidiEvent::NoteOn { note, velocity, .. } => (*note, *velocity > 0),
+      MidiEvent::NoteOff { note, .. } => (*note, false),
+      _ => return,
+    };
+
+    // A note on while the note is held ends it first
+    if let Some(index) = track.held_notes.iter().position(|(held, _)| *held == note) {
+      let (_, start) = track.held_notes.remove(index);
+      track.notes.push((start, tick, note));
+    }
+    if is_note_on {
+      track.held_notes.push((note, tick));
+    }
+  }
+
+  fn track_change(&mut self) {
+    self.tracks.push(Track::default());
+  }
+}
+
+/// Get the beats per minute of a midi tempo, in microseconds a beat, rounded to
+/// hundredths.
+fn to_bpm(tempo: u32) -> f32 {
+  const MICROSECONDS_PER_MINUTE: f64 = 60_000_000.0;
+  ((MICROSECONDS_PER_MINUTE / tempo as f64) * 100.0).round() as f32 / 100.0
+}
+
+/// Read a Standard MIDI File into a composition. Each track with notes becomes
+/// a pattern named after the track, or only the chosen track when there is
+/// one. Notes on the General MIDI drum channel are skipped.
+///
+/// Notes are quantised to beat intervals and notes starting together are
+/// grouped into chord events, named when they form an internal chord. Rests
+/// are placed where every note ends before the next chord. The tempo and time
+/// signature meta events of every track set the tempo and meter of each
+/// pattern.
+///
+/// # Arguments
+/// * `path` - The path of the midi file.
+/// * `track` - The index of the track to import, counting from the first track
+///   of the file, or `None` to import every track with notes.
+pub fn import_midi(
+  path: &str,
+  track: Option<usize>,
+) -> Result<composition::Composition, crate::FailResult> {
+  const SMPTE_TIME_BASE_FLAG: u16 = 0x8000;
+
+  let mut handler = MidiHandler::default();
+  let midi_path = path::Path::new(path);
+  let is_read = match Reader::new(&mut handler, midi_path) {
+    Ok(mut reader) => reader.read().is_ok(),
+    Err(_) => false,
+  };
+  if !is_read
+    || handler.is_tick_overflowed
+    || handler.time_base == 0
+    || handler.time_base & SMPTE_TIME_BASE_FLAG != 0
+  {
+    return Err(crate::FailResult::ImportMIDI);
+  }
+
+  let track_indexes: Vec<usize> = match track {
+    Some(index) if index < handler.tracks.len() => vec![index],
+    Some(index) => return Err(crate::FailResult::NoFoundTrack(index)),
+    None => (0..handler.tracks.len())
+      .filter(|index| !handler.tracks[*index].notes.is_empty())
+      .collect(),
+  };
+
+  let name = midi_path
+    .file_stem()
+    .and_then(|stem| stem.to_str())
+    .unwrap_or("imported");
+
+  let mut composition = composition::Composition::new(name);
+  for index in track_indexes {
+    let track = &handler.tracks[index];
+    let pattern_name = match &track.name {
+      Some(track_name) if !track_name.is_empty() => track_name.clone(),
+      _ => format!("track_{}", index + 1),
+    };
+    composition.push_pattern(track_to_pattern(&pattern_name, track, &handler)?);
+  }
+
+  composition.check()?;
+  Ok(composition)
+}
+
+fn track_to_pattern(
+  name: &str,
+  track: &Track,
+  handler: &MidiHandler,
+) -> Result<composition::Pattern, crate::FailResult> {
+  const DEFAULT_BPM: f32 = 120.0;
+  const DEFAULT_SIGNATURE: (u8, u8) = (4, 4);
+
+  let to_tick_index = |tick: u32| to_beat_interval_tick_index(tick, handler.time_base);
+
+  let mut signatures = handler.signatures.clone();
+  signatures.sort_by_key(|(tick, _)| *tick);
+  let first_signature = match signatures.first() {
+    Some((0, signature)) => *signature,
+    _ => DEFAULT_SIGNATURE,
+  };
+
+  let mut tempos = handler.tempos.clone();
+  tempos.sort_by_key(|(tick, _)| *tick);
+  let first_bpm = match tempos.first() {
+    Some((0, bpm)) => *bpm,
+    _ => DEFAULT_BPM,
+  };
+
+  let (numerator, denominator) = first_signature;
+  let mut pattern =
+    composition::Pattern::new(name, first_bpm, TimeSignature::new(numerator, denominator));
+
+  // Meter changes part way through a bar start on the following bar
+  for (tick, (numerator, denominator)) in signatures {
+    let (time, offset) = pattern.get_time_at_tick_index(to_tick_index(tick));
+    let is_bar_start = time.get_beat() == 1 && time.get_beat_interval() == 1 && offset == 0;
+    let bar = if is_bar_start {
+      time.get_bar()
+    } else {
+      time.get_bar() + 1
+    };
+    let signature = TimeSignature::new(numerator, denominator);
+    if bar > 1 && signature != pattern.get_time_signature_at(bar) {
+      pattern.push_time_signature_change(bar, signature);
+    }
+  }
+
+  let mut bpm = first_bpm;
+  for (tick, tempo_bpm) in tempos {
+    let tick_index = to_tick_index(tick);
+    if tick_index > 0 && tempo_bpm != bpm {
+      let (time, _) = pattern.get_time_at_tick_index(tick_index);
+      pattern.push_tempo_change(time, composition::TempoChange::Instant(tempo_bpm));
+      bpm = tempo_bpm;
+    }
+  }
+
+  // Held notes end with the track
+  let mut notes = track.notes.clone();
+  notes.extend(
+    track
+      .held_notes
+      .iter()
+      .map(|(note, start)| (*start, track.tick, *note)),
+  );
+
+  let interval_ticks = composition::BEAT_INTERVAL_TICKS as u32;
+  let mut notes: Vec<TrackNote> = notes
+    .into_iter()
+    .map(|(start, end, note)| {
+      let start = to_tick_index(start);
+      (start, to_tick_index(end).max(start + interval_ticks), note)
+    })
+    .collect();
+  notes.sort_unstable_by_key(|(start, end, note)| (*start, *note, *end));
+  notes.dedup_by_key(|(start, _, note)| (*start, *note));
+
+  let mut onsets: Vec<u32> = notes.iter().map(|(start, _, _)| *start).collect();
+  onsets.dedup();
+
+  for (i, onset) in onsets.iter().enumerate() {
+    let chord_notes: Vec<&TrackNote> = notes
+      .iter()
+      .filter(|(start, _, _)| start == onset)
+      .collect();
+    let midi_notes: Vec<u8> = chord_notes.iter().map(|(_, _, note)| *note).collect();
+    let chord_end = chord_notes
+      .iter()
+      .map(|(_, end, _)| *end)
+      .max()
+      .unwrap_or(*onset);
+
+    let (time, _) = pattern.get_time_at_tick_index(*onset);
+    pattern.push_chord_event(to_chord_event(time, midi_notes))?;
+
+    // The last chord rests where it ends, unless it lasts to the end of its bar
+    let is_rest = match onsets.get(i + 1) {
+      Some(next_onset) => chord_end < *next_onset,
+      None => chord_end != pattern.get_tick_index(&MusicTime::new(time.get_bar() + 1, 1, 1), 0),
+    };
+    if is_rest {
+      let (rest_time, _) = pattern.get_time_at_tick_index(chord_end);
+      let rest_symbol = composition::ChordSymbol::new(chords::REST_NAME, Key::C, 0, Vec::new(), 3);
+      pattern.push_chord_event(composition::ChordEvent::new_with_symbol(
+        rest_time,
+        0,
+        Vec::new(),
+        rest_symbol,
+      ))?;
+    }
+  }
+
+  Ok(pattern)
+}
+
+/// Build a chord event of midi notes, keeping the chord name when the notes
+/// form an internal chord.
+fn to_chord_event(time: MusicTime, midi_notes: Vec<u8>) -> composition::ChordEvent {
+  const CHORD_OCTAVE: i8 = 3;
+  const MIDDLE_C: i16 = 60;
+
+  match chords::recognise_chord(&midi_notes) {
+    Some((name, root_note)) => {
+      let symbol = composition::ChordSymbol::new(
+        name,
+        Key::C,
+        (root_note as i16 - MIDDLE_C) as i8,
+        chords::string_to_chord(name),
+        CHORD_OCTAVE,
+      );
+      composition::ChordEvent::new_with_symbol(time, 0, midi_notes, symbol)
+    }
+    None => composition::ChordEvent::new(time, 0, midi_notes),
+  }
+}
+
+/// Get the tick index of a midi tick, quantised to the nearest beat interval.
+fn to_beat_interval_tick_index(tick: u32, time_base: u16) -> u32 {
+  const BEAT_INTERVALS: u64 = 8;
+  let interval_ticks = composition::BEAT_INTERVAL_TICKS as u64;
+  let time_base = time_base as u64;
+
+  let intervals = (tick as u64 * BEAT_INTERVALS + time_base / 2) / time_base;
+  (intervals * interval_ticks) as u32
+}
+
+mod tests {
+
+  #[test]
+  fn test_beat_interval_tick_index() {
+    use crate::io::importer::*;
+
+    assert_eq!(to_beat_interval_tick_index(0, 480), 0);
+    assert_eq!(to_beat_interval_tick_index(480, 480), 480);
+    assert_eq!(to_beat_interval_tick_index(29, 480), 0);
+    assert_eq!(to_beat_interval_tick_index(31, 480), 60);
+    assert_eq!(to_beat_interval_tick_index(96, 96), 480);
+    assert_eq!(to_beat_interval_tick_index(1000, 960), 480);
+
+    assert_eq!(to_bpm(500_000), 120.0);
+    assert_eq!(to_bpm(869_565), 69.0);
+  }
+
+  #[test]
+  fn test_tick_overflow() {
+    use crate::io::importer::*;
+
+    let note_on = MidiEvent::NoteOn {
+      ch: 0,
+      note: 60,
+      velocity: 64,
+    };
+    let mut handler = MidiHandler::default();
+    handler.midi_event(u32::MAX - 1, &note_on);
+    handler.meta_event(1, &MetaEvent::EndOfTrack, &Vec::new());
+    assert_eq!(handler.track().tick, u32::MAX);
+    assert!(!handler.is_tick_overflowed);
+
+    handler.midi_event(1, &note_on);
+    assert_eq!(handler.track().tick, u32::MAX);
+    assert_eq!(handler.track().notes, Vec::new());
+    assert!(handler.is_tick_overflowed);
+  }
+}
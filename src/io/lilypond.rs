@@ -0,0 +1,280 @@
+use crate::{
+  composition,
+  io::notation::{self, BarChord},
+  notes,
+};
+
+/// The LilyPond version the source is written for.
+const LILYPOND_VERSION: &str = "2.20.0";
+
+/// Write a composition as LilyPond source, a `\chordmode` line of chord names
+/// over a staff of the voiced chords.
+///
+/// Each pattern is a section starting with a rehearsal mark named after it, its
+/// key, time signature and tempo. Events last until the next event and are
+/// tied across bar lines. Chords without a common chord symbol are named by
+/// their steps above the root.
+///
+/// # Arguments
+/// * `composition` - The composition to write.
+pub fn composition_to_lilypond(composition: &composition::Composition) -> String {
+  let mut harmonies = String::new();
+  let mut voicing = String::new();
+
+  let patterns = composition.get_patterns();
+  for (pattern_index, pattern) in patterns.iter().enumerate() {
+    let key = notation::to_pattern_key(pattern);
+    let fifths = notation::key_to_fifths(key);
+    let bars = notation::pattern_to_bars(pattern);
+
+    harmonies.push_str(&format!("  % {}\n", pattern.get_name()));
+    voicing.push_str(&format!("  % {}\n", pattern.get_name()));
+    voicing.push_str(&format!(
+      "  \\mark \\markup {{ \\box {} }}\n",
+      to_lilypond_string(pattern.get_name())
+    ));
+    let (letter, alter) = notation::to_note_spelling(key, fifths);
+    voicing.push_str(&format!(
+      "  \\key {} \\major\n",
+      to_lilypond_pitch_name(letter, alter)
+    ));
+
+    for (i, bar) in bars.iter().enumerate() {
+      if i == 0 || bars[i - 1].get_time_signature() != bar.get_time_signature() {
+        let (numerator, denominator) = bar.get_time_signature().as_tuple();
+        voicing.push_str(&format!("  \\time {}/{}\n", numerator, denominator));
+      }
+
+      let mut harmony_bar: Vec<String> = Vec::new();
+      let mut voicing_bar: Vec<String> = Vec::new();
+      for chord in bar.get_chords() {
+        let chord_end = chord.get_start() + chord.get_length();
+        for (units, bpm) in bar.get_tempos() {
+          if *units >= chord.get_start() && *units < chord_end {
+            voicing_bar.push(format!(
+              "\\tempo {} = {}",
+              bar.get_time_signature().get_denominator(),
+              bpm.round()
+            ));
+          }
+        }
+        harmony_bar.push(chord_to_chord_mode(chord, fifths));
+        voicing_bar.push(chord_to_notes(chord, fifths));
+      }
+      harmonies.push_str(&format!("  {} |\n", harmony_bar.join(" ")));
+      voicing.push_str(&format!("  {} |\n", voicing_bar.join(" ")));
+    }
+
+    let bar_line = if pattern_index + 1 == patterns.len() {
+      "|."
+    } else {
+      "||"
+    };
+    voicing.push_str(&format!("  \\bar \"{}\"\n", bar_line));
+  }
+
+  let mut lilypond = format!("\\version \"{}\"\n\n", LILYPOND_VERSION);
+  lilypond.push_str(&format!(
+    "\\header {{\n  title = {}\n}}\n\n",
+    to_lilypond_string(composition.get_name())
+  ));
+  lilypond.push_str(&format!("harmonies = \\chordmode {{\n{}}}\n\n", harmonies));
+  lilypond.push_str(&format!("voicing = {{\n  \\clef treble\n{}}}\n\n", voicing));
+  lilypond.push_str("\\score {\n  <<\n    \\new ChordNames \\harmonies\n    \\new Staff \\voicing\n  >>\n  \\layout { }\n}\n");
+  lilypond
+}
+
+/// The chord name of a chord in chord mode, with spacers where it is held and
+/// rests where there is no chord.
+fn chord_to_chord_mode(chord: &BarChord, fifths: i8) -> String {
+  let name = chord.get_label().map(|label| {
+    let (letter, alter) = notation::to_note_spelling(label.get_root(), fifths);
+    let modifier = match label.get_quality() {
+      Some(quality) => quality.get_chord_mode().to_string(),
+      None => to_chord_mode_steps(chord.get_notes(), label.get_root()),
+    };
+    (to_lilypond_pitch_name(letter, alter), modifier)
+  });
+
+  to_lilypond_durations(chord.get_length())
+    .iter()
+    .enumerate()
+    .map(|(i, duration)| match &name {
+      _ if chord.get_notes().is_empty() => format!("r{}", duration),
+      Some((root, modifier)) if i == 0 && modifier.is_empty() => format!("{}{}", root, duration),
+      Some((root, modifier)) if i == 0 => format!("{}{}:{}", root, duration, modifier),
+      _ => format!("s{}", duration),
+    })
+    .collect::<Vec<String>>()
+    .join(" ")
+}
+
+/// The chord mode steps of notes above a root, e.g. `1.3-.5+`.
+fn to_chord_mode_steps(midi_notes: &[u8], root: notes::Key) -> String {
+  const NOTES_IN_OCTAVE_COUNT: u8 = 12;
+  const STEPS: [&str; 12] = [
+    "1", "2-", "2", "3-", "3", "4", "5-", "5", "5+", "6", "7", "7+",
+  ];
+
+  let root_index = notes::key_to_index(root);
+  let mut pitch_classes: Vec<u8> = midi_notes
+    .iter()
+    .map(|note| (note + NOTES_IN_OCTAVE_COUNT - root_index) % NOTES_IN_OCTAVE_COUNT)
+    .filter(|pitch_class| *pitch_class != 0)
+    .collect();
+  pitch_classes.sort_unstable();
+  pitch_classes.dedup();
+
+  let mut steps = vec![STEPS[0]];
+  steps.extend(
+    pitch_classes
+      .iter()
+      .map(|pitch_class| STEPS[*pitch_class as usize]),
+  );
+  steps.join(".")
+}
+
+/// The voiced notes of a chord, or a rest, as durations tied together.
+fn chord_to_notes(chord: &BarChord, fifths: i8) -> String {
+  let mut midi_notes = chord.get_notes().clone();
+  midi_notes.sort_unstable();
+  let pitches: Vec<String> = midi_notes
+    .iter()
+    .map(|note| to_lilypond_pitch(*note, fifths))
+    .collect();
+  let notes = match pitches.len() {
+    0 => "r".to_string(),
+    1 => pitches[0].clone(),
+    _ => format!("<{}>", pitches.join(" ")),
+  };
+
+  let durations = to_lilypond_durations(chord.get_length());
+  durations
+    .iter()
+    .enumerate()
+    .map(|(i, duration)| {
+      let is_tied = !midi_notes.is_empty() && (i + 1 < durations.len() || chord.is_tied());
+      if is_tied {
+        format!("{}{} ~", notes, duration)
+      } else {
+        format!("{}{}", notes, duration)
+      }
+    })
+    .collect::<Vec<String>>()
+    .join(" ")
+}
+
+/// Get the durations of a length of notation units, scaled when a length isn't
+/// a sum of note values, e.g. `4.` or `8*3/2`.
+fn to_lilypond_durations(length: u32) -> Vec<String> {
+  notation::to_note_values(length)
+    .into_iter()
+    .map(|(value, dots, units)| {
+      let mut duration = format!("{}{}", value, ".".repeat(dots as usize));
+      let value_units = notation::to_note_value_units(value, dots);
+      if units != value_units {
+        let divisor = notation::to_greatest_common_divisor(units, value_units);
+        duration.push_str(&format!("*{}/{}", units / divisor, value_units / divisor));
+      }
+      duration
+    })
+    .collect()
+}
+
+/// Get the LilyPond name of a pitch without its octave, e.g. `fis` or `bes`.
+fn to_lilypond_pitch_name(letter: char, alter: i8) -> String {
+  let letter = letter.to_ascii_lowercase();
+  match (letter, alter) {
+    ('e', -1) => "es".to_string(),
+    ('a', -1) => "as".to_string(),
+    (_, 1) => format!("{}is", letter),
+    (_, -1) => format!("{}es", letter),
+    _ => letter.to_string(),
+  }
+}
+
+/// Get the LilyPond pitch of a midi note, where `c'` is middle C.
+fn to_lilypond_pitch(midi_note: u8, fifths: i8) -> String {
+  // The octave of `c`, the C below middle C
+  const UNMARKED_OCTAVE: i8 = 3;
+  let (letter, alter, octave) = notation::to_pitch(midi_note, fifths);
+  let octave_marks = match octave - UNMARKED_OCTAVE {
+    marks if marks > 0 => "'".repeat(marks as usize),
+    marks => ",".repeat(-marks as usize),
+  };
+  format!("{}{}", to_lilypond_pitch_name(letter, alter), octave_marks)
+}
+
+fn to_lilypond_string(text: &str) -> String {
+  format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+mod tests {
+
+  #[test]
+  fn test_lilypond_pitches() {
+    use crate::io::lilypond::*;
+
+    assert_eq!(to_lilypond_pitch(60, 0), "c'");
+    assert_eq!(to_lilypond_pitch(48, 0), "c");
+    assert_eq!(to_lilypond_pitch(47, 0), "b,");
+    assert_eq!(to_lilypond_pitch(75, -3), "es''");
+    assert_eq!(to_lilypond_pitch(66, 2), "fis'");
+    assert_eq!(to_lilypond_pitch(68, -1), "as'");
+
+    assert_eq!(to_lilypond_durations(3840 * 3), vec!["2."]);
+    assert_eq!(to_lilypond_durations(3840 * 5), vec!["1", "4"]);
+    assert_eq!(to_lilypond_durations(1920 + 960 + 8), vec!["8.*361/360"]);
+
+    assert_eq!(to_chord_mode_steps(&[62, 65, 70], notes::Key::C), "1.2.4.7");
+    assert_eq!(to_chord_mode_steps(&[60, 63, 68], notes::Key::C), "1.3-.5+");
+    assert_eq!(to_chord_mode_steps(&[60, 61], notes::Key::C), "1.2-");
+  }
+
+  #[test]
+  fn test_composition_lilypond() {
+    use crate::io::lilypond::*;
+    use crate::theory::{
+      chords,
+      composition::{ChordEvent, ChordSymbol, Composition, Pattern},
+      notes::Key,
+    };
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut verse = Pattern::new("verse", 90.0, TimeSignature::new(3, 4));
+    let symbol = ChordSymbol::new(
+      "MINOR_SEVENTH",
+      Key::F,
+      2,
+      chords::MINOR_SEVENTH.to_vec(),
+      3,
+    );
+    verse
+      .push_chord_event(ChordEvent::new_with_symbol(
+        MusicTime::new(1, 1, 1),
+        0,
+        vec![67, 70, 74, 77],
+        symbol,
+      ))
+      .unwrap();
+    verse
+      .push_event(MusicTime::new(2, 2, 1), vec![60, 64, 67])
+      .unwrap();
+    let mut chorus = Pattern::new("chorus", 120.0, TimeSignature::new(4, 4));
+    chorus
+      .push_event(MusicTime::new(1, 1, 1), vec![60, 61, 67])
+      .unwrap();
+    let composition = Composition::new_with_patterns("song", vec![verse, chorus]);
+
+    let lilypond = composition_to_lilypond(&composition);
+    assert!(lilypond.starts_with("\\version \"2.20.0\"\n"));
+    assert!(lilypond.contains("title = \"song\""));
+    assert!(lilypond.contains("  g2.:m7 |\n  s4 c2 |\n"));
+    assert!(lilypond.contains("  c1:1.2-.5 |\n"));
+    assert!(lilypond.contains(
+      "  \\mark \\markup { \\box \"verse\" }\n  \\key f \\major\n  \\time 3/4\n  \\tempo 4 = 90 <g' bes' d'' f''>2. ~ |\n  <g' bes' d'' f''>4 <c' e' g'>2 |\n  \\bar \"||\"\n"
+    ));
+    assert!(lilypond.contains("  \\time 4/4\n  \\tempo 4 = 120 <c' cis' g'>1 |\n  \\bar \"|.\"\n"));
+    assert!(lilypond.contains("\\new ChordNames \\harmonies"));
+  }
+}
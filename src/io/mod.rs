@@ -1,2 +1,9 @@
+pub mod abc;
+pub mod chart;
 pub mod deseralizer;
 pub mod exporter;
+pub mod importer;
+pub mod lilypond;
+pub mod musicxml;
+pub mod notation;
+pub mod serializer;
@@ -0,0 +1,433 @@
+use crate::{
+  composition,
+  io::notation::{self, Bar, BarChord},
+};
+
+/// The notation units of a quarter note, the MusicXML divisions before they are
+/// reduced.
+const QUARTER_NOTE_UNITS: u32 = notation::WHOLE_NOTE_UNITS / 4;
+
+/// A written note, the note value, its count of dots, its notation units and
+/// the actual and normal notes of its tuplet.
+type WrittenNote = (u32, u8, u32, Option<(u32, u32)>);
+
+/// Write a composition as a MusicXML partwise score with a single part.
+///
+/// Each pattern starts with a rehearsal mark named after it, its key signature,
+/// time signature and tempo marking. Chord events are written as `<harmony>`
+/// chord symbols over their voiced notes, lasting until the next event and
+/// tied across bar lines.
+///
+/// # Arguments
+/// * `composition` - The composition to write.
+pub fn composition_to_musicxml(composition: &composition::Composition) -> String {
+  let patterns: Vec<(&composition::Pattern, Vec<Bar>)> = composition
+    .get_patterns()
+    .iter()
+    .map(|pattern| (pattern, notation::pattern_to_bars(pattern)))
+    .collect();
+
+  // The longest unit every duration and offset is a whole number of
+  let unit = patterns
+    .iter()
+    .flat_map(|(_, bars)| bars)
+    .flat_map(|bar| {
+      let chord_units = bar.get_chords().iter().flat_map(|chord| {
+        to_written_notes(chord.get_length())
+          .into_iter()
+          .map(|(_, _, units, _)| units)
+          .chain(Some(chord.get_start()))
+      });
+      let tempo_units = bar.get_tempos().iter().map(|(units, _)| *units);
+      chord_units.chain(tempo_units).collect::<Vec<u32>>()
+    })
+    .fold(QUARTER_NOTE_UNITS, notation::to_greatest_common_divisor);
+
+  let name = escape_xml(composition.get_name());
+  let mut xml = String::new();
+  xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n");
+  xml.push_str("<!DOCTYPE score-partwise PUBLIC \"-//Recordare//DTD MusicXML 3.1 Partwise//EN\" \"http://www.musicxml.org/dtds/partwise.dtd\">\n");
+  xml.push_str("<score-partwise version=\"3.1\">\n");
+  xml.push_str(&format!(
+    "  <work>\n    <work-title>{}</work-title>\n  </work>\n",
+    name
+  ));
+  xml.push_str("  <part-list>\n    <score-part id=\"P1\">\n");
+  xml.push_str(&format!("      <part-name>{}</part-name>\n", name));
+  xml.push_str("    </score-part>\n  </part-list>\n");
+  xml.push_str("  <part id=\"P1\">\n");
+
+  let mut measure_number = 1;
+  for (pattern, bars) in &patterns {
+    let fifths = notation::key_to_fifths(notation::to_pattern_key(pattern));
+
+    for (i, bar) in bars.iter().enumerate() {
+      xml.push_str(&format!("    <measure number=\"{}\">\n", measure_number));
+
+      let is_first_bar = i == 0;
+      let is_new_signature = i > 0 && bars[i - 1].get_time_signature() != bar.get_time_signature();
+      if is_first_bar || is_new_signature {
+        xml.push_str("      <attributes>\n");
+        if is_first_bar {
+          xml.push_str(&format!(
+            "        <divisions>{}</divisions>\n",
+            QUARTER_NOTE_UNITS / unit
+          ));
+          xml.push_str(&format!(
+            "        <key>\n          <fifths>{}</fifths>\n        </key>\n",
+            fifths
+          ));
+        }
+        let (numerator, denominator) = bar.get_time_signature().as_tuple();
+        xml.push_str(&format!(
+          "        <time>\n          <beats>{}</beats>\n          <beat-type>{}</beat-type>\n        </time>\n",
+          numerator, denominator
+        ));
+        if is_first_bar {
+          xml.push_str(
+            "        <clef>\n          <sign>G</sign>\n          <line>2</line>\n        </clef>\n",
+          );
+        }
+        xml.push_str("      </attributes>\n");
+      }
+
+      if is_first_bar {
+        xml.push_str(&format!(
+          "      <direction placement=\"above\">\n        <direction-type>\n          <rehearsal>{}</rehearsal>\n        </direction-type>\n      </direction>\n",
+          escape_xml(pattern.get_name())
+        ));
+      }
+
+      for chord in bar.get_chords() {
+        let chord_end = chord.get_start() + chord.get_length();
+        for (units, bpm) in bar.get_tempos() {
+          if *units >= chord.get_start() && *units < chord_end {
+            xml.push_str(&tempo_to_musicxml(
+              bar,
+              *units - chord.get_start(),
+              *bpm,
+              unit,
+            ));
+          }
+        }
+        if let Some(label) = chord.get_label() {
+          xml.push_str(&label_to_harmony(label, fifths));
+        }
+        xml.push_str(&chord_to_notes(chord, fifths, unit));
+      }
+
+      xml.push_str("    </measure>\n");
+      measure_number += 1;
+    }
+  }
+
+  xml.push_str("  </part>\n");
+  xml.push_str("</score-partwise>\n");
+  xml
+}
+
+/// A tempo marking of the beat of a bar, offset from the chord it is written
+/// before.
+fn tempo_to_musicxml(bar: &Bar, offset: u32, bpm: f32, unit: u32) -> String {
+  let beat_unit = note_value_to_type(bar.get_time_signature().get_denominator() as u32);
+  let quarter_bpm = bpm * QUARTER_NOTE_UNITS as f32 / bar.get_beat_length() as f32;
+
+  let mut xml = String::from("      <direction placement=\"above\">\n        <direction-type>\n");
+  xml.push_str(&format!(
+    "          <metronome>\n            <beat-unit>{}</beat-unit>\n            <per-minute>{}</per-minute>\n          </metronome>\n",
+    beat_unit, bpm
+  ));
+  xml.push_str("        </direction-type>\n");
+  if offset > 0 {
+    xml.push_str(&format!("        <offset>{}</offset>\n", offset / unit));
+  }
+  xml.push_str(&format!(
+    "        <sound tempo=\"{}\"/>\n      </direction>\n",
+    quarter_bpm
+  ));
+  xml
+}
+
+fn label_to_harmony(label: &notation::ChordLabel, fifths: i8) -> String {
+  let (step, alter) = notation::to_note_spelling(label.get_root(), fifths);
+  let kind = label
+    .get_quality()
+    .map_or("other", |quality| quality.get_kind());
+
+  let mut xml = String::from("      <harmony>\n        <root>\n");
+  xml.push_str(&format!("          <root-step>{}</root-step>\n", step));
+  if alter != 0 {
+    xml.push_str(&format!("          <root-alter>{}</root-alter>\n", alter));
+  }
+  xml.push_str("        </root>\n");
+  xml.push_str(&format!(
+    "        <kind text=\"{}\">{}</kind>\n",
+    escape_xml(&label.get_suffix()),
+    kind
+  ));
+
+  // Added and altered notes the kind doesn't spell
+  let degrees = label
+    .get_quality()
+    .map_or(&[][..], |quality| quality.get_degrees());
+  for (value, alter, degree_type) in degrees {
+    xml.push_str(&format!(
+      "        <degree print-object=\"no\">\n          <degree-value>{}</degree-value>\n          <degree-alter>{}</degree-alter>\n          <degree-type>{}</degree-type>\n        </degree>\n",
+      value, alter, degree_type
+    ));
+  }
+  xml.push_str("      </harmony>\n");
+  xml
+}
+
+/// The notes of a chord, or a rest, as note values tied together.
+fn chord_to_notes(chord: &BarChord, fifths: i8, unit: u32) -> String {
+  let note_values = to_written_notes(chord.get_length());
+  let mut notes = chord.get_notes().clone();
+  notes.sort_unstable();
+
+  let mut xml = String::new();
+  for (i, (value, dots, units, tuplet)) in note_values.iter().enumerate() {
+    let is_tie_stop = i > 0 || chord.is_continued();
+    let is_tie_start = i + 1 < note_values.len() || chord.is_tied();
+    let note_type = note_value_to_type(*value);
+    let is_written = tuplet.is_some() || *units == notation::to_note_value_units(*value, *dots);
+
+    let pitches: Vec<Option<u8>> = if notes.is_empty() {
+      vec![None]
+    } else {
+      notes.iter().map(|note| Some(*note)).collect()
+    };
+    for (j, note) in pitches.iter().enumerate() {
+      xml.push_str("      <note>\n");
+      if j > 0 {
+        xml.push_str("        <chord/>\n");
+      }
+      match note {
+        Some(note) => {
+          let (step, alter, octave) = notation::to_pitch(*note, fifths);
+          xml.push_str(&format!(
+            "        <pitch>\n          <step>{}</step>\n",
+            step
+          ));
+          if alter != 0 {
+            xml.push_str(&format!("          <alter>{}</alter>\n", alter));
+          }
+          xml.push_str(&format!(
+            "          <octave>{}</octave>\n        </pitch>\n",
+            octave
+          ));
+        }
+        None => xml.push_str("        <rest/>\n"),
+      }
+      xml.push_str(&format!("        <duration>{}</duration>\n", units / unit));
+      if note.is_some() && is_tie_stop {
+        xml.push_str("        <tie type=\"stop\"/>\n");
+      }
+      if note.is_some() && is_tie_start {
+        xml.push_str("        <tie type=\"start\"/>\n");
+      }
+      xml.push_str("        <voice>1</voice>\n");
+      if is_written {
+        xml.push_str(&format!("        <type>{}</type>\n", note_type));
+        for _ in 0..*dots {
+          xml.push_str("        <dot/>\n");
+        }
+      }
+      if let Some((actual_notes, normal_notes)) = tuplet {
+        xml.push_str(&format!(
+          "        <time-modification>\n          <actual-notes>{}</actual-notes>\n          <normal-notes>{}</normal-notes>\n        </time-modification>\n",
+          actual_notes, normal_notes
+        ));
+      }
+      if note.is_some() && (is_tie_stop || is_tie_start) {
+        xml.push_str("        <notations>\n");
+        if is_tie_stop {
+          xml.push_str("          <tied type=\"stop\"/>\n");
+        }
+        if is_tie_start {
+          xml.push_str("          <tied type=\"start\"/>\n");
+        }
+        xml.push_str("        </notations>\n");
+      }
+      xml.push_str("      </note>\n");
+    }
+  }
+  xml
+}
+
+/// Split a length of notation units into written notes tied together. A length that isn't a sum of note values ends with a
+/// tuplet note when what is left over is one, e.g. a triplet eighth note.
+fn to_written_notes(length: u32) -> Vec<WrittenNote> {
+  let note_values = notation::to_note_values(length);
+  let is_exact = note_values
+    .iter()
+    .all(|(value, dots, units)| *units == notation::to_note_value_units(*value, *dots));
+
+  if !is_exact {
+    let mut start = 0;
+    for (i, (_, _, units)) in note_values.iter().enumerate() {
+      if let Some((value, tuplet)) = to_tuplet(length - start) {
+        return note_values[..i]
+          .iter()
+          .map(|(value, dots, units)| (*value, *dots, *units, None))
+          .chain(Some((value, 0, length - start, Some(tuplet))))
+          .collect();
+      }
+      start += units;
+    }
+  }
+
+  note_values
+    .into_iter()
+    .map(|(value, dots, units)| (value, dots, units, None))
+    .collect()
+}
+
+/// Find the note value a length of notation units is a tuplet of, and the
+/// actual and normal notes of the tuplet, e.g. 3 in the time of 2. Only
+/// tuplets in the time of a power of two notes are found.
+fn to_tuplet(length: u32) -> Option<(u32, (u32, u32))> {
+  let mut value = 1;
+  while value <= 128 {
+    let units = notation::WHOLE_NOTE_UNITS / value;
+    if units > length && units < length * 2 {
+      let divisor = notation::to_greatest_common_divisor(units, length);
+      let (actual_notes, normal_notes) = (units / divisor, length / divisor);
+      if normal_notes.is_power_of_two() {
+        return Some((value, (actual_notes, normal_notes)));
+      }
+    }
+    value *= 2;
+  }
+  None
+}
+
+/// Get the MusicXML type of a note value, 1 for a whole note up to 128.
+fn note_value_to_type(value: u32) -> &'static str {
+  match value {
+    1 => "whole",
+    2 => "half",
+    4 => "quarter",
+    8 => "eighth",
+    16 => "16th",
+    32 => "32nd",
+    64 => "64th",
+    _ => "128th",
+  }
+}
+
+fn escape_xml(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+    .replace('\'', "&apos;")
+}
+
+mod tests {
+
+  #[test]
+  fn test_composition_musicxml() {
+    use crate::io::musicxml::*;
+    use crate::theory::{
+      chords,
+      composition::{ChordEvent, ChordSymbol, Composition, Pattern},
+      notes::Key,
+    };
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = Pattern::new("verse & chorus", 90.0, TimeSignature::new(3, 4));
+    let symbol = ChordSymbol::new(
+      "MINOR_SEVENTH",
+      Key::F,
+      2,
+      chords::MINOR_SEVENTH.to_vec(),
+      3,
+    );
+    pattern
+      .push_chord_event(ChordEvent::new_with_symbol(
+        MusicTime::new(1, 1, 1),
+        0,
+        vec![67, 70, 74, 77],
+        symbol,
+      ))
+      .unwrap();
+    pattern
+      .push_event(MusicTime::new(2, 2, 1), vec![60, 64, 67])
+      .unwrap();
+    let composition = Composition::new_with_patterns("song", vec![pattern]);
+
+    let xml = composition_to_musicxml(&composition);
+    assert!(xml.contains("<work-title>song</work-title>"));
+    assert!(xml.contains("<divisions>1</divisions>"));
+    assert!(xml.contains("<fifths>-1</fifths>"));
+    assert!(xml.contains("<beats>3</beats>\n          <beat-type>4</beat-type>"));
+    assert!(xml.contains("<rehearsal>verse &amp; chorus</rehearsal>"));
+    assert!(xml.contains("<per-minute>90</per-minute>"));
+    assert!(xml.contains("<sound tempo=\"90\"/>"));
+    assert!(xml.contains(
+      "<root-step>G</root-step>\n        </root>\n        <kind text=\"m7\">minor-seventh</kind>"
+    ));
+    assert!(xml.contains("<kind text=\"\">major</kind>"));
+    assert!(
+      xml.contains("<step>B</step>\n          <alter>-1</alter>\n          <octave>4</octave>")
+    );
+    assert_eq!(xml.matches("<measure number=").count(), 2);
+
+    // The G minor seventh lasts a dotted half note then ties to a quarter note
+    assert_eq!(xml.matches("<duration>3</duration>").count(), 4);
+    assert_eq!(xml.matches("<type>half</type>").count(), 7);
+    assert_eq!(xml.matches("<tie type=\"start\"/>").count(), 4);
+    assert_eq!(xml.matches("<tie type=\"stop\"/>").count(), 4);
+  }
+
+  #[test]
+  fn test_degrees_and_tuplets_musicxml() {
+    use crate::io::musicxml::*;
+    use crate::theory::composition::{ChordEvent, Composition, Pattern};
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    // A C add9 chord then a triplet of eighth notes
+    let mut pattern = Pattern::new("a", 120.0, TimeSignature::new(2, 4));
+    pattern
+      .push_event(MusicTime::new(1, 1, 1), vec![60, 62, 64, 67])
+      .unwrap();
+    pattern
+      .push_event(MusicTime::new(1, 2, 1), vec![60, 64, 67])
+      .unwrap();
+    pattern
+      .push_chord_event(ChordEvent::new(
+        MusicTime::new(1, 2, 3),
+        40,
+        vec![62, 65, 69],
+      ))
+      .unwrap();
+    pattern
+      .push_chord_event(ChordEvent::new(
+        MusicTime::new(1, 2, 6),
+        20,
+        vec![64, 67, 71],
+      ))
+      .unwrap();
+    let composition = Composition::new_with_patterns("song", vec![pattern]);
+
+    let xml = composition_to_musicxml(&composition);
+    assert!(xml.contains(
+      "<kind text=\"add9\">major</kind>\n        <degree print-object=\"no\">\n          <degree-value>9</degree-value>\n          <degree-alter>0</degree-alter>\n          <degree-type>add</degree-type>\n        </degree>"
+    ));
+    assert_eq!(xml.matches("<degree ").count(), 1);
+
+    assert!(xml.contains("<divisions>3</divisions>"));
+    assert_eq!(xml.matches("<duration>1</duration>").count(), 9);
+    assert_eq!(xml.matches("<type>eighth</type>").count(), 9);
+    assert_eq!(
+      xml
+        .matches("<time-modification>\n          <actual-notes>3</actual-notes>\n          <normal-notes>2</normal-notes>\n        </time-modification>")
+        .count(),
+      9
+    );
+    assert!(!xml.contains("<tie "));
+  }
+}
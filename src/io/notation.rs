@@ -0,0 +1,483 @@
+use crate::{
+  chords, composition,
+  notes::{self, Key},
+};
+use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+/// The length of a whole note in notation units. Every beat interval and tick
+/// offset of the supported time signatures is a whole number of units.
+pub const WHOLE_NOTE_UNITS: u32 = 15360;
+
+/// The bars written on each line of a chart or an ABC tune.
+pub const BARS_PER_LINE: usize = 4;
+
+/// The chord symbol of a rest after a chord.
+pub const NO_CHORD: &str = "N.C.";
+
+/// The shortest note value written, a 128th note.
+const SHORTEST_NOTE_VALUE: u32 = 128;
+
+/// The chord symbol of an event, its root and how its quality is written.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ChordLabel {
+  root: Key,
+  quality: Option<chords::ChordQuality>,
+  name: String,
+}
+
+impl ChordLabel {
+  pub fn get_root(&self) -> Key {
+    self.root
+  }
+
+  /// Get how the chord is written in chord symbols, or `None` when it has no
+  /// common chord symbol.
+  pub fn get_quality(&self) -> Option<chords::ChordQuality> {
+    self.quality
+  }
+
+  /// Get the text written after the root, the quality's suffix or the chord
+  /// name in brackets when it has no common chord symbol.
+  pub fn get_suffix(&self) -> String {
+    match self.quality {
+      Some(quality) => quality.get_suffix().to_string(),
+      None => format!("({})", self.name),
+    }
+  }
+
+  /// Get the chord symbol, the root spelt in a key signature followed by the
+  /// suffix, e.g. `Bbm7`.
+  ///
+  /// # Arguments
+  /// * `fifths` - The key signature, a count of sharps, or of flats when
+  ///   negative.
+  pub fn to_symbol(&self, fifths: i8) -> String {
+    format!("{}{}", to_note_name(self.root, fifths), self.get_suffix())
+  }
+}
+
+/// Get the chord symbol of an event, or `None` for rests.
+///
+/// Events keep the root of their chord symbol. Chords without a common chord
+/// symbol, and events of midi notes, are recognised from their notes when they
+/// form an internal chord.
+pub fn to_chord_label(event: &composition::ChordEvent) -> Option<ChordLabel> {
+  if event.get_notes().is_empty() {
+    return None;
+  }
+
+  let recognised = chords::recognise_chord(event.get_notes()).map(|(name, root_note)| {
+    let (_, root) = notes::midi_to_note(root_note);
+    let quality = chords::chord_quality(&chords::string_to_chord(name));
+    (root, quality)
+  });
+
+  match (event.get_symbol(), recognised) {
+    (Some(symbol), _) if chords::chord_quality(symbol.get_intervals()).is_some() => {
+      Some(ChordLabel {
+        root: symbol.get_root(),
+        quality: chords::chord_quality(symbol.get_intervals()),
+        name: symbol.get_name().to_string(),
+      })
+    }
+    (symbol, Some((root, Some(quality)))) => Some(ChordLabel {
+      root,
+      quality: Some(quality),
+      name: symbol
+        .map_or("custom", |symbol| symbol.get_name())
+        .to_string(),
+    }),
+    (Some(symbol), _) => Some(ChordLabel {
+      root: symbol.get_root(),
+      quality: None,
+      name: symbol.get_name().to_string(),
+    }),
+    (None, _) => {
+      let lowest_note = *event.get_notes().iter().min()?;
+      let (_, root) = notes::midi_to_note(lowest_note);
+      Some(ChordLabel {
+        root,
+        quality: None,
+        name: "custom".to_string(),
+      })
+    }
+  }
+}
+
+/// Get the key a pattern was written in, the key of its first chord symbol, or
+/// C when it has none.
+pub fn to_pattern_key(pattern: &composition::Pattern) -> Key {
+  pattern
+    .get_chord_events()
+    .iter()
+    .find_map(|event| event.get_symbol())
+    .map_or(Key::C, |symbol| symbol.get_key())
+}
+
+/// Get the key signature of a major key as a count of sharps, or of flats when
+/// negative. Keys on black notes are written with flats, except F#.
+pub fn key_to_fifths(key: Key) -> i8 {
+  match key {
+    Key::C => 0,
+    Key::Cs => -5,
+    Key::D => 2,
+    Key::Ds => -3,
+    Key::E => 4,
+    Key::F => -1,
+    Key::Fs => 6,
+    Key::G => 1,
+    Key::Gs => -4,
+    Key::A => 3,
+    Key::As => -2,
+    Key::B => 5,
+  }
+}
+
+/// Get the letter and alteration of a note in a key signature. Black keys are
+/// spelt with flats in flat keys and with sharps in the key signature of sharp
+/// keys, otherwise as C#, Eb, F#, Ab and Bb.
+///
+/// # Arguments
+/// * `key` - The note to spell.
+/// * `fifths` - The key signature, a count of sharps, or of flats when negative.
+pub fn to_note_spelling(key: Key, fifths: i8) -> (char, i8) {
+  const SHARPS: [Key; 5] = [Key::Fs, Key::Cs, Key::Gs, Key::Ds, Key::As];
+  let is_sharp_in_key = SHARPS
+    .iter()
+    .take(fifths.max(0) as usize)
+    .any(|sharp| *sharp == key);
+  let is_sharp = fifths >= 0 && (is_sharp_in_key || key == Key::Cs || key == Key::Fs);
+
+  match (key, is_sharp) {
+    (Key::C, _) => ('C', 0),
+    (Key::Cs, true) => ('C', 1),
+    (Key::Cs, false) => ('D', -1),
+    (Key::D, _) => ('D', 0),
+    (Key::Ds, true) => ('D', 1),
+    (Key::Ds, false) => ('E', -1),
+    (Key::E, _) => ('E', 0),
+    (Key::F, _) => ('F', 0),
+    (Key::Fs, true) => ('F', 1),
+    (Key::Fs, false) => ('G', -1),
+    (Key::G, _) => ('G', 0),
+    (Key::Gs, true) => ('G', 1),
+    (Key::Gs, false) => ('A', -1),
+    (Key::A, _) => ('A', 0),
+    (Key::As, true) => ('A', 1),
+    (Key::As, false) => ('B', -1),
+    (Key::B, _) => ('B', 0),
+  }
+}
+
+/// Get the name of a note spelt in a key signature, e.g. `Eb` or `F#`.
+pub fn to_note_name(key: Key, fifths: i8) -> String {
+  let (letter, alter) = to_note_spelling(key, fifths);
+  match alter {
+    1 => format!("{}#", letter),
+    -1 => format!("{}b", letter),
+    _ => letter.to_string(),
+  }
+}
+
+/// Get the letter, alteration and octave of a midi note, where middle C, 60, is
+/// in octave 4.
+pub fn to_pitch(midi_note: u8, fifths: i8) -> (char, i8, i8) {
+  const NOTES_IN_OCTAVE_COUNT: i8 = 12;
+  let octave = midi_note as i8 / NOTES_IN_OCTAVE_COUNT - 1;
+  let key = notes::index_to_key(midi_note as i8 % NOTES_IN_OCTAVE_COUNT);
+  let (letter, alter) = to_note_spelling(key, fifths);
+  (letter, alter, octave)
+}
+
+/// A chord or rest of a pattern within one bar.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BarChord {
+  start: u32,
+  length: u32,
+  notes: Vec<u8>,
+  label: Option<ChordLabel>,
+  is_continued: bool,
+  is_tied: bool,
+}
+
+impl BarChord {
+  /// Get the notation units from the start of the bar to the chord.
+  pub fn get_start(&self) -> u32 {
+    self.start
+  }
+
+  /// Get the notation units the chord lasts in the bar.
+  pub fn get_length(&self) -> u32 {
+    self.length
+  }
+
+  /// Get the midi notes of the chord, empty for rests.
+  pub fn get_notes(&self) -> &Vec<u8> {
+    &self.notes
+  }
+
+  /// Get the chord symbol, or `None` for rests.
+  pub fn get_label(&self) -> Option<&ChordLabel> {
+    self.label.as_ref()
+  }
+
+  /// Get if the chord carries on from the bar before.
+  pub fn is_continued(&self) -> bool {
+    self.is_continued
+  }
+
+  /// Get if the chord carries on into the next bar.
+  pub fn is_tied(&self) -> bool {
+    self.is_tied
+  }
+}
+
+/// A bar of a pattern with its chords and tempo markings.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Bar {
+  signature: TimeSignature,
+  chords: Vec<BarChord>,
+  tempos: Vec<(u32, f32)>,
+}
+
+impl Bar {
+  pub fn get_time_signature(&self) -> TimeSignature {
+    self.signature
+  }
+
+  /// Get the notation units of a beat of the bar.
+  pub fn get_beat_length(&self) -> u32 {
+    WHOLE_NOTE_UNITS / self.signature.get_denominator() as u32
+  }
+
+  pub fn get_chords(&self) -> &Vec<BarChord> {
+    &self.chords
+  }
+
+  /// Get the tempos marked in the bar, the notation units from the start of the
+  /// bar and the beats per minute.
+  pub fn get_tempos(&self) -> &Vec<(u32, f32)> {
+    &self.tempos
+  }
+}
+
+/// Lay a pattern out in bars for notation. Each event lasts until the next
+/// event, and the last until the end of its bar. Chords crossing a bar line
+/// are split and tied, and the time before the first event is a rest.
+///
+/// The pattern's tempo is marked at the start of the first bar. Tempo changes
+/// are marked where they happen, and ramps where they arrive at their tempo.
+pub fn pattern_to_bars(pattern: &composition::Pattern) -> Vec<Bar> {
+  let end_tick = pattern.get_end_tick_index();
+  let last_bar = match pattern.get_chord_events().last() {
+    Some(event) => event.get_time().get_bar(),
+    None => return Vec::new(),
+  };
+
+  let mut events: Vec<(u32, &composition::ChordEvent)> = pattern
+    .get_chord_events()
+    .iter()
+    .map(|event| {
+      let tick = pattern.get_tick_index(event.get_time(), event.get_offset());
+      (tick, event)
+    })
+    .collect();
+  events.sort_by_key(|(tick, _)| *tick);
+
+  // The spans of ticks of each chord and rest
+  let mut spans: Vec<(u32, u32, Option<&composition::ChordEvent>)> = Vec::new();
+  if let Some((first_tick, _)) = events.first() {
+    spans.push((0, *first_tick, None));
+  }
+  for (i, (tick, event)) in events.iter().enumerate() {
+    let next_tick = events
+      .get(i + 1)
+      .map_or(end_tick, |(next_tick, _)| *next_tick);
+    spans.push((*tick, next_tick, Some(*event)));
+  }
+  spans.retain(|(start, end, _)| end > start);
+
+  let mut tempos: Vec<(u32, f32)> = vec![(0, pattern.get_bpm())];
+  for (time, change) in pattern.get_tempo_changes() {
+    tempos.push(match change {
+      composition::TempoChange::Instant(bpm) => (pattern.get_tick_index(time, 0), *bpm),
+      composition::TempoChange::Ramp(bpm, end_time) => (pattern.get_tick_index(end_time, 0), *bpm),
+    });
+  }
+
+  let mut bars = Vec::with_capacity(last_bar as usize);
+  for number in 1..=last_bar {
+    let signature = pattern.get_time_signature_at(number);
+    let bar_start = pattern.get_tick_index(&MusicTime::new(number, 1, 1), 0);
+    let bar_end = pattern.get_tick_index(&MusicTime::new(number + 1, 1, 1), 0);
+    let units_per_tick = WHOLE_NOTE_UNITS
+      / (composition::BEAT_INTERVAL_TICKS as u32 * 8)
+      / signature.get_denominator() as u32;
+    let to_units = |tick: u32| (tick - bar_start) * units_per_tick;
+
+    let chords = spans
+      .iter()
+      .filter(|(start, end, _)| *start < bar_end && *end > bar_start)
+      .map(|(start, end, event)| {
+        let notes = event.map_or(Vec::new(), |event| event.get_notes().clone());
+        let is_continued = *start < bar_start;
+        BarChord {
+          start: to_units((*start).max(bar_start)),
+          length: to_units((*end).min(bar_end)) - to_units((*start).max(bar_start)),
+          label: if is_continued {
+            None
+          } else {
+            event.and_then(to_chord_label)
+          },
+          is_continued: is_continued && !notes.is_empty(),
+          is_tied: *end > bar_end && !notes.is_empty(),
+          notes,
+        }
+      })
+      .collect();
+
+    let bar_tempos = tempos
+      .iter()
+      .filter(|(tick, _)| *tick >= bar_start && *tick < bar_end)
+      .map(|(tick, bpm)| (to_units(*tick), *bpm))
+      .collect();
+
+    bars.push(Bar {
+      signature,
+      chords,
+      tempos: bar_tempos,
+    });
+  }
+  bars
+}
+
+/// Split a length of notation units into written note values, the longest
+/// first, to be tied together. Each is the note value, 1 for a whole note up
+/// to 128, its count of dots and its notation units. A length that isn't a sum
+/// of note values adds what is left over to its last note.
+pub fn to_note_values(length: u32) -> Vec<(u32, u8, u32)> {
+  const MAX_DOTS: u8 = 2;
+
+  let mut note_values = Vec::new();
+  let mut remaining = length;
+  let mut value = 1;
+  while value <= SHORTEST_NOTE_VALUE && remaining > 0 {
+    let dotted = (0..=MAX_DOTS)
+      .rev()
+      .map(|dots| (dots, to_note_value_units(value, dots)))
+      .find(|(_, units)| *units <= remaining);
+    match dotted {
+      Some((dots, units)) => {
+        note_values.push((value, dots, units));
+        remaining -= units;
+      }
+      None => value *= 2,
+    }
+  }
+
+  match note_values.last_mut() {
+    Some((_, _, units)) => *units += remaining,
+    None if remaining > 0 => note_values.push((SHORTEST_NOTE_VALUE, 0, remaining)),
+    None => (),
+  }
+  note_values
+}
+
+/// Get the notation units of a note value, 1 for a whole note up to 128, with
+/// a count of dots.
+pub fn to_note_value_units(value: u32, dots: u8) -> u32 {
+  let units = WHOLE_NOTE_UNITS / value;
+  units * 2 - units / (1 << dots)
+}
+
+pub fn to_greatest_common_divisor(a: u32, b: u32) -> u32 {
+  match b {
+    0 => a,
+    _ => to_greatest_common_divisor(b, a % b),
+  }
+}
+
+mod tests {
+
+  #[test]
+  fn test_note_values() {
+    use crate::io::notation::*;
+
+    assert_eq!(to_note_values(WHOLE_NOTE_UNITS), vec![(1, 0, 15360)]);
+    assert_eq!(to_note_values(3840 * 3), vec![(2, 1, 11520)]);
+    assert_eq!(to_note_values(3840 * 5), vec![(1, 0, 15360), (4, 0, 3840)]);
+    assert_eq!(to_note_values(3840 * 7 / 4), vec![(4, 2, 6720)]);
+    assert_eq!(to_note_values(1920 + 960 + 8), vec![(8, 1, 2888)]);
+    assert_eq!(to_note_values(8), vec![(128, 0, 8)]);
+    assert_eq!(to_note_values(0), vec![]);
+  }
+
+  #[test]
+  fn test_pattern_bars() {
+    use crate::io::notation::*;
+    use crate::theory::composition::{ChordEvent, ChordSymbol, Pattern, TempoChange};
+
+    let mut pattern = Pattern::new("a", 100.0, TimeSignature::new(4, 4));
+    let symbol = ChordSymbol::new(
+      "MINOR_SEVENTH",
+      Key::F,
+      5,
+      chords::MINOR_SEVENTH.to_vec(),
+      3,
+    );
+    pattern
+      .push_chord_event(ChordEvent::new_with_symbol(
+        MusicTime::new(1, 2, 1),
+        0,
+        vec![70, 73, 77, 80],
+        symbol,
+      ))
+      .unwrap();
+    pattern
+      .push_event(MusicTime::new(2, 3, 1), vec![62, 66, 69])
+      .unwrap();
+    pattern.push_time_signature_change(2, TimeSignature::new(6, 8));
+    pattern.push_tempo_change(MusicTime::new(2, 1, 1), TempoChange::Instant(80.0));
+
+    let bars = pattern_to_bars(&pattern);
+    assert_eq!(bars.len(), 2);
+    assert_eq!(bars[0].get_tempos(), &vec![(0, 100.0)]);
+    assert_eq!(bars[1].get_tempos(), &vec![(0, 80.0)]);
+
+    let chords = bars[0].get_chords();
+    assert_eq!(chords.len(), 2);
+    assert!(chords[0].get_notes().is_empty());
+    assert_eq!((chords[0].get_start(), chords[0].get_length()), (0, 3840));
+    assert_eq!(
+      (chords[1].get_start(), chords[1].get_length()),
+      (3840, 11520)
+    );
+    assert!(chords[1].is_tied());
+    let label = chords[1].get_label().unwrap();
+    assert_eq!(label.get_root(), Key::As);
+    assert_eq!(label.get_suffix(), "m7");
+    assert_eq!(label.to_symbol(-1), "Bbm7");
+    assert_eq!(label.to_symbol(5), "A#m7");
+    assert_eq!(to_note_spelling(Key::As, 0), ('B', -1));
+    assert_eq!(to_note_spelling(Key::As, 5), ('A', 1));
+
+    let chords = bars[1].get_chords();
+    assert_eq!(chords.len(), 2);
+    assert!(chords[0].is_continued());
+    assert_eq!(chords[0].get_label(), None);
+    assert_eq!((chords[0].get_start(), chords[0].get_length()), (0, 3840));
+    assert_eq!(
+      (chords[1].get_start(), chords[1].get_length()),
+      (3840, 7680)
+    );
+    assert_eq!(chords[1].get_label().unwrap().get_root(), Key::D);
+
+    assert_eq!(key_to_fifths(to_pattern_key(&pattern)), -1);
+    assert_eq!(to_pitch(60, 0), ('C', 0, 4));
+    assert_eq!(to_pitch(71, -2), ('B', 0, 4));
+    assert_eq!(to_pitch(61, -2), ('D', -1, 4));
+    assert_eq!(to_pitch(61, 0), ('C', 1, 4));
+    assert_eq!(to_pitch(51, 2), ('E', -1, 3));
+    assert_eq!(to_pitch(51, 4), ('D', 1, 3));
+  }
+}
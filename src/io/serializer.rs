@@ -0,0 +1,741 @@
+use crate::{
+  arpeggiator, bass, chords, composition, drums,
+  io::deseralizer::{
+    ArpeggioParameters, BeatIntervalObject, CompositionParameters, CustomChords, DrumParameters,
+    GrooveParameters, HumanizeParameters, MasterParameters, MelodyParameters, PatternObject,
+    PatternParameters, RhythmHitObject, RhythmParameters, TimeChangeObject,
+  },
+  io::notation,
+  melody,
+  notes::{self, Key},
+};
+use music_timer::music_time::MusicTime;
+
+/// The octave chords are voiced from when a composition is loaded.
+const CHORD_OCTAVE: i8 = 3;
+
+/// Write a composition as composition YAML, which loads back as an equivalent
+/// composition.
+///
+/// # Arguments
+/// * `composition` - The composition to write.
+pub fn composition_to_yaml(
+  composition: &composition::Composition,
+) -> Result<String, crate::FailResult> {
+  parameters_to_yaml(&composition_to_parameters(composition))
+}
+
+/// Get the composition parameters of a composition.
+///
+/// Each pattern is written in the key of its melody, or of its first chord
+/// symbol. Chord symbols keep their chord names and transposes, and the chords
+/// of composition defined chord symbols are added to the composition. Events
+/// without a chord symbol, or voiced differently to it, are written as
+/// composition defined chords of their midi notes. Seeds are always written,
+/// so generated parts play the same when loaded.
+///
+/// # Arguments
+/// * `composition` - The composition to get the parameters of.
+pub fn composition_to_parameters(composition: &composition::Composition) -> CompositionParameters {
+  let mut custom_chords: CustomChords = Vec::new();
+  let mut grooves: Vec<GrooveParameters> = Vec::new();
+  let mut rhythms: Vec<RhythmParameters> = Vec::new();
+
+  let master = composition
+    .get_patterns()
+    .first()
+    .map(|pattern| {
+      (
+        to_pattern_key(pattern),
+        pattern.get_bpm(),
+        pattern.get_time_signature().as_tuple(),
+      )
+    })
+    .unwrap_or((Key::C, 120.0, (4, 4)));
+
+  let mut patterns = Vec::new();
+  for pattern in composition.get_patterns() {
+    let key = to_pattern_key(pattern);
+    let bpm = pattern.get_bpm();
+    let signature = pattern.get_time_signature().as_tuple();
+
+    let groove = pattern.get_groove().map(|groove| {
+      if !grooves
+        .iter()
+        .any(|other| other.get_name() == groove.get_name())
+      {
+        grooves.push(GrooveParameters::new(
+          groove.get_name(),
+          groove.get_note_value(),
+          groove.get_timing().clone(),
+          groove.get_velocity().clone(),
+        ));
+      }
+      groove.get_name().to_string()
+    });
+
+    let rhythm = pattern.get_rhythm().map(|rhythm| {
+      if !rhythms
+        .iter()
+        .any(|other| other.get_name() == rhythm.get_name())
+      {
+        let hits = rhythm
+          .get_hits()
+          .iter()
+          .map(|hit| match hit {
+            (beat, beat_interval, None) => RhythmHitObject::Hit(*beat, *beat_interval),
+            (beat, beat_interval, Some(length)) => {
+              RhythmHitObject::HitWithLength(*beat, *beat_interval, *length)
+            }
+          })
+          .collect();
+        rhythms.push(RhythmParameters::new(
+          rhythm.get_name(),
+          rhythm.get_beats(),
+          hits,
+        ));
+      }
+      rhythm.get_name().to_string()
+    });
+
+    let swing = pattern
+      .get_swing()
+      .map(|swing| (swing.get_note_value(), swing.get_ratio()));
+    let humanize = pattern.get_humanize().map(|humanize| {
+      HumanizeParameters::new(
+        humanize.get_timing(),
+        humanize.get_spread(),
+        humanize.get_velocity(),
+        Some(humanize.get_seed()),
+      )
+    });
+    let is_master_overridden = (key, bpm, signature) != master
+      || swing.is_some()
+      || groove.is_some()
+      || humanize.is_some()
+      || rhythm.is_some();
+    let pattern_master = if is_master_overridden {
+      Some(MasterParameters::new(
+        Some(notes::key_to_string(key).to_string()).filter(|_| key != master.0),
+        Some(bpm).filter(|_| bpm != master.1),
+        Some(signature).filter(|_| signature != master.2),
+        swing,
+        groove,
+        humanize,
+        rhythm,
+      ))
+    } else {
+      None
+    };
+
+    let time_changes = pattern
+      .get_tempo_changes()
+      .iter()
+      .map(|(time, change)| {
+        let (bar, beat, beat_interval) =
+          (time.get_bar(), time.get_beat(), time.get_beat_interval());
+        match change {
+          composition::TempoChange::Instant(bpm) => {
+            TimeChangeObject::Instant(bar, beat, beat_interval, *bpm)
+          }
+          composition::TempoChange::Ramp(bpm, end_time) => TimeChangeObject::Ramp(
+            bar,
+            beat,
+            beat_interval,
+            *bpm,
+            (
+              end_time.get_bar(),
+              end_time.get_beat(),
+              end_time.get_beat_interval(),
+            ),
+          ),
+        }
+      })
+      .collect();
+
+    let signature_changes = pattern
+      .get_time_signature_changes()
+      .iter()
+      .map(|(bar, signature)| (*bar, signature.as_tuple()))
+      .collect();
+
+    let arpeggio = pattern.get_arpeggiator().map(|arpeggiator| {
+      ArpeggioParameters::new(
+        arpeggiator::order_to_string(arpeggiator.get_order()),
+        arpeggiator.get_rate(),
+        arpeggiator.get_octaves(),
+        arpeggiator.get_gate(),
+        Some(arpeggiator.get_seed()),
+      )
+    });
+
+    let melody = pattern.get_melody().map(|melody| {
+      MelodyParameters::new(
+        melody::scale_to_string(melody.get_scale()),
+        melody.get_rate(),
+        melody.get_density(),
+        melody.get_range(),
+        melody::contour_to_string(melody.get_contour()),
+        Some(melody.get_seed()),
+      )
+    });
+
+    let events = pattern
+      .get_chord_events()
+      .iter()
+      .map(|event| event_to_pattern_object(event, key, &mut custom_chords))
+      .collect();
+
+    patterns.push(PatternParameters::new(
+      pattern.get_name(),
+      pattern_master,
+      time_changes,
+      signature_changes,
+      Some(pattern.get_channel()).filter(|channel| *channel != 0),
+      arpeggio,
+      pattern
+        .get_bass()
+        .map(|style| bass::bass_style_to_string(style).to_string()),
+      melody,
+      pattern.get_drums().map(drums_to_parameters),
+      events,
+    ));
+  }
+
+  let (key, bpm, signature) = master;
+  CompositionParameters::new_with_patterns(
+    composition.get_name(),
+    MasterParameters::new(
+      Some(notes::key_to_string(key).to_string()),
+      Some(bpm),
+      Some(signature),
+      None,
+      None,
+      None,
+      None,
+    ),
+    custom_chords,
+    grooves,
+    rhythms,
+    patterns,
+  )
+}
+
+/// Write composition parameters as composition YAML.
+///
+/// # Arguments
+/// * `parameters` - The composition parameters to write.
+pub fn parameters_to_yaml(parameters: &CompositionParameters) -> Result<String, crate::FailResult> {
+  match serde_yaml::to_string(parameters) {
+    Ok(yaml) => Ok(yaml),
+    _ => Err(crate::FailResult::Serialize),
+  }
+}
+
+/// Get the key a pattern is written in, the key of its melody, or of its first
+/// chord symbol.
+fn to_pattern_key(pattern: &composition::Pattern) -> Key {
+  match pattern.get_melody() {
+    Some(melody) => melody.get_key(),
+    None => notation::to_pattern_key(pattern),
+  }
+}
+
+/// Get the drum parameters of drums, as their style when they are named after
+/// one, with a grid lane for each lane that isn't the style's.
+fn drums_to_parameters(pattern_drums: &drums::Drums) -> DrumParameters {
+  let style = drums::string_to_drum_style(pattern_drums.get_name()).filter(|style| {
+    style
+      .get_lanes()
+      .iter()
+      .all(|(note, _)| pattern_drums.get_lanes().iter().any(|lane| lane.0 == *note))
+  });
+
+  let grid = pattern_drums
+    .get_lanes()
+    .iter()
+    .filter(|lane| match &style {
+      Some(style) => !style.get_lanes().contains(lane),
+      None => true,
+    })
+    .filter_map(|(note, velocities)| {
+      let drum = drums::note_to_drum_name(*note)?;
+      Some((drum.to_string(), drums::velocities_to_grid(velocities)))
+    })
+    .collect();
+
+  DrumParameters::new(
+    style.map(|style| style.get_name().to_string()),
+    pattern_drums.get_note_value(),
+    grid,
+  )
+}
+
+fn event_to_pattern_object(
+  event: &composition::ChordEvent,
+  key: Key,
+  custom_chords: &mut CustomChords,
+) -> PatternObject {
+  let time = event.get_time();
+  let (chord_name, transpose) = event_to_chord(event, key, custom_chords);
+  (
+    time.get_bar(),
+    time.get_beat(),
+    to_beat_interval_object(time, event.get_offset()),
+    chord_name,
+    transpose,
+  )
+}
+
+/// The beat interval of an event, or a `"k/n"` tuplet when it is offset from
+/// its beat interval.
+fn to_beat_interval_object(time: &MusicTime, offset: u8) -> BeatIntervalObject {
+  const BEAT_INTERVALS: u32 = 8;
+  let interval_ticks = composition::BEAT_INTERVAL_TICKS as u32;
+  let beat_ticks = interval_ticks * BEAT_INTERVALS;
+
+  if offset == 0 {
+    return BeatIntervalObject::Interval(time.get_beat_interval());
+  }
+
+  let ticks = (time.get_beat_interval() as u32 - 1) * interval_ticks + offset as u32;
+  let divisor = notation::to_greatest_common_divisor(ticks, beat_ticks);
+  BeatIntervalObject::Tuplet(format!("{}/{}", ticks / divisor + 1, beat_ticks / divisor))
+}
+
+/// Get the chord name and transpose of an event in the key of its pattern,
+/// adding a composition defined chord when it isn't an internal chord.
+fn event_to_chord(
+  event: &composition::ChordEvent,
+  key: Key,
+  custom_chords: &mut CustomChords,
+) -> (String, i8) {
+  const MIDDLE_C: i8 = 60;
+  let key_index = notes::key_to_index(key) as i8;
+
+  let symbol = event
+    .get_symbol()
+    .and_then(|symbol| Some((symbol, to_symbol_transpose(symbol, key_index)?)));
+  if let Some((symbol, transpose)) = symbol {
+    let name = symbol.get_name();
+    let intervals = symbol.get_intervals();
+    let is_voiced =
+      to_chord_notes(intervals, key_index as i16 + transpose as i16) == *event.get_notes();
+    let internal_intervals = chords::string_to_chord(name);
+
+    if name == chords::REST_NAME && event.get_notes().is_empty() {
+      return (name.to_string(), 0);
+    } else if is_voiced && !internal_intervals.is_empty() {
+      if internal_intervals == *intervals {
+        return (name.to_string(), transpose);
+      }
+    } else if is_voiced && is_custom_chord_name(name) {
+      let is_name_free = custom_chords.iter().all(|(custom_name, custom_intervals)| {
+        custom_name != name || custom_intervals == intervals
+      });
+      if is_name_free {
+        if !custom_chords
+          .iter()
+          .any(|(custom_name, _)| custom_name == name)
+        {
+          custom_chords.push((name.to_string(), intervals.clone()));
+        }
+        return (name.to_string(), transpose);
+      }
+    }
+  }
+
+  let intervals: Vec<i8> = event
+    .get_notes()
+    .iter()
+    .map(|note| *note as i8 - MIDDLE_C)
+    .collect();
+  let name = match custom_chords
+    .iter()
+    .find(|(_, custom_intervals)| *custom_intervals == intervals)
+  {
+    Some((name, _)) => name.clone(),
+    None => {
+      let name = (1..)
+        .map(|count| format!("custom_{}", count))
+        .find(|name| {
+          custom_chords
+            .iter()
+            .all(|(custom_name, _)| custom_name != name)
+        })
+        .unwrap_or_default();
+      custom_chords.push((name.clone(), intervals));
+      name
+    }
+  };
+  (name, -key_index)
+}
+
+/// Get the transpose of a chord symbol from the key of its pattern, at the
+/// octave chords are voiced from. Returns `None` if it is outside `i8`.
+fn to_symbol_transpose(symbol: &composition::ChordSymbol, key_index: i8) -> Option<i8> {
+  const NOTES_IN_OCTAVE_COUNT: i16 = 12;
+  let transpose = notes::key_to_index(symbol.get_key()) as i16 - key_index as i16
+    + symbol.get_transpose() as i16
+    + (symbol.get_octave() as i16 - CHORD_OCTAVE as i16) * NOTES_IN_OCTAVE_COUNT;
+  if transpose < i8::MIN as i16 || transpose > i8::MAX as i16 {
+    None
+  } else {
+    Some(transpose as i8)
+  }
+}
+
+/// Get the midi notes of chord intervals transposed from C, as they are voiced
+/// when a composition is loaded, kept within the notes `notes::to_midi_note`
+/// resolves to.
+fn to_chord_notes(intervals: &[i8], transpose: i16) -> Vec<u8> {
+  const NOTES_IN_OCTAVE_COUNT: i16 = 12;
+  const MIN_VALUE: i16 = 24;
+  const MAX_VALUE: i16 = 107;
+  intervals
+    .iter()
+    .map(|interval| {
+      let note =
+        MIN_VALUE + *interval as i16 + transpose + CHORD_OCTAVE as i16 * NOTES_IN_OCTAVE_COUNT;
+      note.clamp(MIN_VALUE, MAX_VALUE) as u8
+    })
+    .collect()
+}
+
+/// Whether a chord symbol name can be written as a composition defined chord,
+/// a name that isn't reserved and is read back unchanged.
+fn is_custom_chord_name(name: &str) -> bool {
+  !name.is_empty()
+    && name.trim() == name
+    && name != chords::REST_NAME
+    && name != chords::RANDOM_NAME
+    && name != chords::RANDOM_CUSTOM_NAME
+}
+
+mod tests {
+
+  #[test]
+  fn test_beat_interval_to_yaml() {
+    use crate::io::serializer::*;
+
+    assert_eq!(
+      to_beat_interval_object(&MusicTime::new(1, 1, 5), 0),
+      BeatIntervalObject::Interval(5)
+    );
+    assert_eq!(
+      to_beat_interval_object(&MusicTime::new(1, 1, 3), 40),
+      BeatIntervalObject::Tuplet("2/3".to_string())
+    );
+    assert_eq!(
+      to_beat_interval_object(&MusicTime::new(1, 2, 1), 1),
+      BeatIntervalObject::Tuplet("2/480".to_string())
+    );
+  }
+
+  #[test]
+  fn test_composition_round_trip() {
+    use crate::io::{deseralizer, serializer::*};
+
+    let yaml = r#"
+      name: round trip
+
+      master:
+          key: D
+          time: 128
+          signature: [3, 4]
+
+      chords:
+          - [custom1, [0, 3, 8]]
+
+      grooves:
+          - name: laid_back
+            subdivision: 8
+            timing: [0, 10, 0, 20]
+            velocity: [10, -6, 4, -6]
+
+      rhythms:
+          - name: charleston
+            beats: 3
+            hits:
+                - [1, 1, 3]
+                - [2, 5]
+
+      patterns:
+          - name: part_a
+            master:
+                swing: [8, 0.6]
+                groove: laid_back
+                humanize:
+                    timing: 8
+                    spread: 4
+                    velocity: 6
+                    seed: 1
+                rhythm: charleston
+            time_changes:
+                - [2, 1, 1, 100, [3, 1, 1]]
+            arpeggio:
+                order: up_down
+                rate: 16
+                octaves: 2
+                gate: 0.8
+                seed: 2
+            bass: walking
+            pattern:
+                - [1, 1, 1, MAJOR_SEVENTH, 0]
+                - [1, 3, "2/3", custom1, -2]
+                - [2, 1, 1, REST, 0]
+                - [3, 1, 1, MAJOR_NINTH, 14]
+
+          - name: part_b
+            master:
+                key: C#
+                time: 69
+                signature: [4, 8]
+            signature_changes:
+                - [2, [3, 8]]
+            melody:
+                scale: dorian
+                density: 0.75
+                seed: 3
+            drums:
+                style: rock
+                grid:
+                    - [crash, "X......."]
+            pattern:
+                - [1, 1, 1, MINOR_SEVENTH, 5]
+                - [2, 2, 1, custom1, 0]
+    "#;
+
+    let composition =
+      crate::parameters_to_composition(&deseralizer::deserialize_string(yaml).unwrap()).unwrap();
+    let saved_yaml = composition_to_yaml(&composition).unwrap();
+    let saved_composition =
+      crate::parameters_to_composition(&deseralizer::deserialize_string(&saved_yaml).unwrap())
+        .unwrap();
+
+    assert_eq!(composition_to_yaml(&saved_composition).unwrap(), saved_yaml);
+    let parameters = deseralizer::deserialize_string(&saved_yaml).unwrap();
+    assert_eq!(
+      parameters.get_master(),
+      &Some(MasterParameters::new(
+        Some("D".to_string()),
+        Some(128.0),
+        Some((3, 4)),
+        None,
+        None,
+        None,
+        None
+      ))
+    );
+    assert_eq!(
+      parameters.get_custom_chords(),
+      &Some(vec![("custom1".to_string(), vec![0, 3, 8])])
+    );
+    let saved_patterns = parameters.get_patterns().as_ref().unwrap();
+    let saved_events = saved_patterns[0].get_pattern().as_ref().unwrap();
+    assert_eq!(
+      saved_events[1],
+      (
+        1,
+        3,
+        BeatIntervalObject::Tuplet("2/3".to_string()),
+        "custom1".to_string(),
+        -2
+      )
+    );
+    assert_eq!(
+      saved_events[3],
+      (
+        3,
+        1,
+        BeatIntervalObject::Interval(1),
+        "MAJOR_NINTH".to_string(),
+        14
+      )
+    );
+    let saved_master = saved_patterns[1].get_master().as_ref().unwrap();
+    assert_eq!(saved_master.get_key(), Some("C#".to_string()));
+    assert_eq!(saved_master.get_time(), Some(69.0));
+    assert_eq!(
+      saved_patterns[1].get_drums(),
+      &Some(DrumParameters::new(
+        Some("rock".to_string()),
+        16,
+        vec![("crash_cymbal_1".to_string(), "X.......".to_string())]
+      ))
+    );
+
+    assert_eq!(saved_composition.get_name(), "round trip");
+    let patterns = composition.get_patterns().iter();
+    for (pattern, saved_pattern) in patterns.zip(saved_composition.get_patterns()) {
+      assert_eq!(saved_pattern.get_name(), pattern.get_name());
+      assert_eq!(saved_pattern.get_chord_events(), pattern.get_chord_events());
+      assert_eq!(
+        saved_pattern.get_performed_events(),
+        pattern.get_performed_events()
+      );
+      assert_eq!(saved_pattern.get_drum_notes(), pattern.get_drum_notes());
+      assert_eq!(
+        saved_pattern
+          .get_bass_line()
+          .map(|line| line.get_chord_events().clone()),
+        pattern
+          .get_bass_line()
+          .map(|line| line.get_chord_events().clone())
+      );
+      assert_eq!(
+        saved_pattern
+          .get_melody_line()
+          .map(|line| line.get_chord_events().clone()),
+        pattern
+          .get_melody_line()
+          .map(|line| line.get_chord_events().clone())
+      );
+      assert_eq!(saved_pattern.get_drums(), pattern.get_drums());
+    }
+  }
+
+  #[test]
+  fn test_built_composition_to_yaml() {
+    use crate::io::{deseralizer, serializer::*};
+    use crate::theory::composition::{ChordEvent, ChordSymbol, Composition, Pattern};
+    use music_timer::time_signature::TimeSignature;
+
+    let mut pattern = Pattern::new("built", 90.0, TimeSignature::new(4, 4));
+    pattern
+      .push_chord_event(ChordEvent::new_with_symbol(
+        MusicTime::new(1, 1, 1),
+        0,
+        vec![65, 69, 72],
+        ChordSymbol::new("MAJOR", Key::F, 0, chords::MAJOR.to_vec(), 3),
+      ))
+      .unwrap();
+    pattern
+      .push_chord_event(ChordEvent::new_with_symbol(
+        MusicTime::new(1, 3, 1),
+        0,
+        vec![62, 65, 69],
+        ChordSymbol::new("MINOR", Key::C, 2, chords::MINOR.to_vec(), 3),
+      ))
+      .unwrap();
+    pattern
+      .push_event(MusicTime::new(2, 1, 1), vec![60, 64, 70])
+      .unwrap();
+    pattern
+      .push_chord_event(ChordEvent::new_with_symbol(
+        MusicTime::new(2, 3, 1),
+        0,
+        vec![62, 64, 69],
+        ChordSymbol::new("sus2", Key::F, -3, vec![0, 2, 7], 3),
+      ))
+      .unwrap();
+    let composition = Composition::new_with_patterns("built", vec![pattern]);
+
+    let yaml = composition_to_yaml(&composition).unwrap();
+    let parameters = deseralizer::deserialize_string(&yaml).unwrap();
+    assert_eq!(
+      parameters.get_master().as_ref().unwrap().get_key(),
+      Some("F".to_string())
+    );
+    assert_eq!(
+      parameters.get_custom_chords(),
+      &Some(vec![
+        ("custom_1".to_string(), vec![0, 4, 10]),
+        ("sus2".to_string(), vec![0, 2, 7])
+      ])
+    );
+    let chords: Vec<(String, i8)> = parameters.get_patterns().as_ref().unwrap()[0]
+      .get_pattern()
+      .iter()
+      .flatten()
+      .map(|(_, _, _, chord_name, transpose)| (chord_name.clone(), *transpose))
+      .collect();
+    assert_eq!(
+      chords,
+      vec![
+        ("MAJOR".to_string(), 0),
+        ("MINOR".to_string(), -3),
+        ("custom_1".to_string(), -5),
+        ("sus2".to_string(), -3)
+      ]
+    );
+
+    let saved_composition =
+      crate::parameters_to_composition(&deseralizer::deserialize_string(&yaml).unwrap()).unwrap();
+    let notes: Vec<&Vec<u8>> = saved_composition.get_patterns()[0]
+      .get_chord_events()
+      .iter()
+      .map(|event| event.get_notes())
+      .collect();
+    assert_eq!(
+      notes,
+      vec![
+        &vec![65, 69, 72],
+        &vec![62, 65, 69],
+        &vec![60, 64, 70],
+        &vec![62, 64, 69]
+      ]
+    );
+  }
+
+  #[test]
+  fn test_control_characters_round_trip() {
+    use crate::io::{deseralizer, serializer::*};
+    use crate::theory::composition::{ChordEvent, ChordSymbol, Composition, Pattern};
+    use music_timer::time_signature::TimeSignature;
+
+    let mut pattern = Pattern::new("verse\n\t\"one\"", 120.0, TimeSignature::new(4, 4));
+    pattern
+      .push_chord_event(ChordEvent::new_with_symbol(
+        MusicTime::new(1, 1, 1),
+        0,
+        vec![60, 62, 67],
+        ChordSymbol::new("sus\n2", Key::C, 0, vec![0, 2, 7], 3),
+      ))
+      .unwrap();
+    let composition = Composition::new_with_patterns("line\nbreak\\", vec![pattern]);
+
+    let yaml = composition_to_yaml(&composition).unwrap();
+    let saved_composition =
+      crate::parameters_to_composition(&deseralizer::deserialize_string(&yaml).unwrap()).unwrap();
+
+    assert_eq!(saved_composition.get_name(), "line\nbreak\\");
+    let saved_pattern = &saved_composition.get_patterns()[0];
+    assert_eq!(saved_pattern.get_name(), "verse\n\t\"one\"");
+    assert_eq!(
+      saved_pattern.get_chord_events(),
+      composition.get_patterns()[0].get_chord_events()
+    );
+    assert_eq!(composition_to_yaml(&saved_composition).unwrap(), yaml);
+  }
+
+  #[test]
+  fn test_out_of_range_transpose_to_yaml() {
+    use crate::io::serializer::*;
+    use crate::theory::composition::{ChordEvent, ChordSymbol};
+
+    let mut custom_chords = Vec::new();
+    let high_octave = ChordEvent::new_with_symbol(
+      MusicTime::new(1, 1, 1),
+      0,
+      vec![60, 64, 67],
+      ChordSymbol::new("MAJOR", Key::B, 100, chords::MAJOR.to_vec(), 9),
+    );
+    assert_eq!(
+      event_to_chord(&high_octave, Key::C, &mut custom_chords),
+      ("custom_1".to_string(), 0)
+    );
+
+    let high_transpose = ChordEvent::new_with_symbol(
+      MusicTime::new(1, 1, 1),
+      0,
+      vec![60, 64, 67],
+      ChordSymbol::new("MAJOR", Key::C, 120, chords::MAJOR.to_vec(), 3),
+    );
+    assert_eq!(
+      event_to_chord(&high_transpose, Key::B, &mut custom_chords),
+      ("custom_1".to_string(), -11)
+    );
+    assert_eq!(custom_chords, vec![("custom_1".to_string(), vec![0, 4, 7])]);
+  }
+}
@@ -9,20 +9,115 @@ pub mod theory;
 use music_timer::{music_time, time_signature};
 use performance::performance_engine;
 use std::{io::Write, path::Path};
-use theory::{chords, composition, notes};
+use theory::{
+  arpeggiator, bass, chords, composition, drums, groove, humanize, melody, notes, rhythm,
+};
 
 /// Possible failures.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum FailResult {
   Deserialize,
-  ExportMIDI,
+  /// The composition parameters couldn't be written as composition YAML.
+  Serialize,
+  /// The midi file couldn't be written, with its path.
+  ExportMIDI(String),
+  /// The midi couldn't be written to the writer, with the error.
+  WriteMIDI(String),
   ExportTemplate,
+  /// The composition YAML file couldn't be written, with its path.
+  ExportYAML(String),
+  /// The MusicXML file couldn't be written, with its path.
+  ExportMusicXML(String),
+  /// The LilyPond file couldn't be written, with its path.
+  ExportLilyPond(String),
+  /// The ABC file couldn't be written, with its path.
+  ExportABC(String),
+  /// The chart or ChordPro file couldn't be written, with its path.
+  ExportChart(String),
+  /// The export would replace a file or directory it didn't write, with its
+  /// path.
+  ExportExists(String),
+  /// The manifest listing the files of an export couldn't be written, with its
+  /// path.
+  ExportManifest(String),
+  /// The export directory couldn't be created, with its path.
+  CreateDirectory(String),
+  /// A file of an earlier export couldn't be removed, with its path.
+  RemoveFile(String),
+  ImportMIDI,
+  ImportABC,
+  /// A chord symbol of an ABC tune isn't known, with the chord symbol.
+  UnknownChordSymbol(String),
+  /// The midi file has no track at the index.
+  NoFoundTrack(usize),
   NoPatterns,
   NoFoundPattern(String),
+  NoFoundPatternIndex(usize),
+  NoFoundEvent(usize),
   EmptyPatterns,
   TimeReverse(music_time::MusicTime, usize, String),
   UnreachableTime(music_time::MusicTime, usize, String),
+  /// A beat interval tuplet isn't `k/n`, the k-th of n divisions of the beat,
+  /// with n dividing the 480 ticks of a beat, with the tuplet.
+  Tuplet(String),
+  /// An event's chord name isn't known, with the pattern name, the event index,
+  /// the chord name and suggested chord names.
+  UnknownChord(String, usize, String, Vec<String>),
+  UnreachableTimeChange(music_time::MusicTime, usize),
+  UnreachableSignatureChange(u16, usize),
+  /// Transposing would move a note outside the midi range, with the semitones.
+  TransposeRange(i8),
+  /// Shifting would move an event before the start of the pattern or past the
+  /// last bar, with the bars and beats.
+  ShiftRange(i32, i32),
+  /// The stretch factor isn't positive, or would move an event past the last
+  /// bar.
+  Stretch(f32),
+  /// The pattern has no tempo change at the index.
+  NoFoundTimeChange(usize),
+  /// The pattern has no time signature change at the index.
+  NoFoundSignatureChange(usize),
   TimeSignature(time_signature::TimeSignature),
+  /// Overlaid patterns have different time signatures, with the first bar
+  /// where they differ.
+  OverlayTimeSignature(u16),
+  Tempo(f32),
+  /// The swing note value isn't 8 or 16, or the ratio is outside 0.5 to 0.75.
+  Swing(u8, f32),
+  /// The groove template's subdivision isn't supported, or a timing offset is
+  /// a whole subdivision or more.
+  Groove(String),
+  NoFoundGroove(String),
+  /// The arpeggiator's rate, octaves or gate isn't supported.
+  Arpeggio(u8, u8, f32),
+  UnknownArpeggioOrder(String),
+  /// The rhythm template has no hits, or a hit is outside the template or has
+  /// no length.
+  Rhythm(String),
+  NoFoundRhythm(String),
+  UnknownBassStyle(String),
+  /// The drum pattern's step note value isn't supported, or it has no lanes,
+  /// a lane of a drum outside the General MIDI drum map or a lane without steps.
+  Drums(String),
+  UnknownDrumStyle(String),
+  /// A named drum style was given a subdivision other than the one its grid is
+  /// written in.
+  DrumSubdivision(u8),
+  UnknownDrum(String),
+  /// The drum grid has a step that isn't `X`, `x`, `o`, `.` or `-`.
+  DrumGrid(String),
+  /// The melody's rate, density or range isn't supported.
+  Melody(u8, f32, u8, u8),
+  UnknownScale(String),
+  UnknownContour(String),
+  /// The midi channel isn't from 0 to 15.
+  MidiChannel(u8),
+  /// The pulses per quarter note aren't from 1 to 32767.
+  MidiPpq(u16),
+  /// The velocity isn't from 1 to 127.
+  MidiVelocity(u8),
+  /// The General MIDI program isn't from 0 to 127.
+  MidiProgram(u8),
   LoadSampler,
 }
 
@@ -34,6 +129,302 @@ pub enum SuccessResult {
   Playback,
 }
 
+/// The layout of an exported Standard MIDI File.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MidiFileFormat {
+  /// Format 0, every event in a single track.
+  SingleTrack,
+  /// Format 1, a meta track followed by a track for each part.
+  MultiTrack,
+}
+
+/// How an export treats files already at its path.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ExportMode {
+  /// Fail when the export directory or file already exists.
+  Refuse,
+  /// Replace the files of an earlier export, failing when any other file is in
+  /// the way. Exports to a directory list the files they write in a
+  /// `.chord_composer_export` file in it, and only the files it lists are
+  /// replaced. No existing file is replaced in a directory without one. Single
+  /// file exports replace the file at their path.
+  Overwrite,
+  /// Export to the first free path, adding `_2`, `_3` and so on to the name.
+  Versioned,
+}
+
+/// Settings of the midi files exported. The defaults are 480 pulses per
+/// quarter note, a multi-track file with a meta track, each pattern's own
+/// channel, a velocity of 64, no program change and overwriting an earlier
+/// export.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct MidiExportOptions {
+  ppq: u16,
+  format: MidiFileFormat,
+  channel: Option<u8>,
+  velocity: u8,
+  program: Option<u8>,
+  is_meta_track_included: bool,
+  mode: ExportMode,
+}
+
+impl Default for MidiExportOptions {
+  fn default() -> Self {
+    MidiExportOptions {
+      ppq: 480,
+      format: MidiFileFormat::MultiTrack,
+      channel: None,
+      velocity: composition::DEFAULT_VELOCITY,
+      program: None,
+      is_meta_track_included: true,
+      mode: ExportMode::Overwrite,
+    }
+  }
+}
+
+impl MidiExportOptions {
+  /// Set the pulses per quarter note, the resolution of the file.
+  pub fn with_ppq(mut self, ppq: u16) -> Self {
+    self.ppq = ppq;
+    self
+  }
+
+  pub fn with_format(mut self, format: MidiFileFormat) -> Self {
+    self.format = format;
+    self
+  }
+
+  /// Set the midi channel of the chords of every pattern, from 0 to 15.
+  /// Bass lines, melodies and drums keep their own channels.
+  pub fn with_channel(mut self, channel: u8) -> Self {
+    self.channel = Some(channel);
+    self
+  }
+
+  /// Set the velocity of notes before grooves, humanize and drum accents move
+  /// them, from 1 to 127.
+  pub fn with_velocity(mut self, velocity: u8) -> Self {
+    self.velocity = velocity;
+    self
+  }
+
+  /// Set the General MIDI program of each pitched part, from 0 to 127.
+  pub fn with_program(mut self, program: u8) -> Self {
+    self.program = Some(program);
+    self
+  }
+
+  /// Set if the tempo, time signature and marker meta events are exported.
+  pub fn with_meta_track(mut self, is_meta_track_included: bool) -> Self {
+    self.is_meta_track_included = is_meta_track_included;
+    self
+  }
+
+  /// Set how files already at the export path are treated.
+  pub fn with_mode(mut self, mode: ExportMode) -> Self {
+    self.mode = mode;
+    self
+  }
+
+  pub fn get_ppq(&self) -> u16 {
+    self.ppq
+  }
+
+  pub fn get_format(&self) -> MidiFileFormat {
+    self.format
+  }
+
+  pub fn get_channel(&self) -> Option<u8> {
+    self.channel
+  }
+
+  pub fn get_velocity(&self) -> u8 {
+    self.velocity
+  }
+
+  pub fn get_program(&self) -> Option<u8> {
+    self.program
+  }
+
+  pub fn is_meta_track_included(&self) -> bool {
+    self.is_meta_track_included
+  }
+
+  pub fn get_mode(&self) -> ExportMode {
+    self.mode
+  }
+
+  /// Check the settings can be written to a midi file.
+  pub fn check(&self) -> Result<(), FailResult> {
+    const MAX_PPQ: u16 = 0x7FFF;
+    const MAX_CHANNEL: u8 = 15;
+    const MAX_MIDI_VALUE: u8 = 127;
+
+    match self {
+      MidiExportOptions { ppq, .. } if *ppq == 0 || *ppq > MAX_PPQ => {
+        Err(FailResult::MidiPpq(*ppq))
+      }
+      MidiExportOptions {
+        channel: Some(channel),
+        ..
+      } if *channel > MAX_CHANNEL => Err(FailResult::MidiChannel(*channel)),
+      MidiExportOptions { velocity, .. } if *velocity == 0 || *velocity > MAX_MIDI_VALUE => {
+        Err(FailResult::MidiVelocity(*velocity))
+      }
+      MidiExportOptions {
+        program: Some(program),
+        ..
+      } if *program > MAX_MIDI_VALUE => Err(FailResult::MidiProgram(*program)),
+      _ => Ok(()),
+    }
+  }
+}
+
+/// Settings of composition playback. The defaults are no metronome and no
+/// audio samples, so only the performance state callbacks are triggered.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct PlaybackOptions {
+  is_metronome_enabled: bool,
+  sample_paths_metronome: Vec<String>,
+  sample_paths_piano: Vec<String>,
+  sample_paths_drums: Vec<String>,
+}
+
+impl PlaybackOptions {
+  /// Set if the metronome is to be played during playback. This is only
+  /// relevant when metronome samples are set and the compiler feature
+  /// `with-sound` is used.
+  pub fn with_metronome(mut self, is_metronome_enabled: bool) -> Self {
+    self.is_metronome_enabled = is_metronome_enabled;
+    self
+  }
+
+  /// Set the paths to the 2 metronome audio files, tick and tock.
+  pub fn with_metronome_samples(mut self, sample_paths_metronome: Vec<String>) -> Self {
+    self.sample_paths_metronome = sample_paths_metronome;
+    self
+  }
+
+  /// Set the paths to the playback instrument audio files.
+  pub fn with_piano_samples(mut self, sample_paths_piano: Vec<String>) -> Self {
+    self.sample_paths_piano = sample_paths_piano;
+    self
+  }
+
+  /// Set the paths to the drum audio files, one for each General MIDI drum
+  /// from the acoustic bass drum, note 35, to the open triangle, note 81.
+  pub fn with_drum_samples(mut self, sample_paths_drums: Vec<String>) -> Self {
+    self.sample_paths_drums = sample_paths_drums;
+    self
+  }
+
+  pub fn is_metronome_enabled(&self) -> bool {
+    self.is_metronome_enabled
+  }
+
+  pub fn get_metronome_samples(&self) -> &Vec<String> {
+    &self.sample_paths_metronome
+  }
+
+  pub fn get_piano_samples(&self) -> &Vec<String> {
+    &self.sample_paths_piano
+  }
+
+  pub fn get_drum_samples(&self) -> &Vec<String> {
+    &self.sample_paths_drums
+  }
+}
+
+/// The severity of a `ValidationIssue`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+  /// The composition can't be built.
+  Error,
+  /// The composition can be built, but may not be as intended.
+  Warning,
+}
+
+/// Possible warnings found when validating a composition.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ValidationWarning {
+  /// More than one pattern has the same name.
+  DuplicatePatternName,
+  /// An event is placed after the pattern's `bars` count.
+  EventPastBarCount(u16),
+  /// An event's custom chord has no notes and will be silent. Use `REST` for
+  /// silence.
+  EmptyChord,
+}
+
+/// The kind of problem found when validating a composition.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ValidationKind {
+  Error(FailResult),
+  Warning(ValidationWarning),
+}
+
+/// A problem found when validating a composition.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ValidationIssue {
+  kind: ValidationKind,
+  pattern_name: String,
+  index: Option<usize>,
+  value: String,
+}
+
+impl ValidationIssue {
+  fn error(error: FailResult, pattern_name: &str, index: Option<usize>, value: &str) -> Self {
+    ValidationIssue {
+      kind: ValidationKind::Error(error),
+      pattern_name: pattern_name.to_string(),
+      index,
+      value: value.to_string(),
+    }
+  }
+
+  fn warning(
+    warning: ValidationWarning,
+    pattern_name: &str,
+    index: Option<usize>,
+    value: &str,
+  ) -> Self {
+    ValidationIssue {
+      kind: ValidationKind::Warning(warning),
+      pattern_name: pattern_name.to_string(),
+      index,
+      value: value.to_string(),
+    }
+  }
+
+  pub fn get_severity(&self) -> Severity {
+    match self.kind {
+      ValidationKind::Error(_) => Severity::Error,
+      ValidationKind::Warning(_) => Severity::Warning,
+    }
+  }
+
+  pub fn get_kind(&self) -> &ValidationKind {
+    &self.kind
+  }
+
+  /// Get the name of the pattern the issue is in. Empty for issues with the
+  /// whole composition.
+  pub fn get_pattern_name(&self) -> &str {
+    &self.pattern_name
+  }
+
+  /// Get the index of the event, or time or signature change, in its list in
+  /// the pattern.
+  pub fn get_index(&self) -> Option<usize> {
+    self.index
+  }
+
+  /// Get the offending value as written in the YAML.
+  pub fn get_value(&self) -> &str {
+    &self.value
+  }
+}
+
 /// Returns all the internally supported chord keywords.
 /// * Interpreted when parsing composition YAML.
 /// * Case sensitive.
@@ -45,12 +436,44 @@ pub fn get_chord_keywords() -> Vec<&'static str> {
 /// Export a composition to a midi files. Each pattern will be
 /// exported as a different midi file.
 ///
+/// The files are written to a directory named after the composition, next to
+/// the export path, with the default `MidiExportOptions`.
+///
 /// # Arguments
 /// * `composition` - The composition to export from.
 /// * `export_path` - The path to export the midi patterns.
 pub fn export_to_midi_file(
   composition: &composition::Composition,
   export_path: &str,
+) -> Result<SuccessResult, FailResult> {
+  export_to_midi_file_with_options(composition, export_path, &MidiExportOptions::default())
+}
+
+/// Load a composition then export it to midi files. Each pattern will be
+/// exported as a different midi file.
+///
+/// # Arguments
+/// * `composition_path` - The file path to the composition YAML file and the export path
+/// of the midi files.
+pub fn export_file_to_midi(composition_path: &str) -> Result<SuccessResult, FailResult> {
+  export_file_to_midi_with_options(composition_path, &MidiExportOptions::default())
+}
+
+/// Export a composition to a midi files with export options. Each pattern will be
+/// exported as a different midi file.
+///
+/// The files are written to a directory named after the composition, next to
+/// the export path. Names are made safe to use as file names, and the options'
+/// `ExportMode` decides what happens to an earlier export.
+///
+/// # Arguments
+/// * `composition` - The composition to export from.
+/// * `export_path` - The path to export the midi patterns.
+/// * `options` - The settings of the midi files.
+pub fn export_to_midi_file_with_options(
+  composition: &composition::Composition,
+  export_path: &str,
+  options: &MidiExportOptions,
 ) -> Result<SuccessResult, FailResult> {
   let parent_directory = Path::new(export_path)
     .parent()
@@ -58,150 +481,637 @@ pub fn export_to_midi_file(
     .to_str()
     .unwrap_or("./");
 
-  io::exporter::export_composition(&composition, parent_directory)
+  io::exporter::export_composition(&composition, parent_directory, options)
 }
 
-/// Load a composition then export it to midi files. Each pattern will be
-/// exported as a different midi file.
+/// Load a composition then export it to midi files with export options. Each
+/// pattern will be exported as a different midi file.
 ///
 /// # Arguments
 /// * `composition_path` - The file path to the composition YAML file and the export path
-/// of the midi files.
-pub fn export_file_to_midi(composition_path: &str) -> Result<SuccessResult, FailResult> {
+///   of the midi files.
+/// * `options` - The settings of the midi files.
+pub fn export_file_to_midi_with_options(
+  composition_path: &str,
+  options: &MidiExportOptions,
+) -> Result<SuccessResult, FailResult> {
   let composition_parameters = io::deseralizer::deserialize_file(composition_path)?;
   let composition = parameters_to_composition(&composition_parameters)?;
 
-  export_to_midi_file(&composition, composition_path)
+  export_to_midi_file_with_options(&composition, composition_path, options)
 }
 
-/// Helper to build music events. Chord intervals will be transposed and
-/// converted to midi key values in the returned `PatternEvent`.
+/// Export a composition to a single midi file, with its patterns laid end to
+/// end in order. Tempo and time signature changes are kept in the first track
+/// with a marker named after each pattern where it starts.
 ///
 /// # Arguments
-/// * `bar` - The bar for the music event.
-/// * `beat` - The beat of the msuic event.
-/// * `beat_interval` - The interval value between beats. There are 8
-/// * `beat_intervals` in a `beat`, this is the same as a 16th.
-/// * `chord_intervals` - The note intervals in the chord.
-/// * `transpose` - The transpose of all the chord intervals.
-pub fn build_event(
-  bar: u16,
-  beat: u8,
-  beat_interval: u8,
-  chord_intervals: Vec<i8>,
-  transpose: i8,
-) -> composition::PatternEvent {
-  (
-    music_time::MusicTime::new(bar, beat, beat_interval),
-    chords::IntervalChord::new(chord_intervals, transpose)
-      .transpose_octave(3)
-      .to_midi(),
-  )
+/// * `composition` - The composition to export.
+/// * `midi_path` - The path of the midi file to export to.
+pub fn export_to_single_midi_file(
+  composition: &composition::Composition,
+  midi_path: &str,
+) -> Result<SuccessResult, FailResult> {
+  export_to_single_midi_file_with_options(composition, midi_path, &MidiExportOptions::default())
 }
 
-/// Play a composition starting from the composition's pattern index
-/// and time.
+/// Load a composition then export it to a single midi file, named after the
+/// composition next to the composition YAML file.
 ///
 /// # Arguments
-/// * `composition` - The composition to play.
-/// * `performance_state` - The state performance with callbacks to be triggered.
-/// * `is_metronome_enabled` - Set if the metronome is to be played during playback.
-/// This is only relevant when `sample_paths_metronome` contains audio file paths
-/// and the compiler feature `with-sound` is used.
-/// * `sample_paths_metronome` - Paths to the 2 metronome audio files, tick and tock.
-/// * `sample_paths_piano` - Paths to the playback instrument audio files.
-/// * `playback_start` - The `MusicTime` to begin playback at.
-/// * `pattern_start_index` - The index of the `MusicPattern` to begin from.
-pub fn play_from_index<State: performance_engine::PerformanceState>(
+/// * `composition_path` - The file path to the composition YAML file.
+pub fn export_file_to_single_midi(composition_path: &str) -> Result<SuccessResult, FailResult> {
+  export_file_to_single_midi_with_options(composition_path, &MidiExportOptions::default())
+}
+
+/// Export a composition to a single midi file with export options, with its
+/// patterns laid end to end in order.
+///
+/// # Arguments
+/// * `composition` - The composition to export.
+/// * `midi_path` - The path of the midi file to export to.
+/// * `options` - The settings of the midi file.
+pub fn export_to_single_midi_file_with_options(
   composition: &composition::Composition,
-  performance_state: &mut State,
-  is_metronome_enabled: bool,
-  sample_paths_metronome: &Vec<String>,
-  sample_paths_piano: &Vec<String>,
-  playback_start: &music_timer::music_time::MusicTime,
-  pattern_start_index: usize,
+  midi_path: &str,
+  options: &MidiExportOptions,
 ) -> Result<SuccessResult, FailResult> {
-  let mut performance_engine = performance_engine::PerformanceEngine::new(
-    &composition,
-    performance_state,
-    sample_paths_metronome,
-    sample_paths_piano,
-  )?;
+  io::exporter::export_composition_single(composition, midi_path, options)
+}
 
-  performance_engine.set_metronome_enabled(is_metronome_enabled);
-  performance_engine.run_from(playback_start, pattern_start_index);
-  Ok(SuccessResult::Playback)
+/// Load a composition then export it to a single midi file with export options,
+/// named after the composition next to the composition YAML file.
+///
+/// # Arguments
+/// * `composition_path` - The file path to the composition YAML file.
+/// * `options` - The settings of the midi file.
+pub fn export_file_to_single_midi_with_options(
+  composition_path: &str,
+  options: &MidiExportOptions,
+) -> Result<SuccessResult, FailResult> {
+  let (composition, midi_path) = to_composition_export(composition_path, "mid")?;
+  export_to_single_midi_file_with_options(&composition, &midi_path, options)
 }
 
-/// Play a composition starting from a composition's pattern name
-/// and time.
+/// Load a composition and get the path of a file named after it next to the
+/// composition YAML file, with an extension.
+fn to_composition_export(
+  composition_path: &str,
+  extension: &str,
+) -> Result<(composition::Composition, String), FailResult> {
+  let composition_parameters = io::deseralizer::deserialize_file(composition_path)?;
+  let composition = parameters_to_composition(&composition_parameters)?;
+
+  let parent_directory = Path::new(composition_path)
+    .parent()
+    .unwrap_or(Path::new("./"));
+  let file_name = format!(
+    "{}.{}",
+    io::exporter::sanitise_file_name(composition.get_name()),
+    extension
+  );
+  let export_path = parent_directory.join(file_name);
+
+  Ok((composition, export_path.to_string_lossy().to_string()))
+}
+
+/// Write the text of an export to a file, treating a file already at the path
+/// by the export mode, and failing with the error for its path.
+fn write_export(
+  path: &str,
+  text: &str,
+  mode: ExportMode,
+  to_fail_result: fn(String) -> FailResult,
+) -> Result<SuccessResult, FailResult> {
+  let export_path = io::exporter::to_export_file_path(path, mode)?;
+  let path = export_path.to_string_lossy().to_string();
+  match std::fs::write(&export_path, text) {
+    Ok(()) => Ok(SuccessResult::Export(vec![path])),
+    _ => Err(to_fail_result(path)),
+  }
+}
+
+/// Write a composition as a single midi file to any writer, laid out as
+/// `export_to_single_midi_file` lays it out.
 ///
 /// # Arguments
-/// * `composition` - The composition to play.
-/// * `performance_state` - The state performance with callbacks to be triggered.
-/// * `is_metronome_enabled` - Set if the metronome is to be played during playback.
-/// This is only relevant when `sample_paths_metronome` contains audio file paths
-/// and the compiler feature `with-sound` is used.
-/// * `sample_paths_metronome` - Paths to the 2 metronome audio files, tick and tock.
-/// * `sample_paths_piano` - Paths to the playback instrument audio files.
-/// * `playback_start` - The `MusicTime` to begin playback at.
-/// * `pattern_start_name` - The name of the `MusicPattern` to begin from.
+/// * `composition` - The composition to write.
+/// * `writer` - The writer to write the midi file to.
+/// * `options` - The settings of the midi file.
+pub fn export_to_midi_writer<W: Write>(
+  composition: &composition::Composition,
+  writer: &mut W,
+  options: &MidiExportOptions,
+) -> Result<(), FailResult> {
+  io::exporter::write_composition(composition, writer, options)
+}
+
+/// Get the bytes of a composition as a single midi file, laid out as
+/// `export_to_single_midi_file` lays it out.
 ///
-/// # Example
-/// ```
-/// use chord_composer::{
-///   performance::performance_engine::PerformanceState,
-///   theory::composition::{Composition, Pattern, PatternEvent},
-///   FailResult, SuccessResult,
-/// };
-/// use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
-/// 
-/// struct MyState {
-///   events: u16,
-///   current_time: MusicTime,
-/// }
-/// impl PerformanceState for MyState {
-///   fn on_ready(&mut self, _composition: &Composition) {
-///     println!("on_ready");
-///   }
-///   fn on_beat_interval_change(&mut self, current_time: &MusicTime) {
-///     self.current_time = current_time.clone();
-///     println!("on_beat_interval_change: {:?}", current_time);
-///   }
-///   fn on_beat_change(&mut self, current_time: &MusicTime) {
-///     println!("on_beat_change: {:?}", current_time);
-///   }
-///   fn on_bar_change(&mut self, current_time: &MusicTime) {
-///     println!("on_bar_change: {:?}", current_time);
-///   }
-///   fn on_event(&mut self, event: &PatternEvent) {
-///     self.events += 1;
+/// # Arguments
+/// * `composition` - The composition to export.
+/// * `options` - The settings of the midi file.
+pub fn export_to_midi_bytes(
+  composition: &composition::Composition,
+  options: &MidiExportOptions,
+) -> Result<Vec<u8>, FailResult> {
+  let mut bytes = Vec::new();
+  export_to_midi_writer(composition, &mut bytes, options)?;
+  Ok(bytes)
+}
+
+/// Write a pattern as a midi file to any writer, with the same tracks as its
+/// file from `export_to_midi_file`.
 ///
-///     if self.events == 1 {
-///       assert_eq!(event, &chord_composer::build_event(2, 1, 1, vec![1], -1));
-///     } else if self.events == 2 {
-///       assert_eq!(event, &chord_composer::build_event(3, 5, 1, vec![2], -2));
-///     }
-///     println!("on_event");
-///   }
-///   fn on_pattern_playback_begin(&mut self, _pattern: &Pattern) {
-///     println!("on_pattern_playback_begin");
-///   }
-///   fn on_pattern_playback_end(&mut self, _pattern: &Pattern) {
-///     println!("on_pattern_playback_end");
-///   }
-///   fn on_completed(&mut self, composition: &Composition) {
-///     assert_eq!(composition.get_name(), "test compo");
-///     println!("on_completed");
-///   }
-/// }
+/// # Arguments
+/// * `pattern` - The pattern to write.
+/// * `writer` - The writer to write the midi file to.
+/// * `options` - The settings of the midi file.
+pub fn export_pattern_to_midi_writer<W: Write>(
+  pattern: &composition::Pattern,
+  writer: &mut W,
+  options: &MidiExportOptions,
+) -> Result<(), FailResult> {
+  io::exporter::write_pattern(pattern, writer, options)
+}
+
+/// Get the bytes of a pattern as a midi file, with the same tracks as its file
+/// from `export_to_midi_file`.
 ///
-/// let composition = Composition::new_with_patterns(
-///   "test compo",
-///   vec![
-///     Pattern::new_with_events(
+/// # Arguments
+/// * `pattern` - The pattern to export.
+/// * `options` - The settings of the midi file.
+pub fn export_pattern_to_midi_bytes(
+  pattern: &composition::Pattern,
+  options: &MidiExportOptions,
+) -> Result<Vec<u8>, FailResult> {
+  let mut bytes = Vec::new();
+  export_pattern_to_midi_writer(pattern, &mut bytes, options)?;
+  Ok(bytes)
+}
+
+/// Import a Standard MIDI File as a composition. Each track with notes becomes
+/// a pattern, with the tempo and time signature meta events of the file. Notes
+/// are quantised to beat intervals and notes starting together become chord
+/// events, keeping the chord name when they form an internal chord.
+///
+/// # Arguments
+/// * `midi_path` - The path of the midi file.
+/// * `track` - The index of the track to import, from 0 for the first track of
+///   the file, or `None` to import every track with notes.
+pub fn import_midi_file(
+  midi_path: &str,
+  track: Option<usize>,
+) -> Result<composition::Composition, FailResult> {
+  io::importer::import_midi(midi_path, track)
+}
+
+/// Import a Standard MIDI File then write it as a composition YAML file in the
+/// key of C. Recognised chords are written by name, other chords as
+/// composition defined chords.
+///
+/// # Arguments
+/// * `midi_path` - The path of the midi file.
+/// * `track` - The index of the track to import, or `None` to import every
+///   track with notes.
+/// * `yaml_path` - The path to write the composition YAML file to.
+/// * `mode` - How a file already at the path is treated.
+pub fn import_midi_file_to_yaml(
+  midi_path: &str,
+  track: Option<usize>,
+  yaml_path: &str,
+  mode: ExportMode,
+) -> Result<SuccessResult, FailResult> {
+  let composition = import_midi_file(midi_path, track)?;
+  export_to_yaml_file(&composition, yaml_path, mode)
+}
+
+/// Load a composition from a composition YAML file.
+///
+/// # Arguments
+/// * `composition_path` - The file path to the composition YAML file.
+pub fn load_file(composition_path: &str) -> Result<composition::Composition, FailResult> {
+  let composition_parameters = io::deseralizer::deserialize_file(composition_path)?;
+  parameters_to_composition(&composition_parameters)
+}
+
+/// Load a composition from composition YAML.
+///
+/// # Arguments
+/// * `composition_yaml` - Composition YAML.
+pub fn load_yaml(composition_yaml: &str) -> Result<composition::Composition, FailResult> {
+  let composition_parameters = io::deseralizer::deserialize_string(composition_yaml)?;
+  parameters_to_composition(&composition_parameters)
+}
+
+/// Write a composition as composition YAML, which loads back as an equivalent
+/// composition. Each pattern is written in the key of its chord symbols,
+/// keeping their chord names and transposes, with its tempo and time signature
+/// changes, grooves, rhythms and generated parts.
+///
+/// # Arguments
+/// * `composition` - The composition to write.
+pub fn composition_to_yaml(composition: &composition::Composition) -> Result<String, FailResult> {
+  io::serializer::composition_to_yaml(composition)
+}
+
+/// Save a composition to a composition YAML file, to load or edit later.
+///
+/// # Arguments
+/// * `composition` - The composition to save.
+/// * `yaml_path` - The path of the composition YAML file to save to.
+/// * `mode` - How a file already at the path is treated.
+pub fn export_to_yaml_file(
+  composition: &composition::Composition,
+  yaml_path: &str,
+  mode: ExportMode,
+) -> Result<SuccessResult, FailResult> {
+  write_export(
+    yaml_path,
+    &composition_to_yaml(composition)?,
+    mode,
+    FailResult::ExportYAML,
+  )
+}
+
+/// Write a composition as a MusicXML score, with a rehearsal mark, key
+/// signature, time signature and tempo marking for each pattern, and chord
+/// symbols over the voiced chords.
+///
+/// # Arguments
+/// * `composition` - The composition to write.
+pub fn composition_to_musicxml(composition: &composition::Composition) -> String {
+  io::musicxml::composition_to_musicxml(composition)
+}
+
+/// Export a composition to a MusicXML file, to open in notation software.
+///
+/// # Arguments
+/// * `composition` - The composition to export.
+/// * `musicxml_path` - The path of the MusicXML file to export to.
+/// * `mode` - How a file already at the path is treated.
+pub fn export_to_musicxml_file(
+  composition: &composition::Composition,
+  musicxml_path: &str,
+  mode: ExportMode,
+) -> Result<SuccessResult, FailResult> {
+  write_export(
+    musicxml_path,
+    &composition_to_musicxml(composition),
+    mode,
+    FailResult::ExportMusicXML,
+  )
+}
+
+/// Load a composition then export it to a MusicXML file, named after the
+/// composition next to the composition YAML file.
+///
+/// # Arguments
+/// * `composition_path` - The file path to the composition YAML file.
+/// * `mode` - How a file already at the path is treated.
+pub fn export_file_to_musicxml(
+  composition_path: &str,
+  mode: ExportMode,
+) -> Result<SuccessResult, FailResult> {
+  let (composition, musicxml_path) = to_composition_export(composition_path, "musicxml")?;
+  export_to_musicxml_file(&composition, &musicxml_path, mode)
+}
+
+/// Write a composition as LilyPond source, a line of chord names over a staff
+/// of the voiced chords, with a rehearsal mark, key, time signature and tempo
+/// for each pattern.
+///
+/// # Arguments
+/// * `composition` - The composition to write.
+pub fn composition_to_lilypond(composition: &composition::Composition) -> String {
+  io::lilypond::composition_to_lilypond(composition)
+}
+
+/// Export a composition to a LilyPond file, to engrave as a chord chart.
+///
+/// # Arguments
+/// * `composition` - The composition to export.
+/// * `lilypond_path` - The path of the LilyPond file to export to.
+/// * `mode` - How a file already at the path is treated.
+pub fn export_to_lilypond_file(
+  composition: &composition::Composition,
+  lilypond_path: &str,
+  mode: ExportMode,
+) -> Result<SuccessResult, FailResult> {
+  write_export(
+    lilypond_path,
+    &composition_to_lilypond(composition),
+    mode,
+    FailResult::ExportLilyPond,
+  )
+}
+
+/// Load a composition then export it to a LilyPond file, named after the
+/// composition next to the composition YAML file.
+///
+/// # Arguments
+/// * `composition_path` - The file path to the composition YAML file.
+/// * `mode` - How a file already at the path is treated.
+pub fn export_file_to_lilypond(
+  composition_path: &str,
+  mode: ExportMode,
+) -> Result<SuccessResult, FailResult> {
+  let (composition, lilypond_path) = to_composition_export(composition_path, "ly")?;
+  export_to_lilypond_file(&composition, &lilypond_path, mode)
+}
+
+/// Write a composition as a plain-text lead sheet chart, each pattern a
+/// section of bars with a chord symbol or `.` for each beat, e.g.
+/// `| Cmaj7 . . . | Am7 . D7 . |`.
+///
+/// # Arguments
+/// * `composition` - The composition to write.
+pub fn composition_to_chart(composition: &composition::Composition) -> String {
+  io::chart::composition_to_chart(composition)
+}
+
+/// Write a composition as a ChordPro song, each pattern a grid section of the
+/// bars of its lead sheet chart.
+///
+/// # Arguments
+/// * `composition` - The composition to write.
+pub fn composition_to_chordpro(composition: &composition::Composition) -> String {
+  io::chart::composition_to_chordpro(composition)
+}
+
+/// Export a composition to a plain-text lead sheet chart file.
+///
+/// # Arguments
+/// * `composition` - The composition to export.
+/// * `chart_path` - The path of the chart file to export to.
+/// * `mode` - How a file already at the path is treated.
+pub fn export_to_chart_file(
+  composition: &composition::Composition,
+  chart_path: &str,
+  mode: ExportMode,
+) -> Result<SuccessResult, FailResult> {
+  write_export(
+    chart_path,
+    &composition_to_chart(composition),
+    mode,
+    FailResult::ExportChart,
+  )
+}
+
+/// Export a composition to a ChordPro file.
+///
+/// # Arguments
+/// * `composition` - The composition to export.
+/// * `chordpro_path` - The path of the ChordPro file to export to.
+/// * `mode` - How a file already at the path is treated.
+pub fn export_to_chordpro_file(
+  composition: &composition::Composition,
+  chordpro_path: &str,
+  mode: ExportMode,
+) -> Result<SuccessResult, FailResult> {
+  write_export(
+    chordpro_path,
+    &composition_to_chordpro(composition),
+    mode,
+    FailResult::ExportChart,
+  )
+}
+
+/// Load a composition then export it to a plain-text lead sheet chart file,
+/// named after the composition next to the composition YAML file.
+///
+/// # Arguments
+/// * `composition_path` - The file path to the composition YAML file.
+/// * `mode` - How a file already at the path is treated.
+pub fn export_file_to_chart(
+  composition_path: &str,
+  mode: ExportMode,
+) -> Result<SuccessResult, FailResult> {
+  let (composition, chart_path) = to_composition_export(composition_path, "txt")?;
+  export_to_chart_file(&composition, &chart_path, mode)
+}
+
+/// Load a composition then export it to a ChordPro file, named after the
+/// composition next to the composition YAML file.
+///
+/// # Arguments
+/// * `composition_path` - The file path to the composition YAML file.
+/// * `mode` - How a file already at the path is treated.
+pub fn export_file_to_chordpro(
+  composition_path: &str,
+  mode: ExportMode,
+) -> Result<SuccessResult, FailResult> {
+  let (composition, chordpro_path) = to_composition_export(composition_path, "cho")?;
+  export_to_chordpro_file(&composition, &chordpro_path, mode)
+}
+
+/// Write a composition as ABC notation, a tune for each pattern with its
+/// name, time signature, tempo and key, and chord symbols in quotes over the
+/// voiced chords.
+///
+/// # Arguments
+/// * `composition` - The composition to write.
+pub fn composition_to_abc(composition: &composition::Composition) -> String {
+  io::abc::composition_to_abc(composition)
+}
+
+/// Export a composition to an ABC file.
+///
+/// # Arguments
+/// * `composition` - The composition to export.
+/// * `abc_path` - The path of the ABC file to export to.
+/// * `mode` - How a file already at the path is treated.
+pub fn export_to_abc_file(
+  composition: &composition::Composition,
+  abc_path: &str,
+  mode: ExportMode,
+) -> Result<SuccessResult, FailResult> {
+  write_export(
+    abc_path,
+    &composition_to_abc(composition),
+    mode,
+    FailResult::ExportABC,
+  )
+}
+
+/// Load a composition then export it to an ABC file, named after the
+/// composition next to the composition YAML file.
+///
+/// # Arguments
+/// * `composition_path` - The file path to the composition YAML file.
+/// * `mode` - How a file already at the path is treated.
+pub fn export_file_to_abc(
+  composition_path: &str,
+  mode: ExportMode,
+) -> Result<SuccessResult, FailResult> {
+  let (composition, abc_path) = to_composition_export(composition_path, "abc")?;
+  export_to_abc_file(&composition, &abc_path, mode)
+}
+
+/// Read the first tune of ABC notation as a pattern. Each chord symbol becomes
+/// a chord event where the rhythm of the tune places it, voiced in the tune's
+/// key, and `"N.C."` becomes a rest.
+///
+/// # Arguments
+/// * `abc` - The ABC notation to read.
+pub fn abc_to_pattern(abc: &str) -> Result<composition::Pattern, FailResult> {
+  io::abc::abc_to_patterns(abc)?
+    .into_iter()
+    .next()
+    .ok_or(FailResult::ImportABC)
+}
+
+/// Import an ABC file as a composition named after the file, with a pattern
+/// for each tune.
+///
+/// # Arguments
+/// * `abc_path` - The path of the ABC file.
+pub fn import_abc_file(abc_path: &str) -> Result<composition::Composition, FailResult> {
+  let abc = std::fs::read_to_string(abc_path).map_err(|_| FailResult::ImportABC)?;
+  let patterns = io::abc::abc_to_patterns(&abc)?;
+  if patterns.is_empty() {
+    return Err(FailResult::ImportABC);
+  }
+
+  let name = Path::new(abc_path)
+    .file_stem()
+    .and_then(|stem| stem.to_str())
+    .unwrap_or("imported");
+  let composition = composition::Composition::new_with_patterns(name, patterns);
+  composition.check()?;
+  Ok(composition)
+}
+
+/// Helper to build music events. Chord intervals will be transposed and
+/// converted to midi key values in the returned `PatternEvent`.
+///
+/// # Arguments
+/// * `bar` - The bar for the music event.
+/// * `beat` - The beat of the msuic event.
+/// * `beat_interval` - The interval value between beats. There are 8
+/// * `beat_intervals` in a `beat`, this is the same as a 16th.
+/// * `chord_intervals` - The note intervals in the chord.
+/// * `transpose` - The transpose of all the chord intervals.
+pub fn build_event(
+  bar: u16,
+  beat: u8,
+  beat_interval: u8,
+  chord_intervals: Vec<i8>,
+  transpose: i8,
+) -> composition::PatternEvent {
+  (
+    music_time::MusicTime::new(bar, beat, beat_interval),
+    chords::IntervalChord::new(chord_intervals, transpose)
+      .transpose_octave(3)
+      .to_midi(),
+  )
+}
+
+/// Play a composition starting from the composition's pattern index
+/// and time.
+///
+/// # Arguments
+/// * `composition` - The composition to play.
+/// * `performance_state` - The state performance with callbacks to be triggered.
+/// * `is_metronome_enabled` - Set if the metronome is to be played during playback.
+/// This is only relevant when `sample_paths_metronome` contains audio file paths
+/// and the compiler feature `with-sound` is used.
+/// * `sample_paths_metronome` - Paths to the 2 metronome audio files, tick and tock.
+/// * `sample_paths_piano` - Paths to the playback instrument audio files.
+/// * `playback_start` - The `MusicTime` to begin playback at.
+/// * `pattern_start_index` - The index of the `MusicPattern` to begin from.
+pub fn play_from_index<State: performance_engine::PerformanceState>(
+  composition: &composition::Composition,
+  performance_state: &mut State,
+  is_metronome_enabled: bool,
+  sample_paths_metronome: &[String],
+  sample_paths_piano: &[String],
+  playback_start: &music_timer::music_time::MusicTime,
+  pattern_start_index: usize,
+) -> Result<SuccessResult, FailResult> {
+  play_from_index_with_options(
+    composition,
+    performance_state,
+    &to_playback_options(
+      is_metronome_enabled,
+      sample_paths_metronome,
+      sample_paths_piano,
+    ),
+    playback_start,
+    pattern_start_index,
+  )
+}
+
+/// Play a composition starting from a composition's pattern name
+/// and time.
+///
+/// # Arguments
+/// * `composition` - The composition to play.
+/// * `performance_state` - The state performance with callbacks to be triggered.
+/// * `is_metronome_enabled` - Set if the metronome is to be played during playback.
+/// This is only relevant when `sample_paths_metronome` contains audio file paths
+/// and the compiler feature `with-sound` is used.
+/// * `sample_paths_metronome` - Paths to the 2 metronome audio files, tick and tock.
+/// * `sample_paths_piano` - Paths to the playback instrument audio files.
+/// * `playback_start` - The `MusicTime` to begin playback at.
+/// * `pattern_start_name` - The name of the `MusicPattern` to begin from.
+///
+/// # Example
+/// ```
+/// use chord_composer::{
+///   performance::performance_engine::PerformanceState,
+///   theory::composition::{Composition, Pattern, PatternEvent},
+///   FailResult, SuccessResult,
+/// };
+/// use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+///
+/// struct MyState {
+///   events: u16,
+///   current_time: MusicTime,
+/// }
+/// impl PerformanceState for MyState {
+///   fn on_ready(&mut self, _composition: &Composition) {
+///     println!("on_ready");
+///   }
+///   fn on_beat_interval_change(&mut self, current_time: &MusicTime) {
+///     self.current_time = current_time.clone();
+///     println!("on_beat_interval_change: {:?}", current_time);
+///   }
+///   fn on_beat_change(&mut self, current_time: &MusicTime) {
+///     println!("on_beat_change: {:?}", current_time);
+///   }
+///   fn on_bar_change(&mut self, current_time: &MusicTime) {
+///     println!("on_bar_change: {:?}", current_time);
+///   }
+///   fn on_event(&mut self, event: &PatternEvent) {
+///     self.events += 1;
+///
+///     if self.events == 1 {
+///       assert_eq!(event, &chord_composer::build_event(2, 1, 1, vec![1], -1));
+///     } else if self.events == 2 {
+///       assert_eq!(event, &chord_composer::build_event(3, 5, 1, vec![2], -2));
+///     }
+///     println!("on_event");
+///   }
+///   fn on_pattern_playback_begin(&mut self, _pattern: &Pattern) {
+///     println!("on_pattern_playback_begin");
+///   }
+///   fn on_pattern_playback_end(&mut self, _pattern: &Pattern) {
+///     println!("on_pattern_playback_end");
+///   }
+///   fn on_completed(&mut self, composition: &Composition) {
+///     assert_eq!(composition.get_name(), "test compo");
+///     println!("on_completed");
+///   }
+/// }
+///
+/// let composition = Composition::new_with_patterns(
+///   "test compo",
+///   vec![
+///     Pattern::new_with_events(
 ///       "a",
-///       100,
+///       100.0,
 ///       TimeSignature::default(),
 ///       vec![
 ///         chord_composer::build_event(1, 1, 1, vec![0, 3, 7], 0),
@@ -211,7 +1121,7 @@ pub fn play_from_index<State: performance_engine::PerformanceState>(
 ///     ),
 ///     Pattern::new_with_events(
 ///       "b",
-///       150,
+///       150.0,
 ///       TimeSignature::new(7, 4),
 ///       vec![
 ///         chord_composer::build_event(3, 5, 1, vec![2], -2),
@@ -260,28 +1170,22 @@ pub fn play_from<State: performance_engine::PerformanceState>(
   composition: &composition::Composition,
   performance_state: &mut State,
   is_metronome_enabled: bool,
-  sample_paths_metronome: &Vec<String>,
-  sample_paths_piano: &Vec<String>,
+  sample_paths_metronome: &[String],
+  sample_paths_piano: &[String],
   playback_start: &music_timer::music_time::MusicTime,
   pattern_start_name: &str,
 ) -> Result<SuccessResult, FailResult> {
-  let patterns_playback_index = composition
-    .get_patterns()
-    .iter()
-    .position(|pattern| pattern.get_name() == pattern_start_name);
-
-  match patterns_playback_index {
-    Some(pattern_index) => play_from_index(
-      composition,
-      performance_state,
+  play_from_with_options(
+    composition,
+    performance_state,
+    &to_playback_options(
       is_metronome_enabled,
       sample_paths_metronome,
       sample_paths_piano,
-      playback_start,
-      pattern_index,
     ),
-    None => Err(FailResult::NoFoundPattern(pattern_start_name.to_owned())),
-  }
+    playback_start,
+    pattern_start_name,
+  )
 }
 
 /// Play a composition and all it's patterns from the start.
@@ -298,17 +1202,17 @@ pub fn play<State: performance_engine::PerformanceState>(
   composition: &composition::Composition,
   performance_state: &mut State,
   is_metronome_enabled: bool,
-  sample_paths_metronome: &Vec<String>,
-  sample_paths_piano: &Vec<String>,
+  sample_paths_metronome: &[String],
+  sample_paths_piano: &[String],
 ) -> Result<SuccessResult, FailResult> {
-  play_from_index(
+  play_with_options(
     composition,
     performance_state,
-    is_metronome_enabled,
-    sample_paths_metronome,
-    sample_paths_piano,
-    &music_time::MusicTime::default(),
-    0,
+    &to_playback_options(
+      is_metronome_enabled,
+      sample_paths_metronome,
+      sample_paths_piano,
+    ),
   )
 }
 
@@ -326,20 +1230,40 @@ pub fn play_file<State: performance_engine::PerformanceState>(
   composition_path: &str,
   performance_state: &mut State,
   is_metronome_enabled: bool,
-  sample_paths_metronome: &Vec<String>,
-  sample_paths_piano: &Vec<String>,
+  sample_paths_metronome: &[String],
+  sample_paths_piano: &[String],
 ) -> Result<SuccessResult, FailResult> {
-  let composition_parameters = io::deseralizer::deserialize_file(composition_path)?;
-  let composition = parameters_to_composition(&composition_parameters)?;
-  play(
-    &composition,
+  play_file_with_options(
+    composition_path,
     performance_state,
-    is_metronome_enabled,
-    sample_paths_metronome,
-    sample_paths_piano,
+    &to_playback_options(
+      is_metronome_enabled,
+      sample_paths_metronome,
+      sample_paths_piano,
+    ),
   )
 }
 
+/// Load a YAML file of a composition and return every error and warning found
+/// in it, rather than stopping at the first error.
+///
+/// # Arguments
+/// * `composition_path` - The file path to the composition YAML file.
+pub fn validate_file(composition_path: &str) -> Result<Vec<ValidationIssue>, FailResult> {
+  let composition_parameters = io::deseralizer::deserialize_file(composition_path)?;
+  Ok(compile_parameters(&composition_parameters).1)
+}
+
+/// Parse YAML of a composition and return every error and warning found in it,
+/// rather than stopping at the first error.
+///
+/// # Arguments
+/// * `composition_yaml` - Composition YAML.
+pub fn validate_yaml(composition_yaml: &str) -> Result<Vec<ValidationIssue>, FailResult> {
+  let composition_parameters = io::deseralizer::deserialize_string(composition_yaml)?;
+  Ok(compile_parameters(&composition_parameters).1)
+}
+
 /// Parse YAML of a composition then play all it's patterns from the start.
 ///
 /// # Arguments
@@ -354,17 +1278,17 @@ pub fn play_yaml<State: performance_engine::PerformanceState>(
   composition_yaml: &str,
   performance_state: &mut State,
   is_metronome_enabled: bool,
-  sample_paths_metronome: &Vec<String>,
-  sample_paths_piano: &Vec<String>,
+  sample_paths_metronome: &[String],
+  sample_paths_piano: &[String],
 ) -> Result<SuccessResult, FailResult> {
-  let composition_parameters = io::deseralizer::deserialize_string(composition_yaml)?;
-  let composition = parameters_to_composition(&composition_parameters)?;
-  play(
-    &composition,
+  play_yaml_with_options(
+    composition_yaml,
     performance_state,
-    is_metronome_enabled,
-    sample_paths_metronome,
-    sample_paths_piano,
+    &to_playback_options(
+      is_metronome_enabled,
+      sample_paths_metronome,
+      sample_paths_piano,
+    ),
   )
 }
 
@@ -385,19 +1309,19 @@ pub fn play_file_from<State: performance_engine::PerformanceState>(
   composition_path: &str,
   performance_state: &mut State,
   is_metronome_enabled: bool,
-  sample_paths_metronome: &Vec<String>,
-  sample_paths_piano: &Vec<String>,
+  sample_paths_metronome: &[String],
+  sample_paths_piano: &[String],
   playback_start: &music_timer::music_time::MusicTime,
   pattern_start_name: &str,
 ) -> Result<SuccessResult, FailResult> {
-  let composition_parameters = io::deseralizer::deserialize_file(composition_path)?;
-  let composition = parameters_to_composition(&composition_parameters)?;
-  play_from(
-    &composition,
+  play_file_from_with_options(
+    composition_path,
     performance_state,
-    is_metronome_enabled,
-    sample_paths_metronome,
-    sample_paths_piano,
+    &to_playback_options(
+      is_metronome_enabled,
+      sample_paths_metronome,
+      sample_paths_piano,
+    ),
     playback_start,
     pattern_start_name,
   )
@@ -420,19 +1344,202 @@ pub fn play_yaml_from<State: performance_engine::PerformanceState>(
   composition_yaml: &str,
   performance_state: &mut State,
   is_metronome_enabled: bool,
-  sample_paths_metronome: &Vec<String>,
-  sample_paths_piano: &Vec<String>,
+  sample_paths_metronome: &[String],
+  sample_paths_piano: &[String],
+  playback_start: &music_timer::music_time::MusicTime,
+  pattern_start_name: &str,
+) -> Result<SuccessResult, FailResult> {
+  play_yaml_from_with_options(
+    composition_yaml,
+    performance_state,
+    &to_playback_options(
+      is_metronome_enabled,
+      sample_paths_metronome,
+      sample_paths_piano,
+    ),
+    playback_start,
+    pattern_start_name,
+  )
+}
+
+/// Get the playback options of the metronome and piano samples taken by the
+/// `play` functions.
+fn to_playback_options(
+  is_metronome_enabled: bool,
+  sample_paths_metronome: &[String],
+  sample_paths_piano: &[String],
+) -> PlaybackOptions {
+  PlaybackOptions::default()
+    .with_metronome(is_metronome_enabled)
+    .with_metronome_samples(sample_paths_metronome.to_vec())
+    .with_piano_samples(sample_paths_piano.to_vec())
+}
+
+/// Play a composition with playback options, starting from the composition's
+/// pattern index and time.
+///
+/// # Arguments
+/// * `composition` - The composition to play.
+/// * `performance_state` - The state performance with callbacks to be triggered.
+/// * `playback_options` - The metronome and audio samples used for playback.
+/// * `playback_start` - The `MusicTime` to begin playback at.
+/// * `pattern_start_index` - The index of the `MusicPattern` to begin from.
+pub fn play_from_index_with_options<State: performance_engine::PerformanceState>(
+  composition: &composition::Composition,
+  performance_state: &mut State,
+  playback_options: &PlaybackOptions,
+  playback_start: &music_timer::music_time::MusicTime,
+  pattern_start_index: usize,
+) -> Result<SuccessResult, FailResult> {
+  let mut performance_engine = performance_engine::PerformanceEngine::new_with_drums(
+    composition,
+    performance_state,
+    playback_options.get_metronome_samples(),
+    playback_options.get_piano_samples(),
+    playback_options.get_drum_samples(),
+  )?;
+
+  performance_engine.set_metronome_enabled(playback_options.is_metronome_enabled());
+  performance_engine.run_from(playback_start, pattern_start_index);
+  Ok(SuccessResult::Playback)
+}
+
+/// Play a composition with playback options, starting from a composition's
+/// pattern name and time.
+///
+/// # Arguments
+/// * `composition` - The composition to play.
+/// * `performance_state` - The state performance with callbacks to be triggered.
+/// * `playback_options` - The metronome and audio samples used for playback.
+/// * `playback_start` - The `MusicTime` to begin playback at.
+/// * `pattern_start_name` - The name of the `MusicPattern` to begin from.
+pub fn play_from_with_options<State: performance_engine::PerformanceState>(
+  composition: &composition::Composition,
+  performance_state: &mut State,
+  playback_options: &PlaybackOptions,
+  playback_start: &music_timer::music_time::MusicTime,
+  pattern_start_name: &str,
+) -> Result<SuccessResult, FailResult> {
+  let patterns_playback_index = composition
+    .get_patterns()
+    .iter()
+    .position(|pattern| pattern.get_name() == pattern_start_name);
+
+  match patterns_playback_index {
+    Some(pattern_index) => play_from_index_with_options(
+      composition,
+      performance_state,
+      playback_options,
+      playback_start,
+      pattern_index,
+    ),
+    None => Err(FailResult::NoFoundPattern(pattern_start_name.to_owned())),
+  }
+}
+
+/// Play a composition and all it's patterns from the start with playback options.
+///
+/// # Arguments
+/// * `composition` - The composition to play.
+/// * `performance_state` - The state performance with callbacks to be triggered.
+/// * `playback_options` - The metronome and audio samples used for playback.
+pub fn play_with_options<State: performance_engine::PerformanceState>(
+  composition: &composition::Composition,
+  performance_state: &mut State,
+  playback_options: &PlaybackOptions,
+) -> Result<SuccessResult, FailResult> {
+  play_from_index_with_options(
+    composition,
+    performance_state,
+    playback_options,
+    &music_time::MusicTime::default(),
+    0,
+  )
+}
+
+/// Load a YAML file of a composition then play all it's patterns from the start
+/// with playback options.
+///
+/// # Arguments
+/// * `composition_path` - Path to the composition YAML file.
+/// * `performance_state` - The state performance with callbacks to be triggered.
+/// * `playback_options` - The metronome and audio samples used for playback.
+pub fn play_file_with_options<State: performance_engine::PerformanceState>(
+  composition_path: &str,
+  performance_state: &mut State,
+  playback_options: &PlaybackOptions,
+) -> Result<SuccessResult, FailResult> {
+  let composition_parameters = io::deseralizer::deserialize_file(composition_path)?;
+  let composition = parameters_to_composition(&composition_parameters)?;
+  play_with_options(&composition, performance_state, playback_options)
+}
+
+/// Parse YAML of a composition then play all it's patterns from the start with
+/// playback options.
+///
+/// # Arguments
+/// * `composition_yaml` - Composition YAML.
+/// * `performance_state` - The state performance with callbacks to be triggered.
+/// * `playback_options` - The metronome and audio samples used for playback.
+pub fn play_yaml_with_options<State: performance_engine::PerformanceState>(
+  composition_yaml: &str,
+  performance_state: &mut State,
+  playback_options: &PlaybackOptions,
+) -> Result<SuccessResult, FailResult> {
+  let composition_parameters = io::deseralizer::deserialize_string(composition_yaml)?;
+  let composition = parameters_to_composition(&composition_parameters)?;
+  play_with_options(&composition, performance_state, playback_options)
+}
+
+/// Load a YAML file of a composition then play it's patterns with playback
+/// options, starting from a composition's pattern name and time.
+///
+/// # Arguments
+/// * `composition_path` - Path to the composition YAML file.
+/// * `performance_state` - The state performance with callbacks to be triggered.
+/// * `playback_options` - The metronome and audio samples used for playback.
+/// * `playback_start` - The `MusicTime` to begin playback at.
+/// * `pattern_start_name` - The name of the `MusicPattern` to begin from.
+pub fn play_file_from_with_options<State: performance_engine::PerformanceState>(
+  composition_path: &str,
+  performance_state: &mut State,
+  playback_options: &PlaybackOptions,
+  playback_start: &music_timer::music_time::MusicTime,
+  pattern_start_name: &str,
+) -> Result<SuccessResult, FailResult> {
+  let composition_parameters = io::deseralizer::deserialize_file(composition_path)?;
+  let composition = parameters_to_composition(&composition_parameters)?;
+  play_from_with_options(
+    &composition,
+    performance_state,
+    playback_options,
+    playback_start,
+    pattern_start_name,
+  )
+}
+
+/// Parse YAML of a composition then play it's patterns with playback options,
+/// starting from a composition's pattern name and time.
+///
+/// # Arguments
+/// * `composition_yaml` - Composition YAML.
+/// * `performance_state` - The state performance with callbacks to be triggered.
+/// * `playback_options` - The metronome and audio samples used for playback.
+/// * `playback_start` - The `MusicTime` to begin playback at.
+/// * `pattern_start_name` - The name of the `MusicPattern` to begin from.
+pub fn play_yaml_from_with_options<State: performance_engine::PerformanceState>(
+  composition_yaml: &str,
+  performance_state: &mut State,
+  playback_options: &PlaybackOptions,
   playback_start: &music_timer::music_time::MusicTime,
   pattern_start_name: &str,
 ) -> Result<SuccessResult, FailResult> {
   let composition_parameters = io::deseralizer::deserialize_string(composition_yaml)?;
   let composition = parameters_to_composition(&composition_parameters)?;
-  play_from(
+  play_from_with_options(
     &composition,
     performance_state,
-    is_metronome_enabled,
-    sample_paths_metronome,
-    sample_paths_piano,
+    playback_options,
     playback_start,
     pattern_start_name,
   )
@@ -462,6 +1569,7 @@ master:
     key: F# 
 
     # The beats per minute of the composition.
+    # Fractional values are supported, e.g 92.5.
     time: 120
 
     # The time signature of the composition.
@@ -476,15 +1584,81 @@ chords:
     - [custom1, [0, 3, 8]]
     - [custom2, [0, 5]]
 
+# Composition defined groove templates, repeating from the start of each bar.
+grooves:
+    - name: laid_back
+      # The subdivision of the bar. Supported values: 4, 8, 16, 32.
+      subdivision: 8
+      # Timing offset of each subdivision in ticks, 60 ticks to a beat interval.
+      timing: [0, 10, 0, 20]
+      # Velocity offset of each subdivision.
+      velocity: [10, -6, 4, -6]
+
+# Composition defined rhythm templates, re-triggering each chord on the hits until the next chord.
+# The template repeats from the start of each bar.
+rhythms:
+    - name: charleston
+      # The length of the template in beats.
+      beats: 4
+      # Each hit = [beat, beat interval] sounding until the next hit,
+      # or [beat, beat interval, length in beat intervals].
+      hits:
+          - [1, 1, 3]
+          - [2, 5, 2]
+
 # The composition's chord patterns/progressions.
 patterns:
     - name: part_a
+      # Optional count of bars, events after it are warned about when validating.
+      bars: 4
+      # Optional arpeggiator, playing each chord as a sequence of single notes.
+      # order = up, down, up_down, random or as_played.
+      # rate = The note value the notes are played at, e.g 16 for sixteenths or 12 for eighth note triplets.
+      # octaves = The count of octaves to play each chord over, 1 to 4.
+      # gate = The share of each step a note sounds for, above 0 and up to 1.
+      # seed = Optional seed of the random order.
+      arpeggio:
+          order: up_down
+          rate: 16
+          octaves: 2
+          gate: 0.8
+      # Optional bass line generated from the chords, exported as its own track on midi channel 2.
+      # root = The chord root held, root_fifth = Root and fifth every beat, octave = Root and octave every eighth,
+      # walking = Chord tones every beat, approaching the next root from a semitone below.
+      bass: walking
+      # Optional melody generated over the chords in the key of the pattern, exported as its own track on midi channel 3.
+      # Notes on the beat are chord tones, notes between beats are passing tones from the scale.
+      # scale = major, minor, harmonic_minor, melodic_minor, dorian, phrygian, lydian, mixolydian, locrian,
+      #         major_pentatonic, minor_pentatonic or blues.
+      # rate = Optional note value of each step, e.g 8 for eighths.
+      # density = Optional chance of a note on each step, above 0 and up to 1.
+      # range = Optional lowest and highest midi note, at least an octave apart.
+      # contour = Optional shape of the line, arch, ascending, descending, wave or random.
+      # seed = Optional seed of the note choices.
+      melody:
+          scale: major
+          rate: 8
+          density: 0.6
+          range: [60, 79]
+          contour: arch
+      # Optional drums played under the pattern, exported as its own track on midi channel 10.
+      # style = Optional named drum pattern, rock, half_time, four_on_the_floor or waltz.
+      # subdivision = Optional note value of each grid step, 4, 8, 16 or 32. A style keeps its own, 16 for the named styles.
+      # grid = Optional lanes of [drum, steps], replacing the lane of the same drum in the style.
+      #        Drums are General MIDI drum names, e.g acoustic_snare or cowbell, or kick, snare, hat, open_hat, ride or crash.
+      #        Steps repeat from the start of each bar, X = accent, x = hit, o = ghost note, . or - = rest.
+      drums:
+          style: rock
+          subdivision: 16
+          grid:
+              - [crash, "x..............."]
       # Each pattern event = [bar, beat, beat interval, chord name, chord transpose].
       pattern:
           - [1, 1, 1, MAJOR_SEVENTH, 0]
           - [1, 3, 1, custom1, 0]
           - [2, 1, 1, MAJOR_NINTH, 0]
           - [2, 3, 1, custom1, 0]
+          - [2, 4, 1, REST, 0] # REST = Silence until the next event.
           - [3, 1, 1, MAJOR_SEVENTH, 3]
           - [3, 2, 1, custom1, 0]
           - [4, 1, 1, MAJOR_NINTH, -3]
@@ -495,12 +1669,42 @@ patterns:
           signature: [3, 4]
           key: C#
           time: 69
+          # Swing = [swung note value, share of each pair taken by the first note].
+          # Note value supported values: 8, 16. Share supported values: 0.5 (straight) to 0.75.
+          swing: [8, 0.6]
+          # The groove template to play the pattern with.
+          groove: laid_back
+          # The rhythm template to play the chords with.
+          rhythm: charleston
+          # Random offsets added when exporting and playing, the pattern is unchanged.
+          # timing = Most ticks a chord starts early or late, 60 ticks to a beat interval.
+          # spread = Most ticks a note starts after its chord.
+          # velocity = Most the velocity of a note is raised or lowered by.
+          # seed = Optional seed to perform the same offsets every time.
+          humanize:
+              timing: 8
+              spread: 4
+              velocity: 6
+              seed: 1
+      # Tempo changes within the pattern.
+      # [bar, beat, beat interval, time] = Change the beats per minute at a time.
+      # [bar, beat, beat interval, time, [bar, beat, beat interval]] = Ramp the beats per minute until a time.
+      time_changes:
+          - [2, 1, 1, 80]
+          - [3, 1, 1, 120, [4, 1, 1]]
+      # Time signature changes within the pattern, [bar, [numerator, denominator]].
+      signature_changes:
+          - [4, [4, 4]]
       # Each pattern event = [bar, beat, beat interval, chord name, chord transpose].
+      # The beat interval can be a tuplet "k/n", the k-th of n equal divisions of the beat,
+      # where n divides the 480 ticks of a beat, such as 2, 3, 5 and 6. Septuplets, 11-tuplets
+      # and other tuplets off the grid of a beat are rejected.
       pattern:
           - [1, 1, 1, MAJOR_SEVENTH, 0]
           - [1, 2, 1, custom1, 0]
           - [2, 1, 5, MAJOR_NINTH, 0]
           - [2, 2, 1, custom1, 0]
+          - [2, 2, "3/3", custom1, 2]
           - [3, 1, 5, MAJOR_SEVENTH, 3]
           - [3, 2, 1, custom1, 0]
           - [4, 1, 1, MAJOR_NINTH, -3]
@@ -515,203 +1719,681 @@ patterns:
 }
 
 /// Convert YAML deserialized composition parameters to a `Composition` data type.
+/// Returns the first error found.
 ///
 /// # Arguments
 /// * `params` - The `CompositionParameters` to convert into a `Composition`.
 fn parameters_to_composition(
   params: &io::deseralizer::CompositionParameters,
 ) -> Result<composition::Composition, crate::FailResult> {
+  let (composition, issues) = compile_parameters(params);
+  let first_error = issues.into_iter().find_map(|issue| match issue.kind {
+    ValidationKind::Error(error) => Some(error),
+    _ => None,
+  });
+
+  match first_error {
+    Some(error) => Err(error),
+    None => Ok(composition),
+  }
+}
+
+/// Convert YAML deserialized composition parameters to a `Composition` data type,
+/// collecting every error and warning found on the way. The `Composition` is
+/// only complete when there are no errors.
+///
+/// # Arguments
+/// * `params` - The `CompositionParameters` to convert into a `Composition`.
+fn compile_parameters(
+  params: &io::deseralizer::CompositionParameters,
+) -> (composition::Composition, Vec<ValidationIssue>) {
   let default_master: io::deseralizer::MasterParameters = match params.get_master() {
     Some(master) => master.clone(),
     None => io::deseralizer::MasterParameters::default(),
   };
 
-  let mut composition_result = Ok(composition::Composition::new(&params.get_name()));
-  let mut count = 0;
+  let mut composition = composition::Composition::new(&params.get_name());
+  let mut issues = Vec::new();
+
+  let patterns = match params.get_patterns() {
+    Some(patterns) if patterns.is_empty() => {
+      issues.push(ValidationIssue::error(
+        FailResult::EmptyPatterns,
+        "",
+        None,
+        "",
+      ));
+      return (composition, issues);
+    }
+    Some(patterns) => patterns,
+    None => {
+      issues.push(ValidationIssue::error(FailResult::NoPatterns, "", None, ""));
+      return (composition, issues);
+    }
+  };
 
-  match params.get_patterns() {
-    None => Err(crate::FailResult::NoPatterns),
-    Some(patterns) => {
-      if patterns.is_empty() {
-        Err(crate::FailResult::EmptyPatterns)
-      } else {
-        for pattern in patterns {
-          if composition_result.is_err() {
-            break;
-          }
+  let additional_chords = match params.get_custom_chords() {
+    Some(custom_chords) => custom_chords.clone(),
+    None => Vec::new(),
+  };
 
-          match pattern.get_pattern() {
-            Some(pattern_pattern) => {
-              let pattern_master = match pattern.get_master() {
-                Some(pattern_master) => {
-                  io::deseralizer::MasterParameters::from_overrides(&default_master, pattern_master)
-                }
-                _ => default_master.clone(),
-              };
-
-              let name = match pattern.get_name() {
-                Some(name) => name.to_string(),
-                _ => format!("unnamed_pattern_{}", count),
-              };
-              let composition_pattern = {
-                let mut pattern = {
-                  let bpm = pattern_master.get_time_or_default();
-                  let (numerator, denominator) = pattern_master.get_signature_or_default();
-                  let time_signature = time_signature::TimeSignature::new(numerator, denominator);
-
-                  if !time_signature.is_valid() {
-                    composition_result = Err(FailResult::TimeSignature(time_signature));
-                    break;
-                  }
-
-                  composition::Pattern::new(&name, bpm, time_signature)
-                };
-
-                let additional_chords = match params.get_custom_chords() {
-                  Some(custom_chords) => custom_chords.clone(),
-                  None => Vec::new(),
-                };
-
-                for (bar, beat, beat_interval, chord_string, transpose) in pattern_pattern {
-                  let chord_notes = {
-                    let mut chord_intervals = chords::IntervalChord::from_string_with_custom(
-                      chord_string,
-                      &additional_chords,
-                    );
-
-                    let key_offset = {
-                      let key_string = pattern_master.get_key_or_default();
-                      let key = notes::string_to_key(&key_string);
-                      notes::key_to_index(key) as i8
-                    };
-                    chord_intervals
-                      .transpose(key_offset)
-                      .transpose(*transpose)
-                      .transpose_octave(3)
-                      .to_midi()
-                  };
-
-                  let time = music_time::MusicTime::new(*bar, *beat, *beat_interval);
-
-                  const INTERVAL_RESOLUTION: u8 = 16;
-                  let unreachable_beat_interval = time.get_beat_interval()
-                    > INTERVAL_RESOLUTION / 2
-                    || time.get_beat_interval() == 0;
-                  let unreachable_beat = time.get_beat()
-                    > pattern.get_time_signature().get_numerator()
-                    || time.get_beat() == 0;
-                  let unreachable_bar = time.get_bar() == 0;
-
-                  if unreachable_bar || unreachable_beat_interval || unreachable_beat {
-                    composition_result = Err(FailResult::UnreachableTime(
-                      time,
-                      pattern.len(),
-                      chord_string.to_string(),
-                    ));
-                  } else if pattern.len() != 0 {
-                    let previous_time = pattern.get(pattern.len() - 1).0;
-                    let time_does_not_advance = time == previous_time;
-
-                    if time_does_not_advance {
-                      composition_result = Err(FailResult::UnreachableTime(
-                        time,
-                        pattern.len(),
-                        chord_string.to_string(),
-                      ));
-                    } else {
-                      let reverse_time_flow = time < previous_time;
-                      if reverse_time_flow {
-                        composition_result = Err(FailResult::TimeReverse(
-                          time,
-                          pattern.len(),
-                          chord_string.to_string(),
-                        ));
-                      }
-                    }
-                  }
-
-                  pattern.push_event(time, chord_notes);
-                }
-                pattern
-              };
-
-              match &mut composition_result {
-                Ok(composition) => composition.push_pattern(composition_pattern),
-                _ => break,
-              }
-
-              count += 1;
-            }
-            None => composition_result = Err(FailResult::NoPatterns),
-          }
-        }
+  let mut grooves = std::collections::HashMap::new();
+  for groove_params in params.get_grooves().iter().flatten() {
+    let groove = groove::Groove::new(
+      groove_params.get_name(),
+      groove_params.get_subdivision(),
+      groove_params.get_timing(),
+      groove_params.get_velocity(),
+    );
 
-        composition_result
-      }
+    if !groove.is_valid() {
+      issues.push(ValidationIssue::error(
+        FailResult::Groove(groove.get_name().to_string()),
+        "",
+        None,
+        groove.get_name(),
+      ));
     }
+
+    grooves.insert(groove.get_name().to_string(), groove);
   }
-}
 
-#[test]
-fn test_new_composition() {
-  let params = io::deseralizer::deserialize_string(
-    r#"
-      name: bc_000_a
+  let mut rhythms = std::collections::HashMap::new();
+  for rhythm_params in params.get_rhythms().iter().flatten() {
+    let rhythm = rhythm::Rhythm::new(
+      rhythm_params.get_name(),
+      rhythm_params.get_beats(),
+      rhythm_params
+        .get_hits()
+        .iter()
+        .map(|hit| hit.to_hit())
+        .collect(),
+    );
 
-      # Can be overridden by patterns
-      master:
-          key: D             
-          time: 128
-          signature: [3, 4]
-     
-      chords:
-          - [custom1, [0, 3, 8]]
+    if !rhythm.is_valid() {
+      issues.push(ValidationIssue::error(
+        FailResult::Rhythm(rhythm.get_name().to_string()),
+        "",
+        None,
+        rhythm.get_name(),
+      ));
+    }
 
-      patterns:
-          - name: part_a
-            pattern:
-                - [1,1,1, MAJOR_SEVENTH, 0]
-                - [1,3,1, custom1, 0]
-                - [2,1,1, MAJOR_NINTH, 0]
-                - [2,3,1, custom1, 0]
-                - [3,1,1, MAJOR_SEVENTH, 3]
-                - [3,2,1, custom1, 0]
-                - [4,1,1, MAJOR_NINTH, -3]
-                - [4,2,1, custom1, -3]
+    rhythms.insert(rhythm.get_name().to_string(), rhythm);
+  }
 
-          - name: part_b
-            master:
-                signature: [4, 8]
-                key: C#
-                time: 69
-            pattern:
-                - [1,1,1, MAJOR_SEVENTH, 0]
-                - [1,2,1, custom1, 0]
-                - [2,1,1, MAJOR_NINTH, 0]
-                - [2,2,1, custom1, 0]
-                - [3,1,1, MAJOR_SEVENTH, 3]
-                - [3,2,1, custom1, 0]
-                - [4,1,1, MAJOR_NINTH, -3]
-                - [4,2,1, custom1, 0]
+  let mut pattern_names = std::collections::HashSet::new();
+  for (count, pattern_params) in patterns.iter().enumerate() {
+    let name = match pattern_params.get_name() {
+      Some(name) => name.to_string(),
+      _ => format!("unnamed_pattern_{}", count),
+    };
+
+    if !pattern_names.insert(name.clone()) {
+      issues.push(ValidationIssue::warning(
+        ValidationWarning::DuplicatePatternName,
+        &name,
+        None,
+        &name,
+      ));
+    }
 
-        "#,
-  );
+    let pattern_events = match pattern_params.get_pattern() {
+      Some(pattern_events) => pattern_events,
+      None => {
+        issues.push(ValidationIssue::error(
+          FailResult::NoPatterns,
+          &name,
+          None,
+          "",
+        ));
+        continue;
+      }
+    };
 
-  assert_ne!(params, Err(crate::FailResult::Deserialize));
+    let pattern_master = match pattern_params.get_master() {
+      Some(pattern_master) => {
+        io::deseralizer::MasterParameters::from_overrides(&default_master, pattern_master)
+      }
+      _ => default_master.clone(),
+    };
+
+    let mut pattern = {
+      let mut bpm = pattern_master.get_time_or_default();
+      let (numerator, denominator) = pattern_master.get_signature_or_default();
+      let mut time_signature = time_signature::TimeSignature::new(numerator, denominator);
+
+      // An invalid signature or tempo is reported, and the rest of the pattern
+      // is checked with the default one
+      if !time_signature.is_valid() {
+        issues.push(ValidationIssue::error(
+          FailResult::TimeSignature(time_signature),
+          &name,
+          None,
+          &format!("[{}, {}]", numerator, denominator),
+        ));
+        let (numerator, denominator) = io::deseralizer::MasterParameters::DEFAULT_SIGNATURE;
+        time_signature = time_signature::TimeSignature::new(numerator, denominator);
+      }
 
-  let compo = parameters_to_composition(&params.unwrap()).unwrap();
+      if !composition::is_tempo_valid(bpm) {
+        issues.push(ValidationIssue::error(
+          FailResult::Tempo(bpm),
+          &name,
+          None,
+          &bpm.to_string(),
+        ));
+        bpm = io::deseralizer::MasterParameters::DEFAULT_TIME;
+      }
 
-  assert_eq!(compo.len(), 2);
-  assert_eq!(compo.get(0).len(), 8);
-  assert_eq!(compo.get(1).len(), 8);
+      composition::Pattern::new(&name, bpm, time_signature)
+    };
+
+    if let Some((note_value, ratio)) = pattern_master.get_swing() {
+      if let Err(error) = pattern.set_swing(Some(groove::Swing::new(note_value, ratio))) {
+        issues.push(ValidationIssue::error(
+          error,
+          &name,
+          None,
+          &format!("[{}, {}]", note_value, ratio),
+        ));
+      }
+    }
 
-  assert_eq!(compo.get(0).get_bpm(), 128);
-  assert_eq!(
-    compo.get(0).get_time_signature(),
-    time_signature::TimeSignature::new(3, 4)
-  );
+    if let Some(humanize_params) = pattern_master.get_humanize() {
+      pattern.set_humanize(Some(humanize::Humanize::new(
+        humanize_params.get_timing_or_default(),
+        humanize_params.get_spread_or_default(),
+        humanize_params.get_velocity_or_default(),
+        humanize_params.get_seed().unwrap_or_else(rand::random),
+      )));
+    }
 
-  assert_eq!(compo.get(1).get_bpm(), 69);
+    if let Some(arpeggio_params) = pattern_params.get_arpeggio() {
+      match arpeggiator::string_to_order(arpeggio_params.get_order()) {
+        Some(order) => {
+          let arpeggiator = arpeggiator::Arpeggiator::new(
+            order,
+            arpeggio_params.get_rate_or_default(),
+            arpeggio_params.get_octaves_or_default(),
+            arpeggio_params.get_gate_or_default(),
+            arpeggio_params.get_seed().unwrap_or_else(rand::random),
+          );
+
+          if let Err(error) = pattern.set_arpeggiator(Some(arpeggiator)) {
+            issues.push(ValidationIssue::error(
+              error,
+              &name,
+              None,
+              arpeggio_params.get_order(),
+            ));
+          }
+        }
+        None => issues.push(ValidationIssue::error(
+          FailResult::UnknownArpeggioOrder(arpeggio_params.get_order().to_string()),
+          &name,
+          None,
+          arpeggio_params.get_order(),
+        )),
+      }
+    }
+
+    if let Some(channel) = pattern_params.get_channel() {
+      if let Err(error) = pattern.set_channel(channel) {
+        issues.push(ValidationIssue::error(
+          error,
+          &name,
+          None,
+          &channel.to_string(),
+        ));
+      }
+    }
+
+    if let Some(bass_style) = pattern_params.get_bass() {
+      match bass::string_to_bass_style(bass_style) {
+        Some(style) => pattern.set_bass(Some(style)),
+        None => issues.push(ValidationIssue::error(
+          FailResult::UnknownBassStyle(bass_style.to_string()),
+          &name,
+          None,
+          bass_style,
+        )),
+      }
+    }
+
+    if let Some(melody_params) = pattern_params.get_melody() {
+      let scale = melody::string_to_scale(melody_params.get_scale());
+      let contour = melody::string_to_contour(melody_params.get_contour_or_default());
+      match (scale, contour) {
+        (Some(scale), Some(contour)) => {
+          let melody = melody::Melody::new(
+            notes::string_to_key(&pattern_master.get_key_or_default()),
+            scale,
+            melody_params.get_rate_or_default(),
+            melody_params.get_density_or_default(),
+            melody_params.get_range_or_default(),
+            contour,
+            melody_params.get_seed().unwrap_or_else(rand::random),
+          );
+
+          if let Err(error) = pattern.set_melody(Some(melody)) {
+            issues.push(ValidationIssue::error(
+              error,
+              &name,
+              None,
+              melody_params.get_scale(),
+            ));
+          }
+        }
+        (None, _) => issues.push(ValidationIssue::error(
+          FailResult::UnknownScale(melody_params.get_scale().to_string()),
+          &name,
+          None,
+          melody_params.get_scale(),
+        )),
+        (_, None) => issues.push(ValidationIssue::error(
+          FailResult::UnknownContour(melody_params.get_contour_or_default().to_string()),
+          &name,
+          None,
+          melody_params.get_contour_or_default(),
+        )),
+      }
+    }
+
+    if let Some(drum_params) = pattern_params.get_drums() {
+      let mut pattern_drums = match drum_params.get_style() {
+        Some(style) => match drums::string_to_drum_style(style) {
+          // A style's lanes are only in time at the style's own subdivision
+          Some(style_drums) => match drum_params.get_subdivision() {
+            Some(style_subdivision) if *style_subdivision != style_drums.get_note_value() => {
+              issues.push(ValidationIssue::error(
+                FailResult::DrumSubdivision(*style_subdivision),
+                &name,
+                None,
+                &style_subdivision.to_string(),
+              ));
+              None
+            }
+            _ => Some(style_drums),
+          },
+          None => {
+            issues.push(ValidationIssue::error(
+              FailResult::UnknownDrumStyle(style.to_string()),
+              &name,
+              None,
+              style,
+            ));
+            None
+          }
+        },
+        None => Some(drums::Drums::new(
+          &name,
+          drum_params.get_subdivision_or_default(),
+          Vec::new(),
+        )),
+      };
+
+      for (drum, grid) in drum_params.get_grid().iter().flatten() {
+        let note = drums::drum_name_to_note(drum);
+        let velocities = drums::grid_to_velocities(grid);
+        match (note, velocities) {
+          (Some(note), Some(velocities)) => {
+            pattern_drums = pattern_drums.map(|drums| drums.with_lane((note, velocities)));
+          }
+          (None, _) => issues.push(ValidationIssue::error(
+            FailResult::UnknownDrum(drum.to_string()),
+            &name,
+            None,
+            drum,
+          )),
+          (_, None) => issues.push(ValidationIssue::error(
+            FailResult::DrumGrid(drum.to_string()),
+            &name,
+            None,
+            grid,
+          )),
+        }
+      }
+
+      if let Some(pattern_drums) = pattern_drums {
+        let note_value = pattern_drums.get_note_value();
+        if let Err(error) = pattern.set_drums(Some(pattern_drums)) {
+          issues.push(ValidationIssue::error(
+            error,
+            &name,
+            None,
+            &note_value.to_string(),
+          ));
+        }
+      }
+    }
+
+    if let Some(rhythm_name) = pattern_master.get_rhythm() {
+      match rhythms.get(&rhythm_name) {
+        Some(rhythm) if rhythm.is_valid() => {
+          let _ = pattern.set_rhythm(Some(rhythm.clone()));
+        }
+        Some(_) => {}
+        None => issues.push(ValidationIssue::error(
+          FailResult::NoFoundRhythm(rhythm_name.clone()),
+          &name,
+          None,
+          &rhythm_name,
+        )),
+      }
+    }
+
+    if let Some(groove_name) = pattern_master.get_groove() {
+      match grooves.get(&groove_name) {
+        Some(groove) if groove.is_valid() => {
+          let _ = pattern.set_groove(Some(groove.clone()));
+        }
+        Some(_) => {}
+        None => issues.push(ValidationIssue::error(
+          FailResult::NoFoundGroove(groove_name.clone()),
+          &name,
+          None,
+          &groove_name,
+        )),
+      }
+    }
+
+    for (index, (bar, (numerator, denominator))) in pattern_params
+      .get_signature_changes()
+      .iter()
+      .flatten()
+      .enumerate()
+    {
+      let time_signature = time_signature::TimeSignature::new(*numerator, *denominator);
+      let value = format!("[{}, [{}, {}]]", bar, numerator, denominator);
+
+      if !time_signature.is_valid() {
+        issues.push(ValidationIssue::error(
+          FailResult::TimeSignature(time_signature),
+          &name,
+          Some(index),
+          &value,
+        ));
+      } else if *bar == 0 {
+        issues.push(ValidationIssue::error(
+          FailResult::UnreachableSignatureChange(*bar, index),
+          &name,
+          Some(index),
+          &value,
+        ));
+      }
+
+      pattern.push_time_signature_change(*bar, time_signature);
+    }
+
+    for (index, time_change) in pattern_params
+      .get_time_changes()
+      .iter()
+      .flatten()
+      .enumerate()
+    {
+      use io::deseralizer::TimeChangeObject;
+
+      let (time, change, value) = match time_change {
+        TimeChangeObject::Instant(bar, beat, beat_interval, bpm) => (
+          music_time::MusicTime::new(*bar, *beat, *beat_interval),
+          composition::TempoChange::Instant(*bpm),
+          format!("[{}, {}, {}, {}]", bar, beat, beat_interval, bpm),
+        ),
+        TimeChangeObject::Ramp(
+          bar,
+          beat,
+          beat_interval,
+          bpm,
+          (end_bar, end_beat, end_beat_interval),
+        ) => (
+          music_time::MusicTime::new(*bar, *beat, *beat_interval),
+          composition::TempoChange::Ramp(
+            *bpm,
+            music_time::MusicTime::new(*end_bar, *end_beat, *end_beat_interval),
+          ),
+          format!(
+            "[{}, {}, {}, {}, [{}, {}, {}]]",
+            bar, beat, beat_interval, bpm, end_bar, end_beat, end_beat_interval
+          ),
+        ),
+      };
+
+      let bpm = match change {
+        composition::TempoChange::Instant(bpm) => bpm,
+        composition::TempoChange::Ramp(bpm, _) => bpm,
+      };
+
+      if !pattern.is_tempo_change_reachable(&time, &change) {
+        issues.push(ValidationIssue::error(
+          FailResult::UnreachableTimeChange(time, index),
+          &name,
+          Some(index),
+          &value,
+        ));
+      } else if !composition::is_tempo_valid(bpm) {
+        issues.push(ValidationIssue::error(
+          FailResult::Tempo(bpm),
+          &name,
+          Some(index),
+          &value,
+        ));
+      }
+
+      pattern.push_tempo_change(time, change);
+    }
+
+    for (index, (bar, beat, beat_interval, chord_string, transpose)) in
+      pattern_events.iter().enumerate()
+    {
+      const CHORD_OCTAVE: i8 = 3;
+      let key = notes::string_to_key(&pattern_master.get_key_or_default());
+      let is_chord_known = chords::is_chord_name_known(chord_string, &additional_chords);
+      let is_rest = chord_string.trim() == chords::REST_NAME;
+      let mut chord_intervals = if is_chord_known {
+        chords::IntervalChord::from_string_with_custom(chord_string, &additional_chords)
+      } else {
+        chords::IntervalChord::new(Vec::new(), 0)
+      };
+
+      let value = match beat_interval {
+        io::deseralizer::BeatIntervalObject::Interval(interval) => {
+          format!(
+            "[{}, {}, {}, {}, {}]",
+            bar, beat, interval, chord_string, transpose
+          )
+        }
+        io::deseralizer::BeatIntervalObject::Tuplet(tuplet) => {
+          format!(
+            "[{}, {}, {}, {}, {}]",
+            bar, beat, tuplet, chord_string, transpose
+          )
+        }
+      };
+
+      let chord_symbol = composition::ChordSymbol::new(
+        chord_string.trim(),
+        key,
+        *transpose,
+        chord_intervals.get_intervals().clone(),
+        CHORD_OCTAVE,
+      );
+
+      let chord_notes = chord_intervals
+        .transpose(notes::key_to_index(key) as i8)
+        .transpose(*transpose)
+        .transpose_octave(CHORD_OCTAVE)
+        .to_midi();
+
+      let (time, offset) = match beat_interval.to_beat_interval() {
+        Some((beat_interval, offset)) => (
+          music_time::MusicTime::new(*bar, *beat, beat_interval),
+          offset,
+        ),
+        None => (music_time::MusicTime::new(*bar, *beat, 0), 0),
+      };
+      let invalid_tuplet = match beat_interval {
+        io::deseralizer::BeatIntervalObject::Tuplet(tuplet)
+          if beat_interval.to_beat_interval().is_none() =>
+        {
+          Some(tuplet)
+        }
+        _ => None,
+      };
+
+      let time_error = if let Some(tuplet) = invalid_tuplet {
+        Some(FailResult::Tuplet(tuplet.clone()))
+      } else if !pattern.is_time_reachable(&time) {
+        Some(FailResult::UnreachableTime(
+          time,
+          index,
+          chord_string.to_string(),
+        ))
+      } else if pattern.len() != 0 {
+        let previous_event = pattern.get_event(pattern.len() - 1);
+        let previous_time = *previous_event.get_time();
+        let previous_offset = previous_event.get_offset();
+        let time_does_not_advance = time == previous_time && offset == previous_offset;
+        let reverse_time_flow =
+          time < previous_time || (time == previous_time && offset < previous_offset);
+
+        if time_does_not_advance {
+          Some(FailResult::UnreachableTime(
+            time,
+            index,
+            chord_string.to_string(),
+          ))
+        } else if reverse_time_flow {
+          Some(FailResult::TimeReverse(
+            time,
+            index,
+            chord_string.to_string(),
+          ))
+        } else {
+          None
+        }
+      } else {
+        None
+      };
+
+      if let Some(error) = &time_error {
+        issues.push(ValidationIssue::error(
+          error.clone(),
+          &name,
+          Some(index),
+          &value,
+        ));
+      }
+
+      if !is_chord_known {
+        issues.push(ValidationIssue::error(
+          FailResult::UnknownChord(
+            name.clone(),
+            index,
+            chord_string.trim().to_string(),
+            chords::suggest_chord_names(chord_string, &additional_chords),
+          ),
+          &name,
+          Some(index),
+          &value,
+        ));
+      } else if chord_intervals.is_empty() && !is_rest {
+        issues.push(ValidationIssue::warning(
+          ValidationWarning::EmptyChord,
+          &name,
+          Some(index),
+          &value,
+        ));
+      }
+
+      if let Some(bars) = pattern_params.get_bars() {
+        if *bar > bars {
+          issues.push(ValidationIssue::warning(
+            ValidationWarning::EventPastBarCount(bars),
+            &name,
+            Some(index),
+            &value,
+          ));
+        }
+      }
+
+      // Only events at valid times are added, so the next event is checked
+      // against the last valid one
+      if time_error.is_none() {
+        let event =
+          composition::ChordEvent::new_with_symbol(time, offset, chord_notes, chord_symbol);
+        if let Err(error) = pattern.push_chord_event(event) {
+          issues.push(ValidationIssue::error(error, &name, Some(index), &value));
+        }
+      }
+    }
+
+    composition.push_pattern(pattern);
+  }
+
+  (composition, issues)
+}
+
+#[test]
+fn test_new_composition() {
+  let params = io::deseralizer::deserialize_string(
+    r#"
+      name: bc_000_a
+
+      # Can be overridden by patterns
+      master:
+          key: D             
+          time: 128
+          signature: [3, 4]
+     
+      chords:
+          - [custom1, [0, 3, 8]]
+
+      patterns:
+          - name: part_a
+            pattern:
+                - [1,1,1, MAJOR_SEVENTH, 0]
+                - [1,3,1, custom1, 0]
+                - [2,1,1, MAJOR_NINTH, 0]
+                - [2,3,1, custom1, 0]
+                - [3,1,1, MAJOR_SEVENTH, 3]
+                - [3,2,1, custom1, 0]
+                - [4,1,1, MAJOR_NINTH, -3]
+                - [4,2,1, custom1, -3]
+
+          - name: part_b
+            master:
+                signature: [4, 8]
+                key: C#
+                time: 69
+            pattern:
+                - [1,1,1, MAJOR_SEVENTH, 0]
+                - [1,2,1, custom1, 0]
+                - [2,1,1, MAJOR_NINTH, 0]
+                - [2,2,1, custom1, 0]
+                - [3,1,1, MAJOR_SEVENTH, 3]
+                - [3,2,1, custom1, 0]
+                - [4,1,1, MAJOR_NINTH, -3]
+                - [4,2,1, custom1, 0]
+
+        "#,
+  );
+
+  assert_ne!(params, Err(crate::FailResult::Deserialize));
+
+  let compo = parameters_to_composition(&params.unwrap()).unwrap();
+
+  assert_eq!(compo.len(), 2);
+  assert_eq!(compo.get(0).len(), 8);
+  assert_eq!(compo.get(1).len(), 8);
+
+  assert_eq!(compo.get(0).get_bpm(), 128.0);
+  assert_eq!(
+    compo.get(0).get_time_signature(),
+    time_signature::TimeSignature::new(3, 4)
+  );
+
+  assert_eq!(compo.get(1).get_bpm(), 69.0);
   assert_eq!(
     compo.get(1).get_time_signature(),
     time_signature::TimeSignature::new(4, 8)
@@ -914,3 +2596,848 @@ fn test_unreachable_time() {
     }
   }
 }
+
+#[test]
+fn test_pattern_changes() {
+  let params = io::deseralizer::deserialize_string(
+    r#"
+      master:
+          time: 120
+      patterns:
+          - name: part_a
+            time_changes:
+                - [2, 1, 1, 100]
+                - [3, 1, 1, 80, [4, 1, 1]]
+            signature_changes:
+                - [3, [3, 4]]
+            pattern:
+                - [1,1,1, MAJOR, 0]
+                - [3,3,1, MINOR, 0]
+                - [4,3,1, MINOR, 0]
+        "#,
+  );
+  assert_ne!(params, Err(crate::FailResult::Deserialize));
+
+  let compo = parameters_to_composition(&params.unwrap()).unwrap();
+  let pattern = compo.get(0);
+  assert_eq!(pattern.len(), 3);
+  assert_eq!(
+    pattern.get_time_signature_at(3),
+    time_signature::TimeSignature::new(3, 4)
+  );
+  assert_eq!(
+    pattern.get_bpm_at(&music_time::MusicTime::new(2, 4, 1)),
+    100.0
+  );
+  assert_eq!(
+    pattern.get_bpm_at(&music_time::MusicTime::new(4, 1, 1)),
+    80.0
+  );
+}
+
+#[test]
+fn test_unreachable_pattern_changes() {
+  {
+    let params = io::deseralizer::deserialize_string(
+      r#"
+      patterns:
+          - name: part_a
+            signature_changes:
+                - [2, [3, 4]]
+            pattern:
+                - [1,4,1, MAJOR, 0]
+                - [2,4,1, MINOR, 0]
+        "#,
+    );
+
+    match parameters_to_composition(&params.unwrap()) {
+      Err(FailResult::UnreachableTime(music_time, index, chord)) => {
+        assert_eq!(music_time, music_time::MusicTime::new(2, 4, 1));
+        assert_eq!(index, 1);
+        assert_eq!(chord, "MINOR".to_string());
+      }
+      _ => assert!(false),
+    }
+  }
+  {
+    let params = io::deseralizer::deserialize_string(
+      r#"
+      patterns:
+          - name: part_a
+            time_changes:
+                - [3, 1, 1, 80, [2, 1, 1]]
+            pattern:
+                - [1,1,1, MAJOR, 0]
+        "#,
+    );
+
+    assert_eq!(
+      parameters_to_composition(&params.unwrap()).unwrap_err(),
+      FailResult::UnreachableTimeChange(music_time::MusicTime::new(3, 1, 1), 0)
+    );
+  }
+  {
+    let params = io::deseralizer::deserialize_string(
+      r#"
+      patterns:
+          - name: part_a
+            signature_changes:
+                - [0, [3, 4]]
+            pattern:
+                - [1,1,1, MAJOR, 0]
+        "#,
+    );
+
+    assert_eq!(
+      parameters_to_composition(&params.unwrap()).unwrap_err(),
+      FailResult::UnreachableSignatureChange(0, 0)
+    );
+  }
+}
+
+#[test]
+fn test_fractional_tempo() {
+  let params = io::deseralizer::deserialize_string(
+    r#"
+      master:
+          time: 92.5
+      patterns:
+          - name: part_a
+            pattern:
+                - [1,1,1, MAJOR, 0]
+          - name: part_b
+            master:
+                time: 300
+            time_changes:
+                - [2, 1, 1, 280.25]
+            pattern:
+                - [1,1,1, MAJOR, 0]
+        "#,
+  );
+
+  let compo = parameters_to_composition(&params.unwrap()).unwrap();
+  assert_eq!(compo.get(0).get_bpm(), 92.5);
+  assert_eq!(compo.get(1).get_bpm(), 300.0);
+  assert_eq!(
+    compo
+      .get(1)
+      .get_bpm_at(&music_time::MusicTime::new(2, 1, 1)),
+    280.25
+  );
+
+  for yaml in &[
+    "{master: {time: 0}, patterns: [{pattern: [[1, 1, 1, MAJOR, 0]]}]}",
+    "{master: {time: -60}, patterns: [{pattern: [[1, 1, 1, MAJOR, 0]]}]}",
+  ] {
+    let params = io::deseralizer::deserialize_string(yaml).unwrap();
+    match parameters_to_composition(&params) {
+      Err(FailResult::Tempo(_)) => {}
+      _ => assert!(false),
+    }
+  }
+}
+
+#[test]
+fn test_tuplet_events() {
+  let params = io::deseralizer::deserialize_string(
+    r#"
+      patterns:
+          - name: part_a
+            pattern:
+                - [1,1,1, MAJOR, 0]
+                - [1,1,"2/3", MINOR, 0]
+                - [1,1,"3/3", MAJOR, 0]
+                - [1,2,1, MINOR, 0]
+        "#,
+  );
+
+  let compo = parameters_to_composition(&params.unwrap()).unwrap();
+  let pattern = compo.get(0);
+  assert_eq!(pattern.len(), 4);
+  assert_eq!(
+    pattern.get_event(1).get_time(),
+    &music_time::MusicTime::new(1, 1, 3)
+  );
+  assert_eq!(pattern.get_event(1).get_offset(), 40);
+  assert_eq!(
+    pattern.get_event(2).get_time(),
+    &music_time::MusicTime::new(1, 1, 6)
+  );
+  assert_eq!(pattern.get_event(2).get_offset(), 20);
+
+  for (yaml, tuplet) in &[
+    (
+      "{patterns: [{pattern: [[1, 1, \"4/3\", MAJOR, 0]]}]}",
+      "4/3",
+    ),
+    (
+      "{patterns: [{pattern: [[1, 1, \"2/7\", MAJOR, 0]]}]}",
+      "2/7",
+    ),
+  ] {
+    let params = io::deseralizer::deserialize_string(yaml).unwrap();
+    assert_eq!(
+      parameters_to_composition(&params).map(|composition| composition.len()),
+      Err(FailResult::Tuplet(tuplet.to_string()))
+    );
+  }
+
+  let params = io::deseralizer::deserialize_string(
+    "{patterns: [{pattern: [[1, 1, \"1/3\", MAJOR, 0], [1, 1, 1, MAJOR, 0]]}]}",
+  )
+  .unwrap();
+  match parameters_to_composition(&params) {
+    Err(FailResult::UnreachableTime(_, _, _)) => {}
+    _ => assert!(false),
+  }
+
+  let params = io::deseralizer::deserialize_string(
+    "{patterns: [{pattern: [[1, 1, \"3/3\", MAJOR, 0], [1, 1, \"2/3\", MAJOR, 0]]}]}",
+  )
+  .unwrap();
+  match parameters_to_composition(&params) {
+    Err(FailResult::TimeReverse(_, _, _)) => {}
+    _ => assert!(false),
+  }
+}
+
+#[test]
+fn test_chord_symbols() {
+  let params = io::deseralizer::deserialize_string(
+    r#"
+      master:
+          key: D
+      chords:
+          - [custom1, [0, 5]]
+      patterns:
+          - name: part_a
+            pattern:
+                - [1,1,1, MAJOR_SEVENTH, 2]
+                - [2,1,1, " custom1", -3]
+        "#,
+  );
+
+  let compo = parameters_to_composition(&params.unwrap()).unwrap();
+  let pattern = compo.get(0);
+
+  let symbol = pattern.get_event(0).get_symbol().unwrap();
+  assert_eq!(symbol.get_name(), "MAJOR_SEVENTH");
+  assert_eq!(symbol.get_key(), theory::notes::Key::D);
+  assert_eq!(symbol.get_transpose(), 2);
+  assert_eq!(symbol.get_root(), theory::notes::Key::E);
+  assert_eq!(symbol.get_intervals(), &vec![0, 4, 7, 11]);
+  assert_eq!(symbol.get_octave(), 3);
+  assert_eq!(pattern.get(0).1, vec![64, 68, 71, 75]);
+
+  let symbol = pattern.get_event(1).get_symbol().unwrap();
+  assert_eq!(symbol.get_name(), "custom1");
+  assert_eq!(symbol.get_root(), theory::notes::Key::B);
+  assert_eq!(symbol.get_intervals(), &vec![0, 5]);
+}
+
+#[test]
+fn test_validate_all_issues() {
+  let issues = validate_yaml(
+    r#"
+      patterns:
+          - name: part_a
+            bars: 2
+            time_changes:
+                - [1, 9, 1, 100]
+            pattern:
+                - [1,3,1, MAJOR_SEVENTH, 0]
+                - [1,2,1, MINOR, 0]
+                - [1,2,9, MINOR, 0]
+                - [3,1,1, not_a_chord, 0]
+          - name: part_a
+            master:
+                signature: [0, 4]
+                time: 0
+            pattern:
+                - [1,1,1, MAJOR, 0]
+                - [1,1,1, MINOR, 0]
+          - name: part_b
+            pattern:
+                - [1,1,1, MAJOR, 0]
+                - [1,9,1, MINOR, 0]
+                - [1,2,1, MAJOR, 0]
+        "#,
+  )
+  .unwrap();
+
+  let summary: Vec<(Severity, &str, Option<usize>, &str)> = issues
+    .iter()
+    .map(|issue| {
+      (
+        issue.get_severity(),
+        issue.get_pattern_name(),
+        issue.get_index(),
+        issue.get_value(),
+      )
+    })
+    .collect();
+
+  assert_eq!(
+    summary,
+    vec![
+      (Severity::Error, "part_a", Some(0), "[1, 9, 1, 100]"),
+      (Severity::Error, "part_a", Some(1), "[1, 2, 1, MINOR, 0]"),
+      (Severity::Error, "part_a", Some(2), "[1, 2, 9, MINOR, 0]"),
+      (
+        Severity::Error,
+        "part_a",
+        Some(3),
+        "[3, 1, 1, not_a_chord, 0]"
+      ),
+      (
+        Severity::Warning,
+        "part_a",
+        Some(3),
+        "[3, 1, 1, not_a_chord, 0]"
+      ),
+      (Severity::Warning, "part_a", None, "part_a"),
+      (Severity::Error, "part_a", None, "[0, 4]"),
+      (Severity::Error, "part_a", None, "0"),
+      (Severity::Error, "part_a", Some(1), "[1, 1, 1, MINOR, 0]"),
+      (Severity::Error, "part_b", Some(1), "[1, 9, 1, MINOR, 0]"),
+    ]
+  );
+
+  assert_eq!(
+    issues[1].get_kind(),
+    &ValidationKind::Error(FailResult::TimeReverse(
+      music_time::MusicTime::new(1, 2, 1),
+      1,
+      "MINOR".to_string()
+    ))
+  );
+  assert_eq!(
+    issues[3].get_kind(),
+    &ValidationKind::Error(FailResult::UnknownChord(
+      "part_a".to_string(),
+      3,
+      "not_a_chord".to_string(),
+      Vec::new()
+    ))
+  );
+  assert_eq!(
+    issues[4].get_kind(),
+    &ValidationKind::Warning(ValidationWarning::EventPastBarCount(2))
+  );
+  assert_eq!(
+    issues[5].get_kind(),
+    &ValidationKind::Warning(ValidationWarning::DuplicatePatternName)
+  );
+
+  assert_eq!(
+    validate_yaml("{patterns: [{pattern: [[1, 1, 1, MAJOR, 0]]}]}"),
+    Ok(Vec::new())
+  );
+  assert_eq!(
+    validate_yaml("- not a composition"),
+    Err(FailResult::Deserialize)
+  );
+}
+
+#[test]
+fn test_unknown_chords_and_rests() {
+  let params = io::deseralizer::deserialize_string(
+    r#"
+      chords:
+          - [custom1, [0, 3, 8]]
+      patterns:
+          - name: part_a
+            pattern:
+                - [1,1,1, MAJOR_SEVENTH, 0]
+                - [1,3,1, REST, 0]
+                - [2,1,1, custom1, 0]
+        "#,
+  );
+
+  let compo = parameters_to_composition(&params.unwrap()).unwrap();
+  assert_eq!(compo.get(0).get(1).1, Vec::<u8>::new());
+  assert_eq!(
+    compo.get(0).get_event(1).get_symbol().unwrap().get_name(),
+    "REST"
+  );
+
+  let params = io::deseralizer::deserialize_string(
+    r#"
+      chords:
+          - [custom1, [0, 3, 8]]
+      patterns:
+          - name: part_a
+            pattern:
+                - [1,1,1, MAJOR_SEVENTH, 0]
+                - [1,3,1, MAJOR_SEVENT, 0]
+        "#,
+  );
+
+  match parameters_to_composition(&params.unwrap()) {
+    Err(FailResult::UnknownChord(pattern_name, index, chord, suggestions)) => {
+      assert_eq!(pattern_name, "part_a");
+      assert_eq!(index, 1);
+      assert_eq!(chord, "MAJOR_SEVENT");
+      assert_eq!(suggestions[0], "MAJOR_SEVENTH");
+    }
+    _ => assert!(false),
+  }
+
+  let issues =
+    validate_yaml("{chords: [[silent, []]], patterns: [{pattern: [[1, 1, 1, silent, 0]]}]}")
+      .unwrap();
+  assert_eq!(
+    issues[0].get_kind(),
+    &ValidationKind::Warning(ValidationWarning::EmptyChord)
+  );
+
+  let issues = validate_yaml("{patterns: [{pattern: [[1, 1, 1, \"?\", 0]]}]}").unwrap();
+  assert_eq!(issues[0].get_severity(), Severity::Error);
+}
+
+#[test]
+fn test_swing_and_grooves() {
+  let params = io::deseralizer::deserialize_string(
+    r#"
+      master:
+          swing: [8, 0.75]
+      grooves:
+          - name: accent
+            subdivision: 4
+            timing: [0, 10]
+            velocity: [20, -4]
+      patterns:
+          - name: part_a
+            pattern:
+                - [1,1,1, MAJOR_SEVENTH, 0]
+                - [1,1,5, MAJOR_SEVENTH, 0]
+          - name: part_b
+            master:
+                swing: [8, 0.5]
+                groove: accent
+            pattern:
+                - [1,1,1, MAJOR_SEVENTH, 0]
+                - [1,2,1, MAJOR_SEVENTH, 0]
+        "#,
+  );
+
+  let compo = parameters_to_composition(&params.unwrap()).unwrap();
+  assert_eq!(compo.get(0).get_grooved_events(), vec![(0, 64), (360, 64)]);
+  assert_eq!(compo.get(0).get_groove(), None);
+  assert_eq!(compo.get(1).get_grooved_events(), vec![(0, 84), (490, 60)]);
+  assert_eq!(compo.get(1).get_groove().unwrap().get_name(), "accent");
+
+  let issues = validate_yaml(
+    "{master: {swing: [12, 0.6], groove: missing}, patterns: [{pattern: [[1, 1, 1, MAJOR, 0]]}]}",
+  )
+  .unwrap();
+  assert_eq!(
+    issues[0].get_kind(),
+    &ValidationKind::Error(FailResult::Swing(12, 0.6))
+  );
+  assert_eq!(
+    issues[1].get_kind(),
+    &ValidationKind::Error(FailResult::NoFoundGroove("missing".to_string()))
+  );
+
+  let issues = validate_yaml(
+    "{grooves: [{name: late, subdivision: 16, timing: [200]}], patterns: [{pattern: [[1, 1, 1, MAJOR, 0]]}]}",
+  )
+  .unwrap();
+  assert_eq!(
+    issues[0].get_kind(),
+    &ValidationKind::Error(FailResult::Groove("late".to_string()))
+  );
+}
+
+#[test]
+fn test_humanize() {
+  let yaml = |humanize: &str| {
+    format!(
+      r#"
+      patterns:
+          - name: part_a
+            master:
+                {}
+            pattern:
+                - [1,1,1, MAJOR_SEVENTH, 0]
+                - [1,3,1, MINOR, 0]
+                - [2,1,1, MAJOR, 0]
+        "#,
+      humanize
+    )
+  };
+
+  let exact =
+    parameters_to_composition(&io::deseralizer::deserialize_string(&yaml("key: C")).unwrap())
+      .unwrap();
+  let humanized_yaml = yaml("humanize: {timing: 20, spread: 10, velocity: 12, seed: 3}");
+  let humanized =
+    parameters_to_composition(&io::deseralizer::deserialize_string(&humanized_yaml).unwrap())
+      .unwrap();
+  let repeated =
+    parameters_to_composition(&io::deseralizer::deserialize_string(&humanized_yaml).unwrap())
+      .unwrap();
+
+  let (exact, humanized, repeated) = (exact.get(0), humanized.get(0), repeated.get(0));
+  assert_eq!(humanized.get_chord_events(), exact.get_chord_events());
+  assert_eq!(
+    humanized.get_performed_events(),
+    repeated.get_performed_events()
+  );
+  assert_ne!(
+    humanized.get_performed_events(),
+    exact.get_performed_events()
+  );
+  assert_eq!(humanized.get_humanize().unwrap().get_seed(), 3);
+}
+
+#[test]
+fn test_arpeggio() {
+  let params = io::deseralizer::deserialize_string(
+    r#"
+      patterns:
+          - name: part_a
+            arpeggio:
+                order: up
+                rate: 8
+                gate: 0.5
+            pattern:
+                - [1,1,1, MAJOR, 0]
+                - [1,2,1, MAJOR, 2]
+        "#,
+  );
+
+  let compo = parameters_to_composition(&params.unwrap()).unwrap();
+  let performed_events = compo.get(0).get_performed_events();
+  assert_eq!(performed_events[0].0, 0);
+  assert_eq!(
+    performed_events[0].1,
+    vec![(0, 120, 60, 64), (240, 360, 64, 64)]
+  );
+  assert_eq!(performed_events[1].0, 480);
+  assert_eq!(performed_events[1].1.len(), 6);
+  assert_eq!(compo.get(0).get_event(0).get_notes(), &vec![60, 64, 67]);
+
+  let issues = validate_yaml(
+    "{patterns: [{arpeggio: {order: sideways}, pattern: [[1, 1, 1, MAJOR, 0]]}, {arpeggio: {order: up, rate: 7}, pattern: [[1, 1, 1, MAJOR, 0]]}]}",
+  )
+  .unwrap();
+  assert_eq!(
+    issues[0].get_kind(),
+    &ValidationKind::Error(FailResult::UnknownArpeggioOrder("sideways".to_string()))
+  );
+  assert_eq!(
+    issues[1].get_kind(),
+    &ValidationKind::Error(FailResult::Arpeggio(7, 1, 1.0))
+  );
+}
+
+#[test]
+fn test_rhythms() {
+  let params = io::deseralizer::deserialize_string(
+    r#"
+      rhythms:
+          - name: charleston
+            beats: 4
+            hits:
+                - [1, 1, 3]
+                - [2, 5, 2]
+      master:
+          rhythm: charleston
+      patterns:
+          - name: part_a
+            pattern:
+                - [1,1,1, MAJOR, 0]
+                - [2,1,1, REST, 0]
+                - [3,1,1, MAJOR, 0]
+        "#,
+  );
+
+  let compo = parameters_to_composition(&params.unwrap()).unwrap();
+  let performed_events = compo.get(0).get_performed_events();
+  let performed_ticks = |event: usize| -> Vec<(u32, u32)> {
+    performed_events[event]
+      .1
+      .iter()
+      .filter(|(_, _, note, _)| *note == 60)
+      .map(|(tick_index, end_tick_index, _, _)| (*tick_index, *end_tick_index))
+      .collect()
+  };
+
+  assert_eq!(performed_ticks(0), vec![(0, 180), (720, 840)]);
+  assert!(performed_events[1].1.is_empty());
+  assert_eq!(performed_events[2].0, 3840);
+  assert_eq!(performed_ticks(2), vec![(3840, 4020), (4560, 4680)]);
+  assert_eq!(compo.get(0).len(), 3);
+
+  // Chords between hits sound at their own start, then on the following hits
+  let params = io::deseralizer::deserialize_string(
+    r#"
+      rhythms:
+          - name: downbeats
+            beats: 1
+            hits:
+                - [1, 1]
+      master:
+          rhythm: downbeats
+      patterns:
+          - name: part_a
+            pattern:
+                - [1,1,1, MAJOR, 0]
+                - [1,2,5, MAJOR, 5]
+                - [1,3,1, MAJOR, 7]
+                - [2,2,5, MAJOR, 0]
+                - [2,4,1, REST, 0]
+        "#,
+  );
+  let compo = parameters_to_composition(&params.unwrap()).unwrap();
+  let performed_events = compo.get(0).get_performed_events();
+  let performed_ticks = |event: usize| -> Vec<(u32, u32)> {
+    let (_, notes) = &performed_events[event];
+    notes
+      .iter()
+      .filter(|(_, _, note, _)| *note == notes[0].2)
+      .map(|(tick_index, end_tick_index, _, _)| (*tick_index, *end_tick_index))
+      .collect()
+  };
+  assert_eq!(performed_ticks(0), vec![(0, 480), (480, 720)]);
+  assert_eq!(performed_events[1].0, 720);
+  assert_eq!(performed_ticks(1), vec![(720, 960)]);
+  assert_eq!(performed_ticks(2).len(), 4);
+  assert_eq!(performed_ticks(2)[0], (960, 1440));
+  assert_eq!(performed_events[3].0, 2640);
+  assert_eq!(performed_ticks(3), vec![(2640, 2880), (2880, 3360)]);
+
+  let issues = validate_yaml(
+    "{rhythms: [{name: empty, beats: 4, hits: []}], master: {rhythm: missing}, patterns: [{pattern: [[1, 1, 1, MAJOR, 0]]}]}",
+  )
+  .unwrap();
+  assert_eq!(
+    issues[0].get_kind(),
+    &ValidationKind::Error(FailResult::Rhythm("empty".to_string()))
+  );
+  assert_eq!(
+    issues[1].get_kind(),
+    &ValidationKind::Error(FailResult::NoFoundRhythm("missing".to_string()))
+  );
+}
+
+#[test]
+fn test_bass_lines() {
+  let params = io::deseralizer::deserialize_string(
+    r#"
+      patterns:
+          - name: part_a
+            bass: root_fifth
+            pattern:
+                - [1,1,1, MAJOR, 0]
+                - [2,1,1, MINOR, 9]
+          - name: part_b
+            pattern:
+                - [1,1,1, MAJOR, 0]
+        "#,
+  );
+
+  let compo = parameters_to_composition(&params.unwrap()).unwrap();
+  let bass_line = compo.get(0).get_bass_line().unwrap();
+  assert_eq!(bass_line.get_name(), "part_a_bass");
+  assert_eq!(bass_line.len(), 8);
+  assert_eq!(bass_line.get(1).1, vec![43]);
+  assert_eq!(bass_line.get(4).1, vec![33]);
+  assert!(compo.get(1).get_bass_line().is_none());
+
+  let issues = validate_yaml("{patterns: [{bass: slap, pattern: [[1, 1, 1, MAJOR, 0]]}]}").unwrap();
+  assert_eq!(
+    issues[0].get_kind(),
+    &ValidationKind::Error(FailResult::UnknownBassStyle("slap".to_string()))
+  );
+}
+
+#[test]
+fn test_pattern_channels() {
+  let compo = load_yaml("{patterns: [{channel: 4, pattern: [[1, 1, 1, MAJOR, 0]]}]}").unwrap();
+  assert_eq!(compo.get(0).get_channel(), 4);
+
+  let issues =
+    validate_yaml("{patterns: [{channel: 16, pattern: [[1, 1, 1, MAJOR, 0]]}]}").unwrap();
+  assert_eq!(
+    issues[0].get_kind(),
+    &ValidationKind::Error(FailResult::MidiChannel(16))
+  );
+}
+
+#[test]
+fn test_melodies() {
+  let yaml = |melody: &str| {
+    format!(
+      r#"
+      master:
+          key: D
+      patterns:
+          - name: part_a
+            melody: {}
+            pattern:
+                - [1,1,1, MINOR_SEVENTH, 0]
+                - [2,1,1, MAJOR, 5]
+        "#,
+      melody
+    )
+  };
+
+  let params =
+    io::deseralizer::deserialize_string(&yaml("{scale: dorian, rate: 8, density: 1.0, seed: 9}"));
+  let compo = parameters_to_composition(&params.unwrap()).unwrap();
+  let pattern = compo.get(0);
+  let melody = pattern.get_melody().unwrap();
+  assert_eq!(melody.get_key(), theory::notes::Key::D);
+  assert_eq!(melody.get_scale(), melody::Scale::Dorian);
+  assert_eq!(melody.get_contour(), melody::Contour::Arch);
+
+  let melody_line = pattern.get_melody_line().unwrap();
+  assert_eq!(melody_line.get_name(), "part_a_melody");
+  assert_eq!(melody_line.len(), 16);
+  assert_eq!(
+    melody_line.get_chord_events(),
+    compo.get(0).get_melody_line().unwrap().get_chord_events()
+  );
+
+  let issues = validate_yaml(&yaml("{scale: chromatic}")).unwrap();
+  assert_eq!(
+    issues[0].get_kind(),
+    &ValidationKind::Error(FailResult::UnknownScale("chromatic".to_string()))
+  );
+  let issues = validate_yaml(&yaml("{scale: major, contour: zigzag}")).unwrap();
+  assert_eq!(
+    issues[0].get_kind(),
+    &ValidationKind::Error(FailResult::UnknownContour("zigzag".to_string()))
+  );
+  let issues = validate_yaml(&yaml("{scale: major, range: [60, 65]}")).unwrap();
+  assert_eq!(
+    issues[0].get_kind(),
+    &ValidationKind::Error(FailResult::Melody(8, 0.5, 60, 65))
+  );
+}
+
+#[test]
+fn test_drums() {
+  let yaml = |drums: &str| {
+    format!(
+      r#"
+      patterns:
+          - name: part_a
+            master:
+                signature: [3, 4]
+            drums: {}
+            pattern:
+                - [1,1,1, MAJOR, 0]
+                - [2,1,1, MINOR, 9]
+        "#,
+      drums
+    )
+  };
+
+  let params = io::deseralizer::deserialize_string(&yaml(
+    r#"{style: waltz, grid: [[kick, "X..."], [cowbell, "..x."]]}"#,
+  ));
+  let compo = parameters_to_composition(&params.unwrap()).unwrap();
+  let drums = compo.get(0).get_drums().unwrap();
+  assert_eq!(drums.get_name(), "waltz");
+  assert_eq!(drums.get_lanes().len(), 4);
+  assert_eq!(drums.get_lanes()[0], (36, vec![110, 0, 0, 0]));
+  assert_eq!(drums.get_lanes()[3], (56, vec![0, 0, 80, 0]));
+  assert_eq!(compo.get(0).get_drum_notes()[0], (0, 120, 36, 110));
+
+  let params = io::deseralizer::deserialize_string(&yaml("{style: rock, subdivision: 16}"));
+  let compo = parameters_to_composition(&params.unwrap()).unwrap();
+  assert_eq!(compo.get(0).get_drums().unwrap().get_note_value(), 16);
+
+  let params =
+    io::deseralizer::deserialize_string(&yaml(r#"{subdivision: 8, grid: [[snare, ".x"]]}"#));
+  let compo = parameters_to_composition(&params.unwrap()).unwrap();
+  assert_eq!(compo.get(0).get_drum_notes().len(), 6);
+
+  let error = |drums: &str| {
+    let issues = validate_yaml(&yaml(drums)).unwrap();
+    match issues[0].get_kind() {
+      ValidationKind::Error(error) => error.clone(),
+      _ => panic!(),
+    }
+  };
+  assert_eq!(
+    error("{style: polka}"),
+    FailResult::UnknownDrumStyle("polka".to_string())
+  );
+  assert_eq!(
+    error(r#"{grid: [[timpani, "x..."]]}"#),
+    FailResult::UnknownDrum("timpani".to_string())
+  );
+  assert_eq!(
+    error(r#"{grid: [[kick, "x?.."]]}"#),
+    FailResult::DrumGrid("kick".to_string())
+  );
+  assert_eq!(
+    error(r#"{subdivision: 12, grid: [[kick, "x..."]]}"#),
+    FailResult::Drums("part_a".to_string())
+  );
+  assert_eq!(error("{}"), FailResult::Drums("part_a".to_string()));
+  assert_eq!(
+    error("{style: rock, subdivision: 8}"),
+    FailResult::DrumSubdivision(8)
+  );
+}
+
+#[test]
+fn test_midi_export_options() {
+  let options = MidiExportOptions::default();
+  assert_eq!(options.get_ppq(), 480);
+  assert_eq!(options.get_format(), MidiFileFormat::MultiTrack);
+  assert_eq!(options.get_channel(), None);
+  assert_eq!(options.get_velocity(), 64);
+  assert_eq!(options.get_program(), None);
+  assert!(options.is_meta_track_included());
+  assert_eq!(options.check(), Ok(()));
+
+  let options = options.with_ppq(960).with_channel(15).with_program(0);
+  assert_eq!(options.check(), Ok(()));
+  assert_eq!(options.get_channel(), Some(15));
+
+  let error = |options: MidiExportOptions| options.check().unwrap_err();
+  assert_eq!(
+    error(MidiExportOptions::default().with_ppq(0)),
+    FailResult::MidiPpq(0)
+  );
+  assert_eq!(
+    error(MidiExportOptions::default().with_ppq(0x8000)),
+    FailResult::MidiPpq(0x8000)
+  );
+  assert_eq!(
+    error(MidiExportOptions::default().with_channel(16)),
+    FailResult::MidiChannel(16)
+  );
+  assert_eq!(
+    error(MidiExportOptions::default().with_velocity(0)),
+    FailResult::MidiVelocity(0)
+  );
+  assert_eq!(
+    error(MidiExportOptions::default().with_program(128)),
+    FailResult::MidiProgram(128)
+  );
+
+  let composition = composition::Composition::new_with_patterns(
+    "options",
+    vec![composition::Pattern::new(
+      "a",
+      120.0,
+      time_signature::TimeSignature::default(),
+    )],
+  );
+  assert_eq!(
+    export_to_single_midi_file_with_options(
+      &composition,
+      "./tests/options.mid",
+      &MidiExportOptions::default().with_velocity(128)
+    ),
+    Err(FailResult::MidiVelocity(128))
+  );
+}
@@ -52,7 +52,7 @@ mod tests {
             pattern.get_time_signature(),
             time_signature::TimeSignature::default()
           );
-          assert_eq!(pattern.get_bpm(), 140);
+          assert_eq!(pattern.get_bpm(), 140.0);
           assert_eq!(pattern.len(), 2);
         } else {
           assert_eq!(pattern.get_name(), "pattern_y");
@@ -60,7 +60,7 @@ mod tests {
             pattern.get_time_signature(),
             time_signature::TimeSignature::default()
           );
-          assert_eq!(pattern.get_bpm(), 130);
+          assert_eq!(pattern.get_bpm(), 130.0);
           assert_eq!(pattern.len(), 2);
         }
       }
@@ -81,17 +81,25 @@ mod tests {
     let composition = {
       let mut composition = composition::Composition::new("test compo");
       let mut pattern =
-        composition::Pattern::new("pattern_z", 140, time_signature::TimeSignature::default());
+        composition::Pattern::new("pattern_z", 140.0, time_signature::TimeSignature::default());
 
-      pattern.push_event(music_time::MusicTime::new(1, 3, 1), Vec::new());
-      pattern.push_event(music_time::MusicTime::new(3, 1, 1), Vec::new());
+      pattern
+        .push_event(music_time::MusicTime::new(1, 3, 1), Vec::new())
+        .unwrap();
+      pattern
+        .push_event(music_time::MusicTime::new(3, 1, 1), Vec::new())
+        .unwrap();
       composition.push_pattern(pattern);
 
       let mut pattern =
-        composition::Pattern::new("pattern_y", 130, time_signature::TimeSignature::default());
+        composition::Pattern::new("pattern_y", 130.0, time_signature::TimeSignature::default());
 
-      pattern.push_event(music_time::MusicTime::new(1, 3, 1), Vec::new());
-      pattern.push_event(music_time::MusicTime::new(3, 1, 1), Vec::new());
+      pattern
+        .push_event(music_time::MusicTime::new(1, 3, 1), Vec::new())
+        .unwrap();
+      pattern
+        .push_event(music_time::MusicTime::new(3, 1, 1), Vec::new())
+        .unwrap();
       composition.push_pattern(pattern);
 
       composition
@@ -147,10 +155,14 @@ mod tests {
     let composition = {
       let mut composition = composition::Composition::new("test compo");
       let mut pattern =
-        composition::Pattern::new("pattern_z", 140, time_signature::TimeSignature::default());
+        composition::Pattern::new("pattern_z", 140.0, time_signature::TimeSignature::default());
 
-      pattern.push_event(music_time::MusicTime::new(1, 3, 1), Vec::new());
-      pattern.push_event(music_time::MusicTime::new(5, 1, 1), Vec::new());
+      pattern
+        .push_event(music_time::MusicTime::new(1, 3, 1), Vec::new())
+        .unwrap();
+      pattern
+        .push_event(music_time::MusicTime::new(5, 1, 1), Vec::new())
+        .unwrap();
       composition.push_pattern(pattern);
 
       composition
@@ -174,4 +186,88 @@ mod tests {
     assert_eq!(my_state.beat_interval_count, 44);
     assert_eq!(my_state.current_time, music_time::MusicTime::new(5, 4, 8));
   }
+
+  #[test]
+  fn test_performance_engine_changes() {
+    use crate::{
+      performance_engine::{PerformanceEngine, PerformanceState},
+      theory::{composition, drums, groove},
+    };
+    use music_timer::{music_time, time_signature};
+
+    struct MyState {
+      beat_interval_count: u8,
+      beat_count: u8,
+      bar_count: u8,
+      current_time: music_time::MusicTime,
+    }
+
+    impl PerformanceState for MyState {
+      fn on_ready(&mut self, _composition: &composition::Composition) {}
+      fn on_beat_interval_change(&mut self, current_time: &music_time::MusicTime) {
+        self.beat_interval_count += 1;
+        self.current_time = current_time.clone();
+      }
+      fn on_beat_change(&mut self, _current_time: &music_time::MusicTime) {
+        self.beat_count += 1;
+      }
+      fn on_bar_change(&mut self, _current_time: &music_time::MusicTime) {
+        self.bar_count += 1;
+      }
+      fn on_event(&mut self, _event: &composition::PatternEvent) {}
+      fn on_pattern_playback_begin(&mut self, _pattern: &composition::Pattern) {}
+      fn on_pattern_playback_end(&mut self, _pattern: &composition::Pattern) {}
+      fn on_completed(&mut self, _composition: &composition::Composition) {}
+    }
+
+    let composition = {
+      let mut composition = composition::Composition::new("test compo");
+      let mut pattern =
+        composition::Pattern::new("pattern_z", 140.0, time_signature::TimeSignature::default());
+
+      pattern.push_time_signature_change(2, time_signature::TimeSignature::new(3, 4));
+      pattern.push_tempo_change(
+        music_time::MusicTime::new(1, 3, 1),
+        composition::TempoChange::Ramp(200.0, music_time::MusicTime::new(2, 1, 1)),
+      );
+      pattern
+        .push_event(music_time::MusicTime::new(1, 1, 1), Vec::new())
+        .unwrap();
+      pattern
+        .push_event(music_time::MusicTime::new(2, 1, 1), Vec::new())
+        .unwrap();
+
+      // Swung drums are scheduled between beat intervals and don't change the timing
+      let rock = drums::string_to_drum_style("rock");
+      assert!(pattern.set_drums(rock).is_ok());
+      assert!(pattern.set_swing(Some(groove::Swing::new(16, 0.6))).is_ok());
+      composition.push_pattern(pattern);
+
+      composition
+    };
+
+    let mut my_state = MyState {
+      beat_interval_count: 0,
+      beat_count: 0,
+      bar_count: 0,
+      current_time: music_time::MusicTime::default(),
+    };
+
+    let performance_engine = PerformanceEngine::new_with_drums(
+      &composition,
+      &mut my_state,
+      &Vec::new(),
+      &Vec::new(),
+      &Vec::new(),
+    );
+
+    match performance_engine {
+      Ok(mut performance) => performance.run(),
+      _ => assert!(false, "Cannot create performance engine"),
+    }
+    assert_eq!(my_state.beat_interval_count, 56);
+    assert_eq!(my_state.beat_count, 7);
+    assert_eq!(my_state.bar_count, 2);
+    assert_eq!(my_state.current_time, music_time::MusicTime::new(2, 3, 8));
+  }
 }
@@ -1,7 +1,13 @@
 use crate::audio::basic_sampler;
-use crate::{theory::composition, FailResult};
-use music_timer::{music_time, music_timer_engine};
-use std::{thread, time::Duration};
+use crate::{
+  theory::{composition, drums},
+  FailResult,
+};
+use music_timer::{music_time, music_timer_engine, time_signature};
+use std::{
+  thread,
+  time::{Duration, Instant},
+};
 
 pub trait PerformanceState {
   fn on_ready(&mut self, composition: &composition::Composition);
@@ -9,6 +15,8 @@ pub trait PerformanceState {
   fn on_beat_change(&mut self, current_time: &music_time::MusicTime);
   fn on_bar_change(&mut self, current_time: &music_time::MusicTime);
   fn on_event(&mut self, event: &composition::PatternEvent);
+  /// Called with the full chord event, including its chord symbol, after `on_event`.
+  fn on_chord_event(&mut self, _event: &composition::ChordEvent) {}
   fn on_pattern_playback_begin(&mut self, pattern: &composition::Pattern);
   fn on_pattern_playback_end(&mut self, pattern: &composition::Pattern);
   fn on_completed(&mut self, composition: &composition::Composition);
@@ -17,12 +25,20 @@ pub trait PerformanceState {
 pub struct PerformanceEngine<'a, State: PerformanceState> {
   sampler_metronome: basic_sampler::SamplerPlayer,
   sampler_piano: basic_sampler::SamplerPlayer,
+  sampler_drums: basic_sampler::SamplerPlayer,
   event_head: usize,
   current_pattern: &'a composition::Pattern,
   is_playing: bool,
   composition: &'a composition::Composition,
   state: &'a mut State,
   is_metronome_enabled: bool,
+  previous_beat_time: music_time::MusicTime,
+  previous_bar: u16,
+  scheduled_events: Vec<(Instant, usize)>,
+  scheduled_notes: Vec<(Instant, u8, u8, u8, Option<Duration>)>,
+  performed_events: Vec<(u32, Vec<composition::PerformedNote>)>,
+  drum_head: usize,
+  drum_notes: Vec<composition::PerformedNote>,
 }
 
 impl<'a, State: PerformanceState> PerformanceEngine<'a, State> {
@@ -31,23 +47,51 @@ impl<'a, State: PerformanceState> PerformanceEngine<'a, State> {
     state: &'a mut State,
     sample_paths_metronome: &Vec<String>,
     sample_paths_piano: &Vec<String>,
+  ) -> Result<Self, FailResult> {
+    Self::new_with_drums(
+      composition,
+      state,
+      sample_paths_metronome,
+      sample_paths_piano,
+      &Vec::new(),
+    )
+  }
+
+  /// Create a performance engine that also plays drum tracks through the drum
+  /// samples.
+  pub fn new_with_drums(
+    composition: &'a composition::Composition,
+    state: &'a mut State,
+    sample_paths_metronome: &Vec<String>,
+    sample_paths_piano: &Vec<String>,
+    sample_paths_drums: &Vec<String>,
   ) -> Result<Self, FailResult> {
     if composition.len() > 0 {
       let sampler_metronome = basic_sampler::SamplerPlayer::new(sample_paths_metronome);
       let sampler_piano = basic_sampler::SamplerPlayer::new(&sample_paths_piano);
-      let error_loading = sampler_metronome.is_err() || sampler_piano.is_err();
+      let sampler_drums = basic_sampler::SamplerPlayer::new(sample_paths_drums);
+      let error_loading =
+        sampler_metronome.is_err() || sampler_piano.is_err() || sampler_drums.is_err();
       if error_loading {
         Err(FailResult::LoadSampler)
       } else {
         Ok(PerformanceEngine {
           sampler_metronome: sampler_metronome.unwrap(),
           sampler_piano: sampler_piano.unwrap(),
+          sampler_drums: sampler_drums.unwrap(),
           event_head: 0,
           current_pattern: &composition.get(0),
           is_playing: false,
           composition,
           state,
           is_metronome_enabled: false,
+          previous_beat_time: music_time::MusicTime::new(0, 0, 0),
+          previous_bar: 0,
+          scheduled_events: Vec::new(),
+          scheduled_notes: Vec::new(),
+          performed_events: Vec::new(),
+          drum_head: 0,
+          drum_notes: Vec::new(),
         })
       }
     } else {
@@ -67,11 +111,9 @@ impl<'a, State: PerformanceState> PerformanceEngine<'a, State> {
       self.is_playing = true;
 
       // Create new music timer
-      let mut music_timer = music_timer::create_performance_engine(
-        pattern.get_time_signature().get_numerator(),
-        pattern.get_time_signature().get_denominator(),
-        pattern.get_bpm() as f32,
-      );
+      let mut timer_bpm = pattern.get_bpm_at(start_time);
+      let mut timer_time_signature = pattern.get_time_signature_at(start_time.get_bar());
+      let mut music_timer = create_music_timer(timer_time_signature, timer_bpm);
 
       // Set the current time for playback and
       // advance events to that time
@@ -80,12 +122,50 @@ impl<'a, State: PerformanceState> PerformanceEngine<'a, State> {
 
       // Assign current pattern
       self.current_pattern = pattern;
+      self.performed_events = pattern.get_performed_events();
+      self.drum_notes = pattern.get_drum_notes();
+      let start_tick_index = pattern.get_tick_index(start_time, 0);
+      self.drum_head = self
+        .drum_notes
+        .partition_point(|(tick_index, _, _, _)| *tick_index < start_tick_index);
+      self.previous_beat_time = music_time::MusicTime::new(0, 0, 0);
+      self.previous_bar = 0;
+      let mut beat_interval_start = Instant::now();
 
       // Loop while playback enabled
       while self.is_playing {
-        music_timer.pulse(self);
-        const PULSE_RESOLUTION: Duration = Duration::from_millis(16);
-        thread::sleep(PULSE_RESOLUTION);
+        let next_time = *music_timer.get_current_time();
+        let bpm = pattern.get_bpm_at(&next_time);
+        let time_signature = pattern.get_time_signature_at(next_time.get_bar());
+
+        // Tempo and time signature changes need a new music timer. It is started
+        // once the current beat interval has elapsed at the new tempo.
+        let is_timer_changed = bpm != timer_bpm || time_signature != timer_time_signature;
+        let is_timer_ready =
+          !is_timer_changed || beat_interval_start.elapsed() >= to_beat_interval_duration(bpm);
+
+        if is_timer_changed && is_timer_ready {
+          music_timer = create_music_timer(time_signature, bpm);
+          music_timer.set_music_timer(next_time);
+          timer_bpm = bpm;
+          timer_time_signature = time_signature;
+        }
+
+        if is_timer_ready {
+          music_timer.pulse(self);
+          if *music_timer.get_current_time() != next_time {
+            beat_interval_start = Instant::now();
+          }
+        }
+
+        self.trigger_scheduled_events();
+        thread::sleep(self.to_pulse_duration());
+      }
+
+      // Events offset into the last beat interval play after the timer stops
+      while !self.scheduled_events.is_empty() || !self.scheduled_notes.is_empty() {
+        self.trigger_scheduled_events();
+        thread::sleep(self.to_pulse_duration());
       }
       self.state.on_pattern_playback_end(pattern);
     }
@@ -96,6 +176,140 @@ impl<'a, State: PerformanceState> PerformanceEngine<'a, State> {
   pub fn set_metronome_enabled(&mut self, is_enabled: bool) {
     self.is_metronome_enabled = is_enabled;
   }
+
+  fn trigger_event(&mut self, event_index: usize) {
+    let pattern = self.current_pattern;
+    self.state.on_event(pattern.get(event_index));
+    self.state.on_chord_event(pattern.get_event(event_index));
+
+    // Humanized and arpeggiated notes can start after their chord, and stop at
+    // the end of their rhythm hit or arpeggio gate
+    let now = Instant::now();
+    let interval_duration =
+      to_beat_interval_duration(pattern.get_bpm_at(&pattern.get(event_index).0));
+    let to_duration =
+      |ticks: u32| interval_duration * ticks / composition::BEAT_INTERVAL_TICKS as u32;
+    let (chord_tick_index, notes) = self.performed_events[event_index].clone();
+    let channel = pattern.get_channel();
+    for (tick_index, end_tick_index, note, velocity) in notes {
+      let offset = tick_index - chord_tick_index;
+      let length = to_duration(end_tick_index.saturating_sub(tick_index));
+      self.schedule_note(
+        now,
+        to_duration(offset),
+        channel,
+        note,
+        velocity,
+        Some(length),
+      );
+    }
+  }
+
+  /// Play a note now, or schedule it to play after a delay.
+  fn schedule_note(
+    &mut self,
+    now: Instant,
+    delay: Duration,
+    channel: u8,
+    note: u8,
+    velocity: u8,
+    length: Option<Duration>,
+  ) {
+    if delay == Duration::from_secs(0) {
+      self.play_note(channel, note, velocity, length);
+    } else {
+      let trigger_time = now + delay;
+      let index = self
+        .scheduled_notes
+        .partition_point(|(other_time, _, _, _, _)| *other_time <= trigger_time);
+      self
+        .scheduled_notes
+        .insert(index, (trigger_time, channel, note, velocity, length));
+    }
+  }
+
+  /// Play a note through the drum samples on the drum channel, otherwise the
+  /// piano samples. Piano notes stop after their length, drums always ring out.
+  /// The default velocity plays at full volume, quieter velocities below it.
+  /// Piano notes below the lowest sample aren't played.
+  fn play_note(&self, channel: u8, note: u8, velocity: u8, length: Option<Duration>) {
+    const MIDI_OFFSET: usize = 24;
+    const DRUM_MIDI_OFFSET: usize = 35;
+    let volume = (velocity as f32 / composition::DEFAULT_VELOCITY as f32).min(1.0);
+    if channel == drums::DRUM_CHANNEL {
+      let sample_index = (note as usize).saturating_sub(DRUM_MIDI_OFFSET);
+      self.sampler_drums.play_with_volume(sample_index, volume);
+    } else {
+      let sample_index = match (note as usize).checked_sub(MIDI_OFFSET) {
+        Some(sample_index) => sample_index,
+        None => return,
+      };
+      match length {
+        Some(length) => self
+          .sampler_piano
+          .play_with_length(sample_index, volume, length),
+        None => self.sampler_piano.play_with_volume(sample_index, volume),
+      }
+    }
+  }
+
+  /// Play or schedule the drum notes starting before a tick index.
+  fn trigger_drum_notes(&mut self, interval_tick_index: u32, until_tick_index: u32, bpm: f32) {
+    let now = Instant::now();
+    let interval_duration = to_beat_interval_duration(bpm);
+    while self.drum_head < self.drum_notes.len() {
+      let (tick_index, _, note, velocity) = self.drum_notes[self.drum_head];
+      if tick_index >= until_tick_index {
+        break;
+      }
+      self.drum_head += 1;
+      let offset = tick_index.saturating_sub(interval_tick_index);
+      let delay = interval_duration * offset / composition::BEAT_INTERVAL_TICKS as u32;
+      self.schedule_note(now, delay, drums::DRUM_CHANNEL, note, velocity, None);
+    }
+  }
+
+  fn trigger_scheduled_events(&mut self) {
+    let now = Instant::now();
+    while !self.scheduled_events.is_empty() && self.scheduled_events[0].0 <= now {
+      let (_, event_index) = self.scheduled_events.remove(0);
+      self.trigger_event(event_index);
+    }
+    while !self.scheduled_notes.is_empty() && self.scheduled_notes[0].0 <= now {
+      let (_, channel, note, velocity, length) = self.scheduled_notes.remove(0);
+      self.play_note(channel, note, velocity, length);
+    }
+  }
+
+  /// Get the time to sleep between pulses, waking early for scheduled events
+  /// and notes.
+  fn to_pulse_duration(&self) -> Duration {
+    const PULSE_RESOLUTION: Duration = Duration::from_millis(16);
+    let next_event_time = self.scheduled_events.first().map(|(time, _)| *time);
+    let next_note_time = self.scheduled_notes.first().map(|(time, _, _, _, _)| *time);
+    match next_event_time.into_iter().chain(next_note_time).min() {
+      Some(trigger_time) => trigger_time
+        .saturating_duration_since(Instant::now())
+        .min(PULSE_RESOLUTION),
+      None => PULSE_RESOLUTION,
+    }
+  }
+}
+
+fn create_music_timer(
+  time_signature: time_signature::TimeSignature,
+  bpm: f32,
+) -> music_timer_engine::MusicTimerEngine {
+  music_timer::create_performance_engine(
+    time_signature.get_numerator(),
+    time_signature.get_denominator(),
+    bpm,
+  )
+}
+
+fn to_beat_interval_duration(bpm: f32) -> Duration {
+  const BEAT_INTERVALS: f32 = 8.0;
+  Duration::from_secs_f32(60.0 / bpm / BEAT_INTERVALS)
 }
 
 impl<'a, State: PerformanceState> music_timer_engine::MusicTimerState
@@ -105,37 +319,56 @@ impl<'a, State: PerformanceState> music_timer_engine::MusicTimerState
     let events_complete = self.event_head == self.current_pattern.len();
 
     const MAX_BEAT_INTERVALS: u8 = 8;
+    let numerator = self
+      .current_pattern
+      .get_time_signature_at(current_time.get_bar())
+      .get_numerator();
     self.is_playing = !(events_complete
-      && current_time.get_beat() == self.current_pattern.get_time_signature().get_numerator()
+      && current_time.get_beat() == numerator
       && current_time.get_beat_interval() == MAX_BEAT_INTERVALS);
 
     self.state.on_beat_interval_change(current_time);
-    if !events_complete {
-      let current_event = self.current_pattern.get(self.event_head);
-
-      let (event_time, event_notes) = current_event;
-
-      let is_event_trigger_time = current_time == event_time;
-      if is_event_trigger_time {
-        self.state.on_event(&current_event);
-        self.event_head += 1;
-
-        for note in event_notes {
-          let sample_index = {
-            const MIDI_OFFSET: usize = 24;
-            *note as usize - MIDI_OFFSET
-          };
+    let interval_tick_index = self.current_pattern.get_tick_index(current_time, 0);
+    let interval_ticks = composition::BEAT_INTERVAL_TICKS as u32;
+    self.trigger_drum_notes(
+      interval_tick_index,
+      interval_tick_index + interval_ticks,
+      self.current_pattern.get_bpm_at(current_time),
+    );
+    while self.event_head < self.current_pattern.len() {
+      // Events are triggered at their tick after swing, groove and humanize are applied
+      let tick_index = self.performed_events[self.event_head].0;
+      let is_event_trigger_time = tick_index < interval_tick_index + interval_ticks;
+      if !is_event_trigger_time {
+        break;
+      }
 
-          self.sampler_piano.play(sample_index);
-        }
+      // Events offset between beat intervals are scheduled to play later
+      let event_index = self.event_head;
+      self.event_head += 1;
+      let offset = tick_index.saturating_sub(interval_tick_index);
+      if offset == 0 {
+        self.trigger_event(event_index);
+      } else {
+        let offset_duration =
+          to_beat_interval_duration(self.current_pattern.get_bpm_at(current_time)) * offset
+            / interval_ticks;
+        self
+          .scheduled_events
+          .push((Instant::now() + offset_duration, event_index));
       }
     }
   }
 
   fn on_beat(&mut self, current_time: &music_time::MusicTime) {
-    if !self.is_playing {
+    // A new music timer reports the beat and bar it starts on, even
+    // when a tempo or time signature change happens mid-bar
+    let is_beat_repeated = current_time.get_bar() == self.previous_beat_time.get_bar()
+      && current_time.get_beat() == self.previous_beat_time.get_beat();
+    if !self.is_playing || is_beat_repeated {
       return;
     }
+    self.previous_beat_time = *current_time;
 
     self.state.on_beat_change(current_time);
     if self.is_metronome_enabled && current_time.get_beat() != 1 {
@@ -144,9 +377,11 @@ impl<'a, State: PerformanceState> music_timer_engine::MusicTimerState
   }
 
   fn on_bar(&mut self, current_time: &music_time::MusicTime) {
-    if !self.is_playing {
+    let is_bar_repeated = current_time.get_bar() == self.previous_bar;
+    if !self.is_playing || is_bar_repeated {
       return;
     }
+    self.previous_bar = current_time.get_bar();
 
     self.state.on_bar_change(current_time);
     if self.is_metronome_enabled {
@@ -0,0 +1,244 @@
+use super::composition::{PerformedNote, BEAT_INTERVAL_TICKS};
+use super::humanize::HumanizedNote;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// The order an arpeggiator plays the notes of a chord in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArpeggioOrder {
+  /// Lowest note to highest.
+  Up,
+  /// Highest note to lowest.
+  Down,
+  /// Lowest note to highest and back, without repeating the highest and lowest.
+  UpDown,
+  /// Notes picked at random.
+  Random,
+  /// The order the notes are written in the chord.
+  AsPlayed,
+}
+
+pub fn string_to_order(order: &str) -> Option<ArpeggioOrder> {
+  match order {
+    "up" => Some(ArpeggioOrder::Up),
+    "down" => Some(ArpeggioOrder::Down),
+    "up_down" => Some(ArpeggioOrder::UpDown),
+    "random" => Some(ArpeggioOrder::Random),
+    "as_played" => Some(ArpeggioOrder::AsPlayed),
+    _ => None,
+  }
+}
+
+pub fn order_to_string(order: ArpeggioOrder) -> &'static str {
+  match order {
+    ArpeggioOrder::Up => "up",
+    ArpeggioOrder::Down => "down",
+    ArpeggioOrder::UpDown => "up_down",
+    ArpeggioOrder::Random => "random",
+    ArpeggioOrder::AsPlayed => "as_played",
+  }
+}
+
+/// An arpeggiator turns each chord into a sequence of single notes, played at
+/// a rate until the next chord.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arpeggiator {
+  order: ArpeggioOrder,
+  rate: u8,
+  octaves: u8,
+  gate: f32,
+  seed: u64,
+}
+
+impl Arpeggiator {
+  /// The most octaves a chord can be arpeggiated over.
+  pub const MAX_OCTAVES: u8 = 4;
+
+  /// Create an arpeggiator.
+  ///
+  /// # Arguments
+  /// * `order` - The order to play the notes of a chord in.
+  /// * `rate` - The note value the notes are played at, e.g 16 for sixteenths or
+  ///   12 for eighth note triplets.
+  /// * `octaves` - The count of octaves to play the chord over, from 1 to 4.
+  /// * `gate` - The share of each step a note sounds for, above 0 and up to 1.
+  /// * `seed` - The seed of the random order.
+  pub fn new(order: ArpeggioOrder, rate: u8, octaves: u8, gate: f32, seed: u64) -> Self {
+    Arpeggiator {
+      order,
+      rate,
+      octaves,
+      gate,
+      seed,
+    }
+  }
+
+  /// Check the rate divides a whole note into ticks, the octaves are in range
+  /// and the gate is above 0 and up to 1.
+  pub fn is_valid(&self) -> bool {
+    const MAX_RATE: u8 = 32;
+    let is_rate_valid =
+      self.rate > 0 && self.rate <= MAX_RATE && to_whole_note_ticks() % self.rate as u32 == 0;
+    let is_octaves_valid = self.octaves > 0 && self.octaves <= Arpeggiator::MAX_OCTAVES;
+    let is_gate_valid = self.gate > 0.0 && self.gate <= 1.0;
+    is_rate_valid && is_octaves_valid && is_gate_valid
+  }
+
+  pub fn get_order(&self) -> ArpeggioOrder {
+    self.order
+  }
+
+  pub fn get_rate(&self) -> u8 {
+    self.rate
+  }
+
+  pub fn get_octaves(&self) -> u8 {
+    self.octaves
+  }
+
+  pub fn get_gate(&self) -> f32 {
+    self.gate
+  }
+
+  pub fn get_seed(&self) -> u64 {
+    self.seed
+  }
+
+  /// Arpeggiate the chords of a pattern. Each note keeps the velocity, and the
+  /// offset from the start of its chord, of the chord note it was made from.
+  ///
+  /// # Arguments
+  /// * `chords` - The start and end tick index and the notes of each chord.
+  pub fn apply(&self, chords: &[(u32, u32, Vec<HumanizedNote>)]) -> Vec<Vec<PerformedNote>> {
+    const MAX_MIDI_NOTE: u16 = 127;
+
+    let mut rng = StdRng::seed_from_u64(self.seed);
+    let step_ticks = to_whole_note_ticks() / self.rate.max(1) as u32;
+    let gate_ticks = ((step_ticks as f32 * self.gate).round() as u32).max(1);
+
+    chords
+      .iter()
+      .map(|(start_tick_index, end_tick_index, notes)| {
+        let mut sorted_notes = notes.clone();
+        sorted_notes.sort_by_key(|(_, note, _)| *note);
+        let chord_notes = match self.order {
+          ArpeggioOrder::AsPlayed | ArpeggioOrder::Random => notes,
+          _ => &sorted_notes,
+        };
+
+        let mut steps: Vec<HumanizedNote> = (0..self.octaves as u16)
+          .flat_map(|octave| {
+            chord_notes
+              .iter()
+              .filter(move |(_, note, _)| *note as u16 + octave * 12 <= MAX_MIDI_NOTE)
+              .map(move |(tick_index, note, velocity)| {
+                (
+                  *tick_index - start_tick_index,
+                  (*note as u16 + octave * 12) as u8,
+                  *velocity,
+                )
+              })
+          })
+          .collect();
+
+        match self.order {
+          ArpeggioOrder::Down => steps.reverse(),
+          ArpeggioOrder::UpDown if steps.len() > 2 => {
+            let down: Vec<HumanizedNote> =
+              steps[1..steps.len() - 1].iter().rev().cloned().collect();
+            steps.extend(down);
+          }
+          _ => {}
+        }
+
+        if steps.is_empty() {
+          return Vec::new();
+        }
+
+        let mut performed_notes = Vec::new();
+        let mut step_tick_index = *start_tick_index;
+        let mut step = 0;
+        while step_tick_index < *end_tick_index {
+          let (offset, note, velocity) = match self.order {
+            ArpeggioOrder::Random => steps[rng.gen_range(0, steps.len())],
+            _ => steps[step % steps.len()],
+          };
+
+          let note_tick_index = (step_tick_index + offset).min(end_tick_index - 1);
+          let note_end_tick_index = (note_tick_index + gate_ticks).min(*end_tick_index);
+          performed_notes.push((note_tick_index, note_end_tick_index, note, velocity));
+
+          step_tick_index += step_ticks;
+          step += 1;
+        }
+
+        performed_notes
+      })
+      .collect()
+  }
+}
+
+/// The count of ticks in a whole note, where the beat is a quarter note.
+fn to_whole_note_ticks() -> u32 {
+  const BEAT_INTERVALS: u32 = 8;
+  BEAT_INTERVAL_TICKS as u32 * BEAT_INTERVALS * 4
+}
+
+mod tests {
+
+  #[test]
+  fn test_arpeggio_orders() {
+    use crate::theory::arpeggiator::*;
+
+    let notes = vec![(0, 64, 70), (0, 60, 64), (0, 67, 60)];
+    let chords = vec![(0, 480, notes)];
+    let played_notes = |order: ArpeggioOrder| -> Vec<u8> {
+      Arpeggiator::new(order, 16, 1, 1.0, 0).apply(&chords)[0]
+        .iter()
+        .map(|(_, _, note, _)| *note)
+        .collect()
+    };
+
+    assert_eq!(played_notes(ArpeggioOrder::Up), vec![60, 64, 67, 60]);
+    assert_eq!(played_notes(ArpeggioOrder::Down), vec![67, 64, 60, 67]);
+    assert_eq!(played_notes(ArpeggioOrder::UpDown), vec![60, 64, 67, 64]);
+    assert_eq!(played_notes(ArpeggioOrder::AsPlayed), vec![64, 60, 67, 64]);
+
+    let random = played_notes(ArpeggioOrder::Random);
+    assert_eq!(random.len(), 4);
+    assert!(random.iter().all(|note| [60, 64, 67].contains(note)));
+    assert_eq!(random, played_notes(ArpeggioOrder::Random));
+
+    assert_eq!(string_to_order("up_down"), Some(ArpeggioOrder::UpDown));
+    assert_eq!(string_to_order("sideways"), None);
+  }
+
+  #[test]
+  fn test_arpeggio_rate_and_gate() {
+    use crate::theory::arpeggiator::*;
+
+    let chords = vec![(0, 300, vec![(0, 60, 64), (0, 64, 80)])];
+    let arpeggiator = Arpeggiator::new(ArpeggioOrder::Up, 8, 2, 0.5, 0);
+    assert!(arpeggiator.is_valid());
+    assert_eq!(
+      arpeggiator.apply(&chords)[0],
+      vec![(0, 120, 60, 64), (240, 300, 64, 80)]
+    );
+
+    let chords = vec![(0, 960, vec![(0, 60, 64), (0, 64, 80)])];
+    assert_eq!(
+      Arpeggiator::new(ArpeggioOrder::Up, 8, 2, 1.0, 0).apply(&chords)[0],
+      vec![
+        (0, 240, 60, 64),
+        (240, 480, 64, 80),
+        (480, 720, 72, 64),
+        (720, 960, 76, 80)
+      ]
+    );
+
+    assert!(Arpeggiator::new(ArpeggioOrder::Up, 12, 1, 1.0, 0).is_valid());
+    assert!(!Arpeggiator::new(ArpeggioOrder::Up, 7, 1, 1.0, 0).is_valid());
+    assert!(!Arpeggiator::new(ArpeggioOrder::Up, 16, 0, 1.0, 0).is_valid());
+    assert!(!Arpeggiator::new(ArpeggioOrder::Up, 16, 5, 1.0, 0).is_valid());
+    assert!(!Arpeggiator::new(ArpeggioOrder::Up, 16, 1, 0.0, 0).is_valid());
+  }
+}
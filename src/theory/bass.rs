@@ -0,0 +1,134 @@
+use super::composition::BEAT_INTERVAL_TICKS;
+
+/// The midi channel bass lines are exported on, the second channel.
+pub const BASS_CHANNEL: u8 = 1;
+
+/// The lowest root note of a bass line, E1.
+const LOWEST_ROOT_NOTE: u8 = 28;
+
+/// The style a bass line is generated in from the chords of a pattern.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BassStyle {
+  /// The root of each chord, held until the next chord.
+  Root,
+  /// The root and fifth of each chord, alternating every beat.
+  RootFifth,
+  /// The root and the octave above, alternating every eighth note.
+  Octave,
+  /// A note every beat, starting on the root, walking through the chord tones
+  /// and approaching the next root from a semitone below.
+  Walking,
+}
+
+pub fn string_to_bass_style(style: &str) -> Option<BassStyle> {
+  match style {
+    "root" => Some(BassStyle::Root),
+    "root_fifth" => Some(BassStyle::RootFifth),
+    "octave" => Some(BassStyle::Octave),
+    "walking" => Some(BassStyle::Walking),
+    _ => None,
+  }
+}
+
+pub fn bass_style_to_string(style: BassStyle) -> &'static str {
+  match style {
+    BassStyle::Root => "root",
+    BassStyle::RootFifth => "root_fifth",
+    BassStyle::Octave => "octave",
+    BassStyle::Walking => "walking",
+  }
+}
+
+impl BassStyle {
+  /// Get the count of ticks between bass notes, or `None` if the chord is held.
+  pub fn get_step_ticks(&self) -> Option<u32> {
+    const BEAT_TICKS: u32 = BEAT_INTERVAL_TICKS as u32 * 8;
+    match self {
+      BassStyle::Root => None,
+      BassStyle::RootFifth | BassStyle::Walking => Some(BEAT_TICKS),
+      BassStyle::Octave => Some(BEAT_TICKS / 2),
+    }
+  }
+
+  /// Get the notes to play over a chord.
+  ///
+  /// # Arguments
+  /// * `root` - The pitch class of the chord root, 0 for C.
+  /// * `tones` - The pitch classes of the chord.
+  /// * `next_root` - The pitch class of the next chord root, if there is one.
+  /// * `steps` - The count of notes to play.
+  pub fn get_notes(&self, root: u8, tones: &[u8], next_root: Option<u8>, steps: usize) -> Vec<u8> {
+    let root_note = to_bass_note(root);
+    let mut intervals: Vec<u8> = tones
+      .iter()
+      .map(|tone| (*tone as i16 - root as i16).rem_euclid(12) as u8)
+      .filter(|interval| *interval != 0)
+      .collect();
+    intervals.sort_unstable();
+    intervals.dedup();
+
+    const FIFTH: u8 = 7;
+    let fifth = match intervals.iter().find(|interval| **interval == FIFTH) {
+      Some(fifth) => *fifth,
+      None => *intervals
+        .iter()
+        .min_by_key(|interval| (**interval as i8 - FIFTH as i8).abs())
+        .unwrap_or(&FIFTH),
+    };
+
+    (0..steps)
+      .map(|step| match self {
+        BassStyle::Root => root_note,
+        BassStyle::RootFifth if step % 2 == 1 => root_note + fifth,
+        BassStyle::RootFifth => root_note,
+        BassStyle::Octave if step % 2 == 1 => root_note + 12,
+        BassStyle::Octave => root_note,
+        BassStyle::Walking => match (step, next_root) {
+          (0, _) => root_note,
+          (step, Some(next_root)) if step == steps - 1 => to_bass_note(next_root) - 1,
+          (step, _) if intervals.is_empty() => root_note + fifth * (step % 2) as u8,
+          (step, _) => root_note + intervals[(step - 1) % intervals.len()],
+        },
+      })
+      .collect()
+  }
+}
+
+/// Get the bass note of a pitch class, from E1 to D#2.
+fn to_bass_note(pitch_class: u8) -> u8 {
+  LOWEST_ROOT_NOTE + (pitch_class as i16 - LOWEST_ROOT_NOTE as i16).rem_euclid(12) as u8
+}
+
+mod tests {
+
+  #[test]
+  fn test_bass_notes() {
+    use crate::theory::bass::*;
+
+    let c_major = [0, 4, 7];
+    assert_eq!(BassStyle::Root.get_notes(0, &c_major, Some(5), 1), vec![36]);
+    assert_eq!(
+      BassStyle::RootFifth.get_notes(0, &c_major, Some(5), 4),
+      vec![36, 43, 36, 43]
+    );
+    assert_eq!(
+      BassStyle::Octave.get_notes(4, &[4, 7, 11], None, 3),
+      vec![28, 40, 28]
+    );
+    assert_eq!(
+      BassStyle::Walking.get_notes(0, &c_major, Some(5), 4),
+      vec![36, 40, 43, 28]
+    );
+    assert_eq!(
+      BassStyle::Walking.get_notes(9, &[9, 0, 3], Some(2), 4),
+      vec![33, 36, 39, 37]
+    );
+    assert_eq!(
+      BassStyle::RootFifth.get_notes(0, &[0, 3, 6], None, 2),
+      vec![36, 42]
+    );
+
+    assert_eq!(string_to_bass_style("walking"), Some(BassStyle::Walking));
+    assert_eq!(string_to_bass_style("slap"), None);
+  }
+}
@@ -4,6 +4,17 @@ use super::notes;
 
 pub type CustomChords = Vec<(String, Vec<i8>)>;
 
+/// A MusicXML degree of a chord, the degree, its alteration in semitones and
+/// its type.
+pub type ChordDegree = (u8, i8, &'static str);
+
+/// The chord name of a rest, an event that silences the previous chord.
+pub const REST_NAME: &str = "REST";
+/// The chord name to select a random user defined chord.
+pub const RANDOM_CUSTOM_NAME: &str = "?";
+/// The chord name to select a random chord from user defined and internal chords.
+pub const RANDOM_NAME: &str = "??";
+
 // TODO: remove chords? Allow user created chords only?
 pub const AUGMENTED: [i8; 3] = [0, 4, 8];
 pub const AUGMENTED_ELEVENTH: [i8; 6] = [0, 4, 7, 10, 2, 6];
@@ -314,6 +325,338 @@ pub fn chord_to_string_array() -> Vec<&'static str> {
   ]
 }
 
+/// Returns the names of all the internally supported chords.
+pub fn chord_names() -> Vec<&'static str> {
+  chord_to_string_array()
+    .into_iter()
+    .filter_map(|chord| chord.split(" = ").next())
+    .collect()
+}
+
+/// Check if a chord name can be resolved, as an internal chord, a custom chord,
+/// a random chord or a rest.
+pub fn is_chord_name_known(name: &str, custom_chords: &CustomChords) -> bool {
+  let name = name.trim();
+  let is_random_custom = name == RANDOM_CUSTOM_NAME && !custom_chords.is_empty();
+  let is_custom = custom_chords
+    .iter()
+    .any(|(custom_name, _)| custom_name.trim() == name);
+
+  name == REST_NAME
+    || name == RANDOM_NAME
+    || is_random_custom
+    || is_custom
+    || !string_to_chord(name).is_empty()
+}
+
+/// Recognise the internal chord of midi notes by their pitch classes, trying
+/// roots from the lowest note upward and common chords before the rest.
+///
+/// Returns the chord name and the midi note of its root, at or below the
+/// lowest note, or in the octave above when that would be below midi note 0.
+/// Returns `None` if no internal chord has the same pitch classes.
+pub fn recognise_chord(midi_notes: &[u8]) -> Option<(&'static str, u8)> {
+  const NOTES_IN_OCTAVE_COUNT: u8 = 12;
+  const PREFERRED_NAMES: [&str; 17] = [
+    "MAJOR",
+    "MINOR",
+    "DOMINANT_SEVENTH",
+    "MAJOR_SEVENTH",
+    "MINOR_SEVENTH",
+    "DIMINISHED",
+    "AUGMENTED",
+    "HALF_DIMINISHED_SEVENTH",
+    "DIMINISHED_SEVENTH",
+    "MAJOR_SIXTH",
+    "MINOR_SIXTH",
+    "SUSPENDED",
+    "POWER",
+    "MAJOR_NINTH",
+    "MINOR_NINTH",
+    "DOMINANT_NINTH",
+    "MINOR_MAJOR_SEVENTH",
+  ];
+
+  let mut notes = midi_notes.to_vec();
+  notes.sort_unstable();
+  let lowest_note = *notes.first()?;
+
+  let mut roots: Vec<u8> = Vec::new();
+  for note in &notes {
+    let root = note % NOTES_IN_OCTAVE_COUNT;
+    if !roots.contains(&root) {
+      roots.push(root);
+    }
+  }
+
+  let names = PREFERRED_NAMES.iter().copied().chain(chord_names());
+  let chords: Vec<(&'static str, Vec<u8>)> = names
+    .map(|name| {
+      let intervals = string_to_chord(name)
+        .into_iter()
+        .map(|interval| interval.rem_euclid(NOTES_IN_OCTAVE_COUNT as i8) as u8);
+      (name, to_pitch_classes(intervals))
+    })
+    .collect();
+
+  for root in roots {
+    let intervals = to_pitch_classes(notes.iter().map(|note| note + NOTES_IN_OCTAVE_COUNT - root));
+    if let Some((name, _)) = chords.iter().find(|(_, chord)| *chord == intervals) {
+      let below = (lowest_note + NOTES_IN_OCTAVE_COUNT - root) % NOTES_IN_OCTAVE_COUNT;
+      let root_note = lowest_note
+        .checked_sub(below)
+        .unwrap_or(lowest_note + NOTES_IN_OCTAVE_COUNT - below);
+      return Some((name, root_note));
+    }
+  }
+  None
+}
+
+/// How a chord is written in chord symbols, the text after its root, its
+/// MusicXML kind and its LilyPond chord mode modifier.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ChordQuality {
+  suffix: &'static str,
+  kind: &'static str,
+  chord_mode: &'static str,
+}
+
+impl ChordQuality {
+  /// Get the text written after the root of a chord symbol, e.g. `m7`.
+  pub fn get_suffix(&self) -> &'static str {
+    self.suffix
+  }
+
+  /// Get the MusicXML kind of the chord, e.g. `minor-seventh`.
+  pub fn get_kind(&self) -> &'static str {
+    self.kind
+  }
+
+  /// Get the LilyPond chord mode modifier written after `:`, e.g. `m7.5-`.
+  /// Empty for major chords.
+  pub fn get_chord_mode(&self) -> &'static str {
+    self.chord_mode
+  }
+
+  /// Get the MusicXML degrees the chord adds to or alters in its kind, each
+  /// the degree, its alteration in semitones and whether it is `add` or
+  /// `alter`. Empty when the kind spells the whole chord.
+  pub fn get_degrees(&self) -> &'static [ChordDegree] {
+    CHORD_DEGREES
+      .iter()
+      .find(|(suffix, _)| *suffix == self.suffix)
+      .map_or(&[], |(_, degrees)| degrees)
+  }
+}
+
+/// The pitch classes of common chords, their chord symbol suffix, MusicXML
+/// kind and LilyPond chord mode modifier.
+const CHORD_QUALITIES: [(&[u8], &str, &str, &str); 39] = [
+  (&[0, 4, 7], "", "major", ""),
+  (&[0, 3, 7], "m", "minor", "m"),
+  (&[0, 4, 8], "aug", "augmented", "aug"),
+  (&[0, 3, 6], "dim", "diminished", "dim"),
+  (&[0, 4, 7, 10], "7", "dominant", "7"),
+  (&[0, 4, 7, 11], "maj7", "major-seventh", "maj7"),
+  (&[0, 3, 7, 10], "m7", "minor-seventh", "m7"),
+  (&[0, 3, 6, 9], "dim7", "diminished-seventh", "dim7"),
+  (&[0, 4, 8, 10], "aug7", "augmented-seventh", "aug7"),
+  (&[0, 3, 6, 10], "m7b5", "half-diminished", "m7.5-"),
+  (&[0, 3, 7, 11], "m(maj7)", "major-minor", "m7+"),
+  (&[0, 4, 7, 9], "6", "major-sixth", "6"),
+  (&[0, 3, 7, 9], "m6", "minor-sixth", "m6"),
+  (&[0, 2, 4, 7, 10], "9", "dominant-ninth", "9"),
+  (&[0, 2, 4, 7, 11], "maj9", "major-ninth", "maj9"),
+  (&[0, 2, 3, 7, 10], "m9", "minor-ninth", "m9"),
+  (&[0, 2, 4, 5, 7, 10], "11", "dominant-11th", "11"),
+  (&[0, 2, 4, 5, 7, 11], "maj11", "major-11th", "maj11"),
+  (&[0, 2, 3, 5, 7, 10], "m11", "minor-11th", "m11"),
+  (&[0, 2, 4, 5, 7, 9, 10], "13", "dominant-13th", "13.11"),
+  (&[0, 2, 4, 5, 7, 9, 11], "maj13", "major-13th", "maj13.11"),
+  (
+    &[0, 2, 4, 6, 7, 9, 11],
+    "maj13#11",
+    "major-13th",
+    "maj13.11+",
+  ),
+  (&[0, 2, 3, 5, 7, 9, 10], "m13", "minor-13th", "m13.11"),
+  (&[0, 2, 7], "sus2", "suspended-second", "sus2"),
+  (&[0, 5, 7], "sus4", "suspended-fourth", "sus4"),
+  (&[0, 5, 7, 10], "7sus4", "suspended-fourth", "7sus4"),
+  (&[0, 7], "5", "power", "1.5"),
+  (&[0, 2, 4, 7], "add9", "major", "5.9"),
+  (&[0, 2, 4, 7, 9], "6/9", "major-sixth", "6.9"),
+  (&[0, 2, 3, 7, 9], "m6/9", "minor-sixth", "m6.9"),
+  (&[0, 1, 4, 7, 10], "7b9", "dominant", "7.9-"),
+  (&[0, 3, 4, 7, 10], "7#9", "dominant", "7.9+"),
+  (&[0, 4, 6, 10], "7b5", "dominant", "7.5-"),
+  (&[0, 4, 7, 9, 10], "7add13", "dominant", "7.13"),
+  (&[0, 2, 4, 8, 10], "9#5", "dominant-ninth", "9.5+"),
+  (&[0, 2, 4, 6, 10], "9b5", "dominant-ninth", "9.5-"),
+  (&[0, 2, 4, 6, 7, 10], "9#11", "dominant-ninth", "9.11+"),
+  (&[0, 4, 6, 7, 11], "maj7#11", "major-seventh", "maj7.11+"),
+  (&[0, 4, 8, 11], "maj7#5", "major-seventh", "maj7.5+"),
+];
+
+/// The MusicXML degrees of the chords in `CHORD_QUALITIES` their kind doesn't
+/// spell, by suffix.
+const CHORD_DEGREES: [(&str, &[ChordDegree]); 14] = [
+  ("maj13#11", &[(11, 1, "alter")]),
+  ("7sus4", &[(7, -1, "add")]),
+  ("add9", &[(9, 0, "add")]),
+  ("6/9", &[(9, 0, "add")]),
+  ("m6/9", &[(9, 0, "add")]),
+  ("7b9", &[(9, -1, "add")]),
+  ("7#9", &[(9, 1, "add")]),
+  ("7b5", &[(5, -1, "alter")]),
+  ("7add13", &[(13, 0, "add")]),
+  ("9#5", &[(5, 1, "alter")]),
+  ("9b5", &[(5, -1, "alter")]),
+  ("9#11", &[(11, 1, "add")]),
+  ("maj7#11", &[(11, 1, "add")]),
+  ("maj7#5", &[(5, 1, "alter")]),
+];
+
+/// Other ways chord symbol suffixes are written, and the suffix they are
+/// written as in `CHORD_QUALITIES`.
+const SUFFIX_ALIASES: [(&str, &str); 22] = [
+  ("M", ""),
+  ("maj", ""),
+  ("min", "m"),
+  ("-", "m"),
+  ("+", "aug"),
+  ("o", "dim"),
+  ("°", "dim"),
+  ("o7", "dim7"),
+  ("°7", "dim7"),
+  ("+7", "aug7"),
+  ("7#5", "aug7"),
+  ("ø", "m7b5"),
+  ("ø7", "m7b5"),
+  ("m7-5", "m7b5"),
+  ("M7", "maj7"),
+  ("Δ", "maj7"),
+  ("Δ7", "maj7"),
+  ("min7", "m7"),
+  ("-7", "m7"),
+  ("mM7", "m(maj7)"),
+  ("sus", "sus4"),
+  ("7sus", "7sus4"),
+];
+
+/// Find how a chord is written in chord symbols from the pitch classes of its
+/// intervals above the root.
+///
+/// Returns `None` if the chord has no common chord symbol.
+pub fn chord_quality(intervals: &[i8]) -> Option<ChordQuality> {
+  const NOTES_IN_OCTAVE_COUNT: i8 = 12;
+
+  let pitch_classes = to_pitch_classes(
+    intervals
+      .iter()
+      .map(|interval| interval.rem_euclid(NOTES_IN_OCTAVE_COUNT) as u8),
+  );
+  CHORD_QUALITIES
+    .iter()
+    .find(|(chord, _, _, _)| *chord == pitch_classes.as_slice())
+    .map(|(_, suffix, kind, chord_mode)| ChordQuality {
+      suffix,
+      kind,
+      chord_mode,
+    })
+}
+
+/// Find the chord of a chord symbol from the text after its root, e.g. `m7` or
+/// `maj7`.
+///
+/// Returns the name of the internal chord with the same pitch classes, or
+/// `None` when there isn't one, and the intervals of the chord. Returns `None`
+/// if the suffix isn't a common chord symbol.
+pub fn suffix_to_chord(suffix: &str) -> Option<(Option<&'static str>, Vec<i8>)> {
+  const ROOT_NOTE: u8 = 60;
+
+  let suffix = SUFFIX_ALIASES
+    .iter()
+    .find(|(alias, _)| *alias == suffix)
+    .map_or(suffix, |(_, suffix)| suffix);
+  let (pitch_classes, _, _, _) = CHORD_QUALITIES
+    .iter()
+    .find(|(_, chord_suffix, _, _)| *chord_suffix == suffix)?;
+
+  let midi_notes: Vec<u8> = pitch_classes
+    .iter()
+    .map(|pitch_class| ROOT_NOTE + pitch_class)
+    .collect();
+  match recognise_chord(&midi_notes) {
+    Some((name, ROOT_NOTE)) => Some((Some(name), string_to_chord(name))),
+    _ => Some((
+      None,
+      pitch_classes
+        .iter()
+        .map(|pitch_class| *pitch_class as i8)
+        .collect(),
+    )),
+  }
+}
+
+/// The sorted pitch classes of notes or intervals, without repeats.
+fn to_pitch_classes(notes: impl Iterator<Item = u8>) -> Vec<u8> {
+  const NOTES_IN_OCTAVE_COUNT: u8 = 12;
+  let mut pitch_classes: Vec<u8> = notes.map(|note| note % NOTES_IN_OCTAVE_COUNT).collect();
+  pitch_classes.sort_unstable();
+  pitch_classes.dedup();
+  pitch_classes
+}
+
+/// Returns up to 3 internal and custom chord names similar to a name, the most
+/// similar first. Used to suggest corrections for unknown chord names.
+pub fn suggest_chord_names(name: &str, custom_chords: &CustomChords) -> Vec<String> {
+  const MAX_SUGGESTIONS: usize = 3;
+  const MIN_MAX_DISTANCE: usize = 2;
+
+  let name = name.trim().to_uppercase();
+  let max_distance = MIN_MAX_DISTANCE.max(name.chars().count() / 3);
+
+  let mut suggestions: Vec<(usize, String)> = chord_names()
+    .into_iter()
+    .map(|chord_name| chord_name.to_string())
+    .chain(
+      custom_chords
+        .iter()
+        .map(|(custom_name, _)| custom_name.trim().to_string()),
+    )
+    .map(|chord_name| (edit_distance(&name, &chord_name.to_uppercase()), chord_name))
+    .filter(|(distance, _)| *distance <= max_distance)
+    .collect();
+
+  suggestions.sort();
+  suggestions.dedup_by(|a, b| a.1 == b.1);
+  suggestions
+    .into_iter()
+    .take(MAX_SUGGESTIONS)
+    .map(|(_, chord_name)| chord_name)
+    .collect()
+}
+
+/// The count of single character insertions, deletions and substitutions to
+/// change one string to another.
+fn edit_distance(a: &str, b: &str) -> usize {
+  let b: Vec<char> = b.chars().collect();
+  let mut distances: Vec<usize> = (0..=b.len()).collect();
+
+  for (i, a_char) in a.chars().enumerate() {
+    let mut previous_diagonal = distances[0];
+    distances[0] = i + 1;
+    for (j, b_char) in b.iter().enumerate() {
+      let substitution = previous_diagonal + if a_char == *b_char { 0 } else { 1 };
+      previous_diagonal = distances[j + 1];
+      distances[j + 1] = substitution.min(distances[j] + 1).min(distances[j + 1] + 1);
+    }
+  }
+
+  distances[b.len()]
+}
+
 pub struct IntervalChord {
   intervals: Vec<i8>,
   transpose: i8,
@@ -333,7 +676,7 @@ impl IntervalChord {
   ) -> Self {
     use rand::Rng;
 
-    if interval_chord_string.trim() == "?" {
+    if interval_chord_string.trim() == RANDOM_CUSTOM_NAME && !custom_chords.is_empty() {
       let random_chord_intervals = {
         let index = rand::thread_rng().gen_range(0, custom_chords.len());
         custom_chords[index].1.clone()
@@ -343,7 +686,7 @@ impl IntervalChord {
         intervals: random_chord_intervals,
         transpose: 0,
       }
-    } else if interval_chord_string.trim() == "??" {
+    } else if interval_chord_string.trim() == RANDOM_NAME {
       let random_chord_intervals = {
         let mut custom_chords_intervals = Vec::with_capacity(custom_chords.len());
 
@@ -410,6 +753,10 @@ impl IntervalChord {
     self.intervals.is_empty()
   }
 
+  pub fn get_intervals(&self) -> &Vec<i8> {
+    &self.intervals
+  }
+
   pub fn get_interval(&self, index: usize) -> i8 {
     self.intervals[index] + self.transpose
   }
@@ -460,6 +807,109 @@ mod tests {
     assert_ne!(chord.intervals.len(), 0);
   }
 
+  #[test]
+  fn test_chord_names() {
+    use crate::theory::chords::*;
+    let custom_chords = vec![("customA".to_string(), vec![0, 1, 2])];
+
+    assert_eq!(chord_names().len(), 73);
+    assert_eq!(chord_names()[0], "AUGMENTED");
+
+    assert!(is_chord_name_known(" MAJOR_SEVENTH ", &custom_chords));
+    assert!(is_chord_name_known("customA", &custom_chords));
+    assert!(is_chord_name_known("REST", &custom_chords));
+    assert!(is_chord_name_known("?", &custom_chords));
+    assert!(is_chord_name_known("??", &custom_chords));
+    assert!(!is_chord_name_known("?", &Vec::new()));
+    assert!(!is_chord_name_known("MAJOR_SEVENT", &custom_chords));
+    assert!(!is_chord_name_known("major", &custom_chords));
+
+    assert_eq!(
+      suggest_chord_names("MAJOR_SEVENT", &custom_chords)[0],
+      "MAJOR_SEVENTH"
+    );
+    assert_eq!(
+      suggest_chord_names("MINOR_NINTHS", &custom_chords)[0],
+      "MINOR_NINTH"
+    );
+    assert_eq!(
+      suggest_chord_names("major", &custom_chords),
+      vec!["MAJOR", "MINOR"]
+    );
+    assert_eq!(
+      suggest_chord_names("customa", &custom_chords),
+      vec!["customA"]
+    );
+    assert_eq!(suggest_chord_names("MAJOR_SEVENT", &custom_chords).len(), 3);
+    assert!(suggest_chord_names("xyzzy", &custom_chords).is_empty());
+  }
+
+  #[test]
+  fn test_recognise_chord() {
+    use crate::theory::chords::*;
+
+    assert_eq!(recognise_chord(&[60, 64, 67]), Some(("MAJOR", 60)));
+    assert_eq!(recognise_chord(&[64, 67, 72]), Some(("MAJOR", 60)));
+    assert_eq!(recognise_chord(&[57, 60, 64]), Some(("MINOR", 57)));
+    assert_eq!(
+      recognise_chord(&[43, 59, 62, 65]),
+      Some(("DOMINANT_SEVENTH", 43))
+    );
+    assert_eq!(
+      recognise_chord(&[60, 64, 67, 69]),
+      Some(("MAJOR_SIXTH", 60))
+    );
+    assert_eq!(recognise_chord(&[62, 69, 74]), Some(("POWER", 62)));
+    assert_eq!(recognise_chord(&[60, 61, 62]), None);
+    assert_eq!(recognise_chord(&[4, 9, 12]), Some(("MINOR", 9)));
+    assert_eq!(recognise_chord(&[0, 4, 7]), Some(("MAJOR", 0)));
+    assert_eq!(recognise_chord(&[2, 5, 9]), Some(("MINOR", 2)));
+    assert_eq!(recognise_chord(&[60]), None);
+    assert_eq!(recognise_chord(&[]), None);
+  }
+
+  #[test]
+  fn test_chord_quality() {
+    use crate::theory::chords::*;
+
+    let suffix = |intervals: &[i8]| chord_quality(intervals).map(|quality| quality.get_suffix());
+    assert_eq!(suffix(&MAJOR), Some(""));
+    assert_eq!(suffix(&MINOR_SEVENTH), Some("m7"));
+    assert_eq!(suffix(&HALF_DIMINISHED_SEVENTH), Some("m7b5"));
+    assert_eq!(suffix(&MAJOR_SEVENTH), Some("maj7"));
+    assert_eq!(suffix(&[0, 16, 7, -1]), Some("maj7"));
+    assert_eq!(suffix(&THIRTEENTH_FLAT_NINTH), None);
+    assert_eq!(suffix(&DREAM), None);
+    assert_eq!(
+      chord_quality(&DOMINANT_NINTH).map(|quality| quality.get_kind()),
+      Some("dominant-ninth")
+    );
+    assert_eq!(
+      chord_quality(&HALF_DIMINISHED_SEVENTH).map(|quality| quality.get_chord_mode()),
+      Some("m7.5-")
+    );
+    assert_eq!(
+      chord_quality(&[0, 4, 7, 10, 13]).map(|quality| quality.get_degrees()),
+      Some(&[(9, -1, "add")][..])
+    );
+    assert_eq!(
+      chord_quality(&MAJOR).map(|quality| quality.get_degrees()),
+      Some(&[][..])
+    );
+
+    assert_eq!(
+      suffix_to_chord("m7"),
+      Some((Some("MINOR_SEVENTH"), MINOR_SEVENTH.to_vec()))
+    );
+    assert_eq!(
+      suffix_to_chord("Δ7"),
+      Some((Some("MAJOR_SEVENTH"), MAJOR_SEVENTH.to_vec()))
+    );
+    assert_eq!(suffix_to_chord(""), Some((Some("MAJOR"), MAJOR.to_vec())));
+    assert_eq!(suffix_to_chord("sus2"), Some((None, vec![0, 2, 7])));
+    assert_eq!(suffix_to_chord("7b13"), None);
+  }
+
   #[test]
   fn test_interval_chord_custom() {
     use crate::theory::chords::*;
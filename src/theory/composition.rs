@@ -1,28 +1,233 @@
+use super::arpeggiator::Arpeggiator;
+use super::bass::{self, BassStyle};
+use super::drums::Drums;
+use super::groove::{Groove, Swing};
+use super::humanize::Humanize;
+use super::melody::{self, Melody};
+use super::notes::{self, Key};
+use super::rhythm::Rhythm;
+use crate::FailResult;
 use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
 
 pub type PatternEvent = (MusicTime, Vec<u8>);
+pub type TempoEvent = (MusicTime, TempoChange);
+pub type TimeSignatureEvent = (u16, TimeSignature);
+/// A note as it is performed, the tick index it starts and ends at, the midi
+/// note and its velocity.
+pub type PerformedNote = (u32, u32, u8, u8);
 
-#[derive(Debug)]
+/// The count of ticks between two beat intervals. Events can be offset from
+/// their beat interval by ticks, allowing tuplets and finer placement.
+pub const BEAT_INTERVAL_TICKS: u8 = 60;
+
+/// The velocity of events before a groove accents them.
+pub const DEFAULT_VELOCITY: u8 = 64;
+
+/// Check if beats per minute can be played and exported.
+///
+/// # Arguments
+/// * `bpm` - The beats per minute to check.
+pub fn is_tempo_valid(bpm: f32) -> bool {
+  bpm.is_finite() && bpm > 0.0
+}
+
+/// The chord a pattern event was built from, before it was voiced into midi
+/// notes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChordSymbol {
+  name: String,
+  key: Key,
+  transpose: i8,
+  intervals: Vec<i8>,
+  octave: i8,
+}
+
+impl ChordSymbol {
+  pub fn new(name: &str, key: Key, transpose: i8, intervals: Vec<i8>, octave: i8) -> Self {
+    ChordSymbol {
+      name: name.to_owned(),
+      key,
+      transpose,
+      intervals,
+      octave,
+    }
+  }
+
+  pub fn get_name(&self) -> &str {
+    &self.name
+  }
+
+  /// Get the key of the pattern the chord was written in.
+  pub fn get_key(&self) -> Key {
+    self.key
+  }
+
+  /// Get the semitones the chord is transposed from the key.
+  pub fn get_transpose(&self) -> i8 {
+    self.transpose
+  }
+
+  /// Get the root of the chord, the key after the transpose.
+  pub fn get_root(&self) -> Key {
+    const NOTES_IN_OCTAVE_COUNT: i8 = 12;
+    let root = notes::key_to_index(self.key) as i8 + self.transpose;
+    notes::index_to_key(root.rem_euclid(NOTES_IN_OCTAVE_COUNT))
+  }
+
+  pub fn get_intervals(&self) -> &Vec<i8> {
+    &self.intervals
+  }
+
+  /// Get the octave the chord intervals are voiced from.
+  pub fn get_octave(&self) -> i8 {
+    self.octave
+  }
+
+  /// Get the voicing of the chord, the semitones of each note above the
+  /// root of the voicing octave.
+  pub fn get_voicing(&self) -> Vec<i8> {
+    const NOTES_IN_OCTAVE_COUNT: i8 = 12;
+    self
+      .intervals
+      .iter()
+      .map(|interval| interval + self.octave * NOTES_IN_OCTAVE_COUNT)
+      .collect()
+  }
+}
+
+/// A chord event in a pattern, placed at a beat interval and offset from it
+/// by ticks. Events built from chord names keep their `ChordSymbol`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChordEvent {
+  event: PatternEvent,
+  offset: u8,
+  symbol: Option<ChordSymbol>,
+}
+
+impl ChordEvent {
+  pub fn new(time: MusicTime, offset: u8, notes: Vec<u8>) -> Self {
+    ChordEvent {
+      event: (time, notes),
+      offset,
+      symbol: None,
+    }
+  }
+
+  pub fn new_with_symbol(time: MusicTime, offset: u8, notes: Vec<u8>, symbol: ChordSymbol) -> Self {
+    ChordEvent {
+      event: (time, notes),
+      offset,
+      symbol: Some(symbol),
+    }
+  }
+
+  pub fn get_time(&self) -> &MusicTime {
+    &self.event.0
+  }
+
+  /// Get the count of ticks the event is placed after its beat interval.
+  pub fn get_offset(&self) -> u8 {
+    self.offset
+  }
+
+  pub fn get_notes(&self) -> &Vec<u8> {
+    &self.event.1
+  }
+
+  fn get_symbol_name(&self) -> String {
+    self
+      .symbol
+      .as_ref()
+      .map_or_else(String::new, |symbol| symbol.name.clone())
+  }
+
+  fn cmp_time(&self, other: &ChordEvent) -> Ordering {
+    self
+      .get_time()
+      .cmp(other.get_time())
+      .then(self.offset.cmp(&other.offset))
+  }
+
+  pub fn get_symbol(&self) -> Option<&ChordSymbol> {
+    self.symbol.as_ref()
+  }
+
+  pub fn as_pattern_event(&self) -> &PatternEvent {
+    &self.event
+  }
+}
+
+impl From<PatternEvent> for ChordEvent {
+  fn from(event: PatternEvent) -> Self {
+    ChordEvent {
+      event,
+      offset: 0,
+      symbol: None,
+    }
+  }
+}
+
+impl PartialEq<PatternEvent> for ChordEvent {
+  fn eq(&self, other: &PatternEvent) -> bool {
+    self.offset == 0 && &self.event == other
+  }
+}
+
+/// A change of tempo within a pattern.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TempoChange {
+  /// Change to the beats per minute immediately.
+  Instant(f32),
+  /// Gradually change to the beats per minute, arriving at it by the `MusicTime`.
+  /// Used for ritardando and accelerando.
+  Ramp(f32, MusicTime),
+}
+
+#[derive(Debug, Clone)]
 pub struct Pattern {
   name: String,
-  bpm: u8,
+  bpm: f32,
   signature: TimeSignature,
-  events: Vec<PatternEvent>,
+  events: Vec<ChordEvent>,
+  tempo_changes: Vec<TempoEvent>,
+  signature_changes: Vec<TimeSignatureEvent>,
+  swing: Option<Swing>,
+  groove: Option<Groove>,
+  humanize: Option<Humanize>,
+  arpeggiator: Option<Arpeggiator>,
+  rhythm: Option<Rhythm>,
+  bass: Option<BassStyle>,
+  melody: Option<Melody>,
+  drums: Option<Drums>,
+  channel: u8,
 }
 
 impl Pattern {
-  pub fn new(name: &str, bpm: u8, signature: TimeSignature) -> Self {
+  pub fn new(name: &str, bpm: f32, signature: TimeSignature) -> Self {
     Pattern {
       name: name.to_owned(),
       bpm,
       signature,
       events: Vec::new(),
+      tempo_changes: Vec::new(),
+      signature_changes: Vec::new(),
+      swing: None,
+      groove: None,
+      humanize: None,
+      arpeggiator: None,
+      rhythm: None,
+      bass: None,
+      melody: None,
+      drums: None,
+      channel: 0,
     }
   }
 
   pub fn new_with_events(
     name: &str,
-    bpm: u8,
+    bpm: f32,
     signature: TimeSignature,
     events: Vec<PatternEvent>,
   ) -> Self {
@@ -30,204 +235,2582 @@ impl Pattern {
       name: name.to_owned(),
       bpm,
       signature,
-      events,
+      events: events.into_iter().map(ChordEvent::from).collect(),
+      tempo_changes: Vec::new(),
+      signature_changes: Vec::new(),
+      swing: None,
+      groove: None,
+      humanize: None,
+      arpeggiator: None,
+      rhythm: None,
+      bass: None,
+      melody: None,
+      drums: None,
+      channel: 0,
     };
     pattern.sort_events();
     pattern
   }
 
-  pub fn push_event(&mut self, time: MusicTime, notes: Vec<u8>) -> &Self {
-    self.events.push((time, notes));
+  /// Push an event in time order. Fails if the time is unreachable or already
+  /// has an event, in which case the pattern is unchanged. Returns the index of
+  /// the pushed event.
+  pub fn push_event(&mut self, time: MusicTime, notes: Vec<u8>) -> Result<usize, FailResult> {
+    self.push_chord_event(ChordEvent::new(time, 0, notes))
+  }
+
+  /// Push an event placed a count of ticks after the beat interval of `time`.
+  pub fn push_event_with_offset(
+    &mut self,
+    time: MusicTime,
+    offset: u8,
+    notes: Vec<u8>,
+  ) -> Result<usize, FailResult> {
+    self.push_chord_event(ChordEvent::new(time, offset, notes))
+  }
+
+  /// Push a chord event in time order. Offsets of a beat interval or more are
+  /// carried into the following beat intervals. Fails like `insert_chord_event`.
+  pub fn push_chord_event(&mut self, event: ChordEvent) -> Result<usize, FailResult> {
+    let mut event = event;
+    while event.offset >= BEAT_INTERVAL_TICKS {
+      let signature = self.get_time_signature_at(event.event.0.get_bar());
+      event.event.0.advance_beat_interval(&signature);
+      event.offset -= BEAT_INTERVAL_TICKS;
+    }
+    self.insert_chord_event(event)
+  }
+
+  /// Push a tempo change without validation. Use `insert_tempo_change` to keep
+  /// the pattern valid.
+  pub(crate) fn push_tempo_change(&mut self, time: MusicTime, change: TempoChange) -> &Self {
+    self.tempo_changes.push((time, change));
+    self.tempo_changes.sort_by_key(|change| change.0);
     self
   }
 
-  pub fn len(&self) -> usize {
-    self.events.len()
+  /// Push a time signature change without validation, replacing the change of
+  /// the same bar. Use `insert_time_signature_change` to keep the pattern
+  /// valid.
+  pub(crate) fn push_time_signature_change(&mut self, bar: u16, signature: TimeSignature) -> &Self {
+    self
+      .signature_changes
+      .retain(|(change_bar, _)| *change_bar != bar);
+    self.signature_changes.push((bar, signature));
+    self.signature_changes.sort_by_key(|change| change.0);
+    self
   }
 
-  pub fn get_bpm(&self) -> u8 {
-    self.bpm
+  /// Insert a tempo change in time order. Fails if the tempo is invalid, the
+  /// change is unreachable or the time already has a change, in which case the
+  /// pattern is unchanged. Returns the index of the inserted change.
+  pub fn insert_tempo_change(
+    &mut self,
+    time: MusicTime,
+    change: TempoChange,
+  ) -> Result<usize, FailResult> {
+    let index = self
+      .tempo_changes
+      .partition_point(|(other_time, _)| *other_time < time);
+    let is_repeated = self
+      .tempo_changes
+      .get(index)
+      .is_some_and(|(other_time, _)| *other_time == time);
+    let bpm = match change {
+      TempoChange::Instant(bpm) => bpm,
+      TempoChange::Ramp(bpm, _) => bpm,
+    };
+
+    if is_repeated || !self.is_tempo_change_reachable(&time, &change) {
+      Err(FailResult::UnreachableTimeChange(time, index))
+    } else if !is_tempo_valid(bpm) {
+      Err(FailResult::Tempo(bpm))
+    } else {
+      self.tempo_changes.insert(index, (time, change));
+      Ok(index)
+    }
   }
 
-  pub fn get_time_signature(&self) -> TimeSignature {
-    self.signature
+  pub fn remove_tempo_change(&mut self, index: usize) -> Result<TempoEvent, FailResult> {
+    if index < self.tempo_changes.len() {
+      Ok(self.tempo_changes.remove(index))
+    } else {
+      Err(FailResult::NoFoundTimeChange(index))
+    }
   }
 
-  pub fn get(&self, index: usize) -> &PatternEvent {
-    &self.events[index]
+  /// Insert a time signature change in bar order. Fails if the signature is
+  /// invalid, the bar is 0 or already has a change, or the change leaves events
+  /// or tempo changes unreachable, in which case the pattern is unchanged.
+  /// Returns the index of the inserted change.
+  pub fn insert_time_signature_change(
+    &mut self,
+    bar: u16,
+    signature: TimeSignature,
+  ) -> Result<usize, FailResult> {
+    let index = self
+      .signature_changes
+      .partition_point(|(other_bar, _)| *other_bar < bar);
+    let is_repeated = self
+      .signature_changes
+      .get(index)
+      .is_some_and(|(other_bar, _)| *other_bar == bar);
+
+    if !signature.is_valid() {
+      return Err(FailResult::TimeSignature(signature));
+    } else if is_repeated || bar == 0 {
+      return Err(FailResult::UnreachableSignatureChange(bar, index));
+    }
+
+    self.signature_changes.insert(index, (bar, signature));
+    match self.check() {
+      Ok(()) => Ok(index),
+      Err(error) => {
+        self.signature_changes.remove(index);
+        Err(error)
+      }
+    }
   }
 
-  pub fn get_events(&self) -> &Vec<PatternEvent> {
-    &self.events
+  /// Remove a time signature change. Fails if the change isn't found, or
+  /// removing it leaves events or tempo changes unreachable, in which case the
+  /// pattern is unchanged.
+  pub fn remove_time_signature_change(
+    &mut self,
+    index: usize,
+  ) -> Result<TimeSignatureEvent, FailResult> {
+    if index >= self.signature_changes.len() {
+      return Err(FailResult::NoFoundSignatureChange(index));
+    }
+
+    let change = self.signature_changes.remove(index);
+    match self.check() {
+      Ok(()) => Ok(change),
+      Err(error) => {
+        self.signature_changes.insert(index, change);
+        Err(error)
+      }
+    }
   }
 
-  pub fn get_name(&self) -> &str {
-    &self.name
+  /// Insert an event in time order. Fails if the time is unreachable or already
+  /// has an event. Returns the index of the inserted event.
+  pub fn insert_event(&mut self, time: MusicTime, notes: Vec<u8>) -> Result<usize, FailResult> {
+    self.insert_chord_event(ChordEvent::new(time, 0, notes))
   }
 
-  pub fn find_next_event_index(&self, time: &MusicTime) -> usize {
-    self
+  /// Insert a chord event in time order. Fails if the time and offset are
+  /// unreachable or already have an event. Returns the index of the inserted
+  /// event.
+  pub fn insert_chord_event(&mut self, event: ChordEvent) -> Result<usize, FailResult> {
+    let index = self.find_insert_index(&event)?;
+    self.events.insert(index, event);
+    Ok(index)
+  }
+
+  pub fn remove_event(&mut self, index: usize) -> Result<ChordEvent, FailResult> {
+    if index < self.events.len() {
+      Ok(self.events.remove(index))
+    } else {
+      Err(FailResult::NoFoundEvent(index))
+    }
+  }
+
+  /// Replace an event, keeping the events in time order. The pattern is
+  /// unchanged if the new event can't be inserted. Returns the index of the new
+  /// event.
+  pub fn replace_event(&mut self, index: usize, event: ChordEvent) -> Result<usize, FailResult> {
+    let previous_event = self.remove_event(index)?;
+    match self.insert_chord_event(event) {
+      Ok(index) => Ok(index),
+      Err(error) => {
+        self.events.insert(index, previous_event);
+        Err(error)
+      }
+    }
+  }
+
+  /// Move an event to a new time and offset, keeping the events in time order.
+  /// Returns the new index of the event.
+  pub fn move_event(
+    &mut self,
+    index: usize,
+    time: MusicTime,
+    offset: u8,
+  ) -> Result<usize, FailResult> {
+    let mut event = self
       .events
-      .iter()
-      .position(|event| &event.0 >= time)
-      .unwrap_or(0)
+      .get(index)
+      .cloned()
+      .ok_or(FailResult::NoFoundEvent(index))?;
+    event.event.0 = time;
+    event.offset = offset;
+    self.replace_event(index, event)
   }
 
-  pub fn sort_events(&mut self) {
-    self.events.sort_by(|a, b| a.0.cmp(&b.0));
+  pub fn set_name(&mut self, name: &str) {
+    self.name = name.to_owned();
   }
-}
 
-#[derive(Debug)]
-pub struct Composition {
-  name: String,
-  patterns: Vec<Pattern>,
-}
+  pub fn set_bpm(&mut self, bpm: f32) -> Result<(), FailResult> {
+    if is_tempo_valid(bpm) {
+      self.bpm = bpm;
+      Ok(())
+    } else {
+      Err(FailResult::Tempo(bpm))
+    }
+  }
 
-impl Composition {
-  pub fn new(name: &str) -> Self {
-    Composition {
-      name: name.to_string(),
-      patterns: Vec::new(),
+  /// Set the time signature of the pattern. Fails if the signature is invalid
+  /// or leaves events or changes unreachable, in which case the pattern is
+  /// unchanged.
+  pub fn set_time_signature(&mut self, signature: TimeSignature) -> Result<(), FailResult> {
+    if !signature.is_valid() {
+      return Err(FailResult::TimeSignature(signature));
+    }
+
+    let previous_signature = self.signature;
+    self.signature = signature;
+    let result = self.check();
+    if result.is_err() {
+      self.signature = previous_signature;
     }
+    result
   }
 
-  pub fn new_with_patterns(name: &str, patterns: Vec<Pattern>) -> Self {
-    Composition {
-      name: name.to_string(),
-      patterns,
+  /// Set the swing of the pattern, or `None` to play straight. Fails if the
+  /// swing is invalid.
+  pub fn set_swing(&mut self, swing: Option<Swing>) -> Result<(), FailResult> {
+    match swing {
+      Some(swing) if !swing.is_valid() => {
+        Err(FailResult::Swing(swing.get_note_value(), swing.get_ratio()))
+      }
+      _ => {
+        self.swing = swing;
+        Ok(())
+      }
     }
   }
 
-  pub fn push_new_pattern(&mut self, name: String, bpm: u8, signature: TimeSignature) {
-    self.patterns.push(Pattern::new(&name, bpm, signature));
+  /// Set the groove template of the pattern, or `None` to remove it. Fails if
+  /// the groove is invalid.
+  pub fn set_groove(&mut self, groove: Option<Groove>) -> Result<(), FailResult> {
+    match groove {
+      Some(groove) if !groove.is_valid() => Err(FailResult::Groove(groove.get_name().to_string())),
+      _ => {
+        self.groove = groove;
+        Ok(())
+      }
+    }
   }
 
-  pub fn push_pattern(&mut self, pattern: Pattern) {
-    self.patterns.push(pattern);
+  /// Set the humanize of the pattern, or `None` to play exactly. Humanize is
+  /// applied when the pattern is exported or played, the events are unchanged.
+  pub fn set_humanize(&mut self, humanize: Option<Humanize>) {
+    self.humanize = humanize;
   }
 
-  pub fn len(&self) -> usize {
-    self.patterns.len()
+  /// Set the arpeggiator of the pattern, or `None` to play block chords. Fails
+  /// if the arpeggiator is invalid.
+  pub fn set_arpeggiator(&mut self, arpeggiator: Option<Arpeggiator>) -> Result<(), FailResult> {
+    match arpeggiator {
+      Some(arpeggiator) if !arpeggiator.is_valid() => Err(FailResult::Arpeggio(
+        arpeggiator.get_rate(),
+        arpeggiator.get_octaves(),
+        arpeggiator.get_gate(),
+      )),
+      _ => {
+        self.arpeggiator = arpeggiator;
+        Ok(())
+      }
+    }
   }
 
-  pub fn get(&self, index: usize) -> &Pattern {
-    &self.patterns[index]
+  /// Set the rhythm template of the pattern, or `None` to play each event once.
+  /// Fails if the rhythm is invalid.
+  pub fn set_rhythm(&mut self, rhythm: Option<Rhythm>) -> Result<(), FailResult> {
+    match rhythm {
+      Some(rhythm) if !rhythm.is_valid() => Err(FailResult::Rhythm(rhythm.get_name().to_string())),
+      _ => {
+        self.rhythm = rhythm;
+        Ok(())
+      }
+    }
   }
 
-  pub fn get_mut(&mut self, index: usize) -> &mut Pattern {
-    &mut self.patterns[index]
+  /// Set the style of the bass line generated from the pattern, or `None` for
+  /// no bass line.
+  pub fn set_bass(&mut self, bass: Option<BassStyle>) {
+    self.bass = bass;
   }
 
-  pub fn get_patterns(&self) -> &Vec<Pattern> {
-    &self.patterns
+  /// Set the melody generated over the pattern, or `None` for no melody.
+  ///
+  /// Fails if the melody is invalid.
+  pub fn set_melody(&mut self, melody: Option<Melody>) -> Result<(), FailResult> {
+    match melody {
+      Some(melody) if !melody.is_valid() => {
+        let (lowest, highest) = melody.get_range();
+        Err(FailResult::Melody(
+          melody.get_rate(),
+          melody.get_density(),
+          lowest,
+          highest,
+        ))
+      }
+      _ => {
+        self.melody = melody;
+        Ok(())
+      }
+    }
   }
 
-  pub fn get_name(&self) -> &str {
-    &self.name
+  /// Set the drums played under the pattern, or `None` for no drums.
+  ///
+  /// Fails if the drums are invalid.
+  pub fn set_drums(&mut self, drums: Option<Drums>) -> Result<(), FailResult> {
+    match drums {
+      Some(drums) if !drums.is_valid() => Err(FailResult::Drums(drums.get_name().to_string())),
+      _ => {
+        self.drums = drums;
+        Ok(())
+      }
+    }
   }
-}
 
-mod tests {
-  #[test]
-  fn test_create() {
-    use crate::composition::*;
-    let mut compo = Composition::new("test");
-    assert_eq!(compo.get_name(), "test");
+  /// Set the midi channel of the pattern, from 0 to 15.
+  pub fn set_channel(&mut self, channel: u8) -> Result<(), FailResult> {
+    const MAX_CHANNEL: u8 = 15;
+    if channel <= MAX_CHANNEL {
+      self.channel = channel;
+      Ok(())
+    } else {
+      Err(FailResult::MidiChannel(channel))
+    }
+  }
 
-    compo.push_new_pattern("a".to_string(), 120, TimeSignature::default());
-    compo
-      .get_mut(0)
-      .push_event(MusicTime::new(1, 1, 1), vec![0, 1, 2]);
-    compo
-      .get_mut(0)
-      .push_event(MusicTime::new(2, 1, 1), vec![2, 3, 4]);
+  /// Check the pattern by the same rules as compositions loaded from YAML:
+  /// tempos and time signatures are valid, changes are reachable, and events
+  /// are reachable and in time order without repeated times.
+  pub fn check(&self) -> Result<(), FailResult> {
+    if !is_tempo_valid(self.bpm) {
+      return Err(FailResult::Tempo(self.bpm));
+    }
+    if !self.signature.is_valid() {
+      return Err(FailResult::TimeSignature(self.signature));
+    }
 
-    compo.push_new_pattern("a".to_string(), 54, TimeSignature::new(3, 4));
-    compo
-      .get_mut(1)
-      .push_event(MusicTime::new(1, 3, 1), vec![51, 51, 52]);
-    compo
-      .get_mut(1)
-      .push_event(MusicTime::new(2, 4, 1), vec![52, 53, 54]);
+    for (index, (bar, signature)) in self.signature_changes.iter().enumerate() {
+      if !signature.is_valid() {
+        return Err(FailResult::TimeSignature(*signature));
+      } else if *bar == 0 {
+        return Err(FailResult::UnreachableSignatureChange(*bar, index));
+      }
+    }
 
-    assert_eq!(compo.len(), 2);
-    assert_eq!(compo.get(0).len(), 2);
-    assert_eq!(compo.get(1).len(), 2);
+    for (index, (time, change)) in self.tempo_changes.iter().enumerate() {
+      let bpm = match change {
+        TempoChange::Instant(bpm) => bpm,
+        TempoChange::Ramp(bpm, _) => bpm,
+      };
 
-    assert_eq!(compo.get(0).get_bpm(), 120);
-    assert_eq!(compo.get(0).get_time_signature(), TimeSignature::new(4, 4));
+      if !self.is_tempo_change_reachable(time, change) {
+        return Err(FailResult::UnreachableTimeChange(*time, index));
+      } else if !is_tempo_valid(*bpm) {
+        return Err(FailResult::Tempo(*bpm));
+      }
+    }
 
-    assert_eq!(compo.get(1).get_bpm(), 54);
-    assert_eq!(compo.get(1).get_time_signature(), TimeSignature::new(3, 4));
+    for (index, event) in self.events.iter().enumerate() {
+      let time = *event.get_time();
+      let is_reachable = self.is_time_reachable(&time) && event.offset < BEAT_INTERVAL_TICKS;
+      let previous_order = match index {
+        0 => Ordering::Greater,
+        _ => event.cmp_time(&self.events[index - 1]),
+      };
 
-    let (time, notes) = compo.get(0).get(0);
-    assert_eq!(time, &MusicTime::new(1, 1, 1));
-    assert_eq!(notes, &vec![0, 1, 2]);
+      let chord_name = event.get_symbol_name();
+      if !is_reachable || previous_order == Ordering::Equal {
+        return Err(FailResult::UnreachableTime(time, index, chord_name));
+      } else if previous_order == Ordering::Less {
+        return Err(FailResult::TimeReverse(time, index, chord_name));
+      }
+    }
 
-    let (time, notes) = compo.get(0).get(1);
-    assert_eq!(time, &MusicTime::new(2, 1, 1));
-    assert_eq!(notes, &vec![2, 3, 4]);
+    Ok(())
+  }
 
-    let (time, notes) = compo.get(1).get(0);
-    assert_eq!(time, &MusicTime::new(1, 3, 1));
-    assert_eq!(notes, &vec![51, 51, 52]);
+  /// Check if a `MusicTime` can be reached in the pattern, by the time
+  /// signature of the bar it is in.
+  pub fn is_time_reachable(&self, time: &MusicTime) -> bool {
+    const BEAT_INTERVALS: u8 = 8;
+    let unreachable_beat_interval =
+      time.get_beat_interval() > BEAT_INTERVALS || time.get_beat_interval() == 0;
+    let unreachable_beat = time.get_beat()
+      > self.get_time_signature_at(time.get_bar()).get_numerator()
+      || time.get_beat() == 0;
+    let unreachable_bar = time.get_bar() == 0;
 
-    let (time, notes) = compo.get(1).get(1);
-    assert_eq!(time, &MusicTime::new(2, 4, 1));
-    assert_eq!(notes, &vec![52, 53, 54]);
+    !(unreachable_bar || unreachable_beat_interval || unreachable_beat)
   }
 
-  #[test]
-  fn test_find_next_event() {
-    use crate::composition::Pattern;
-    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+  /// Check if a tempo change can be reached in the pattern. Ramps must end
+  /// after they start.
+  pub fn is_tempo_change_reachable(&self, time: &MusicTime, change: &TempoChange) -> bool {
+    self.is_time_reachable(time)
+      && match change {
+        TempoChange::Ramp(_, end_time) => {
+          self.is_time_reachable(end_time)
+            && self.get_beat_interval_index(end_time) > self.get_beat_interval_index(time)
+        }
+        _ => true,
+      }
+  }
 
-    let pattern = Pattern::new_with_events(
-      "test pattern",
-      85,
-      TimeSignature::default(),
-      vec![
-        (MusicTime::new(1, 1, 1), vec![0]),
-        (MusicTime::new(2, 1, 1), vec![0]),
-        (MusicTime::new(3, 1, 1), vec![0]),
-        (MusicTime::new(3, 4, 1), vec![0]),
-        (MusicTime::new(4, 1, 1), vec![0]),
-      ],
-    );
+  fn find_insert_index(&self, event: &ChordEvent) -> Result<usize, FailResult> {
+    let index = self
+      .events
+      .partition_point(|other| other.cmp_time(event) == Ordering::Less);
+    let is_reachable =
+      self.is_time_reachable(event.get_time()) && event.offset < BEAT_INTERVAL_TICKS;
+    let is_repeated = self
+      .events
+      .get(index)
+      .is_some_and(|other| other.cmp_time(event) == Ordering::Equal);
 
-    let time = MusicTime::new(3, 3, 2);
-    let result = Pattern::find_next_event_index(&pattern, &time);
-    assert_eq!(result, 3);
+    if is_reachable && !is_repeated {
+      Ok(index)
+    } else {
+      Err(FailResult::UnreachableTime(
+        *event.get_time(),
+        index,
+        event.get_symbol_name(),
+      ))
+    }
   }
 
-  #[test]
-  fn test_event_order() {
-    use crate::composition::Pattern;
-    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+  pub fn len(&self) -> usize {
+    self.events.len()
+  }
 
-    let pattern = Pattern::new_with_events(
-      "test pattern",
-      85,
-      TimeSignature::default(),
-      vec![
-        (MusicTime::new(2, 1, 1), vec![0]),
-        (MusicTime::new(1, 1, 1), vec![0]),
-        (MusicTime::new(4, 1, 1), vec![0]),
-        (MusicTime::new(3, 1, 1), vec![0]),
-        (MusicTime::new(3, 4, 1), vec![0]),
-      ],
-    );
+  pub fn get_bpm(&self) -> f32 {
+    self.bpm
+  }
 
-    assert_eq!(
-      pattern.get_events(),
-      &vec![
-        (MusicTime::new(1, 1, 1), vec![0]),
-        (MusicTime::new(2, 1, 1), vec![0]),
-        (MusicTime::new(3, 1, 1), vec![0]),
-        (MusicTime::new(3, 4, 1), vec![0]),
-        (MusicTime::new(4, 1, 1), vec![0]),
-      ]
-    )
+  /// Get the beats per minute at a time in the pattern, after all tempo changes
+  /// and ramps up to that time have been applied.
+  pub fn get_bpm_at(&self, time: &MusicTime) -> f32 {
+    let time_index = self.get_beat_interval_index(time);
+    let mut bpm = self.bpm;
+
+    for (change_time, change) in &self.tempo_changes {
+      let change_index = self.get_beat_interval_index(change_time);
+      if change_index > time_index {
+        break;
+      }
+
+      match change {
+        TempoChange::Instant(target_bpm) => bpm = *target_bpm,
+        TempoChange::Ramp(target_bpm, end_time) => {
+          let end_index = self.get_beat_interval_index(end_time);
+          if time_index >= end_index {
+            bpm = *target_bpm;
+          } else {
+            let progress = (time_index - change_index) as f32 / (end_index - change_index) as f32;
+            bpm += (*target_bpm - bpm) * progress;
+          }
+        }
+      }
+    }
+
+    bpm
+  }
+
+  pub fn get_time_signature(&self) -> TimeSignature {
+    self.signature
+  }
+
+  /// Get the time signature of a bar in the pattern, after all time signature
+  /// changes up to that bar have been applied.
+  pub fn get_time_signature_at(&self, bar: u16) -> TimeSignature {
+    self
+      .signature_changes
+      .iter()
+      .take_while(|(change_bar, _)| *change_bar <= bar)
+      .last()
+      .map_or(self.signature, |(_, signature)| *signature)
+  }
+
+  pub fn get_tempo_changes(&self) -> &Vec<TempoEvent> {
+    &self.tempo_changes
+  }
+
+  pub fn get_time_signature_changes(&self) -> &Vec<TimeSignatureEvent> {
+    &self.signature_changes
+  }
+
+  pub fn get_swing(&self) -> Option<&Swing> {
+    self.swing.as_ref()
+  }
+
+  pub fn get_groove(&self) -> Option<&Groove> {
+    self.groove.as_ref()
+  }
+
+  pub fn get_humanize(&self) -> Option<&Humanize> {
+    self.humanize.as_ref()
+  }
+
+  pub fn get_arpeggiator(&self) -> Option<&Arpeggiator> {
+    self.arpeggiator.as_ref()
+  }
+
+  pub fn get_rhythm(&self) -> Option<&Rhythm> {
+    self.rhythm.as_ref()
+  }
+
+  pub fn get_bass(&self) -> Option<BassStyle> {
+    self.bass
+  }
+
+  pub fn get_melody(&self) -> Option<&Melody> {
+    self.melody.as_ref()
+  }
+
+  pub fn get_drums(&self) -> Option<&Drums> {
+    self.drums.as_ref()
+  }
+
+  pub fn get_channel(&self) -> u8 {
+    self.channel
+  }
+
+  /// Get the bass line of the pattern in its bass style, if it has one.
+  pub fn get_bass_line(&self) -> Option<Self> {
+    self.bass.map(|style| self.bass_line(style))
+  }
+
+  /// Get the melody line of the pattern, if it has a melody.
+  pub fn get_melody_line(&self) -> Option<Self> {
+    self.melody.as_ref().map(|melody| self.melody_line(melody))
+  }
+
+  /// Get the tick index each event is performed at and its notes, after the
+  /// rhythm template, swing, the groove template, humanize and the arpeggiator
+  /// are applied. Notes sound until the next event, and the last event until
+  /// the end of its bar, unless their rhythm hit is shorter or they are
+  /// arpeggiated.
+  pub fn get_performed_events(&self) -> Vec<(u32, Vec<PerformedNote>)> {
+    // Each event is played once, or on every hit of the rhythm template until
+    // the next event
+    let mut hits: Vec<(usize, u32, Option<u32>)> = Vec::new();
+    for (i, (start_tick_index, end_tick_index)) in self.get_event_spans().into_iter().enumerate() {
+      match &self.rhythm {
+        Some(rhythm) => hits.extend(
+          self
+            .get_rhythm_hits(rhythm, start_tick_index, end_tick_index)
+            .into_iter()
+            .map(|(tick_index, length)| (i, tick_index, length)),
+        ),
+        None => hits.push((i, start_tick_index, None)),
+      }
+    }
+
+    let hit_tick_indexes: Vec<u32> = hits.iter().map(|(_, tick_index, _)| *tick_index).collect();
+    let chords: Vec<(u32, u8, &Vec<u8>)> = self
+      .groove_tick_indexes(&hit_tick_indexes)
+      .into_iter()
+      .zip(&hits)
+      .map(|((tick_index, velocity), (i, _, _))| {
+        (tick_index, velocity, self.events[*i].get_notes())
+      })
+      .collect();
+
+    let humanized_chords = match &self.humanize {
+      Some(humanize) => humanize.apply(&chords),
+      None => chords
+        .iter()
+        .map(|(tick_index, velocity, notes)| {
+          let notes = notes
+            .iter()
+            .map(|note| (*tick_index, *note, *velocity))
+            .collect();
+          (*tick_index, notes)
+        })
+        .collect(),
+    };
+
+    let end_tick_index = self.get_end_tick_index();
+    let timed_chords: Vec<(u32, u32, Vec<_>)> = humanized_chords
+      .iter()
+      .zip(&hits)
+      .enumerate()
+      .map(|(j, ((tick_index, notes), (_, _, length)))| {
+        let next_tick_index = match humanized_chords.get(j + 1) {
+          Some((next_tick_index, _)) => *next_tick_index,
+          None => end_tick_index,
+        };
+        let chord_end_tick_index = match length {
+          Some(length) => next_tick_index.min(tick_index + length),
+          None => next_tick_index,
+        };
+        (*tick_index, chord_end_tick_index, notes.clone())
+      })
+      .collect();
+
+    let performed_chords: Vec<Vec<PerformedNote>> = match &self.arpeggiator {
+      Some(arpeggiator) => arpeggiator.apply(&timed_chords),
+      None => timed_chords
+        .into_iter()
+        .map(|(_, chord_end_tick_index, notes)| {
+          notes
+            .into_iter()
+            .map(|(note_tick_index, note, velocity)| {
+              (
+                note_tick_index,
+                chord_end_tick_index.max(note_tick_index),
+                note,
+                velocity,
+              )
+            })
+            .collect()
+        })
+        .collect(),
+    };
+
+    // Events start at their first hit
+    let mut performed_events: Vec<(u32, Vec<PerformedNote>)> = self
+      .get_grooved_events()
+      .into_iter()
+      .map(|(tick_index, _)| (tick_index, Vec::new()))
+      .collect();
+    let mut is_event_started = vec![false; performed_events.len()];
+    for (((i, _, _), (tick_index, _)), notes) in
+      hits.iter().zip(&humanized_chords).zip(performed_chords)
+    {
+      if !is_event_started[*i] {
+        is_event_started[*i] = true;
+        performed_events[*i].0 = *tick_index;
+      }
+      performed_events[*i].1.extend(notes);
+    }
+
+    performed_events
+  }
+
+  /// Get the notes of the pattern's drums from the start of the pattern until
+  /// its end, in time order, after swing, the groove template and humanize are
+  /// applied. The drums repeat from the start of each bar and each hit sounds
+  /// for a step.
+  pub fn get_drum_notes(&self) -> Vec<PerformedNote> {
+    const BEAT_TICKS: u32 = BEAT_INTERVAL_TICKS as u32 * 8;
+    const MAX_VELOCITY: i16 = 127;
+
+    let drums = match &self.drums {
+      Some(drums) => drums,
+      None => return Vec::new(),
+    };
+
+    let end_tick_index = self.get_end_tick_index();
+    let mut hits = Vec::new();
+    let mut bar = 1;
+    loop {
+      let bar_tick_index = self.get_tick_index(&MusicTime::new(bar, 1, 1), 0);
+      let bar_ticks = self.get_time_signature_at(bar).get_numerator() as u32 * BEAT_TICKS;
+      if bar_tick_index >= end_tick_index || bar_ticks == 0 {
+        break;
+      }
+
+      hits.extend(
+        drums
+          .get_bar_hits(bar_ticks)
+          .into_iter()
+          .map(|(bar_tick, note, velocity)| (bar_tick_index + bar_tick, note, velocity)),
+      );
+      bar += 1;
+    }
+
+    // Groove velocities accent the velocity of each hit
+    let tick_indexes: Vec<u32> = hits.iter().map(|(tick_index, _, _)| *tick_index).collect();
+    let notes: Vec<Vec<u8>> = hits.iter().map(|(_, note, _)| vec![*note]).collect();
+    let chords: Vec<(u32, u8, &Vec<u8>)> = self
+      .groove_tick_indexes(&tick_indexes)
+      .into_iter()
+      .zip(&hits)
+      .zip(&notes)
+      .map(
+        |(((tick_index, groove_velocity), (_, _, velocity)), notes)| {
+          let velocity = (*velocity as i16 + groove_velocity as i16 - DEFAULT_VELOCITY as i16)
+            .clamp(1, MAX_VELOCITY);
+          (tick_index, velocity as u8, notes)
+        },
+      )
+      .collect();
+
+    let humanized_hits = match &self.humanize {
+      Some(humanize) => humanize.apply(&chords),
+      None => chords
+        .iter()
+        .map(|(tick_index, velocity, notes)| {
+          (*tick_index, vec![(*tick_index, notes[0], *velocity)])
+        })
+        .collect(),
+    };
+
+    let step_ticks = drums.get_step_ticks();
+    let mut drum_notes: Vec<PerformedNote> = humanized_hits
+      .into_iter()
+      .flat_map(|(_, notes)| notes)
+      .map(|(tick_index, note, velocity)| (tick_index, tick_index + step_ticks, note, velocity))
+      .collect();
+    drum_notes.sort_by_key(|(tick_index, _, _, _)| *tick_index);
+    drum_notes
+  }
+
+  /// Get the tick index and velocity each event is performed at, after swing
+  /// and the groove template are applied. Events never move before the start of
+  /// the pattern or the event before them.
+  pub fn get_grooved_events(&self) -> Vec<(u32, u8)> {
+    let tick_indexes: Vec<u32> = self
+      .events
+      .iter()
+      .map(|event| self.get_tick_index(event.get_time(), event.get_offset()))
+      .collect();
+    self.groove_tick_indexes(&tick_indexes)
+  }
+
+  /// Apply swing and the groove template to tick indexes in time order.
+  fn groove_tick_indexes(&self, tick_indexes: &[u32]) -> Vec<(u32, u8)> {
+    const BEAT_TICKS: u32 = BEAT_INTERVAL_TICKS as u32 * 8;
+    const MAX_VELOCITY: i16 = 127;
+
+    let mut previous_tick_index = 0;
+    tick_indexes
+      .iter()
+      .map(|tick_index| {
+        let bar = self.get_time_at_tick_index(*tick_index).0.get_bar();
+        let bar_tick = tick_index - self.get_tick_index(&MusicTime::new(bar, 1, 1), 0);
+
+        let swing_offset = match &self.swing {
+          Some(swing) => swing.get_tick_offset(bar_tick % BEAT_TICKS),
+          None => 0,
+        };
+        let (groove_offset, velocity_offset) = match &self.groove {
+          Some(groove) => groove.get_offsets(bar_tick),
+          None => (0, 0),
+        };
+
+        let grooved_tick_index = (*tick_index as i64 + (swing_offset + groove_offset) as i64)
+          .max(previous_tick_index as i64) as u32;
+        previous_tick_index = grooved_tick_index;
+
+        let velocity = (DEFAULT_VELOCITY as i16 + velocity_offset).clamp(1, MAX_VELOCITY);
+        (grooved_tick_index, velocity as u8)
+      })
+      .collect()
+  }
+
+  /// Get the tick index and length of each hit of a rhythm template from a
+  /// start tick index until an end tick index. The template repeats from the
+  /// start of each bar. When no hit falls on the start tick index, a hit
+  /// sounding until the next hit is added there, so chords between hits are
+  /// neither dropped nor delayed.
+  fn get_rhythm_hits(
+    &self,
+    rhythm: &Rhythm,
+    start_tick_index: u32,
+    end_tick_index: u32,
+  ) -> Vec<(u32, Option<u32>)> {
+    const BEAT_TICKS: u32 = BEAT_INTERVAL_TICKS as u32 * 8;
+
+    let mut hits = Vec::new();
+    let mut bar = self.get_time_at_tick_index(start_tick_index).0.get_bar();
+    loop {
+      let bar_tick_index = self.get_tick_index(&MusicTime::new(bar, 1, 1), 0);
+      let bar_ticks = self.get_time_signature_at(bar).get_numerator() as u32 * BEAT_TICKS;
+      if bar_tick_index >= end_tick_index || bar_ticks == 0 {
+        break;
+      }
+
+      for (bar_tick, length) in rhythm.get_bar_hits(bar_ticks) {
+        let tick_index = bar_tick_index + bar_tick;
+        if tick_index >= start_tick_index && tick_index < end_tick_index {
+          hits.push((tick_index, length));
+        }
+      }
+      bar += 1;
+    }
+
+    if hits.first().map(|(tick_index, _)| *tick_index) != Some(start_tick_index) {
+      hits.insert(0, (start_tick_index, None));
+    }
+    hits
+  }
+
+  /// Get the count of beat intervals from the start of the pattern to a time,
+  /// honouring the time signature of each bar.
+  pub fn get_beat_interval_index(&self, time: &MusicTime) -> u32 {
+    const BEAT_INTERVALS: u32 = 8;
+
+    let bar_intervals: u32 = (1..time.get_bar())
+      .map(|bar| self.get_time_signature_at(bar).get_numerator() as u32 * BEAT_INTERVALS)
+      .sum();
+    let beat_intervals = (time.get_beat() as u32).saturating_sub(1) * BEAT_INTERVALS;
+    let intervals = (time.get_beat_interval() as u32).saturating_sub(1);
+
+    bar_intervals + beat_intervals + intervals
+  }
+
+  pub fn get(&self, index: usize) -> &PatternEvent {
+    self.events[index].as_pattern_event()
+  }
+
+  pub fn get_event(&self, index: usize) -> &ChordEvent {
+    &self.events[index]
+  }
+
+  /// Get the events as times and notes. Tick offsets and chord symbols are
+  /// left out, use `get_chord_events` to keep them.
+  pub fn get_events(&self) -> Vec<PatternEvent> {
+    self
+      .events
+      .iter()
+      .map(|event| event.as_pattern_event().clone())
+      .collect()
+  }
+
+  /// Get the events with their tick offsets and chord symbols.
+  pub fn get_chord_events(&self) -> &Vec<ChordEvent> {
+    &self.events
+  }
+
+  pub fn get_name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn find_next_event_index(&self, time: &MusicTime) -> usize {
+    self
+      .events
+      .iter()
+      .position(|event| event.get_time() >= time)
+      .unwrap_or(0)
+  }
+
+  pub fn sort_events(&mut self) {
+    self.events.sort_by(|a, b| a.cmp_time(b));
+  }
+
+  /// Get the count of ticks from the start of the pattern to a time and its
+  /// tick offset, honouring the time signature of each bar.
+  pub fn get_tick_index(&self, time: &MusicTime, offset: u8) -> u32 {
+    self.get_beat_interval_index(time) * BEAT_INTERVAL_TICKS as u32 + offset as u32
+  }
+
+  /// Get the time and tick offset at a count of ticks from the start of the
+  /// pattern, honouring the time signature of each bar.
+  pub fn get_time_at_tick_index(&self, tick_index: u32) -> (MusicTime, u8) {
+    const BEAT_INTERVALS: u32 = 8;
+    let interval_ticks = BEAT_INTERVAL_TICKS as u32;
+    let beat_ticks = interval_ticks * BEAT_INTERVALS;
+
+    let mut bar = 1;
+    let mut ticks = tick_index;
+    loop {
+      let bar_ticks = self.get_time_signature_at(bar).get_numerator() as u32 * beat_ticks;
+      if ticks < bar_ticks || bar_ticks == 0 {
+        break;
+      }
+      ticks -= bar_ticks;
+      bar += 1;
+    }
+
+    let time = MusicTime::new(
+      bar,
+      (ticks / beat_ticks + 1) as u8,
+      (ticks % beat_ticks / interval_ticks + 1) as u8,
+    );
+    (time, (ticks % interval_ticks) as u8)
+  }
+
+  /// Get the tick index of the end of the pattern, the start of the bar after
+  /// the last event.
+  pub fn get_end_tick_index(&self) -> u32 {
+    match self.events.last() {
+      Some(event) => self.get_tick_index(&MusicTime::new(event.get_time().get_bar() + 1, 1, 1), 0),
+      None => 0,
+    }
+  }
+
+  /// Get a copy of the pattern with all notes and chord symbols transposed by
+  /// semitones. Fails if a note would move outside the midi range, so chord
+  /// symbols always name the transposed notes.
+  pub fn transposed(&self, semitones: i8) -> Result<Self, FailResult> {
+    const MAX_MIDI_NOTE: i16 = 127;
+
+    let events = self
+      .events
+      .iter()
+      .map(|event| {
+        let mut event = event.clone();
+        for note in &mut event.event.1 {
+          let transposed_note = *note as i16 + semitones as i16;
+          if !(0..=MAX_MIDI_NOTE).contains(&transposed_note) {
+            return Err(FailResult::TransposeRange(semitones));
+          }
+          *note = transposed_note as u8;
+        }
+        if let Some(symbol) = &mut event.symbol {
+          symbol.transpose = symbol
+            .transpose
+            .checked_add(semitones)
+            .ok_or(FailResult::TransposeRange(semitones))?;
+        }
+        Ok(event)
+      })
+      .collect::<Result<Vec<ChordEvent>, FailResult>>()?;
+
+    Ok(self.clone_with_events(events))
+  }
+
+  /// Get a copy of the pattern with events and tempo changes moved by bars then
+  /// beats. Time signature changes move by the bars. Changes moved before the
+  /// start of the pattern set its starting tempo and time signature. Fails if
+  /// an event would move before the start of the pattern or past the last bar.
+  pub fn shifted(&self, bars: i16, beats: i16) -> Result<Self, FailResult> {
+    self.shifted_by(bars as i32, beats as i32)
+  }
+
+  fn shifted_by(&self, bars: i32, beats: i32) -> Result<Self, FailResult> {
+    let out_of_range = || FailResult::ShiftRange(bars, beats);
+    let mut pattern = self.clone_with_events(Vec::new());
+
+    pattern.signature_changes.clear();
+    for (bar, signature) in &self.signature_changes {
+      let shifted_bar = *bar as i32 + bars;
+      if shifted_bar > 1 {
+        let shifted_bar = u16::try_from(shifted_bar).map_err(|_| out_of_range())?;
+        pattern.push_time_signature_change(shifted_bar, *signature);
+      } else {
+        pattern.signature = *signature;
+      }
+    }
+
+    let tick_index_limit = pattern.get_tick_index_limit();
+    pattern.events = self
+      .events
+      .iter()
+      .map(|event| {
+        let (time, offset) = pattern
+          .shift_time(
+            event.get_time(),
+            event.offset,
+            bars,
+            beats,
+            tick_index_limit,
+          )?
+          .ok_or_else(out_of_range)?;
+        let mut event = event.clone();
+        event.event.0 = time;
+        event.offset = offset;
+        Ok(event)
+      })
+      .collect::<Result<_, FailResult>>()?;
+    pattern.sort_events();
+
+    pattern.tempo_changes.clear();
+    for (time, change) in &self.tempo_changes {
+      let shifted_time = pattern.shift_time(time, 0, bars, beats, tick_index_limit)?;
+      let shifted_change = match change {
+        TempoChange::Instant(bpm) => (shifted_time, TempoChange::Instant(*bpm)),
+        TempoChange::Ramp(bpm, end_time) => {
+          match pattern.shift_time(end_time, 0, bars, beats, tick_index_limit)? {
+            Some((end_time, _)) => (
+              shifted_time.or_else(|| Some((MusicTime::default(), 0))),
+              TempoChange::Ramp(*bpm, end_time),
+            ),
+            None => (shifted_time, TempoChange::Instant(*bpm)),
+          }
+        }
+      };
+
+      match shifted_change {
+        (Some((time, _)), change) => {
+          pattern.push_tempo_change(time, change);
+        }
+        (None, TempoChange::Instant(bpm)) => pattern.bpm = bpm,
+        (None, TempoChange::Ramp(..)) => {}
+      }
+    }
+
+    pattern.check()?;
+    Ok(pattern)
+  }
+
+  /// Get a copy of the pattern with its timing stretched by a factor, e.g. `2.0`
+  /// for half time and `0.5` for double time. Time signature changes are moved
+  /// to the nearest bar. Fails if the factor isn't positive, if an event would
+  /// move past the last bar, or if events would be rounded onto the same time.
+  pub fn stretched(&self, factor: f32) -> Result<Self, FailResult> {
+    if !(factor.is_finite() && factor > 0.0) {
+      return Err(FailResult::Stretch(factor));
+    }
+
+    let mut pattern = self.clone_with_events(Vec::new());
+
+    pattern.signature_changes.clear();
+    for (bar, signature) in &self.signature_changes {
+      let stretched_bar = 1.0 + (bar.saturating_sub(1) as f64 * factor as f64).round();
+      if stretched_bar > u16::MAX as f64 {
+        return Err(FailResult::Stretch(factor));
+      } else if stretched_bar > 1.0 {
+        pattern.push_time_signature_change(stretched_bar as u16, *signature);
+      } else {
+        pattern.signature = *signature;
+      }
+    }
+
+    let tick_index_limit = pattern.get_tick_index_limit();
+    let stretch = |time: &MusicTime, offset: u8| {
+      let tick_index = (self.get_tick_index(time, offset) as f64 * factor as f64).round();
+      if tick_index < tick_index_limit as f64 {
+        Ok(pattern.get_time_at_tick_index(tick_index as u32))
+      } else {
+        Err(FailResult::Stretch(factor))
+      }
+    };
+
+    let events = self
+      .events
+      .iter()
+      .map(|event| {
+        let (time, offset) = stretch(event.get_time(), event.offset)?;
+        let mut event = event.clone();
+        event.event.0 = time;
+        event.offset = offset;
+        Ok(event)
+      })
+      .collect::<Result<_, FailResult>>()?;
+
+    let tempo_changes = self
+      .tempo_changes
+      .iter()
+      .map(|(time, change)| {
+        let change = match change {
+          TempoChange::Instant(bpm) => TempoChange::Instant(*bpm),
+          TempoChange::Ramp(bpm, end_time) => TempoChange::Ramp(*bpm, stretch(end_time, 0)?.0),
+        };
+        Ok((stretch(time, 0)?.0, change))
+      })
+      .collect::<Result<_, FailResult>>()?;
+
+    pattern.events = events;
+    pattern.sort_events();
+    pattern.tempo_changes = tempo_changes;
+
+    pattern.check()?;
+    Ok(pattern)
+  }
+
+  /// Get a copy of the pattern with its events in retrograde. Each chord keeps
+  /// its length, mirrored within the bars of the pattern. Tempo and time
+  /// signature changes are kept in place.
+  pub fn reversed(&self) -> Self {
+    let end_tick_index = self.get_end_tick_index();
+    let events = self
+      .events
+      .iter()
+      .zip(self.get_event_spans())
+      .map(|(event, (_, event_end))| {
+        let (time, offset) = self.get_time_at_tick_index(end_tick_index - event_end);
+        let mut event = event.clone();
+        event.event.0 = time;
+        event.offset = offset;
+        event
+      })
+      .collect();
+
+    self.clone_with_events(events)
+  }
+
+  /// Get a copy of the bars from `first_bar` to `last_bar` as a pattern starting
+  /// at bar 1. The chord sounding at the start of the slice is kept, as are the
+  /// tempo and time signature in effect.
+  pub fn sliced(&self, first_bar: u16, last_bar: u16) -> Self {
+    let first_bar = first_bar.max(1);
+    let start_time = MusicTime::new(first_bar, 1, 1);
+    let start_tick_index = self.get_tick_index(&start_time, 0);
+    let end_tick_index = self.get_tick_index(&MusicTime::new(last_bar.saturating_add(1), 1, 1), 0);
+    let rebase = |time: &MusicTime| {
+      MusicTime::new(
+        time.get_bar() - first_bar + 1,
+        time.get_beat(),
+        time.get_beat_interval(),
+      )
+    };
+
+    let mut pattern = Pattern::new(
+      &self.name,
+      self.get_bpm_at(&start_time),
+      self.get_time_signature_at(first_bar),
+    );
+    pattern.swing = self.swing;
+    pattern.groove = self.groove.clone();
+    pattern.humanize = self.humanize;
+    pattern.arpeggiator = self.arpeggiator;
+    pattern.rhythm = self.rhythm.clone();
+    pattern.bass = self.bass;
+    pattern.melody = self.melody;
+    pattern.drums = self.drums.clone();
+    pattern.channel = self.channel;
+
+    for (bar, signature) in &self.signature_changes {
+      if *bar > first_bar && *bar <= last_bar {
+        pattern.push_time_signature_change(bar - first_bar + 1, *signature);
+      }
+    }
+
+    for (time, change) in &self.tempo_changes {
+      let tick_index = self.get_tick_index(time, 0);
+      if tick_index >= end_tick_index {
+        continue;
+      }
+
+      match change {
+        TempoChange::Instant(bpm) if tick_index >= start_tick_index => {
+          pattern.push_tempo_change(rebase(time), TempoChange::Instant(*bpm));
+        }
+        TempoChange::Ramp(bpm, end_time) if end_time.get_bar() >= first_bar => {
+          if tick_index >= start_tick_index {
+            pattern.push_tempo_change(rebase(time), TempoChange::Ramp(*bpm, rebase(end_time)));
+          } else if self.get_tick_index(end_time, 0) > start_tick_index {
+            pattern.push_tempo_change(
+              MusicTime::default(),
+              TempoChange::Ramp(*bpm, rebase(end_time)),
+            );
+          }
+        }
+        _ => {}
+      }
+    }
+
+    for (event, (event_start, event_end)) in self.events.iter().zip(self.get_event_spans()) {
+      let is_sounding_at_start = event_start < start_tick_index && event_end > start_tick_index;
+      if event_start >= start_tick_index && event_start < end_tick_index {
+        let mut event = event.clone();
+        event.event.0 = rebase(event.get_time());
+        pattern.events.push(event);
+      } else if is_sounding_at_start && start_tick_index < end_tick_index {
+        let mut event = event.clone();
+        event.event.0 = MusicTime::default();
+        event.offset = 0;
+        pattern.events.push(event);
+      }
+    }
+    pattern.sort_events();
+
+    pattern
+  }
+
+  /// Get a bass line generated from the chord roots and chord tones of the
+  /// pattern, as a pattern of single notes in a bass register on the bass
+  /// channel. Swing, groove and humanize are kept, rests stay silent.
+  pub fn bass_line(&self, style: BassStyle) -> Self {
+    let spans = self.get_event_spans();
+    let chord_tones = self.get_chord_tones();
+
+    let mut events = Vec::new();
+    for (i, (start_tick_index, end_tick_index)) in spans.iter().enumerate() {
+      let (root, tones) = match &chord_tones[i] {
+        Some(chord_tones) => chord_tones,
+        None => {
+          let (time, offset) = self.get_time_at_tick_index(*start_tick_index);
+          events.push(ChordEvent::new(time, offset, Vec::new()));
+          continue;
+        }
+      };
+
+      let step_tick_indexes = match style.get_step_ticks() {
+        Some(step_ticks) => to_step_tick_indexes(*start_tick_index, *end_tick_index, step_ticks),
+        None => vec![*start_tick_index],
+      };
+
+      let next_root = chord_tones
+        .get(i + 1)
+        .and_then(|chord_tones| chord_tones.as_ref().map(|(root, _)| *root));
+      let bass_notes = style.get_notes(*root, tones, next_root, step_tick_indexes.len());
+      for (tick_index, note) in step_tick_indexes.into_iter().zip(bass_notes) {
+        let (time, offset) = self.get_time_at_tick_index(tick_index);
+        events.push(ChordEvent::new(time, offset, vec![note]));
+      }
+    }
+
+    let mut pattern = self.clone_with_events(events);
+    pattern.name = format!("{}_bass", self.name);
+    pattern.arpeggiator = None;
+    pattern.rhythm = None;
+    pattern.bass = None;
+    pattern.channel = bass::BASS_CHANNEL;
+    pattern
+  }
+
+  /// Get a melody written over the chords of the pattern, as a pattern of
+  /// single notes on the melody channel. Each note sounds until the next, rests
+  /// stay silent.
+  pub fn melody_line(&self, melody: &Melody) -> Self {
+    const BEAT_TICKS: u32 = BEAT_INTERVAL_TICKS as u32 * 8;
+    let spans = self.get_event_spans();
+    let chord_tones = self.get_chord_tones();
+
+    let mut steps = Vec::new();
+    let mut rest_tick_indexes = Vec::new();
+    for (i, (start_tick_index, end_tick_index)) in spans.iter().enumerate() {
+      match &chord_tones[i] {
+        Some((_, tones)) => {
+          let step_tick_indexes =
+            to_step_tick_indexes(*start_tick_index, *end_tick_index, melody.get_step_ticks());
+          for tick_index in step_tick_indexes {
+            steps.push((
+              tick_index,
+              tick_index == *start_tick_index,
+              tones.as_slice(),
+            ));
+          }
+        }
+        None => rest_tick_indexes.push(*start_tick_index),
+      }
+    }
+
+    let melody_steps: Vec<melody::MelodyStep> = steps
+      .iter()
+      .map(|(tick_index, is_chord_start, tones)| {
+        (*is_chord_start, tick_index % BEAT_TICKS == 0, *tones)
+      })
+      .collect();
+    let mut tick_notes: Vec<(u32, Vec<u8>)> = steps
+      .iter()
+      .zip(melody.apply(&melody_steps))
+      .filter_map(|((tick_index, _, _), note)| note.map(|note| (*tick_index, vec![note])))
+      .chain(
+        rest_tick_indexes
+          .into_iter()
+          .map(|tick_index| (tick_index, Vec::new())),
+      )
+      .collect();
+    tick_notes.sort_by_key(|(tick_index, _)| *tick_index);
+
+    let events = tick_notes
+      .into_iter()
+      .map(|(tick_index, notes)| {
+        let (time, offset) = self.get_time_at_tick_index(tick_index);
+        ChordEvent::new(time, offset, notes)
+      })
+      .collect();
+
+    let mut pattern = self.clone_with_events(events);
+    pattern.name = format!("{}_melody", self.name);
+    pattern.arpeggiator = None;
+    pattern.rhythm = None;
+    pattern.bass = None;
+    pattern.melody = None;
+    pattern.channel = melody::MELODY_CHANNEL;
+    pattern
+  }
+
+  /// Get the root and the pitch classes of each event, from its chord symbol
+  /// or else its notes with the lowest as the root. `None` for rests.
+  fn get_chord_tones(&self) -> Vec<Option<(u8, Vec<u8>)>> {
+    self
+      .events
+      .iter()
+      .map(|event| {
+        let notes = event.get_notes();
+        let lowest_note = *notes.iter().min()?;
+        Some(match event.get_symbol() {
+          Some(symbol) => {
+            let root = notes::key_to_index(symbol.get_root());
+            let tones = symbol
+              .get_intervals()
+              .iter()
+              .map(|interval| (root as i16 + *interval as i16).rem_euclid(12) as u8)
+              .collect();
+            (root, tones)
+          }
+          None => (
+            lowest_note % 12,
+            notes.iter().map(|note| note % 12).collect(),
+          ),
+        })
+      })
+      .collect()
+  }
+
+  /// Get a copy of the pattern followed by another pattern, starting on the bar
+  /// after the last event of this pattern. The tempo and time signature of the
+  /// other pattern apply from that bar, and tempo ramps running past it end
+  /// there at the tempo they had reached. Fails if the other pattern would
+  /// move past the last bar.
+  pub fn concatenated(&self, other: &Pattern) -> Result<Self, FailResult> {
+    let bars = match self.events.last() {
+      Some(event) => event.get_time().get_bar(),
+      None => {
+        return Ok(Pattern {
+          name: self.name.clone(),
+          ..other.clone()
+        })
+      }
+    };
+
+    let shifted = other.shifted_by(bars as i32, 0)?;
+    let join_bar = bars
+      .checked_add(1)
+      .ok_or(FailResult::ShiftRange(bars as i32, 0))?;
+    let join_time = MusicTime::new(join_bar, 1, 1);
+    let join_bpm = self.get_bpm_at(&join_time);
+    let mut pattern = self.clone();
+    pattern.signature_changes.retain(|(bar, _)| *bar <= bars);
+    pattern
+      .tempo_changes
+      .retain(|(time, _)| time.get_bar() <= bars);
+    for (_, change) in pattern.tempo_changes.iter_mut() {
+      if let TempoChange::Ramp(_, end_time) = change {
+        if end_time.get_bar() > bars {
+          *change = TempoChange::Ramp(join_bpm, join_time);
+        }
+      }
+    }
+
+    if pattern.get_time_signature_at(join_bar) != other.signature {
+      pattern.push_time_signature_change(join_bar, other.signature);
+    }
+    if pattern.get_bpm_at(&join_time) != other.bpm {
+      pattern.push_tempo_change(join_time, TempoChange::Instant(other.bpm));
+    }
+
+    for (bar, signature) in shifted.signature_changes {
+      pattern.push_time_signature_change(bar, signature);
+    }
+    for (time, change) in shifted.tempo_changes {
+      pattern.push_tempo_change(time, change);
+    }
+    pattern.events.extend(shifted.events);
+    pattern.sort_events();
+
+    Ok(pattern)
+  }
+
+  /// Get a copy of the pattern layered with another pattern. At each event of
+  /// either pattern the notes of both sounding chords are combined. Tempo is
+  /// kept from this pattern. Fails if the patterns have different time
+  /// signatures at any bar of either pattern.
+  pub fn overlaid(&self, other: &Pattern) -> Result<Self, FailResult> {
+    let last_bar = |pattern: &Pattern| {
+      pattern
+        .events
+        .last()
+        .map_or(0, |event| event.get_time().get_bar())
+    };
+    let bars = last_bar(self).max(last_bar(other));
+    if let Some(bar) =
+      (1..=bars).find(|bar| self.get_time_signature_at(*bar) != other.get_time_signature_at(*bar))
+    {
+      return Err(FailResult::OverlayTimeSignature(bar));
+    }
+
+    let spans = self.get_event_spans();
+    let other_spans = other.get_event_spans();
+
+    let mut tick_indexes: Vec<u32> = spans
+      .iter()
+      .chain(other_spans.iter())
+      .map(|(start, _)| *start)
+      .collect();
+    tick_indexes.sort_unstable();
+    tick_indexes.dedup();
+
+    let mut events = Vec::with_capacity(tick_indexes.len());
+    for tick_index in tick_indexes {
+      let sounding = find_sounding_event(&self.events, &spans, tick_index);
+      let other_sounding = find_sounding_event(&other.events, &other_spans, tick_index);
+
+      let mut notes: Vec<u8> = sounding
+        .iter()
+        .chain(other_sounding.iter())
+        .flat_map(|(event, _)| event.get_notes().iter().cloned())
+        .collect();
+      notes.sort_unstable();
+      notes.dedup();
+
+      let symbol = match (sounding, other_sounding) {
+        (Some((event, true)), _) | (_, Some((event, true))) => event.symbol.clone(),
+        (Some((event, false)), _) | (None, Some((event, false))) => event.symbol.clone(),
+        (None, None) => continue,
+      };
+
+      let (time, offset) = self.get_time_at_tick_index(tick_index);
+      events.push(ChordEvent {
+        event: (time, notes),
+        offset,
+        symbol,
+      });
+    }
+
+    Ok(self.clone_with_events(events))
+  }
+
+  /// Get the start and end tick index of each event. Events sound until the
+  /// next event, and the last event until the end of its bar.
+  fn get_event_spans(&self) -> Vec<(u32, u32)> {
+    let starts: Vec<u32> = self
+      .events
+      .iter()
+      .map(|event| self.get_tick_index(event.get_time(), event.offset))
+      .collect();
+    let end_tick_index = self.get_end_tick_index();
+
+    starts
+      .iter()
+      .enumerate()
+      .map(|(i, start)| (*start, *starts.get(i + 1).unwrap_or(&end_tick_index)))
+      .collect()
+  }
+
+  /// Move a time and tick offset by bars then beats. Gets `None` if it moves
+  /// before the start of the pattern, and fails if it moves to or past the tick
+  /// index limit.
+  fn shift_time(
+    &self,
+    time: &MusicTime,
+    offset: u8,
+    bars: i32,
+    beats: i32,
+    tick_index_limit: u64,
+  ) -> Result<Option<(MusicTime, u8)>, FailResult> {
+    const BEAT_INTERVALS: i64 = 8;
+
+    let bar = time.get_bar() as i32 + bars;
+    if bar < 1 {
+      return Ok(None);
+    }
+
+    let bar = u16::try_from(bar).map_err(|_| FailResult::ShiftRange(bars, beats))?;
+    let moved_time = MusicTime::new(bar, time.get_beat(), time.get_beat_interval());
+    let tick_index = self.get_beat_interval_index(&moved_time) as i64 * BEAT_INTERVAL_TICKS as i64
+      + offset as i64
+      + beats as i64 * BEAT_INTERVALS * BEAT_INTERVAL_TICKS as i64;
+    if tick_index < 0 {
+      Ok(None)
+    } else if tick_index as u64 >= tick_index_limit {
+      Err(FailResult::ShiftRange(bars, beats))
+    } else {
+      Ok(Some(self.get_time_at_tick_index(tick_index as u32)))
+    }
+  }
+
+  /// Get the tick index after the last bar a `MusicTime` can reach, or after
+  /// the last `u32` tick index if that comes first.
+  fn get_tick_index_limit(&self) -> u64 {
+    const BEAT_INTERVALS: u64 = 8;
+
+    let last_bar = MusicTime::new(u16::MAX, 1, 1);
+    let last_bar_intervals =
+      self.get_time_signature_at(u16::MAX).get_numerator() as u64 * BEAT_INTERVALS;
+    let intervals = self.get_beat_interval_index(&last_bar) as u64 + last_bar_intervals;
+    (intervals * BEAT_INTERVAL_TICKS as u64).min(u32::MAX as u64 + 1)
+  }
+
+  fn clone_with_events(&self, events: Vec<ChordEvent>) -> Self {
+    let mut pattern = Pattern {
+      events,
+      ..self.clone_without_events()
+    };
+    pattern.sort_events();
+    pattern
+  }
+
+  fn clone_without_events(&self) -> Self {
+    Pattern {
+      name: self.name.clone(),
+      bpm: self.bpm,
+      signature: self.signature,
+      events: Vec::new(),
+      tempo_changes: self.tempo_changes.clone(),
+      signature_changes: self.signature_changes.clone(),
+      swing: self.swing,
+      groove: self.groove.clone(),
+      humanize: self.humanize,
+      arpeggiator: self.arpeggiator,
+      rhythm: self.rhythm.clone(),
+      bass: self.bass,
+      melody: self.melody,
+      drums: self.drums.clone(),
+      channel: self.channel,
+    }
+  }
+}
+
+/// Get the tick index of the start of a span and each step after it, aligned
+/// to multiples of the step ticks, until the end of the span.
+fn to_step_tick_indexes(start_tick_index: u32, end_tick_index: u32, step_ticks: u32) -> Vec<u32> {
+  let mut step_tick_indexes = vec![start_tick_index];
+  let mut tick_index = (start_tick_index / step_ticks + 1) * step_ticks;
+  while tick_index < end_tick_index {
+    step_tick_indexes.push(tick_index);
+    tick_index += step_ticks;
+  }
+  step_tick_indexes
+}
+
+/// Find the event sounding at a tick index, and if it starts there.
+fn find_sounding_event<'a>(
+  events: &'a [ChordEvent],
+  spans: &[(u32, u32)],
+  tick_index: u32,
+) -> Option<(&'a ChordEvent, bool)> {
+  events
+    .iter()
+    .zip(spans.iter())
+    .rev()
+    .find(|(_, (start, end))| *start <= tick_index && tick_index < *end)
+    .map(|(event, (start, _))| (event, *start == tick_index))
+}
+
+#[derive(Debug)]
+pub struct Composition {
+  name: String,
+  patterns: Vec<Pattern>,
+}
+
+impl Composition {
+  pub fn new(name: &str) -> Self {
+    Composition {
+      name: name.to_string(),
+      patterns: Vec::new(),
+    }
+  }
+
+  pub fn new_with_patterns(name: &str, patterns: Vec<Pattern>) -> Self {
+    Composition {
+      name: name.to_string(),
+      patterns,
+    }
+  }
+
+  pub fn push_new_pattern(&mut self, name: String, bpm: f32, signature: TimeSignature) {
+    self.patterns.push(Pattern::new(&name, bpm, signature));
+  }
+
+  /// Push a pattern to the end of the composition without validation. Use
+  /// `insert_pattern` to keep the composition valid.
+  pub fn push_pattern(&mut self, pattern: Pattern) {
+    self.patterns.push(pattern);
+  }
+
+  /// Insert a pattern at an index. Fails if the pattern doesn't pass
+  /// `Pattern::check`.
+  pub fn insert_pattern(&mut self, index: usize, pattern: Pattern) -> Result<(), FailResult> {
+    if index > self.patterns.len() {
+      return Err(FailResult::NoFoundPatternIndex(index));
+    }
+    pattern.check()?;
+    self.patterns.insert(index, pattern);
+    Ok(())
+  }
+
+  pub fn remove_pattern(&mut self, index: usize) -> Result<Pattern, FailResult> {
+    if index < self.patterns.len() {
+      Ok(self.patterns.remove(index))
+    } else {
+      Err(FailResult::NoFoundPatternIndex(index))
+    }
+  }
+
+  /// Replace a pattern, returning the replaced pattern. Fails if the pattern
+  /// doesn't pass `Pattern::check`.
+  pub fn replace_pattern(&mut self, index: usize, pattern: Pattern) -> Result<Pattern, FailResult> {
+    if index >= self.patterns.len() {
+      return Err(FailResult::NoFoundPatternIndex(index));
+    }
+    pattern.check()?;
+    Ok(std::mem::replace(&mut self.patterns[index], pattern))
+  }
+
+  /// Move a pattern from one index to another, shifting the patterns between.
+  pub fn move_pattern(&mut self, from_index: usize, to_index: usize) -> Result<(), FailResult> {
+    for index in &[from_index, to_index] {
+      if *index >= self.patterns.len() {
+        return Err(FailResult::NoFoundPatternIndex(*index));
+      }
+    }
+    let pattern = self.patterns.remove(from_index);
+    self.patterns.insert(to_index, pattern);
+    Ok(())
+  }
+
+  pub fn rename_pattern(&mut self, index: usize, name: &str) -> Result<(), FailResult> {
+    match self.patterns.get_mut(index) {
+      Some(pattern) => {
+        pattern.set_name(name);
+        Ok(())
+      }
+      None => Err(FailResult::NoFoundPatternIndex(index)),
+    }
+  }
+
+  pub fn set_name(&mut self, name: &str) {
+    self.name = name.to_string();
+  }
+
+  /// Check all patterns by the same rules as compositions loaded from YAML.
+  pub fn check(&self) -> Result<(), FailResult> {
+    if self.patterns.is_empty() {
+      return Err(FailResult::NoPatterns);
+    }
+    self.patterns.iter().try_for_each(Pattern::check)
+  }
+
+  pub fn len(&self) -> usize {
+    self.patterns.len()
+  }
+
+  pub fn get(&self, index: usize) -> &Pattern {
+    &self.patterns[index]
+  }
+
+  /// Get a pattern to edit through its checked edits. A pattern assigned in
+  /// whole isn't checked, use `replace_pattern` to replace one.
+  pub fn get_mut(&mut self, index: usize) -> &mut Pattern {
+    &mut self.patterns[index]
+  }
+
+  pub fn get_patterns(&self) -> &Vec<Pattern> {
+    &self.patterns
+  }
+
+  pub fn get_name(&self) -> &str {
+    &self.name
+  }
+}
+
+mod tests {
+  #[test]
+  fn test_create() {
+    use crate::composition::*;
+    let mut compo = Composition::new("test");
+    assert_eq!(compo.get_name(), "test");
+
+    compo.push_new_pattern("a".to_string(), 120.0, TimeSignature::default());
+    compo
+      .get_mut(0)
+      .push_event(MusicTime::new(1, 1, 1), vec![0, 1, 2])
+      .unwrap();
+    compo
+      .get_mut(0)
+      .push_event(MusicTime::new(2, 1, 1), vec![2, 3, 4])
+      .unwrap();
+
+    compo.push_new_pattern("a".to_string(), 54.5, TimeSignature::new(3, 4));
+    compo
+      .get_mut(1)
+      .push_event(MusicTime::new(1, 3, 1), vec![51, 51, 52])
+      .unwrap();
+    compo
+      .get_mut(1)
+      .push_event(MusicTime::new(2, 3, 1), vec![52, 53, 54])
+      .unwrap();
+
+    assert_eq!(compo.len(), 2);
+    assert_eq!(compo.get(0).len(), 2);
+    assert_eq!(compo.get(1).len(), 2);
+
+    assert_eq!(compo.get(0).get_bpm(), 120.0);
+    assert_eq!(compo.get(0).get_time_signature(), TimeSignature::new(4, 4));
+
+    assert_eq!(compo.get(1).get_bpm(), 54.5);
+    assert_eq!(compo.get(1).get_time_signature(), TimeSignature::new(3, 4));
+
+    let (time, notes) = compo.get(0).get(0);
+    assert_eq!(time, &MusicTime::new(1, 1, 1));
+    assert_eq!(notes, &vec![0, 1, 2]);
+
+    let (time, notes) = compo.get(0).get(1);
+    assert_eq!(time, &MusicTime::new(2, 1, 1));
+    assert_eq!(notes, &vec![2, 3, 4]);
+
+    let (time, notes) = compo.get(1).get(0);
+    assert_eq!(time, &MusicTime::new(1, 3, 1));
+    assert_eq!(notes, &vec![51, 51, 52]);
+
+    let (time, notes) = compo.get(1).get(1);
+    assert_eq!(time, &MusicTime::new(2, 3, 1));
+    assert_eq!(notes, &vec![52, 53, 54]);
+  }
+
+  #[test]
+  fn test_find_next_event() {
+    use crate::composition::Pattern;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let pattern = Pattern::new_with_events(
+      "test pattern",
+      85.0,
+      TimeSignature::default(),
+      vec![
+        (MusicTime::new(1, 1, 1), vec![0]),
+        (MusicTime::new(2, 1, 1), vec![0]),
+        (MusicTime::new(3, 1, 1), vec![0]),
+        (MusicTime::new(3, 4, 1), vec![0]),
+        (MusicTime::new(4, 1, 1), vec![0]),
+      ],
+    );
+
+    let time = MusicTime::new(3, 3, 2);
+    let result = Pattern::find_next_event_index(&pattern, &time);
+    assert_eq!(result, 3);
+  }
+
+  #[test]
+  fn test_event_order() {
+    use crate::composition::Pattern;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let pattern = Pattern::new_with_events(
+      "test pattern",
+      85.0,
+      TimeSignature::default(),
+      vec![
+        (MusicTime::new(2, 1, 1), vec![0]),
+        (MusicTime::new(1, 1, 1), vec![0]),
+        (MusicTime::new(4, 1, 1), vec![0]),
+        (MusicTime::new(3, 1, 1), vec![0]),
+        (MusicTime::new(3, 4, 1), vec![0]),
+      ],
+    );
+
+    assert_eq!(
+      pattern.get_events(),
+      vec![
+        (MusicTime::new(1, 1, 1), vec![0]),
+        (MusicTime::new(2, 1, 1), vec![0]),
+        (MusicTime::new(3, 1, 1), vec![0]),
+        (MusicTime::new(3, 4, 1), vec![0]),
+        (MusicTime::new(4, 1, 1), vec![0]),
+      ]
+    )
+  }
+
+  #[test]
+  fn test_chord_event_order() {
+    use crate::composition::{ChordEvent, ChordSymbol, Pattern};
+    use crate::notes::Key;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let symbol = ChordSymbol::new("MAJOR", Key::C, 0, vec![0, 4, 7], 3);
+    let mut pattern = Pattern::new("test pattern", 85.0, TimeSignature::default());
+    pattern
+      .push_chord_event(ChordEvent::new_with_symbol(
+        MusicTime::new(2, 1, 1),
+        0,
+        vec![60, 64, 67],
+        symbol.clone(),
+      ))
+      .unwrap();
+    pattern
+      .push_event_with_offset(MusicTime::new(1, 1, 1), 20, vec![1])
+      .unwrap();
+    pattern
+      .push_event(MusicTime::new(1, 1, 1), vec![0])
+      .unwrap();
+
+    assert_eq!(
+      pattern.get_chord_events(),
+      &vec![
+        ChordEvent::new(MusicTime::new(1, 1, 1), 0, vec![0]),
+        ChordEvent::new(MusicTime::new(1, 1, 1), 20, vec![1]),
+        ChordEvent::new_with_symbol(MusicTime::new(2, 1, 1), 0, vec![60, 64, 67], symbol),
+      ]
+    );
+    assert_eq!(
+      pattern.get_events(),
+      vec![
+        (MusicTime::new(1, 1, 1), vec![0]),
+        (MusicTime::new(1, 1, 1), vec![1]),
+        (MusicTime::new(2, 1, 1), vec![60, 64, 67]),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_event_offsets() {
+    use crate::composition::Pattern;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = Pattern::new("test pattern", 85.0, TimeSignature::default());
+    pattern
+      .push_event_with_offset(MusicTime::new(1, 1, 3), 40, vec![2])
+      .unwrap();
+    pattern
+      .push_event_with_offset(MusicTime::new(1, 4, 8), 90, vec![3])
+      .unwrap();
+    pattern
+      .push_event(MusicTime::new(1, 1, 3), vec![1])
+      .unwrap();
+
+    assert_eq!(pattern.get_event(0).get_time(), &MusicTime::new(1, 1, 3));
+    assert_eq!(pattern.get_event(0).get_offset(), 0);
+    assert_eq!(pattern.get_event(1).get_time(), &MusicTime::new(1, 1, 3));
+    assert_eq!(pattern.get_event(1).get_offset(), 40);
+    assert_eq!(pattern.get_event(1).get_notes(), &vec![2]);
+    assert_eq!(pattern.get_event(2).get_time(), &MusicTime::new(2, 1, 1));
+    assert_eq!(pattern.get_event(2).get_offset(), 30);
+    assert_eq!(pattern.get(2), &(MusicTime::new(2, 1, 1), vec![3]));
+    assert_eq!(
+      pattern.get_events(),
+      vec![
+        (MusicTime::new(1, 1, 3), vec![1]),
+        (MusicTime::new(1, 1, 3), vec![2]),
+        (MusicTime::new(2, 1, 1), vec![3]),
+      ]
+    );
+    assert_eq!(pattern.get_chord_events()[1].get_offset(), 40);
+
+    assert!(pattern.remove_event(0).is_ok());
+    assert_eq!(pattern.get_events()[0], (MusicTime::new(1, 1, 3), vec![2]));
+  }
+
+  #[test]
+  fn test_chord_symbol() {
+    use crate::composition::{ChordEvent, ChordSymbol, Pattern};
+    use crate::theory::notes::Key;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let symbol = ChordSymbol::new("MINOR_SEVENTH", Key::A, 5, vec![0, 3, 7, 10], 3);
+    assert_eq!(symbol.get_name(), "MINOR_SEVENTH");
+    assert_eq!(symbol.get_key(), Key::A);
+    assert_eq!(symbol.get_root(), Key::D);
+    assert_eq!(symbol.get_voicing(), vec![36, 39, 43, 46]);
+    assert_eq!(
+      ChordSymbol::new("MAJOR", Key::C, -1, vec![0, 4, 7], 3).get_root(),
+      Key::B
+    );
+
+    let mut pattern = Pattern::new("test pattern", 85.0, TimeSignature::default());
+    pattern
+      .push_chord_event(ChordEvent::new_with_symbol(
+        MusicTime::new(1, 1, 1),
+        0,
+        vec![69, 72, 76, 79],
+        symbol.clone(),
+      ))
+      .unwrap();
+    pattern
+      .push_event(MusicTime::new(2, 1, 1), vec![60])
+      .unwrap();
+
+    assert_eq!(pattern.get_event(0).get_symbol(), Some(&symbol));
+    assert_eq!(pattern.get_event(1).get_symbol(), None);
+    assert_eq!(
+      pattern.get(0),
+      &(MusicTime::new(1, 1, 1), vec![69, 72, 76, 79])
+    );
+  }
+
+  #[test]
+  fn test_tick_index() {
+    use crate::composition::Pattern;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = Pattern::new("test pattern", 120.0, TimeSignature::default());
+    pattern.push_time_signature_change(2, TimeSignature::new(3, 4));
+
+    assert_eq!(pattern.get_tick_index(&MusicTime::new(2, 1, 1), 0), 1920);
+    assert_eq!(pattern.get_tick_index(&MusicTime::new(2, 2, 3), 10), 2530);
+    assert_eq!(
+      pattern.get_time_at_tick_index(2530),
+      (MusicTime::new(2, 2, 3), 10)
+    );
+    assert_eq!(
+      pattern.get_time_at_tick_index(3360),
+      (MusicTime::new(3, 1, 1), 0)
+    );
+    assert_eq!(pattern.get_end_tick_index(), 0);
+  }
+
+  #[test]
+  fn test_transpose_and_shift() {
+    use crate::composition::{Pattern, TempoChange};
+    use crate::FailResult;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = Pattern::new("test pattern", 120.0, TimeSignature::default());
+    pattern
+      .push_event(MusicTime::new(1, 1, 1), vec![60])
+      .unwrap();
+    pattern
+      .push_event(MusicTime::new(2, 3, 1), vec![64])
+      .unwrap();
+    pattern.push_tempo_change(MusicTime::new(2, 1, 1), TempoChange::Instant(100.0));
+
+    let transposed = pattern.transposed(2).unwrap();
+    assert_eq!(transposed.get(0).1, vec![62]);
+    assert_eq!(transposed.get(1).1, vec![66]);
+    assert_eq!(pattern.transposed(-60).unwrap().get(0).1, vec![0]);
+    assert_eq!(
+      pattern.transposed(-100).map(|pattern| pattern.len()),
+      Err(FailResult::TransposeRange(-100))
+    );
+    assert_eq!(
+      pattern.transposed(64).map(|pattern| pattern.len()),
+      Err(FailResult::TransposeRange(64))
+    );
+
+    let mut later = pattern.shifted(1, 0).unwrap();
+    assert_eq!(later.get(0).0, MusicTime::new(2, 1, 1));
+    assert_eq!(later.get(1).0, MusicTime::new(3, 3, 1));
+    assert_eq!(later.get_tempo_changes()[0].0, MusicTime::new(3, 1, 1));
+
+    later.push_tempo_change(MusicTime::new(1, 1, 1), TempoChange::Instant(90.0));
+    let shifted = later.shifted(0, -4).unwrap();
+    assert_eq!(shifted.get(0).0, MusicTime::new(1, 1, 1));
+    assert_eq!(shifted.get(1).0, MusicTime::new(2, 3, 1));
+    assert_eq!(shifted.get_bpm(), 90.0);
+    assert_eq!(shifted.get_tempo_changes()[0].0, MusicTime::new(2, 1, 1));
+
+    assert_eq!(
+      pattern.shifted(0, -2).map(|pattern| pattern.len()),
+      Err(FailResult::ShiftRange(0, -2))
+    );
+    assert_eq!(
+      pattern.shifted(-1, 0).map(|pattern| pattern.len()),
+      Err(FailResult::ShiftRange(-1, 0))
+    );
+    let last = pattern.shifted(i16::MAX, 0).unwrap();
+    assert_eq!(last.get(1).0, MusicTime::new(32769, 3, 1));
+    assert_eq!(
+      last.shifted(i16::MAX, 0).map(|pattern| pattern.len()),
+      Err(FailResult::ShiftRange(i16::MAX as i32, 0))
+    );
+    assert_eq!(
+      last
+        .shifted(i16::MAX - 1, i16::MAX)
+        .map(|pattern| pattern.len()),
+      Err(FailResult::ShiftRange(i16::MAX as i32 - 1, i16::MAX as i32))
+    );
+  }
+
+  #[test]
+  fn test_stretch_and_reverse() {
+    use crate::composition::Pattern;
+    use crate::FailResult;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let pattern = Pattern::new_with_events(
+      "test pattern",
+      120.0,
+      TimeSignature::default(),
+      vec![
+        (MusicTime::new(1, 1, 1), vec![60]),
+        (MusicTime::new(1, 3, 1), vec![62]),
+        (MusicTime::new(2, 1, 1), vec![64]),
+      ],
+    );
+
+    assert_eq!(
+      pattern.stretched(2.0).unwrap().get_chord_events(),
+      &vec![
+        (MusicTime::new(1, 1, 1), vec![60]),
+        (MusicTime::new(2, 1, 1), vec![62]),
+        (MusicTime::new(3, 1, 1), vec![64]),
+      ]
+    );
+    assert_eq!(
+      pattern.stretched(0.5).unwrap().get_chord_events(),
+      &vec![
+        (MusicTime::new(1, 1, 1), vec![60]),
+        (MusicTime::new(1, 2, 1), vec![62]),
+        (MusicTime::new(1, 3, 1), vec![64]),
+      ]
+    );
+    assert_eq!(
+      pattern.stretched(0.0).map(|pattern| pattern.len()),
+      Err(FailResult::Stretch(0.0))
+    );
+    assert_eq!(
+      pattern.stretched(1e9).map(|pattern| pattern.len()),
+      Err(FailResult::Stretch(1e9))
+    );
+    assert_eq!(
+      pattern.stretched(0.0001).map(|pattern| pattern.len()),
+      Err(FailResult::UnreachableTime(
+        MusicTime::new(1, 1, 1),
+        1,
+        String::new()
+      ))
+    );
+
+    // A change at the unreachable bar 0 is stretched onto the first bar
+    let mut changed = pattern.clone();
+    changed.push_time_signature_change(0, TimeSignature::new(3, 4));
+    assert_eq!(
+      changed.stretched(2.0).unwrap().get_time_signature(),
+      TimeSignature::new(3, 4)
+    );
+
+    assert_eq!(
+      pattern.reversed().get_chord_events(),
+      &vec![
+        (MusicTime::new(1, 1, 1), vec![64]),
+        (MusicTime::new(2, 1, 1), vec![62]),
+        (MusicTime::new(2, 3, 1), vec![60]),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_slice_concatenate_overlay() {
+    use crate::composition::{Pattern, TempoChange};
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = Pattern::new_with_events(
+      "a",
+      120.0,
+      TimeSignature::default(),
+      vec![
+        (MusicTime::new(1, 1, 1), vec![60]),
+        (MusicTime::new(2, 3, 1), vec![62]),
+        (MusicTime::new(3, 1, 1), vec![64]),
+      ],
+    );
+    pattern.push_tempo_change(MusicTime::new(3, 1, 1), TempoChange::Instant(90.0));
+
+    let sliced = pattern.sliced(2, 2);
+    assert_eq!(
+      sliced.get_chord_events(),
+      &vec![
+        (MusicTime::new(1, 1, 1), vec![60]),
+        (MusicTime::new(1, 3, 1), vec![62]),
+      ]
+    );
+    assert_eq!(sliced.get_bpm(), 120.0);
+    assert!(sliced.get_tempo_changes().is_empty());
+
+    let sliced = pattern.sliced(3, 3);
+    assert_eq!(
+      sliced.get_chord_events(),
+      &vec![(MusicTime::new(1, 1, 1), vec![64])]
+    );
+    assert_eq!(sliced.get_bpm(), 90.0);
+
+    let other = Pattern::new_with_events(
+      "b",
+      100.0,
+      TimeSignature::new(3, 4),
+      vec![
+        (MusicTime::new(1, 1, 1), vec![70]),
+        (MusicTime::new(2, 1, 1), vec![72]),
+      ],
+    );
+    let concatenated = pattern.concatenated(&other).unwrap();
+    assert_eq!(concatenated.get_name(), "a");
+    assert_eq!(concatenated.len(), 5);
+    assert_eq!(concatenated.get(4).0, MusicTime::new(5, 1, 1));
+    assert_eq!(
+      concatenated.get_time_signature_at(3),
+      TimeSignature::new(4, 4)
+    );
+    assert_eq!(
+      concatenated.get_time_signature_at(5),
+      TimeSignature::new(3, 4)
+    );
+    assert_eq!(concatenated.get_bpm_at(&MusicTime::new(3, 1, 1)), 90.0);
+    assert_eq!(concatenated.get_bpm_at(&MusicTime::new(5, 1, 1)), 100.0);
+
+    let layer = Pattern::new_with_events(
+      "c",
+      120.0,
+      TimeSignature::default(),
+      vec![
+        (MusicTime::new(1, 1, 1), vec![48]),
+        (MusicTime::new(1, 3, 1), vec![50, 60]),
+      ],
+    );
+    assert_eq!(
+      pattern.overlaid(&layer).unwrap().get_chord_events(),
+      &vec![
+        (MusicTime::new(1, 1, 1), vec![48, 60]),
+        (MusicTime::new(1, 3, 1), vec![50, 60]),
+        (MusicTime::new(2, 3, 1), vec![62]),
+        (MusicTime::new(3, 1, 1), vec![64]),
+      ]
+    );
+
+    let waltz = Pattern::new_with_events(
+      "d",
+      120.0,
+      TimeSignature::new(3, 4),
+      vec![(MusicTime::new(2, 1, 1), vec![48])],
+    );
+    assert_eq!(
+      pattern.overlaid(&waltz).err(),
+      Some(crate::FailResult::OverlayTimeSignature(1))
+    );
+
+    let mut ramped = Pattern::new_with_events(
+      "e",
+      120.0,
+      TimeSignature::default(),
+      vec![
+        (MusicTime::new(1, 1, 1), vec![60]),
+        (MusicTime::new(2, 1, 1), vec![62]),
+      ],
+    );
+    ramped.push_tempo_change(
+      MusicTime::new(1, 1, 1),
+      TempoChange::Ramp(60.0, MusicTime::new(5, 1, 1)),
+    );
+    let concatenated = ramped.concatenated(&other).unwrap();
+    assert_eq!(
+      concatenated.get_tempo_changes(),
+      &vec![
+        (
+          MusicTime::new(1, 1, 1),
+          TempoChange::Ramp(90.0, MusicTime::new(3, 1, 1))
+        ),
+        (MusicTime::new(3, 1, 1), TempoChange::Instant(100.0)),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_bass_line() {
+    use crate::composition::{ChordEvent, ChordSymbol, Pattern};
+    use crate::theory::{bass::BassStyle, notes::Key};
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = Pattern::new("a", 120.0, TimeSignature::new(3, 4));
+    pattern
+      .push_chord_event(ChordEvent::new_with_symbol(
+        MusicTime::new(1, 1, 1),
+        0,
+        vec![60, 64, 67],
+        ChordSymbol::new("MAJOR", Key::C, 0, vec![0, 4, 7], 3),
+      ))
+      .unwrap();
+    pattern
+      .push_event(MusicTime::new(2, 1, 1), vec![65, 69, 72])
+      .unwrap();
+    pattern
+      .push_event(MusicTime::new(2, 3, 1), Vec::new())
+      .unwrap();
+
+    let bass_line = pattern.bass_line(BassStyle::Walking);
+    assert_eq!(bass_line.get_name(), "a_bass");
+    assert_eq!(bass_line.get_channel(), 1);
+    assert_eq!(bass_line.get_time_signature(), TimeSignature::new(3, 4));
+    assert_eq!(
+      bass_line.get_chord_events(),
+      &vec![
+        (MusicTime::new(1, 1, 1), vec![36]),
+        (MusicTime::new(1, 2, 1), vec![40]),
+        (MusicTime::new(1, 3, 1), vec![28]),
+        (MusicTime::new(2, 1, 1), vec![29]),
+        (MusicTime::new(2, 2, 1), vec![33]),
+        (MusicTime::new(2, 3, 1), vec![]),
+      ]
+    );
+
+    let bass_line = pattern.bass_line(BassStyle::Root);
+    assert_eq!(bass_line.len(), 3);
+    assert_eq!(bass_line.get(1), &(MusicTime::new(2, 1, 1), vec![29]));
+
+    pattern.set_bass(Some(BassStyle::Octave));
+    assert_eq!(pattern.get_bass_line().unwrap().len(), 11);
+    assert!(pattern.set_channel(16).is_err());
+  }
+
+  #[test]
+  fn test_drum_notes() {
+    use crate::composition::Pattern;
+    use crate::theory::drums::{self, Drums};
+    use crate::theory::groove::Swing;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = Pattern::new("a", 120.0, TimeSignature::new(3, 4));
+    pattern
+      .push_event(MusicTime::new(1, 1, 1), vec![60])
+      .unwrap();
+    pattern
+      .push_event(MusicTime::new(2, 1, 1), vec![62])
+      .unwrap();
+    assert!(pattern.get_drum_notes().is_empty());
+
+    let drums = Drums::new("beat", 8, vec![(36, vec![80, 0]), (42, vec![0, 70])]);
+    assert!(pattern.set_drums(Some(drums)).is_ok());
+    let drum_notes = pattern.get_drum_notes();
+    assert_eq!(drum_notes.len(), 12);
+    assert_eq!(
+      &drum_notes[..3],
+      &[(0, 240, 36, 80), (240, 480, 42, 70), (480, 720, 36, 80)]
+    );
+    assert_eq!(drum_notes[11], (2640, 2880, 42, 70));
+
+    assert!(pattern.set_swing(Some(Swing::new(8, 0.75))).is_ok());
+    assert_eq!(pattern.get_drum_notes()[1], (360, 600, 42, 70));
+
+    let rock = drums::string_to_drum_style("rock").unwrap();
+    assert!(pattern.set_time_signature(TimeSignature::new(4, 4)).is_ok());
+    let _ = pattern.set_swing(None);
+    assert!(pattern.set_drums(Some(rock)).is_ok());
+    assert_eq!(pattern.get_drum_notes().len(), 2 * (3 + 2 + 8));
+
+    let invalid = Drums::new("empty", 16, Vec::new());
+    assert_eq!(
+      pattern.set_drums(Some(invalid)),
+      Err(crate::FailResult::Drums("empty".to_string()))
+    );
+  }
+
+  #[test]
+  fn test_melody_line() {
+    use crate::composition::{ChordEvent, ChordSymbol, Pattern};
+    use crate::theory::melody::{Contour, Melody, Scale};
+    use crate::theory::notes::Key;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = Pattern::new("a", 120.0, TimeSignature::new(4, 4));
+    pattern
+      .push_chord_event(ChordEvent::new_with_symbol(
+        MusicTime::new(1, 1, 1),
+        0,
+        vec![60, 64, 67],
+        ChordSymbol::new("MAJOR", Key::C, 0, vec![0, 4, 7], 3),
+      ))
+      .unwrap();
+    pattern
+      .push_event(MusicTime::new(2, 1, 1), vec![65, 69, 72])
+      .unwrap();
+    pattern
+      .push_event(MusicTime::new(2, 3, 1), Vec::new())
+      .unwrap();
+
+    let melody = Melody::new(Key::C, Scale::Major, 8, 1.0, (60, 79), Contour::Arch, 1);
+    let melody_line = pattern.melody_line(&melody);
+    assert_eq!(melody_line.get_name(), "a_melody");
+    assert_eq!(melody_line.get_channel(), 2);
+    assert_eq!(melody_line.len(), 13);
+    assert_eq!(melody_line.get(12), &(MusicTime::new(2, 3, 1), vec![]));
+
+    for (time, notes) in melody_line.get_events().iter().take(12) {
+      assert_eq!(notes.len(), 1);
+      if time.get_beat_interval() == 1 {
+        let tones: &[u8] = if time.get_bar() == 1 {
+          &[0, 4, 7]
+        } else {
+          &[5, 9, 0]
+        };
+        assert!(tones.contains(&(notes[0] % 12)));
+      }
+    }
+
+    assert!(pattern.get_melody_line().is_none());
+    let invalid = Melody::new(Key::C, Scale::Major, 8, 1.5, (60, 79), Contour::Arch, 1);
+    assert!(pattern.set_melody(Some(invalid)).is_err());
+    assert!(pattern.set_melody(Some(melody)).is_ok());
+    assert_eq!(
+      pattern.get_melody_line().unwrap().get_chord_events(),
+      melody_line.get_chord_events()
+    );
+  }
+
+  #[test]
+  fn test_edit_events() {
+    use crate::composition::{ChordEvent, Pattern};
+    use crate::FailResult;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = Pattern::new("test pattern", 120.0, TimeSignature::new(3, 4));
+    assert_eq!(
+      pattern.insert_event(MusicTime::new(2, 1, 1), vec![62]),
+      Ok(0)
+    );
+    assert_eq!(
+      pattern.insert_event(MusicTime::new(1, 1, 1), vec![60]),
+      Ok(0)
+    );
+    assert_eq!(
+      pattern.insert_chord_event(ChordEvent::new(MusicTime::new(1, 1, 1), 30, vec![61])),
+      Ok(1)
+    );
+    assert_eq!(
+      pattern.insert_event(MusicTime::new(2, 1, 1), vec![0]),
+      Err(FailResult::UnreachableTime(
+        MusicTime::new(2, 1, 1),
+        2,
+        String::new()
+      ))
+    );
+    assert_eq!(
+      pattern.insert_event(MusicTime::new(1, 4, 1), vec![0]),
+      Err(FailResult::UnreachableTime(
+        MusicTime::new(1, 4, 1),
+        2,
+        String::new()
+      ))
+    );
+    assert_eq!(pattern.len(), 3);
+
+    assert_eq!(pattern.move_event(0, MusicTime::new(3, 1, 1), 0), Ok(2));
+    assert_eq!(pattern.get(2), &(MusicTime::new(3, 1, 1), vec![60]));
+    assert!(pattern.move_event(2, MusicTime::new(2, 1, 1), 0).is_err());
+    assert_eq!(pattern.get(2), &(MusicTime::new(3, 1, 1), vec![60]));
+
+    assert_eq!(
+      pattern.replace_event(0, ChordEvent::new(MusicTime::new(1, 2, 1), 0, vec![64])),
+      Ok(0)
+    );
+    assert_eq!(pattern.get(0), &(MusicTime::new(1, 2, 1), vec![64]));
+    assert_eq!(pattern.remove_event(3), Err(FailResult::NoFoundEvent(3)));
+    assert_eq!(pattern.remove_event(0).unwrap().get_notes(), &vec![64]);
+    assert_eq!(pattern.len(), 2);
+
+    assert_eq!(pattern.set_bpm(0.0), Err(FailResult::Tempo(0.0)));
+    assert_eq!(pattern.set_bpm(90.5), Ok(()));
+    assert_eq!(pattern.get_bpm(), 90.5);
+
+    pattern
+      .insert_event(MusicTime::new(1, 3, 1), vec![65])
+      .unwrap();
+    assert_eq!(
+      pattern.set_time_signature(TimeSignature::new(2, 4)),
+      Err(FailResult::UnreachableTime(
+        MusicTime::new(1, 3, 1),
+        0,
+        String::new()
+      ))
+    );
+    assert_eq!(pattern.get_time_signature(), TimeSignature::new(3, 4));
+    assert_eq!(pattern.set_time_signature(TimeSignature::new(4, 4)), Ok(()));
+    assert_eq!(pattern.check(), Ok(()));
+
+    assert_eq!(pattern.push_event(MusicTime::new(1, 1, 1), vec![0]), Ok(0));
+    assert_eq!(
+      pattern.push_event(MusicTime::new(1, 1, 1), vec![1]),
+      Err(FailResult::UnreachableTime(
+        MusicTime::new(1, 1, 1),
+        0,
+        String::new()
+      ))
+    );
+    assert_eq!(
+      pattern.push_event_with_offset(MusicTime::new(1, 5, 1), 0, vec![1]),
+      Err(FailResult::UnreachableTime(
+        MusicTime::new(1, 5, 1),
+        2,
+        String::new()
+      ))
+    );
+    assert_eq!(pattern.len(), 4);
+    assert_eq!(pattern.check(), Ok(()));
+  }
+
+  #[test]
+  fn test_edit_patterns() {
+    use crate::composition::{Composition, Pattern};
+    use crate::FailResult;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut compo = Composition::new("test");
+    compo.set_name("edited");
+    assert_eq!(compo.get_name(), "edited");
+    assert_eq!(compo.check(), Err(FailResult::NoPatterns));
+
+    for name in &["a", "b", "c"] {
+      let pattern = Pattern::new(name, 120.0, TimeSignature::default());
+      assert_eq!(compo.insert_pattern(compo.len(), pattern), Ok(()));
+    }
+    assert_eq!(
+      compo.insert_pattern(5, Pattern::new("d", 120.0, TimeSignature::default())),
+      Err(FailResult::NoFoundPatternIndex(5))
+    );
+    assert_eq!(
+      compo.insert_pattern(0, Pattern::new("d", -1.0, TimeSignature::default())),
+      Err(FailResult::Tempo(-1.0))
+    );
+
+    assert_eq!(compo.move_pattern(0, 2), Ok(()));
+    assert_eq!(compo.get(0).get_name(), "b");
+    assert_eq!(compo.get(2).get_name(), "a");
+    assert_eq!(
+      compo.move_pattern(0, 3),
+      Err(FailResult::NoFoundPatternIndex(3))
+    );
+
+    assert_eq!(compo.rename_pattern(1, "z"), Ok(()));
+    assert_eq!(compo.get(1).get_name(), "z");
+
+    let invalid = Pattern::new_with_events(
+      "e",
+      120.0,
+      TimeSignature::default(),
+      vec![(MusicTime::new(1, 5, 1), vec![0])],
+    );
+    assert!(compo.replace_pattern(0, invalid).is_err());
+    let replaced = compo
+      .replace_pattern(0, Pattern::new("e", 120.0, TimeSignature::default()))
+      .unwrap();
+    assert_eq!(replaced.get_name(), "b");
+
+    assert_eq!(compo.remove_pattern(0).unwrap().get_name(), "e");
+    assert_eq!(compo.len(), 2);
+    assert_eq!(compo.check(), Ok(()));
+  }
+
+  #[test]
+  fn test_time_signature_changes() {
+    use crate::composition::Pattern;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = Pattern::new("test pattern", 120.0, TimeSignature::default());
+    pattern.push_time_signature_change(3, TimeSignature::new(3, 4));
+    pattern.push_time_signature_change(5, TimeSignature::new(7, 8));
+
+    assert_eq!(pattern.get_time_signature_at(1), TimeSignature::new(4, 4));
+    assert_eq!(pattern.get_time_signature_at(2), TimeSignature::new(4, 4));
+    assert_eq!(pattern.get_time_signature_at(3), TimeSignature::new(3, 4));
+    assert_eq!(pattern.get_time_signature_at(4), TimeSignature::new(3, 4));
+    assert_eq!(pattern.get_time_signature_at(9), TimeSignature::new(7, 8));
+
+    assert_eq!(pattern.get_beat_interval_index(&MusicTime::new(1, 1, 1)), 0);
+    assert_eq!(
+      pattern.get_beat_interval_index(&MusicTime::new(2, 1, 1)),
+      32
+    );
+    assert_eq!(
+      pattern.get_beat_interval_index(&MusicTime::new(3, 1, 1)),
+      64
+    );
+    assert_eq!(
+      pattern.get_beat_interval_index(&MusicTime::new(4, 1, 1)),
+      88
+    );
+    assert_eq!(
+      pattern.get_beat_interval_index(&MusicTime::new(4, 2, 3)),
+      98
+    );
+  }
+
+  #[test]
+  fn test_tempo_changes() {
+    use crate::composition::{Pattern, TempoChange};
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = Pattern::new("test pattern", 120.0, TimeSignature::default());
+    pattern.push_tempo_change(
+      MusicTime::new(3, 1, 1),
+      TempoChange::Ramp(80.0, MusicTime::new(4, 1, 1)),
+    );
+    pattern.push_tempo_change(MusicTime::new(2, 1, 1), TempoChange::Instant(100.0));
+
+    assert_eq!(pattern.get_tempo_changes()[0].0, MusicTime::new(2, 1, 1));
+    assert_eq!(pattern.get_bpm_at(&MusicTime::new(1, 4, 8)), 120.0);
+    assert_eq!(pattern.get_bpm_at(&MusicTime::new(2, 1, 1)), 100.0);
+    assert_eq!(pattern.get_bpm_at(&MusicTime::new(3, 1, 1)), 100.0);
+    assert_eq!(pattern.get_bpm_at(&MusicTime::new(3, 3, 1)), 90.0);
+    assert_eq!(pattern.get_bpm_at(&MusicTime::new(4, 1, 1)), 80.0);
+    assert_eq!(pattern.get_bpm_at(&MusicTime::new(8, 1, 1)), 80.0);
+  }
+
+  #[test]
+  fn test_edit_time_changes() {
+    use crate::composition::{Pattern, TempoChange};
+    use crate::FailResult;
+    use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
+
+    let mut pattern = Pattern::new("test pattern", 120.0, TimeSignature::default());
+    assert_eq!(
+      pattern.insert_event(MusicTime::new(3, 4, 1), vec![60]),
+      Ok(0)
+    );
+
+    let instant = TempoChange::Instant(100.0);
+    assert_eq!(
+      pattern.insert_tempo_change(MusicTime::new(3, 1, 1), instant),
+      Ok(0)
+    );
+    assert_eq!(
+      pattern.insert_tempo_change(MusicTime::new(2, 1, 1), instant),
+      Ok(0)
+    );
+    assert_eq!(
+      pattern.insert_tempo_change(MusicTime::new(2, 1, 1), instant),
+      Err(FailResult::UnreachableTimeChange(
+        MusicTime::new(2, 1, 1),
+        0
+      ))
+    );
+    assert_eq!(
+      pattern.insert_tempo_change(MusicTime::new(1, 5, 1), instant),
+      Err(FailResult::UnreachableTimeChange(
+        MusicTime::new(1, 5, 1),
+        0
+      ))
+    );
+    assert_eq!(
+      pattern.insert_tempo_change(
+        MusicTime::new(4, 1, 1),
+        TempoChange::Ramp(80.0, MusicTime::new(3, 1, 1))
+      ),
+      Err(FailResult::UnreachableTimeChange(
+        MusicTime::new(4, 1, 1),
+        2
+      ))
+    );
+    assert_eq!(
+      pattern.insert_tempo_change(MusicTime::new(4, 1, 1), TempoChange::Instant(0.0)),
+      Err(FailResult::Tempo(0.0))
+    );
+    assert_eq!(pattern.get_tempo_changes().len(), 2);
+    assert_eq!(
+      pattern.remove_tempo_change(2),
+      Err(FailResult::NoFoundTimeChange(2))
+    );
+    assert_eq!(
+      pattern.remove_tempo_change(0),
+      Ok((MusicTime::new(2, 1, 1), instant))
+    );
+
+    assert_eq!(
+      pattern.insert_time_signature_change(4, TimeSignature::new(3, 4)),
+      Ok(0)
+    );
+    assert_eq!(
+      pattern.insert_time_signature_change(4, TimeSignature::new(5, 4)),
+      Err(FailResult::UnreachableSignatureChange(4, 0))
+    );
+    assert_eq!(
+      pattern.insert_time_signature_change(0, TimeSignature::new(5, 4)),
+      Err(FailResult::UnreachableSignatureChange(0, 0))
+    );
+    assert_eq!(
+      pattern.insert_time_signature_change(2, TimeSignature::new(0, 4)),
+      Err(FailResult::TimeSignature(TimeSignature::new(0, 4)))
+    );
+    assert_eq!(
+      pattern.insert_time_signature_change(2, TimeSignature::new(3, 4)),
+      Err(FailResult::UnreachableTime(
+        MusicTime::new(3, 4, 1),
+        0,
+        String::new()
+      ))
+    );
+    assert_eq!(pattern.get_time_signature_at(3), TimeSignature::new(4, 4));
+    assert_eq!(
+      pattern.remove_time_signature_change(1),
+      Err(FailResult::NoFoundSignatureChange(1))
+    );
+    assert_eq!(
+      pattern.remove_time_signature_change(0),
+      Ok((4, TimeSignature::new(3, 4)))
+    );
+    assert_eq!(pattern.check(), Ok(()));
   }
 }
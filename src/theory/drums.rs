@@ -0,0 +1,307 @@
+use super::composition::BEAT_INTERVAL_TICKS;
+
+/// The midi channel drums are exported on, channel 10 of the General MIDI
+/// standard.
+pub const DRUM_CHANNEL: u8 = 9;
+
+/// The General MIDI percussion key map, from note 35 to 81.
+pub const GM_DRUM_MAP: [(&str, u8); 47] = [
+  ("acoustic_bass_drum", 35),
+  ("bass_drum", 36),
+  ("side_stick", 37),
+  ("acoustic_snare", 38),
+  ("hand_clap", 39),
+  ("electric_snare", 40),
+  ("low_floor_tom", 41),
+  ("closed_hi_hat", 42),
+  ("high_floor_tom", 43),
+  ("pedal_hi_hat", 44),
+  ("low_tom", 45),
+  ("open_hi_hat", 46),
+  ("low_mid_tom", 47),
+  ("hi_mid_tom", 48),
+  ("crash_cymbal_1", 49),
+  ("high_tom", 50),
+  ("ride_cymbal_1", 51),
+  ("chinese_cymbal", 52),
+  ("ride_bell", 53),
+  ("tambourine", 54),
+  ("splash_cymbal", 55),
+  ("cowbell", 56),
+  ("crash_cymbal_2", 57),
+  ("vibraslap", 58),
+  ("ride_cymbal_2", 59),
+  ("hi_bongo", 60),
+  ("low_bongo", 61),
+  ("mute_hi_conga", 62),
+  ("open_hi_conga", 63),
+  ("low_conga", 64),
+  ("high_timbale", 65),
+  ("low_timbale", 66),
+  ("high_agogo", 67),
+  ("low_agogo", 68),
+  ("cabasa", 69),
+  ("maracas", 70),
+  ("short_whistle", 71),
+  ("long_whistle", 72),
+  ("short_guiro", 73),
+  ("long_guiro", 74),
+  ("claves", 75),
+  ("hi_wood_block", 76),
+  ("low_wood_block", 77),
+  ("mute_cuica", 78),
+  ("open_cuica", 79),
+  ("mute_triangle", 80),
+  ("open_triangle", 81),
+];
+
+/// Get the midi note of a General MIDI drum name, or one of the short names
+/// kick, snare, hat, open_hat, ride and crash.
+pub fn drum_name_to_note(name: &str) -> Option<u8> {
+  match name {
+    "kick" => Some(36),
+    "snare" => Some(38),
+    "hat" => Some(42),
+    "open_hat" => Some(46),
+    "ride" => Some(51),
+    "crash" => Some(49),
+    _ => GM_DRUM_MAP
+      .iter()
+      .find(|(drum_name, _)| *drum_name == name)
+      .map(|(_, note)| *note),
+  }
+}
+
+/// Get the General MIDI drum name of a midi note.
+pub fn note_to_drum_name(note: u8) -> Option<&'static str> {
+  GM_DRUM_MAP
+    .iter()
+    .find(|(_, drum_note)| *drum_note == note)
+    .map(|(name, _)| *name)
+}
+
+/// Read a drum grid, one character a step. `X` is an accent, `x` a hit, `o` a
+/// ghost note and `.` or `-` a rest. Spaces and `|` can divide the grid.
+///
+/// Returns the velocity of each step, 0 for rests, or `None` if the grid
+/// can't be read.
+pub fn grid_to_velocities(grid: &str) -> Option<Vec<u8>> {
+  const ACCENT_VELOCITY: u8 = 110;
+  const HIT_VELOCITY: u8 = 80;
+  const GHOST_VELOCITY: u8 = 40;
+
+  grid
+    .chars()
+    .filter(|step| !step.is_whitespace() && *step != '|')
+    .map(|step| match step {
+      'X' => Some(ACCENT_VELOCITY),
+      'x' => Some(HIT_VELOCITY),
+      'o' => Some(GHOST_VELOCITY),
+      '.' | '-' => Some(0),
+      _ => None,
+    })
+    .collect()
+}
+
+/// Write the velocities of a lane as a drum grid, the closest of `X`, `x` and
+/// `o` for each hit and `.` for each rest.
+pub fn velocities_to_grid(velocities: &[u8]) -> String {
+  velocities
+    .iter()
+    .map(|velocity| match velocity {
+      0 => '.',
+      1..=59 => 'o',
+      60..=94 => 'x',
+      _ => 'X',
+    })
+    .collect()
+}
+
+/// A lane of a drum pattern, the midi note of the drum and the velocity of each
+/// step, 0 for rests.
+pub type DrumLane = (u8, Vec<u8>);
+
+/// A drum pattern, a grid of steps for each drum. Each lane repeats from the
+/// start of each bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drums {
+  name: String,
+  note_value: u8,
+  lanes: Vec<DrumLane>,
+}
+
+impl Drums {
+  /// Create a drum pattern.
+  ///
+  /// # Arguments
+  /// * `name` - The name of the drum pattern.
+  /// * `note_value` - The note value of each step, 4, 8, 16 or 32.
+  /// * `lanes` - The lanes of the pattern.
+  pub fn new(name: &str, note_value: u8, lanes: Vec<DrumLane>) -> Self {
+    Drums {
+      name: name.to_owned(),
+      note_value,
+      lanes,
+    }
+  }
+
+  /// Check the step note value is supported, and the pattern has lanes of
+  /// General MIDI drums with steps.
+  pub fn is_valid(&self) -> bool {
+    let is_note_value_valid = [4, 8, 16, 32].contains(&self.note_value);
+    let are_lanes_valid = self
+      .lanes
+      .iter()
+      .all(|(note, steps)| note_to_drum_name(*note).is_some() && !steps.is_empty());
+    is_note_value_valid && !self.lanes.is_empty() && are_lanes_valid
+  }
+
+  pub fn get_name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn get_note_value(&self) -> u8 {
+    self.note_value
+  }
+
+  pub fn get_lanes(&self) -> &Vec<DrumLane> {
+    &self.lanes
+  }
+
+  /// Get a copy of the drum pattern with a lane added, replacing the lane of
+  /// the same drum if there is one.
+  pub fn with_lane(&self, lane: DrumLane) -> Self {
+    let mut drums = self.clone();
+    match drums.lanes.iter_mut().find(|(note, _)| *note == lane.0) {
+      Some(existing_lane) => *existing_lane = lane,
+      None => drums.lanes.push(lane),
+    }
+    drums
+  }
+
+  /// Get the count of ticks in a step.
+  pub fn get_step_ticks(&self) -> u32 {
+    const BEAT_INTERVALS: u32 = 8;
+    BEAT_INTERVAL_TICKS as u32 * BEAT_INTERVALS * 4 / self.note_value.max(1) as u32
+  }
+
+  /// Get the tick of each hit from the start of a bar, its midi note and
+  /// velocity, in time order. Lanes stop at the end of the bar.
+  ///
+  /// # Arguments
+  /// * `bar_ticks` - The count of ticks in the bar.
+  pub fn get_bar_hits(&self, bar_ticks: u32) -> Vec<(u32, u8, u8)> {
+    let step_ticks = self.get_step_ticks();
+    let mut hits: Vec<(u32, u8, u8)> = self
+      .lanes
+      .iter()
+      .filter(|(_, steps)| !steps.is_empty())
+      .flat_map(|(note, steps)| {
+        (0..(bar_ticks + step_ticks - 1) / step_ticks)
+          .map(move |step| (step * step_ticks, *note, steps[step as usize % steps.len()]))
+      })
+      .filter(|(_, _, velocity)| *velocity > 0)
+      .collect();
+    hits.sort_by_key(|(tick, _, _)| *tick);
+    hits
+  }
+}
+
+/// Get a named drum pattern, rock, half_time, four_on_the_floor or waltz.
+pub fn string_to_drum_style(style: &str) -> Option<Drums> {
+  let lanes = match style {
+    "rock" => vec![
+      ("kick", "x.......x.x....."),
+      ("snare", "....X.......X..."),
+      ("hat", "x.x.x.x.x.x.x.x."),
+    ],
+    "half_time" => vec![
+      ("kick", "x.........x....."),
+      ("snare", "........X......."),
+      ("hat", "x.x.x.x.x.x.x.x."),
+    ],
+    "four_on_the_floor" => vec![
+      ("kick", "X...X...X...X..."),
+      ("hand_clap", "....x.......x..."),
+      ("open_hat", "..x...x...x...x."),
+    ],
+    "waltz" => vec![
+      ("kick", "X..........."),
+      ("snare", "....o...o..."),
+      ("hat", "x.x.x.x.x.x."),
+    ],
+    _ => return None,
+  };
+
+  const STYLE_NOTE_VALUE: u8 = 16;
+  let lanes = lanes
+    .into_iter()
+    .filter_map(|(drum, grid)| Some((drum_name_to_note(drum)?, grid_to_velocities(grid)?)))
+    .collect();
+  Some(Drums::new(style, STYLE_NOTE_VALUE, lanes))
+}
+
+mod tests {
+
+  #[test]
+  fn test_drum_map() {
+    use crate::theory::drums::*;
+
+    assert_eq!(drum_name_to_note("acoustic_snare"), Some(38));
+    assert_eq!(drum_name_to_note("kick"), Some(36));
+    assert_eq!(drum_name_to_note("cowbell"), Some(56));
+    assert_eq!(drum_name_to_note("timpani"), None);
+    assert_eq!(note_to_drum_name(42), Some("closed_hi_hat"));
+    assert_eq!(note_to_drum_name(34), None);
+
+    assert_eq!(
+      grid_to_velocities("X.x- |o..."),
+      Some(vec![110, 0, 80, 0, 40, 0, 0, 0])
+    );
+    assert_eq!(grid_to_velocities("x.y."), None);
+    assert_eq!(
+      velocities_to_grid(&[110, 0, 80, 0, 40, 0, 100, 20]),
+      "X.x.o.Xo"
+    );
+  }
+
+  #[test]
+  fn test_drum_hits() {
+    use crate::theory::drums::*;
+
+    let drums = Drums::new("beat", 8, vec![(36, vec![80, 0]), (38, vec![0, 0, 110, 0])]);
+    assert!(drums.is_valid());
+    assert_eq!(drums.get_step_ticks(), 240);
+    assert_eq!(
+      drums.get_bar_hits(1920),
+      vec![
+        (0, 36, 80),
+        (480, 36, 80),
+        (480, 38, 110),
+        (960, 36, 80),
+        (1440, 36, 80),
+        (1440, 38, 110)
+      ]
+    );
+    assert_eq!(drums.get_bar_hits(720).len(), 3);
+
+    let drums = drums.with_lane((38, vec![40]));
+    assert_eq!(drums.get_lanes().len(), 2);
+    assert_eq!(drums.get_bar_hits(480).len(), 3);
+
+    let rock = string_to_drum_style("rock").unwrap();
+    assert!(rock.is_valid());
+    assert_eq!(rock.get_lanes().len(), 3);
+    assert_eq!(
+      string_to_drum_style("waltz").unwrap().get_lanes()[0]
+        .1
+        .len(),
+      12
+    );
+    assert!(string_to_drum_style("polka").is_none());
+
+    assert!(!Drums::new("none", 16, Vec::new()).is_valid());
+    assert!(!Drums::new("odd", 12, vec![(36, vec![80])]).is_valid());
+    assert!(!Drums::new("piano", 16, vec![(60 + 30, vec![80])]).is_valid());
+  }
+}
@@ -0,0 +1,185 @@
+use super::composition::BEAT_INTERVAL_TICKS;
+
+/// The count of ticks in a beat.
+const BEAT_TICKS: u32 = BEAT_INTERVAL_TICKS as u32 * 8;
+
+/// The count of ticks in a note value, where the beat is a quarter note.
+fn to_note_ticks(note_value: u8) -> u32 {
+  BEAT_TICKS * 4 / note_value.max(1) as u32
+}
+
+/// Swing delays the second note of each pair of eighth or sixteenth notes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Swing {
+  note_value: u8,
+  ratio: f32,
+}
+
+impl Swing {
+  /// The ratio of straight, unswung, notes.
+  pub const STRAIGHT_RATIO: f32 = 0.5;
+  /// The ratio of the hardest supported swing, a dotted note and its partner.
+  pub const MAX_RATIO: f32 = 0.75;
+
+  /// Create a swing.
+  ///
+  /// # Arguments
+  /// * `note_value` - The swung notes, 8 for eighths or 16 for sixteenths.
+  /// * `ratio` - The share of each pair of notes taken by the first note, from
+  ///   0.5 for straight to 0.75. A triplet swing is 0.667.
+  pub fn new(note_value: u8, ratio: f32) -> Self {
+    Swing { note_value, ratio }
+  }
+
+  pub fn is_valid(&self) -> bool {
+    let is_note_value_valid = self.note_value == 8 || self.note_value == 16;
+    let is_ratio_valid = self.ratio >= Swing::STRAIGHT_RATIO && self.ratio <= Swing::MAX_RATIO;
+    is_note_value_valid && is_ratio_valid
+  }
+
+  pub fn get_note_value(&self) -> u8 {
+    self.note_value
+  }
+
+  pub fn get_ratio(&self) -> f32 {
+    self.ratio
+  }
+
+  /// Get the count of ticks to move an event by.
+  ///
+  /// # Arguments
+  /// * `beat_tick` - The tick of the event from the start of its beat.
+  pub fn get_tick_offset(&self, beat_tick: u32) -> i32 {
+    let note_ticks = to_note_ticks(self.note_value) as f32;
+    let pair_tick = (beat_tick % (to_note_ticks(self.note_value) * 2)) as f32;
+    let long_ticks = note_ticks * 2.0 * self.ratio;
+
+    let swung_tick = if pair_tick < note_ticks {
+      pair_tick * long_ticks / note_ticks
+    } else {
+      long_ticks + (pair_tick - note_ticks) * (note_ticks * 2.0 - long_ticks) / note_ticks
+    };
+
+    (swung_tick - pair_tick).round() as i32
+  }
+}
+
+/// A groove template, moving and accenting events by the subdivision of the bar
+/// they fall on. Timing and velocity offsets repeat from the start of each bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Groove {
+  name: String,
+  note_value: u8,
+  timing: Vec<i16>,
+  velocity: Vec<i8>,
+}
+
+impl Groove {
+  /// Create a groove template.
+  ///
+  /// # Arguments
+  /// * `name` - The name of the groove.
+  /// * `note_value` - The subdivision of the bar, 4, 8, 16 or 32.
+  /// * `timing` - The offset in ticks of each subdivision, 60 ticks to a beat interval.
+  /// * `velocity` - The velocity offset of each subdivision.
+  pub fn new(name: &str, note_value: u8, timing: Vec<i16>, velocity: Vec<i8>) -> Self {
+    Groove {
+      name: name.to_owned(),
+      note_value,
+      timing,
+      velocity,
+    }
+  }
+
+  /// Check the subdivision is supported and no timing offset moves an event
+  /// a whole subdivision or more.
+  pub fn is_valid(&self) -> bool {
+    let is_note_value_valid = [4, 8, 16, 32].contains(&self.note_value);
+    let note_ticks = to_note_ticks(self.note_value) as i32;
+    let is_timing_valid = self
+      .timing
+      .iter()
+      .all(|offset| (*offset as i32).abs() < note_ticks);
+    is_note_value_valid && is_timing_valid
+  }
+
+  pub fn get_name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn get_note_value(&self) -> u8 {
+    self.note_value
+  }
+
+  pub fn get_timing(&self) -> &Vec<i16> {
+    &self.timing
+  }
+
+  pub fn get_velocity(&self) -> &Vec<i8> {
+    &self.velocity
+  }
+
+  /// Get the timing offset in ticks and the velocity offset of an event.
+  ///
+  /// # Arguments
+  /// * `bar_tick` - The tick of the event from the start of its bar.
+  pub fn get_offsets(&self, bar_tick: u32) -> (i32, i16) {
+    let subdivision = (bar_tick / to_note_ticks(self.note_value)) as usize;
+    let timing = match self.timing.len() {
+      0 => 0,
+      len => self.timing[subdivision % len] as i32,
+    };
+    let velocity = match self.velocity.len() {
+      0 => 0,
+      len => self.velocity[subdivision % len] as i16,
+    };
+    (timing, velocity)
+  }
+}
+
+mod tests {
+
+  #[test]
+  fn test_swing() {
+    use crate::theory::groove::Swing;
+
+    let swing = Swing::new(8, 0.75);
+    assert!(swing.is_valid());
+    assert_eq!(swing.get_tick_offset(0), 0);
+    assert_eq!(swing.get_tick_offset(120), 60);
+    assert_eq!(swing.get_tick_offset(240), 120);
+    assert_eq!(swing.get_tick_offset(360), 60);
+    assert_eq!(swing.get_tick_offset(480), 0);
+
+    let swing = Swing::new(16, 0.5);
+    assert!(swing.is_valid());
+    assert_eq!(swing.get_tick_offset(120), 0);
+
+    let swing = Swing::new(16, 2.0 / 3.0);
+    assert_eq!(swing.get_tick_offset(120), 40);
+    assert_eq!(swing.get_tick_offset(360), 40);
+
+    assert!(!Swing::new(4, 0.6).is_valid());
+    assert!(!Swing::new(8, 0.4).is_valid());
+    assert!(!Swing::new(8, 0.8).is_valid());
+  }
+
+  #[test]
+  fn test_groove() {
+    use crate::theory::groove::Groove;
+
+    let groove = Groove::new("push", 16, vec![0, -10, 5, 20], vec![12, -8]);
+    assert!(groove.is_valid());
+    assert_eq!(groove.get_offsets(0), (0, 12));
+    assert_eq!(groove.get_offsets(130), (-10, -8));
+    assert_eq!(groove.get_offsets(360), (20, -8));
+    assert_eq!(groove.get_offsets(480), (0, 12));
+
+    let groove = Groove::new("flat", 8, Vec::new(), Vec::new());
+    assert!(groove.is_valid());
+    assert_eq!(groove.get_offsets(240), (0, 0));
+
+    assert!(!Groove::new("bad", 12, Vec::new(), Vec::new()).is_valid());
+    assert!(!Groove::new("late", 16, vec![120], Vec::new()).is_valid());
+  }
+}
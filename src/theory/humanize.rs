@@ -0,0 +1,121 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// A note of a humanized chord, the tick index it starts at, the midi note and
+/// its velocity.
+pub type HumanizedNote = (u32, u8, u8);
+
+/// Humanize adds bounded random offsets to the start of chords, the start of
+/// each note within a chord and velocities. Offsets are drawn from the seed, so
+/// the same seed always performs a pattern the same way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Humanize {
+  timing: u8,
+  spread: u8,
+  velocity: u8,
+  seed: u64,
+}
+
+impl Humanize {
+  /// Create a humanize.
+  ///
+  /// # Arguments
+  /// * `timing` - The most ticks a chord can start early or late.
+  /// * `spread` - The most ticks a note can start after the start of its chord.
+  /// * `velocity` - The most a note's velocity can be raised or lowered by.
+  /// * `seed` - The seed of the random offsets.
+  pub fn new(timing: u8, spread: u8, velocity: u8, seed: u64) -> Self {
+    Humanize {
+      timing,
+      spread,
+      velocity,
+      seed,
+    }
+  }
+
+  pub fn get_timing(&self) -> u8 {
+    self.timing
+  }
+
+  pub fn get_spread(&self) -> u8 {
+    self.spread
+  }
+
+  pub fn get_velocity(&self) -> u8 {
+    self.velocity
+  }
+
+  pub fn get_seed(&self) -> u64 {
+    self.seed
+  }
+
+  /// Humanize the chords of a pattern. Chords never start before the start of
+  /// the pattern or the chord before them.
+  ///
+  /// # Arguments
+  /// * `chords` - The tick index, velocity and notes of each chord.
+  pub fn apply(&self, chords: &[(u32, u8, &Vec<u8>)]) -> Vec<(u32, Vec<HumanizedNote>)> {
+    const MAX_VELOCITY: i16 = 127;
+
+    let mut rng = StdRng::seed_from_u64(self.seed);
+    let timing = self.timing as i64;
+    let velocity = self.velocity as i16;
+
+    let mut previous_tick_index = 0;
+    chords
+      .iter()
+      .map(|(tick_index, chord_velocity, notes)| {
+        let chord_tick_index = (*tick_index as i64 + rng.gen_range(-timing, timing + 1))
+          .max(previous_tick_index as i64) as u32;
+        previous_tick_index = chord_tick_index;
+
+        let performed_notes = notes
+          .iter()
+          .map(|note| {
+            let note_tick_index = chord_tick_index + rng.gen_range(0, self.spread as u32 + 1);
+            let note_velocity = (*chord_velocity as i16 + rng.gen_range(-velocity, velocity + 1))
+              .clamp(1, MAX_VELOCITY);
+            (note_tick_index, *note, note_velocity as u8)
+          })
+          .collect();
+
+        (chord_tick_index, performed_notes)
+      })
+      .collect()
+  }
+}
+
+mod tests {
+
+  #[test]
+  fn test_humanize() {
+    use crate::theory::humanize::Humanize;
+
+    let notes = vec![60, 64, 67];
+    let chords = vec![(0, 64, &notes), (480, 64, &notes), (490, 120, &notes)];
+
+    let humanize = Humanize::new(20, 10, 8, 7);
+    let performed = humanize.apply(&chords);
+    assert_eq!(performed, humanize.apply(&chords));
+    assert_ne!(performed, Humanize::new(20, 10, 8, 8).apply(&chords));
+
+    let mut previous_tick_index = 0;
+    for ((tick_index, velocity, _), (chord_tick_index, notes)) in chords.iter().zip(&performed) {
+      assert!(*chord_tick_index >= previous_tick_index);
+      assert!((*chord_tick_index as i64 - *tick_index as i64).abs() <= 20);
+      previous_tick_index = *chord_tick_index;
+
+      assert_eq!(notes.len(), 3);
+      for (note_tick_index, _, note_velocity) in notes {
+        assert!(*note_tick_index >= *chord_tick_index && *note_tick_index <= chord_tick_index + 10);
+        assert!((*note_velocity as i16 - *velocity as i16).abs() <= 8);
+        assert!(*note_velocity <= 127);
+      }
+    }
+
+    let performed = Humanize::new(0, 0, 0, 7).apply(&chords);
+    assert_eq!(
+      performed[1],
+      (480, vec![(480, 60, 64), (480, 64, 64), (480, 67, 64)])
+    );
+  }
+}
@@ -0,0 +1,356 @@
+use super::composition::BEAT_INTERVAL_TICKS;
+use super::notes::{self, Key};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// The midi channel melodies are exported on, the third channel.
+pub const MELODY_CHANNEL: u8 = 2;
+
+/// A scale the passing tones of a melody are drawn from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scale {
+  Major,
+  Minor,
+  HarmonicMinor,
+  MelodicMinor,
+  Dorian,
+  Phrygian,
+  Lydian,
+  Mixolydian,
+  Locrian,
+  MajorPentatonic,
+  MinorPentatonic,
+  Blues,
+}
+
+pub fn string_to_scale(scale: &str) -> Option<Scale> {
+  match scale {
+    "major" => Some(Scale::Major),
+    "minor" => Some(Scale::Minor),
+    "harmonic_minor" => Some(Scale::HarmonicMinor),
+    "melodic_minor" => Some(Scale::MelodicMinor),
+    "dorian" => Some(Scale::Dorian),
+    "phrygian" => Some(Scale::Phrygian),
+    "lydian" => Some(Scale::Lydian),
+    "mixolydian" => Some(Scale::Mixolydian),
+    "locrian" => Some(Scale::Locrian),
+    "major_pentatonic" => Some(Scale::MajorPentatonic),
+    "minor_pentatonic" => Some(Scale::MinorPentatonic),
+    "blues" => Some(Scale::Blues),
+    _ => None,
+  }
+}
+
+pub fn scale_to_string(scale: Scale) -> &'static str {
+  match scale {
+    Scale::Major => "major",
+    Scale::Minor => "minor",
+    Scale::HarmonicMinor => "harmonic_minor",
+    Scale::MelodicMinor => "melodic_minor",
+    Scale::Dorian => "dorian",
+    Scale::Phrygian => "phrygian",
+    Scale::Lydian => "lydian",
+    Scale::Mixolydian => "mixolydian",
+    Scale::Locrian => "locrian",
+    Scale::MajorPentatonic => "major_pentatonic",
+    Scale::MinorPentatonic => "minor_pentatonic",
+    Scale::Blues => "blues",
+  }
+}
+
+impl Scale {
+  /// Get the intervals of the scale from its root.
+  pub fn get_intervals(&self) -> &'static [u8] {
+    match self {
+      Scale::Major => &[0, 2, 4, 5, 7, 9, 11],
+      Scale::Minor => &[0, 2, 3, 5, 7, 8, 10],
+      Scale::HarmonicMinor => &[0, 2, 3, 5, 7, 8, 11],
+      Scale::MelodicMinor => &[0, 2, 3, 5, 7, 9, 11],
+      Scale::Dorian => &[0, 2, 3, 5, 7, 9, 10],
+      Scale::Phrygian => &[0, 1, 3, 5, 7, 8, 10],
+      Scale::Lydian => &[0, 2, 4, 6, 7, 9, 11],
+      Scale::Mixolydian => &[0, 2, 4, 5, 7, 9, 10],
+      Scale::Locrian => &[0, 1, 3, 5, 6, 8, 10],
+      Scale::MajorPentatonic => &[0, 2, 4, 7, 9],
+      Scale::MinorPentatonic => &[0, 3, 5, 7, 10],
+      Scale::Blues => &[0, 3, 5, 6, 7, 10],
+    }
+  }
+}
+
+/// The overall shape of a melody across its pattern.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Contour {
+  /// Rising to the top of the range in the middle and falling back.
+  Arch,
+  /// Rising from the bottom of the range to the top.
+  Ascending,
+  /// Falling from the top of the range to the bottom.
+  Descending,
+  /// Rising and falling twice.
+  Wave,
+  /// Wandering around a random target for each note.
+  Random,
+}
+
+pub fn string_to_contour(contour: &str) -> Option<Contour> {
+  match contour {
+    "arch" => Some(Contour::Arch),
+    "ascending" => Some(Contour::Ascending),
+    "descending" => Some(Contour::Descending),
+    "wave" => Some(Contour::Wave),
+    "random" => Some(Contour::Random),
+    _ => None,
+  }
+}
+
+pub fn contour_to_string(contour: Contour) -> &'static str {
+  match contour {
+    Contour::Arch => "arch",
+    Contour::Ascending => "ascending",
+    Contour::Descending => "descending",
+    Contour::Wave => "wave",
+    Contour::Random => "random",
+  }
+}
+
+/// A step a melody can play a note on, whether it starts a chord, whether it
+/// falls on a beat and the pitch classes of the chord sounding.
+pub type MelodyStep<'a> = (bool, bool, &'a [u8]);
+
+/// A melody generator, writing a monophonic line over the chords of a pattern.
+/// Notes on the beat are chord tones, notes between beats are passing tones
+/// from the scale. The same seed always writes the same line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Melody {
+  key: Key,
+  scale: Scale,
+  rate: u8,
+  density: f32,
+  range: (u8, u8),
+  contour: Contour,
+  seed: u64,
+}
+
+impl Melody {
+  /// Create a melody generator.
+  ///
+  /// # Arguments
+  /// * `key` - The root of the scale.
+  /// * `scale` - The scale of the passing tones.
+  /// * `rate` - The note value of each step, e.g 8 for eighths.
+  /// * `density` - The chance of a note on each step that doesn't start a
+  ///   chord, above 0 and up to 1. Steps without a note hold the note before.
+  /// * `range` - The lowest and highest midi note, at least an octave apart.
+  /// * `contour` - The shape of the line.
+  /// * `seed` - The seed of the note choices.
+  pub fn new(
+    key: Key,
+    scale: Scale,
+    rate: u8,
+    density: f32,
+    range: (u8, u8),
+    contour: Contour,
+    seed: u64,
+  ) -> Self {
+    Melody {
+      key,
+      scale,
+      rate,
+      density,
+      range,
+      contour,
+      seed,
+    }
+  }
+
+  /// Check the rate divides a whole note into ticks, the density is above 0
+  /// and up to 1 and the range spans at least an octave of midi notes.
+  pub fn is_valid(&self) -> bool {
+    const MAX_RATE: u8 = 32;
+    const MAX_MIDI_NOTE: u8 = 127;
+    let (lowest, highest) = self.range;
+    let is_rate_valid =
+      self.rate > 0 && self.rate <= MAX_RATE && to_whole_note_ticks() % self.rate as u32 == 0;
+    let is_density_valid = self.density > 0.0 && self.density <= 1.0;
+    let is_range_valid = highest <= MAX_MIDI_NOTE && lowest as u16 + 12 <= highest as u16;
+    is_rate_valid && is_density_valid && is_range_valid
+  }
+
+  pub fn get_key(&self) -> Key {
+    self.key
+  }
+
+  pub fn get_scale(&self) -> Scale {
+    self.scale
+  }
+
+  pub fn get_rate(&self) -> u8 {
+    self.rate
+  }
+
+  pub fn get_density(&self) -> f32 {
+    self.density
+  }
+
+  pub fn get_range(&self) -> (u8, u8) {
+    self.range
+  }
+
+  pub fn get_contour(&self) -> Contour {
+    self.contour
+  }
+
+  pub fn get_seed(&self) -> u64 {
+    self.seed
+  }
+
+  /// Get the count of ticks between steps.
+  pub fn get_step_ticks(&self) -> u32 {
+    to_whole_note_ticks() / self.rate.max(1) as u32
+  }
+
+  /// Write the notes of the melody, `None` where a step holds the note before.
+  /// Every step starting a chord plays a note.
+  ///
+  /// # Arguments
+  /// * `steps` - The steps of the line.
+  pub fn apply(&self, steps: &[MelodyStep]) -> Vec<Option<u8>> {
+    let mut rng = StdRng::seed_from_u64(self.seed);
+    let (lowest, highest) = self.range;
+    let key_index = notes::key_to_index(self.key);
+    let scale_notes: Vec<u8> = (lowest..=highest)
+      .filter(|note| {
+        let interval = (*note as i16 - key_index as i16).rem_euclid(12) as u8;
+        self.scale.get_intervals().contains(&interval)
+      })
+      .collect();
+
+    let last_step = steps.len().saturating_sub(1).max(1) as f32;
+    let mut previous_note: Option<u8> = None;
+    steps
+      .iter()
+      .enumerate()
+      .map(|(step, (is_chord_start, is_on_beat, tones))| {
+        if !is_chord_start && rng.gen::<f32>() >= self.density {
+          return None;
+        }
+
+        let chord_notes: Vec<u8> = (lowest..=highest)
+          .filter(|note| tones.contains(&(note % 12)))
+          .collect();
+        let candidates = if *is_on_beat || *is_chord_start || scale_notes.is_empty() {
+          &chord_notes
+        } else {
+          &scale_notes
+        };
+        if candidates.is_empty() {
+          return None;
+        }
+
+        let target = self.get_target(step as f32 / last_step, &mut rng);
+        let mut scored: Vec<(f32, u8)> = candidates
+          .iter()
+          .map(|note| {
+            let leap = previous_note.map_or(0.0, |previous| (*note as f32 - previous as f32).abs());
+            ((*note as f32 - target).abs() + leap * 0.5, *note)
+          })
+          .collect();
+        scored.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let note = scored[rng.gen_range(0, scored.len().min(2))].1;
+        previous_note = Some(note);
+        Some(note)
+      })
+      .collect()
+  }
+
+  /// Get the note the line is drawn towards at a position through the line.
+  fn get_target(&self, position: f32, rng: &mut StdRng) -> f32 {
+    use std::f32::consts::PI;
+    let (lowest, highest) = (self.range.0 as f32, self.range.1 as f32);
+    let span = highest - lowest;
+    match self.contour {
+      Contour::Arch => lowest + span * (PI * position).sin(),
+      Contour::Ascending => lowest + span * position,
+      Contour::Descending => highest - span * position,
+      Contour::Wave => lowest + span * (0.5 - 0.5 * (4.0 * PI * position).cos()),
+      Contour::Random => rng.gen_range(lowest, highest),
+    }
+  }
+}
+
+/// The count of ticks in a whole note, where the beat is a quarter note.
+fn to_whole_note_ticks() -> u32 {
+  const BEAT_INTERVALS: u32 = 8;
+  BEAT_INTERVAL_TICKS as u32 * BEAT_INTERVALS * 4
+}
+
+mod tests {
+
+  #[test]
+  fn test_melody_notes() {
+    use crate::theory::melody::*;
+    use crate::theory::notes::Key;
+
+    let c_major = [0, 4, 7];
+    let g_major = [7, 11, 2];
+    let steps: Vec<MelodyStep> = (0..16)
+      .map(|step| {
+        let tones: &[u8] = if step < 8 { &c_major } else { &g_major };
+        (step % 8 == 0, step % 2 == 0, tones)
+      })
+      .collect();
+
+    let melody = Melody::new(Key::C, Scale::Major, 8, 1.0, (60, 79), Contour::Arch, 3);
+    assert!(melody.is_valid());
+    let notes = melody.apply(&steps);
+    assert_eq!(notes, melody.apply(&steps));
+    assert_ne!(
+      notes,
+      Melody::new(Key::C, Scale::Major, 8, 1.0, (60, 79), Contour::Arch, 4).apply(&steps)
+    );
+
+    for ((_, is_on_beat, tones), note) in steps.iter().zip(&notes) {
+      let note = note.unwrap();
+      assert!((60..=79).contains(&note));
+      if *is_on_beat {
+        assert!(tones.contains(&(note % 12)));
+      } else {
+        assert!(Scale::Major.get_intervals().contains(&(note % 12)));
+      }
+    }
+
+    let sparse = Melody::new(Key::C, Scale::Major, 8, 0.1, (60, 79), Contour::Wave, 3);
+    let notes = sparse.apply(&steps);
+    assert!(notes[0].is_some() && notes[8].is_some());
+    assert!(notes.iter().filter(|note| note.is_some()).count() < 8);
+
+    let rising = Melody::new(
+      Key::C,
+      Scale::Major,
+      8,
+      1.0,
+      (48, 84),
+      Contour::Ascending,
+      3,
+    );
+    let notes = rising.apply(&steps);
+    assert!(notes[0].unwrap() < notes[15].unwrap());
+
+    assert_eq!(
+      Melody::new(Key::C, Scale::Major, 16, 1.0, (60, 72), Contour::Arch, 0).get_step_ticks(),
+      120
+    );
+    assert!(!Melody::new(Key::C, Scale::Major, 7, 1.0, (60, 79), Contour::Arch, 0).is_valid());
+    assert!(!Melody::new(Key::C, Scale::Major, 8, 0.0, (60, 79), Contour::Arch, 0).is_valid());
+    assert!(!Melody::new(Key::C, Scale::Major, 8, 1.0, (60, 71), Contour::Arch, 0).is_valid());
+
+    assert_eq!(
+      string_to_scale("harmonic_minor"),
+      Some(Scale::HarmonicMinor)
+    );
+    assert_eq!(string_to_scale("chromatic"), None);
+    assert_eq!(string_to_contour("wave"), Some(Contour::Wave));
+    assert_eq!(string_to_contour("zigzag"), None);
+  }
+}
@@ -1,3 +1,10 @@
+pub mod arpeggiator;
+pub mod bass;
 pub mod chords;
 pub mod composition;
+pub mod drums;
+pub mod groove;
+pub mod humanize;
+pub mod melody;
 pub mod notes;
+pub mod rhythm;
@@ -0,0 +1,123 @@
+use super::composition::BEAT_INTERVAL_TICKS;
+
+/// A hit of a rhythm template, the beat and beat interval it falls on and an
+/// optional length in beat intervals. Hits without a length sound until the
+/// next hit.
+pub type RhythmHit = (u8, u8, Option<u8>);
+
+/// A rhythm template re-triggers each chord on its hits until the next chord,
+/// e.g a Charleston, a reggae skank or four on the floor stabs. The template
+/// repeats from the start of each bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rhythm {
+  name: String,
+  beats: u8,
+  hits: Vec<RhythmHit>,
+}
+
+impl Rhythm {
+  /// Create a rhythm template.
+  ///
+  /// # Arguments
+  /// * `name` - The name of the rhythm.
+  /// * `beats` - The length of the template in beats.
+  /// * `hits` - The hits of the template.
+  pub fn new(name: &str, beats: u8, hits: Vec<RhythmHit>) -> Self {
+    Rhythm {
+      name: name.to_owned(),
+      beats,
+      hits,
+    }
+  }
+
+  /// Check the template has hits, and every hit is within the template and has
+  /// a length when one is given.
+  pub fn is_valid(&self) -> bool {
+    const BEAT_INTERVALS: u8 = 8;
+    let are_hits_valid = self.hits.iter().all(|(beat, beat_interval, length)| {
+      *beat > 0
+        && *beat <= self.beats
+        && *beat_interval > 0
+        && *beat_interval <= BEAT_INTERVALS
+        && *length != Some(0)
+    });
+    !self.hits.is_empty() && are_hits_valid
+  }
+
+  pub fn get_name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn get_beats(&self) -> u8 {
+    self.beats
+  }
+
+  pub fn get_hits(&self) -> &Vec<RhythmHit> {
+    &self.hits
+  }
+
+  /// Get the tick of each hit from the start of a bar and its length in ticks,
+  /// repeating the template until the end of the bar.
+  ///
+  /// # Arguments
+  /// * `bar_ticks` - The count of ticks in the bar.
+  pub fn get_bar_hits(&self, bar_ticks: u32) -> Vec<(u32, Option<u32>)> {
+    const BEAT_INTERVALS: u32 = 8;
+    let interval_ticks = BEAT_INTERVAL_TICKS as u32;
+    let template_ticks = self.beats as u32 * BEAT_INTERVALS * interval_ticks;
+    if template_ticks == 0 {
+      return Vec::new();
+    }
+
+    let mut hit_ticks: Vec<(u32, Option<u32>)> = self
+      .hits
+      .iter()
+      .map(|(beat, beat_interval, length)| {
+        let tick =
+          ((*beat as u32 - 1) * BEAT_INTERVALS + *beat_interval as u32 - 1) * interval_ticks;
+        (tick, length.map(|length| length as u32 * interval_ticks))
+      })
+      .collect();
+    hit_ticks.sort_by_key(|(tick, _)| *tick);
+    hit_ticks.dedup_by_key(|(tick, _)| *tick);
+
+    (0..(bar_ticks + template_ticks - 1) / template_ticks)
+      .flat_map(|repeat| {
+        hit_ticks
+          .iter()
+          .map(move |(tick, length)| (repeat * template_ticks + tick, *length))
+      })
+      .filter(|(tick, _)| *tick < bar_ticks)
+      .collect()
+  }
+}
+
+mod tests {
+
+  #[test]
+  fn test_rhythm_hits() {
+    use crate::theory::rhythm::Rhythm;
+
+    let charleston = Rhythm::new("charleston", 4, vec![(2, 5, Some(2)), (1, 1, Some(3))]);
+    assert!(charleston.is_valid());
+    assert_eq!(
+      charleston.get_bar_hits(1920),
+      vec![(0, Some(180)), (720, Some(120))]
+    );
+    assert_eq!(
+      charleston.get_bar_hits(1440),
+      vec![(0, Some(180)), (720, Some(120))]
+    );
+
+    let skank = Rhythm::new("skank", 2, vec![(2, 1, None)]);
+    assert_eq!(skank.get_bar_hits(1920), vec![(480, None), (1440, None)]);
+    assert_eq!(skank.get_bar_hits(1440), vec![(480, None)]);
+
+    let stabs = Rhythm::new("stabs", 1, vec![(1, 1, Some(2))]);
+    assert_eq!(stabs.get_bar_hits(1440).len(), 3);
+
+    assert!(!Rhythm::new("empty", 4, Vec::new()).is_valid());
+    assert!(!Rhythm::new("outside", 2, vec![(3, 1, None)]).is_valid());
+    assert!(!Rhythm::new("silent", 2, vec![(1, 1, Some(0))]).is_valid());
+  }
+}
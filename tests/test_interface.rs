@@ -3,7 +3,7 @@ extern crate chord_composer;
 use chord_composer::{
   performance::performance_engine::PerformanceState,
   theory::composition::{Composition, Pattern, PatternEvent},
-  FailResult, SuccessResult,
+  ExportMode, FailResult, MidiExportOptions, MidiFileFormat, SuccessResult,
 };
 use music_timer::{music_time::MusicTime, time_signature::TimeSignature};
 
@@ -80,6 +80,23 @@ fn export_midi_no_patterns() {
   );
 }
 
+#[test]
+fn validate_template() {
+  assert_eq!(
+    chord_composer::export_template("./tests/test_template_validate.yaml"),
+    Ok(chord_composer::SuccessResult::ExportTemplate)
+  );
+
+  let issues = chord_composer::validate_file("./tests/test_template_validate.yaml").unwrap();
+  std::fs::remove_file("./tests/test_template_validate.yaml").unwrap();
+  assert_eq!(issues, Vec::new());
+
+  assert_eq!(
+    chord_composer::validate_file("./tests/no_file.gone"),
+    Err(chord_composer::FailResult::Deserialize)
+  );
+}
+
 #[test]
 fn test_export_template() {
   assert_eq!(
@@ -168,6 +185,615 @@ fn export_midi() {
   };
 
   assert_eq!(buffer, file2_bin);
+
+  std::fs::remove_dir_all("./tests/bc_000_a").unwrap();
+}
+
+#[test]
+fn import_midi() {
+  use chord_composer::theory::composition::TempoChange;
+
+  let file = "./tests/import_test.yaml";
+  let midi_file = "./tests/import_test/part_a.mid";
+  let yaml_file = "./tests/import_test/part_a.yaml";
+
+  assert_eq!(
+    chord_composer::export_file_to_midi(file),
+    Ok(SuccessResult::Export(vec![midi_file.to_string()])),
+  );
+
+  let composition = chord_composer::import_midi_file(midi_file, None).unwrap();
+  assert_eq!(composition.get_name(), "part_a");
+  assert_eq!(composition.len(), 1);
+
+  let pattern = composition.get(0);
+  assert_eq!(pattern.get_name(), "part_a");
+  assert_eq!(pattern.get_bpm(), 100.0);
+  assert_eq!(pattern.get_time_signature(), TimeSignature::new(4, 4));
+  assert_eq!(
+    pattern.get_tempo_changes(),
+    &vec![(MusicTime::new(2, 1, 1), TempoChange::Instant(140.0))]
+  );
+  assert_eq!(
+    pattern.get_time_signature_changes(),
+    &vec![(3, TimeSignature::new(3, 4))]
+  );
+
+  let events: Vec<(MusicTime, Option<(&str, i8)>)> = pattern
+    .get_chord_events()
+    .iter()
+    .map(|event| {
+      let symbol = event
+        .get_symbol()
+        .map(|symbol| (symbol.get_name(), symbol.get_transpose()));
+      (*event.get_time(), symbol)
+    })
+    .collect();
+  assert_eq!(
+    events,
+    vec![
+      (MusicTime::new(1, 1, 1), Some(("MAJOR", 2))),
+      (MusicTime::new(1, 3, 1), Some(("MINOR_SEVENTH", 9))),
+      (MusicTime::new(2, 1, 1), None),
+      (MusicTime::new(2, 3, 1), Some(("REST", 0))),
+      (MusicTime::new(3, 1, 5), Some(("DOMINANT_SEVENTH", -3))),
+    ]
+  );
+  assert_eq!(pattern.get_event(2).get_notes(), &vec![62, 63, 64]);
+
+  let meta_track = chord_composer::import_midi_file(midi_file, Some(0)).unwrap();
+  assert_eq!(meta_track.get(0).len(), 0);
+  assert_eq!(
+    chord_composer::import_midi_file(midi_file, Some(2)).map(|composition| composition.len()),
+    Err(FailResult::NoFoundTrack(2))
+  );
+  assert_eq!(
+    chord_composer::import_midi_file(file, None).map(|composition| composition.len()),
+    Err(FailResult::ImportMIDI)
+  );
+
+  assert_eq!(
+    chord_composer::import_midi_file_to_yaml(midi_file, Some(1), yaml_file, ExportMode::Refuse),
+    Ok(SuccessResult::Export(vec![yaml_file.to_string()])),
+  );
+  assert_eq!(chord_composer::validate_file(yaml_file), Ok(Vec::new()));
+
+  let yaml: serde_yaml::Value =
+    serde_yaml::from_str(&std::fs::read_to_string(yaml_file).unwrap()).unwrap();
+  let to_value = |yaml: &str| serde_yaml::from_str::<serde_yaml::Value>(yaml).unwrap();
+  let pattern = &yaml["patterns"][0];
+  let events = pattern["pattern"].as_sequence().unwrap();
+  assert_eq!(yaml["chords"][0], to_value("[custom_1, [2, 3, 4]]"));
+  assert_eq!(pattern["time_changes"][0], to_value("[2, 1, 1, 140.0]"));
+  assert_eq!(pattern["signature_changes"][0], to_value("[3, [3, 4]]"));
+  assert!(events.contains(&to_value("[1, 3, 1, MINOR_SEVENTH, 9]")));
+  assert!(events.contains(&to_value("[2, 1, 1, custom_1, 0]")));
+  assert!(events.contains(&to_value("[2, 3, 1, REST, 0]")));
+
+  std::fs::remove_dir_all("./tests/import_test").unwrap();
+}
+
+#[test]
+fn export_single_midi() {
+  use chord_composer::theory::composition::TempoChange;
+
+  let midi_file = "./tests/single_song/song.mid";
+  std::fs::create_dir_all("./tests/single_song").unwrap();
+
+  let mut verse = Pattern::new("verse", 100.0, TimeSignature::new(3, 4));
+  verse
+    .push_event(MusicTime::new(1, 1, 1), vec![60, 64, 67])
+    .unwrap();
+  verse
+    .push_event(MusicTime::new(2, 1, 1), vec![57, 60, 64])
+    .unwrap();
+  let mut chorus = Pattern::new("chorus", 140.0, TimeSignature::new(4, 4));
+  chorus
+    .push_event(MusicTime::new(1, 1, 1), vec![65, 69, 72])
+    .unwrap();
+  let composition = Composition::new_with_patterns("song", vec![verse, chorus]);
+
+  assert_eq!(
+    chord_composer::export_to_single_midi_file(&composition, midi_file),
+    Ok(SuccessResult::Export(vec![midi_file.to_string()])),
+  );
+
+  let imported = chord_composer::import_midi_file(midi_file, None).unwrap();
+  assert_eq!(imported.len(), 1);
+
+  let pattern = imported.get(0);
+  assert_eq!(pattern.get_name(), "song");
+  assert_eq!(pattern.get_bpm(), 100.0);
+  assert_eq!(pattern.get_time_signature(), TimeSignature::new(3, 4));
+  assert_eq!(
+    pattern.get_time_signature_changes(),
+    &vec![(3, TimeSignature::new(4, 4))]
+  );
+  assert_eq!(
+    pattern.get_tempo_changes(),
+    &vec![(MusicTime::new(3, 1, 1), TempoChange::Instant(140.0))]
+  );
+
+  let times: Vec<MusicTime> = pattern.get_events().iter().map(|(time, _)| *time).collect();
+  assert_eq!(
+    times,
+    vec![
+      MusicTime::new(1, 1, 1),
+      MusicTime::new(2, 1, 1),
+      MusicTime::new(3, 1, 1)
+    ]
+  );
+
+  let options = MidiExportOptions::default()
+    .with_ppq(96)
+    .with_format(MidiFileFormat::SingleTrack);
+  assert!(chord_composer::export_to_single_midi_file_with_options(
+    &composition,
+    midi_file,
+    &options
+  )
+  .is_ok());
+  let single_track = chord_composer::import_midi_file(midi_file, None).unwrap();
+  assert_eq!(
+    single_track.get(0).get_chord_events(),
+    pattern.get_chord_events()
+  );
+  assert_eq!(
+    single_track.get(0).get_tempo_changes(),
+    pattern.get_tempo_changes()
+  );
+
+  std::fs::remove_dir_all("./tests/single_song").unwrap();
+}
+
+#[test]
+fn export_midi_bytes() {
+  struct FailingWriter;
+
+  impl std::io::Write for FailingWriter {
+    fn write(&mut self, _buffer: &[u8]) -> std::io::Result<usize> {
+      Err(std::io::Error::new(std::io::ErrorKind::Other, "closed"))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+      Ok(())
+    }
+  }
+
+  let pattern_file = "./tests/bytes_song/verse.mid";
+  let song_file = "./tests/bytes_song/song.mid";
+
+  let mut verse = Pattern::new("verse", 90.0, TimeSignature::new(3, 4));
+  verse
+    .push_event(MusicTime::new(1, 1, 1), vec![60, 64, 67])
+    .unwrap();
+  verse
+    .push_event(MusicTime::new(2, 2, 1), vec![57, 60, 64])
+    .unwrap();
+  let composition = Composition::new_with_patterns("bytes_song", vec![verse.clone()]);
+
+  let options = MidiExportOptions::default().with_ppq(96);
+  assert_eq!(
+    chord_composer::export_to_midi_file_with_options(
+      &composition,
+      "./tests/bytes_song.yaml",
+      &options
+    ),
+    Ok(SuccessResult::Export(vec![pattern_file.to_string()])),
+  );
+  assert_eq!(
+    chord_composer::export_pattern_to_midi_bytes(&verse, &options),
+    Ok(std::fs::read(pattern_file).unwrap())
+  );
+
+  assert!(chord_composer::export_to_single_midi_file_with_options(
+    &composition,
+    song_file,
+    &options
+  )
+  .is_ok());
+  let mut song_bytes = Vec::new();
+  assert_eq!(
+    chord_composer::export_to_midi_writer(&composition, &mut song_bytes, &options),
+    Ok(())
+  );
+  assert_eq!(song_bytes, std::fs::read(song_file).unwrap());
+  assert_eq!(
+    chord_composer::export_to_midi_bytes(&composition, &options),
+    Ok(song_bytes)
+  );
+
+  assert_eq!(
+    chord_composer::export_pattern_to_midi_writer(&verse, &mut FailingWriter, &options),
+    Err(FailResult::WriteMIDI("closed".to_string()))
+  );
+  assert_eq!(
+    chord_composer::export_to_midi_bytes(&composition, &options.with_ppq(0)),
+    Err(FailResult::MidiPpq(0))
+  );
+
+  std::fs::remove_dir_all("./tests/bytes_song").unwrap();
+}
+
+#[test]
+fn export_midi_modes() {
+  let export_path = "./tests/export_modes/song.yaml";
+  let first_files = vec![
+    "./tests/export_modes/_escape/_x.mid".to_string(),
+    "./tests/export_modes/_escape/b.mid".to_string(),
+  ];
+
+  let mut escape = Pattern::new("../x", 120.0, TimeSignature::new(4, 4));
+  escape
+    .push_event(MusicTime::new(1, 1, 1), vec![60, 64, 67])
+    .unwrap();
+  let mut b = Pattern::new("b", 120.0, TimeSignature::new(4, 4));
+  b.push_event(MusicTime::new(1, 1, 1), vec![57, 60, 64])
+    .unwrap();
+  let composition = Composition::new_with_patterns("../escape", vec![escape.clone(), b.clone()]);
+
+  let overwrite = MidiExportOptions::default();
+  assert_eq!(
+    chord_composer::export_to_midi_file_with_options(&composition, export_path, &overwrite),
+    Ok(SuccessResult::Export(first_files.clone())),
+  );
+  assert!(!std::path::Path::new("./tests/escape").exists());
+  assert_eq!(
+    chord_composer::export_to_midi_file_with_options(&composition, export_path, &overwrite),
+    Ok(SuccessResult::Export(first_files.clone())),
+  );
+
+  let refuse = MidiExportOptions::default().with_mode(ExportMode::Refuse);
+  assert_eq!(
+    chord_composer::export_to_midi_file_with_options(&composition, export_path, &refuse),
+    Err(FailResult::ExportExists(
+      "./tests/export_modes/_escape".to_string()
+    )),
+  );
+
+  let versioned = MidiExportOptions::default().with_mode(ExportMode::Versioned);
+  assert_eq!(
+    chord_composer::export_to_midi_file_with_options(&composition, export_path, &versioned),
+    Ok(SuccessResult::Export(vec![
+      "./tests/export_modes/_escape_2/_x.mid".to_string(),
+      "./tests/export_modes/_escape_2/b.mid".to_string(),
+    ])),
+  );
+
+  // Patterns no longer in the composition are removed, other files are kept
+  let notes_file = "./tests/export_modes/_escape/notes.txt";
+  std::fs::write(notes_file, "notes").unwrap();
+  let composition = Composition::new_with_patterns("../escape", vec![b.clone()]);
+  assert_eq!(
+    chord_composer::export_to_midi_file_with_options(&composition, export_path, &overwrite),
+    Ok(SuccessResult::Export(vec![first_files[1].clone()])),
+  );
+  assert!(!std::path::Path::new(&first_files[0]).exists());
+  assert!(std::path::Path::new(notes_file).exists());
+
+  // Files not written by an export are never replaced
+  std::fs::write(&first_files[0], "not midi").unwrap();
+  let composition = Composition::new_with_patterns("../escape", vec![escape.clone()]);
+  assert_eq!(
+    chord_composer::export_to_midi_file_with_options(&composition, export_path, &overwrite),
+    Err(FailResult::ExportExists(first_files[0].clone())),
+  );
+  assert_eq!(
+    std::fs::read_to_string(&first_files[0]).unwrap(),
+    "not midi"
+  );
+
+  // Single files are replaced at their path, without a manifest
+  assert_eq!(
+    chord_composer::export_to_single_midi_file_with_options(&composition, &first_files[1], &refuse),
+    Err(FailResult::ExportExists(first_files[1].clone())),
+  );
+  let single_file = "./tests/export_modes/other.mid";
+  std::fs::write(single_file, "not midi").unwrap();
+  assert_eq!(
+    chord_composer::export_to_single_midi_file_with_options(&composition, single_file, &versioned),
+    Ok(SuccessResult::Export(vec![
+      "./tests/export_modes/other_2.mid".to_string()
+    ])),
+  );
+  assert_eq!(
+    chord_composer::export_to_single_midi_file_with_options(&composition, single_file, &overwrite),
+    Ok(SuccessResult::Export(vec![single_file.to_string()])),
+  );
+  assert_ne!(std::fs::read(single_file).unwrap(), b"not midi");
+  assert_eq!(
+    chord_composer::export_to_single_midi_file_with_options(
+      &composition,
+      "./tests/export_modes",
+      &overwrite
+    ),
+    Err(FailResult::ExportExists("./tests/export_modes".to_string())),
+  );
+  assert!(!std::path::Path::new("./tests/export_modes/.chord_composer_export").exists());
+
+  // No file is replaced in a directory without a manifest, even a midi file
+  let versioned_file = "./tests/export_modes/_escape_2/_x.mid";
+  std::fs::remove_file("./tests/export_modes/_escape_2/.chord_composer_export").unwrap();
+  let composition = Composition::new_with_patterns("../escape_2", vec![escape, b]);
+  assert_eq!(
+    chord_composer::export_to_midi_file_with_options(&composition, export_path, &overwrite),
+    Err(FailResult::ExportExists(versioned_file.to_string())),
+  );
+
+  std::fs::remove_dir_all("./tests/export_modes").unwrap();
+}
+
+#[test]
+fn export_musicxml() {
+  let file = "./tests/export_test.yaml";
+  let musicxml_file = "./tests/bc_000_a.musicxml";
+
+  assert_eq!(
+    chord_composer::export_file_to_musicxml(file, ExportMode::Refuse),
+    Ok(SuccessResult::Export(vec![musicxml_file.to_string()])),
+  );
+
+  let xml = std::fs::read_to_string(musicxml_file).unwrap();
+  std::fs::remove_file(musicxml_file).unwrap();
+
+  assert!(xml.starts_with("<?xml"));
+  assert!(xml.ends_with("</score-partwise>\n"));
+  assert_eq!(xml.matches("<measure number=").count(), 8);
+  assert_eq!(xml.matches("<rehearsal>").count(), 2);
+  assert!(xml.contains("<fifths>2</fifths>"));
+  assert!(xml.contains("<fifths>-5</fifths>"));
+  assert!(xml.contains("<beats>4</beats>\n          <beat-type>8</beat-type>"));
+  assert!(xml.contains("<per-minute>128</per-minute>"));
+  assert!(xml.contains("<beat-unit>eighth</beat-unit>\n            <per-minute>69</per-minute>"));
+  assert!(xml.contains(
+    "<root-step>D</root-step>\n        </root>\n        <kind text=\"maj7\">major-seventh</kind>"
+  ));
+  assert!(xml.contains(
+    "<root-step>B</root-step>\n          <root-alter>-1</root-alter>\n        </root>\n        <kind text=\"\">major</kind>"
+  ));
+  assert_eq!(xml.matches("<harmony>").count(), 16);
+
+  assert_eq!(
+    chord_composer::export_to_musicxml_file(
+      &Composition::new("none"),
+      "./tests/no_directory/none.musicxml",
+      ExportMode::Overwrite
+    ),
+    Err(FailResult::ExportMusicXML(
+      "./tests/no_directory/none.musicxml".to_string()
+    )),
+  );
+}
+
+#[test]
+fn export_lilypond() {
+  let file = "./tests/export_test.yaml";
+  let lilypond_file = "./tests/bc_000_a.ly";
+
+  assert_eq!(
+    chord_composer::export_file_to_lilypond(file, ExportMode::Refuse),
+    Ok(SuccessResult::Export(vec![lilypond_file.to_string()])),
+  );
+
+  let lilypond = std::fs::read_to_string(lilypond_file).unwrap();
+  std::fs::remove_file(lilypond_file).unwrap();
+
+  assert!(lilypond.starts_with("\\version"));
+  assert!(lilypond.contains("harmonies = \\chordmode {"));
+  assert_eq!(lilypond.matches("\\mark \\markup").count(), 2);
+  assert!(lilypond.contains("\\key d \\major"));
+  assert!(lilypond.contains("\\key des \\major"));
+  assert!(lilypond.contains("\\time 4/8"));
+  assert!(lilypond.contains("\\tempo 8 = 69"));
+  assert!(lilypond.contains("d2:maj7"));
+  assert!(lilypond.ends_with("\\layout { }\n}\n"));
+
+  assert_eq!(
+    chord_composer::export_to_lilypond_file(
+      &Composition::new("none"),
+      "./tests/no_directory/none.ly",
+      ExportMode::Overwrite
+    ),
+    Err(FailResult::ExportLilyPond(
+      "./tests/no_directory/none.ly".to_string()
+    )),
+  );
+}
+
+#[test]
+fn export_import_abc() {
+  let file = "./tests/export_test.yaml";
+  let abc_file = "./tests/bc_000_a.abc";
+
+  assert_eq!(
+    chord_composer::export_file_to_abc(file, ExportMode::Refuse),
+    Ok(SuccessResult::Export(vec![abc_file.to_string()])),
+  );
+
+  let abc = std::fs::read_to_string(abc_file).unwrap();
+  let imported = chord_composer::import_abc_file(abc_file);
+  std::fs::remove_file(abc_file).unwrap();
+
+  assert_eq!(abc.matches("X:").count(), 2);
+  assert!(abc.contains("K:D\n"));
+  assert!(abc.contains("K:Db\n"));
+  assert!(abc.contains("M:4/8\n"));
+  assert!(abc.contains("Q:1/8=69\n"));
+  assert!(abc.contains("\"Dmaj7\""));
+
+  let composition = imported.unwrap();
+  assert_eq!(composition.get_name(), "bc_000_a");
+  assert_eq!(composition.len(), 2);
+
+  let part_a = composition.get(0);
+  assert_eq!(part_a.get_name(), "part_a");
+  assert_eq!(part_a.get_bpm(), 128.0);
+  assert_eq!(part_a.get_time_signature(), TimeSignature::new(3, 4));
+  assert_eq!(part_a.len(), 9);
+  assert_eq!(part_a.get_event(5).get_time(), &MusicTime::new(3, 2, 1));
+  assert!(part_a.get_event(6).get_notes().is_empty());
+  let symbol = part_a.get_event(0).get_symbol().unwrap();
+  assert_eq!(symbol.get_name(), "MAJOR_SEVENTH");
+  assert_eq!(part_a.get_event(0).get_notes(), &vec![62, 66, 69, 73]);
+
+  let part_b = composition.get(1);
+  assert_eq!(part_b.get_name(), "part_b");
+  assert_eq!(part_b.get_bpm(), 69.0);
+  assert_eq!(part_b.get_time_signature(), TimeSignature::new(4, 8));
+  assert_eq!(part_b.len(), 8);
+  assert_eq!(part_b.get_event(7).get_time(), &MusicTime::new(4, 2, 1));
+
+  assert_eq!(
+    chord_composer::abc_to_pattern("X:1\nT:tune\nK:G\n\"G\"G2 \"Zm\"A2|").unwrap_err(),
+    FailResult::UnknownChordSymbol("Zm".to_string())
+  );
+  assert_eq!(
+    chord_composer::import_abc_file("./tests/none.abc").unwrap_err(),
+    FailResult::ImportABC
+  );
+  assert_eq!(
+    chord_composer::export_to_abc_file(
+      &Composition::new("none"),
+      "./tests/no_directory/none.abc",
+      ExportMode::Overwrite
+    ),
+    Err(FailResult::ExportABC(
+      "./tests/no_directory/none.abc".to_string()
+    )),
+  );
+}
+
+#[test]
+fn save_yaml() {
+  let file = "./tests/export_test.yaml";
+  let saved_file = "./tests/save_test.yaml";
+
+  let mut composition = chord_composer::load_file(file).unwrap();
+  composition.get_mut(0).set_channel(5).unwrap();
+  assert_eq!(
+    chord_composer::export_to_yaml_file(&composition, saved_file, ExportMode::Refuse),
+    Ok(SuccessResult::Export(vec![saved_file.to_string()])),
+  );
+
+  let saved_yaml = std::fs::read_to_string(saved_file).unwrap();
+  let saved_composition = chord_composer::load_file(saved_file).unwrap();
+  std::fs::remove_file(saved_file).unwrap();
+
+  let saved_value: serde_yaml::Value = serde_yaml::from_str(&saved_yaml).unwrap();
+  assert_eq!(saved_value["master"]["key"].as_str(), Some("D"));
+  assert_eq!(saved_value["master"]["time"].as_f64(), Some(128.0));
+  assert_eq!(saved_value["chords"][1][0].as_str(), Some("empty"));
+  assert_eq!(
+    saved_value["chords"][1][1].as_sequence().map(Vec::len),
+    Some(0)
+  );
+  assert_eq!(saved_value["patterns"][0]["channel"].as_u64(), Some(5));
+  assert!(saved_value["patterns"][1]["channel"].is_null());
+  assert_eq!(saved_composition.get(0).get_channel(), 5);
+  assert_eq!(
+    saved_value["patterns"][1]["master"]["key"].as_str(),
+    Some("C#")
+  );
+  assert_eq!(
+    chord_composer::composition_to_yaml(&saved_composition).unwrap(),
+    saved_yaml
+  );
+
+  assert_eq!(saved_composition.get_name(), composition.get_name());
+  assert_eq!(saved_composition.len(), composition.len());
+  for (saved_pattern, pattern) in saved_composition
+    .get_patterns()
+    .iter()
+    .zip(composition.get_patterns())
+  {
+    assert_eq!(saved_pattern.get_name(), pattern.get_name());
+    assert_eq!(saved_pattern.get_bpm(), pattern.get_bpm());
+    assert_eq!(saved_pattern.get_channel(), pattern.get_channel());
+    assert_eq!(
+      saved_pattern.get_time_signature(),
+      pattern.get_time_signature()
+    );
+    assert_eq!(saved_pattern.get_chord_events(), pattern.get_chord_events());
+  }
+
+  assert_eq!(
+    chord_composer::export_to_yaml_file(
+      &composition,
+      "./tests/no_directory/none.yaml",
+      ExportMode::Overwrite
+    ),
+    Err(FailResult::ExportYAML(
+      "./tests/no_directory/none.yaml".to_string()
+    )),
+  );
+  assert_eq!(
+    chord_composer::load_yaml("name: none").unwrap_err(),
+    FailResult::NoPatterns
+  );
+}
+
+#[test]
+fn export_chart() {
+  let file = "./tests/export_test.yaml";
+  let chart_file = "./tests/bc_000_a.txt";
+  let chordpro_file = "./tests/bc_000_a.cho";
+
+  assert_eq!(
+    chord_composer::export_file_to_chart(file, ExportMode::Refuse),
+    Ok(SuccessResult::Export(vec![chart_file.to_string()])),
+  );
+  assert_eq!(
+    chord_composer::export_file_to_chordpro(file, ExportMode::Refuse),
+    Ok(SuccessResult::Export(vec![chordpro_file.to_string()])),
+  );
+
+  // Files already at the path are treated by the export mode
+  assert_eq!(
+    chord_composer::export_file_to_chart(file, ExportMode::Refuse),
+    Err(FailResult::ExportExists(chart_file.to_string())),
+  );
+  assert_eq!(
+    chord_composer::export_file_to_chart(file, ExportMode::Versioned),
+    Ok(SuccessResult::Export(vec![
+      "./tests/bc_000_a_2.txt".to_string()
+    ])),
+  );
+  std::fs::write(chordpro_file, "notes").unwrap();
+  assert_eq!(
+    chord_composer::export_file_to_chordpro(file, ExportMode::Overwrite),
+    Ok(SuccessResult::Export(vec![chordpro_file.to_string()])),
+  );
+
+  let chart = std::fs::read_to_string(chart_file).unwrap();
+  let chordpro = std::fs::read_to_string(chordpro_file).unwrap();
+  assert_eq!(
+    std::fs::read_to_string("./tests/bc_000_a_2.txt").unwrap(),
+    chart
+  );
+  std::fs::remove_file(chart_file).unwrap();
+  std::fs::remove_file("./tests/bc_000_a_2.txt").unwrap();
+  std::fs::remove_file(chordpro_file).unwrap();
+
+  assert!(chart.starts_with("bc_000_a\n\npart_a\nKey: D  Time: 3/4  Tempo: 128\n"));
+  assert!(chart.contains("| Dmaj7 . Bb | Dmaj9 . Bb | Fmaj7 Bb N.C. | Bmaj9 Bb . |\n"));
+  assert!(chart.contains("part_b\nKey: Db  Time: 4/8  Tempo: 69\n"));
+  assert!(chart.contains("| Dbmaj7 A . . | Dbmaj9 A . . | Emaj7 A . . | Bbmaj9 A . . |\n"));
+
+  assert!(chordpro.starts_with("{title: bc_000_a}\n"));
+  assert_eq!(chordpro.matches("{start_of_grid: label=").count(), 2);
+  assert_eq!(chordpro.matches("{end_of_grid}").count(), 2);
+  assert!(chordpro.contains("{key: Db}\n{tempo: 69}\n{time: 4/8}\n"));
+
+  assert_eq!(
+    chord_composer::export_to_chart_file(
+      &Composition::new("none"),
+      "./tests/no_directory/none.txt",
+      ExportMode::Overwrite
+    ),
+    Err(FailResult::ExportChart(
+      "./tests/no_directory/none.txt".to_string()
+    )),
+  );
 }
 
 #[test]
@@ -230,6 +856,51 @@ fn play_middle_c_file() {
   assert_eq!(my_state.current_time, MusicTime::new(1, 4, 8));
 }
 
+#[test]
+fn play_middle_c_yaml_with_options() {
+  use chord_composer::PlaybackOptions;
+  use music_timer::music_time::MusicTime;
+  let yaml = r#"
+  name: middle_c
+  chords:
+      - [single_note, [0]]
+  patterns:
+      - name: part_a
+        drums:
+            style: rock
+        pattern:
+            - [1, 1, 1, single_note, 0]
+  "#;
+
+  let mut my_state = MiddleCState {
+    callback_calls: 0,
+    current_time: MusicTime::default(),
+  };
+
+  let options = PlaybackOptions::default().with_metronome(true);
+  assert!(options.is_metronome_enabled());
+  assert!(options.get_drum_samples().is_empty());
+
+  assert_eq!(
+    chord_composer::play_yaml_with_options(yaml, &mut my_state, &options),
+    Ok(chord_composer::SuccessResult::Playback)
+  );
+
+  assert_eq!(my_state.callback_calls, 42);
+  assert_eq!(my_state.current_time, MusicTime::new(1, 4, 8));
+
+  assert_eq!(
+    chord_composer::play_yaml_from_with_options(
+      yaml,
+      &mut my_state,
+      &options,
+      &MusicTime::new(1, 1, 1),
+      "part_b"
+    ),
+    Err(FailResult::NoFoundPattern("part_b".to_owned())),
+  );
+}
+
 #[test]
 fn play_composition_from() {
   struct MyState {
@@ -277,7 +948,7 @@ fn play_composition_from() {
     vec![
       Pattern::new_with_events(
         "a",
-        100,
+        100.0,
         TimeSignature::default(),
         vec![
           chord_composer::build_event(1, 1, 1, vec![0, 3, 7], 0),
@@ -287,7 +958,7 @@ fn play_composition_from() {
       ),
       Pattern::new_with_events(
         "b",
-        150,
+        150.0,
         TimeSignature::new(7, 4),
         vec![
           chord_composer::build_event(3, 5, 1, vec![2], -2),